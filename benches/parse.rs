@@ -1,7 +1,10 @@
 //! Benchmarks for [`Parser`], the streaming input parser.
 //!
-//! This currently only tests the time/throughput of bracketed paste. Bracketed paste includes
-//! arbitrary content, so the OSC sequence can reach very very long lengths.
+//! This tests the time/throughput of bracketed paste, OSC replies, mouse motion reports and kitty
+//! keyboard protocol key events. Bracketed paste and OSC 52 clipboard replies include arbitrary
+//! content, so these sequences can reach very very long lengths. Mouse tracking with motion
+//! reporting and the kitty protocol's press/repeat/release reporting flood the input with short
+//! sequences instead.
 
 use std::hint::black_box;
 
@@ -12,6 +15,20 @@ use termina::Parser;
 /// `src/event/source/unix.rs`), since bytes only trickle into the parser a chunk at a time.
 const CHUNK_SIZE: usize = 1024;
 
+/// Feeds `input` to a new parser a chunk at a time, as the event sources do.
+fn parse(input: &[u8]) {
+    let mut parser = Parser::default();
+    for chunk in input.chunks(CHUNK_SIZE) {
+        // Mirrors `src/event/source/unix.rs`: `maybe_more` is true when a read fills the whole
+        // buffer, since more bytes are likely still waiting to be read. Events are taken after
+        // each read.
+        parser.parse(black_box(chunk), chunk.len() == CHUNK_SIZE);
+        while let Some(event) = parser.pop() {
+            black_box(event);
+        }
+    }
+}
+
 fn bracketed_paste(content_len: usize) -> Vec<u8> {
     let mut bytes = Vec::with_capacity(content_len + 12);
     bytes.extend_from_slice(b"\x1b[200~");
@@ -23,26 +40,86 @@ fn bracketed_paste(content_len: usize) -> Vec<u8> {
 fn paste(c: &mut Criterion) {
     let mut group = c.benchmark_group("paste");
 
-    for size in [1_000, 20_000, 200_000] {
+    for size in [1_000, 20_000, 200_000, 2_000_000] {
         let input = bracketed_paste(size);
         group.throughput(Throughput::Bytes(input.len() as u64));
         group.bench_with_input(BenchmarkId::from_parameter(size), &input, |b, input| {
-            b.iter(|| {
-                let mut parser = Parser::default();
-                for chunk in input.chunks(CHUNK_SIZE) {
-                    // Mirrors `src/event/source/unix.rs`: `maybe_more` is true when a read fills
-                    // the whole buffer, since more bytes are likely still waiting to be read.
-                    parser.parse(black_box(chunk), chunk.len() == CHUNK_SIZE);
-                }
-                while let Some(event) = parser.pop() {
-                    black_box(event);
-                }
-            })
+            b.iter(|| parse(input))
+        });
+    }
+
+    group.finish();
+}
+
+fn mouse_motion(count: usize) -> Vec<u8> {
+    (0..count)
+        .flat_map(|i| format!("\x1b[<35;{};{}M", i % 200 + 1, i % 50 + 1).into_bytes())
+        .collect()
+}
+
+fn mouse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("mouse");
+
+    for count in [100, 10_000] {
+        let input = mouse_motion(count);
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(count), &input, |b, input| {
+            b.iter(|| parse(input))
+        });
+    }
+
+    group.finish();
+}
+
+/// A clipboard reply to an OSC 52 query, as sent by terminals supporting `Osc::Clipboard`.
+fn clipboard_reply(content_len: usize) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(content_len + 9);
+    bytes.extend_from_slice(b"\x1b]52;c;");
+    bytes.extend(b"aGVsbG8g".iter().copied().cycle().take(content_len));
+    bytes.extend_from_slice(b"\x1b\\");
+    bytes
+}
+
+fn osc(c: &mut Criterion) {
+    let mut group = c.benchmark_group("osc");
+
+    for size in [1_000, 200_000] {
+        let input = clipboard_reply(size);
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &input, |b, input| {
+            b.iter(|| parse(input))
+        });
+    }
+
+    group.finish();
+}
+
+/// Typing with every kitty keyboard protocol enhancement enabled: each key reports a press with
+/// its text, a repeat and a release.
+fn kitty_keys(count: usize) -> Vec<u8> {
+    (0..count)
+        .flat_map(|i| {
+            let c = b'a' + (i % 26) as u8;
+            let modifiers = if i % 5 == 0 { 2 } else { 1 };
+            format!("\x1b[{c};{modifiers}:1;{c}u\x1b[{c};{modifiers}:2u\x1b[{c};{modifiers}:3u")
+                .into_bytes()
+        })
+        .collect()
+}
+
+fn kitty(c: &mut Criterion) {
+    let mut group = c.benchmark_group("kitty");
+
+    for count in [100, 10_000] {
+        let input = kitty_keys(count);
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(count), &input, |b, input| {
+            b.iter(|| parse(input))
         });
     }
 
     group.finish();
 }
 
-criterion_group!(benches, paste);
+criterion_group!(benches, paste, osc, mouse, kitty);
 criterion_main!(benches);
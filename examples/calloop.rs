@@ -0,0 +1,63 @@
+//! Reads terminal events from a calloop event loop alongside a timer.
+//!
+//! Run with `cargo run --example calloop --features calloop`.
+use std::{io, time::Duration};
+
+use calloop::{
+    timer::{TimeoutAction, Timer},
+    EventLoop,
+};
+use termina::{
+    event::{KeyCode, KeyEventKind},
+    integration::calloop::EventSourceAdapter,
+    Event, PlatformTerminal, Terminal,
+};
+
+struct State {
+    ticks: usize,
+    done: bool,
+}
+
+fn main() -> io::Result<()> {
+    let error = |err| io::Error::new(io::ErrorKind::Other, err);
+
+    let mut terminal = PlatformTerminal::new()?;
+    terminal.enter_raw_mode()?;
+    println!("Press keys to print them, q to quit.\r");
+
+    let mut event_loop: EventLoop<State> = EventLoop::try_new().map_err(error)?;
+    let handle = event_loop.handle();
+    let source = EventSourceAdapter::new(
+        terminal.event_reader(),
+        |event| matches!(event, Event::Key(key) if key.kind == KeyEventKind::Press),
+    )?;
+    handle
+        .insert_source(source, |event, _, state| {
+            let Event::Key(key) = event else { return };
+            if key.code == KeyCode::Char('q') {
+                state.done = true;
+            } else {
+                println!("{:?} after {} ticks\r", key.code, state.ticks);
+            }
+        })
+        .map_err(|err| error(err.error))?;
+    handle
+        .insert_source(
+            Timer::from_duration(Duration::from_secs(1)),
+            |_, _, state| {
+                state.ticks += 1;
+                TimeoutAction::ToDuration(Duration::from_secs(1))
+            },
+        )
+        .map_err(|err| error(err.error))?;
+
+    let mut state = State {
+        ticks: 0,
+        done: false,
+    };
+    while !state.done {
+        event_loop.dispatch(None, &mut state).map_err(error)?;
+    }
+
+    terminal.enter_cooked_mode()
+}
@@ -0,0 +1,57 @@
+use std::{
+    io::{self, Write as _},
+    time::Duration,
+};
+
+use termina::{
+    escape::csi::{self, Csi, KittyKeyboardFlags},
+    event::{KeyCode, KeyEventKind},
+    Event, PlatformTerminal, Terminal,
+};
+
+const FLAGS: KittyKeyboardFlags = KittyKeyboardFlags::DISAMBIGUATE_ESCAPE_CODES
+    .union(KittyKeyboardFlags::REPORT_EVENT_TYPES)
+    .union(KittyKeyboardFlags::REPORT_ALTERNATE_KEYS)
+    .union(KittyKeyboardFlags::REPORT_ALL_KEYS_AS_ESCAPE_CODES);
+
+fn main() -> io::Result<()> {
+    let mut terminal = PlatformTerminal::new()?;
+    terminal.enter_raw_mode()?;
+
+    // Check that the terminal supports the protocol before pushing flags.
+    write!(terminal, "{}", Csi::Keyboard(csi::Keyboard::QueryFlags))?;
+    terminal.flush()?;
+    let supported = terminal.poll(Event::is_escape, Some(Duration::from_millis(100)))?
+        && matches!(
+            terminal.read(Event::is_escape)?,
+            Event::Csi(Csi::Keyboard(csi::Keyboard::ReportFlags(_)))
+        );
+    if !supported {
+        terminal.enter_cooked_mode()?;
+        eprintln!("The terminal does not support the Kitty keyboard protocol");
+        return Ok(());
+    }
+
+    write!(
+        terminal,
+        "{}Press keys to see how they are decoded. Press q to exit.\r\n",
+        Csi::Keyboard(csi::Keyboard::PushFlags(FLAGS)),
+    )?;
+    terminal.flush()?;
+
+    loop {
+        let Event::Key(key) = terminal.read(|event| matches!(event, Event::Key(_)))? else {
+            unreachable!()
+        };
+        write!(terminal, "{key:?}\r\n")?;
+        terminal.flush()?;
+        if key.code == KeyCode::Char('q') && key.kind == KeyEventKind::Release {
+            break;
+        }
+    }
+
+    write!(terminal, "{}", Csi::Keyboard(csi::Keyboard::PopFlags(1)))?;
+    terminal.flush()?;
+
+    Ok(())
+}
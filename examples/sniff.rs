@@ -0,0 +1,132 @@
+//! Prints every byte the terminal sends, how Termina parses it and when it arrived.
+//!
+//! Run this in the terminal which misbehaves, reproduce the problem and paste the output into the
+//! bug report:
+//!
+//! ```text
+//! cargo run --example sniff -- --mouse --kitty
+//! ```
+//!
+//! `--mouse` enables mouse reporting and `--kitty` enables the kitty keyboard protocol. Press
+//! Ctrl+C to quit.
+use std::{
+    env,
+    io::{self, Write as _},
+    time::{Duration, Instant},
+};
+
+use termina::{
+    escape::csi::{self, KittyKeyboardFlags},
+    event::{KeyCode, KeyEvent, Modifiers},
+    Event, PlatformTerminal, Terminal,
+};
+
+macro_rules! decset {
+    ($mode:ident) => {
+        csi::Csi::Mode(csi::Mode::SetDecPrivateMode(csi::DecPrivateMode::Code(
+            csi::DecPrivateModeCode::$mode,
+        )))
+    };
+}
+macro_rules! decreset {
+    ($mode:ident) => {
+        csi::Csi::Mode(csi::Mode::ResetDecPrivateMode(csi::DecPrivateMode::Code(
+            csi::DecPrivateModeCode::$mode,
+        )))
+    };
+}
+
+fn main() -> io::Result<()> {
+    let args: Vec<_> = env::args().collect();
+    let mouse = args.iter().any(|a| a == "--mouse");
+    let kitty = args.iter().any(|a| a == "--kitty");
+
+    println!("termina {}", env!("CARGO_PKG_VERSION"));
+    for var in ["TERM", "TERM_PROGRAM", "TERM_PROGRAM_VERSION", "TMUX"] {
+        if let Ok(value) = env::var(var) {
+            println!("{var}={value}");
+        }
+    }
+    println!("mouse: {mouse}, kitty keyboard: {kitty}");
+    println!("Press Ctrl+C to quit.\n");
+
+    let mut terminal = PlatformTerminal::new()?;
+    terminal.enter_raw_mode()?;
+
+    let mut modes = format!("{}{}", decset!(FocusTracking), decset!(BracketedPaste));
+    if mouse {
+        modes += &format!(
+            "{}{}{}",
+            decset!(MouseTracking),
+            decset!(AnyEventMouse),
+            decset!(SGRMouse)
+        );
+    }
+    if kitty {
+        modes += &csi::Csi::Keyboard(csi::Keyboard::PushFlags(
+            KittyKeyboardFlags::DISAMBIGUATE_ESCAPE_CODES
+                | KittyKeyboardFlags::REPORT_EVENT_TYPES
+                | KittyKeyboardFlags::REPORT_ALTERNATE_KEYS,
+        ))
+        .to_string();
+    }
+    write!(terminal, "{modes}")?;
+    terminal.flush()?;
+
+    let reader = terminal.event_reader();
+    let start = Instant::now();
+    let mut last = start;
+    reader.set_raw_tap(move |bytes| {
+        let now = Instant::now();
+        print!(
+            "{} (+{}) {} bytes: {}\r\n",
+            millis(now - start),
+            millis(now - last),
+            bytes.len(),
+            bytes.escape_ascii()
+        );
+        last = now;
+    });
+
+    loop {
+        let event = reader.read(|_| true)?;
+        print!("    {event}\r\n");
+        if let Event::Key(KeyEvent {
+            code: KeyCode::Char('c'),
+            modifiers: Modifiers::CONTROL,
+            ..
+        }) = event
+        {
+            break;
+        }
+    }
+    reader.remove_raw_tap();
+
+    let stats = reader.parser_stats();
+    print!(
+        "\r\n{} bytes, {} events, {} malformed, {} unrecognized\r\n",
+        stats.bytes, stats.events, stats.malformed, stats.unrecognized
+    );
+
+    let mut modes = format!("{}{}", decreset!(FocusTracking), decreset!(BracketedPaste));
+    if mouse {
+        modes += &format!(
+            "{}{}{}",
+            decreset!(MouseTracking),
+            decreset!(AnyEventMouse),
+            decreset!(SGRMouse)
+        );
+    }
+    if kitty {
+        modes += &csi::Csi::Keyboard(csi::Keyboard::PopFlags(1)).to_string();
+    }
+    write!(terminal, "{modes}")?;
+    terminal.flush()?;
+
+    Ok(())
+}
+
+/// Formats a duration as milliseconds with microsecond precision.
+fn millis(duration: Duration) -> String {
+    format!("{:.3}ms", duration.as_secs_f64() * 1000.0)
+}
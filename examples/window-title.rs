@@ -1,10 +1,7 @@
 use std::io::{self, Write as _};
 
 use termina::{
-    escape::{
-        csi::{self, Csi},
-        osc::Osc,
-    },
+    escape::csi::{self, Csi},
     PlatformTerminal, Terminal as _,
 };
 
@@ -14,26 +11,27 @@ fn main() -> io::Result<()> {
 
     write!(
         terminal,
-        "{}{}{}{}Check the window/tab title of your terminal. Press any key to exit. ",
+        "{}",
         Csi::Mode(csi::Mode::SetDecPrivateMode(csi::DecPrivateMode::Code(
             csi::DecPrivateModeCode::ClearAndEnableAlternateScreen
         ))),
-        // Save the current title to the terminal's stack.
-        Csi::Window(Box::new(csi::Window::PushIconAndWindowTitle)),
-        Osc::SetIconNameAndWindowTitle("Hello, world! - termina"),
-        Csi::Cursor(csi::Cursor::Position {
-            line: Default::default(),
-            col: Default::default(),
-        }),
+    )?;
+    // Save the current title to the terminal's stack.
+    terminal.push_title()?;
+    terminal.set_title("Hello, world! - termina")?;
+    terminal.move_to(0, 0)?;
+    write!(
+        terminal,
+        "Check the window/tab title of your terminal. Press any key to exit. "
     )?;
     terminal.flush()?;
     let _ = terminal.read(|event| matches!(event, termina::Event::Key(_)));
 
+    // Restore the title from the terminal's stack.
+    terminal.pop_title()?;
     write!(
         terminal,
-        "{}{}",
-        // Restore the title from the terminal's stack.
-        Csi::Window(Box::new(csi::Window::PopIconAndWindowTitle)),
+        "{}",
         Csi::Mode(csi::Mode::ResetDecPrivateMode(csi::DecPrivateMode::Code(
             csi::DecPrivateModeCode::ClearAndEnableAlternateScreen,
         ))),
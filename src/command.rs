@@ -0,0 +1,210 @@
+//! Commands which can be queued into a terminal's output.
+//!
+//! A [`Command`] is anything which writes an escape sequence or text: the typed sequences from
+//! [`crate::escape`], [`Stylized`] text and plain strings. [`queue!`](crate::queue) writes
+//! commands into any [`io::Write`], such as a [`Terminal`](crate::Terminal) whose output is
+//! buffered, and [`execute!`](crate::execute) also flushes it. Commands are formatted straight into
+//! the writer, so no intermediate strings are allocated.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::io;
+//!
+//! use termina::{
+//!     escape::csi::{Csi, Cursor, Edit, EraseInDisplay},
+//!     execute, queue,
+//!     style::StyleExt as _,
+//!     OneBased,
+//! };
+//!
+//! # fn main() -> io::Result<()> {
+//! # termina::style::Stylized::force_ansi_color(true);
+//! let mut output = Vec::new();
+//! queue!(
+//!     output,
+//!     Csi::Edit(Edit::EraseInDisplay(EraseInDisplay::EraseDisplay)),
+//!     Csi::Cursor(Cursor::Position {
+//!         line: OneBased::new(2).unwrap(),
+//!         col: OneBased::new(3).unwrap(),
+//!     }),
+//! )?;
+//! execute!(output, "done: ", "ok".green())?;
+//! assert_eq!(output, b"\x1b[2J\x1b[2;3Hdone: \x1b[0;32mok\x1b[m");
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # Implementation Notes
+//!
+//! The trait and macros follow the shape of [crossterm's commands] so code migrating from crossterm
+//! keeps its structure. Unlike crossterm, commands have no WinAPI counterparts: Termina always
+//! writes escape sequences, enabling virtual terminal processing on Windows consoles. A legacy
+//! console which doesn't support it translates a core subset of the written sequences to Console
+//! API calls instead: SGR colors and bold, underline and reverse video, cursor movement, erasing
+//! the display or line, and showing or hiding the cursor. Other sequences, such as the alternate
+//! screen, mouse modes and OSC strings, are dropped there. See `ConsoleKind::Legacy`.
+//!
+//! [crossterm's commands]: https://docs.rs/crossterm/latest/crossterm/trait.Command.html
+
+use std::{fmt, io};
+
+use crate::{
+    escape::{apc::Apc, csi::Csi, dcs::Dcs, esc::Esc, osc::Osc, sixel::Sixel},
+    style::{AttributesDiff, Stylized},
+    SizeRequest,
+};
+
+/// An escape sequence or text which can be written to a terminal.
+///
+/// Write commands with [`queue!`](crate::queue), [`execute!`](crate::execute) or [`queue`].
+pub trait Command {
+    /// Writes the command's bytes into `f`.
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result;
+}
+
+impl<T: Command + ?Sized> Command for &T {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        (**self).write_ansi(f)
+    }
+}
+
+impl Command for str {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        f.write_str(self)
+    }
+}
+
+impl Command for String {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        f.write_str(self)
+    }
+}
+
+impl Command for char {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        f.write_char(*self)
+    }
+}
+
+impl Command for fmt::Arguments<'_> {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        f.write_fmt(*self)
+    }
+}
+
+macro_rules! display_commands {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl Command for $ty {
+                fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+                    write!(f, "{self}")
+                }
+            }
+        )*
+    };
+}
+
+display_commands!(
+    Csi,
+    Esc,
+    Osc<'_>,
+    Dcs,
+    Apc<'_>,
+    Sixel<'_>,
+    Stylized<'_>,
+    AttributesDiff,
+    SizeRequest,
+);
+
+/// Writes `command` into `writer` without flushing it.
+///
+/// This is the function behind [`queue!`](crate::queue).
+pub fn queue<W: io::Write + ?Sized>(writer: &mut W, command: impl Command) -> io::Result<()> {
+    let mut adapter = Adapter {
+        writer,
+        error: Ok(()),
+    };
+    match command.write_ansi(&mut adapter) {
+        Ok(()) => Ok(()),
+        Err(fmt::Error) => match adapter.error {
+            Err(err) => Err(err),
+            Ok(()) => Err(io::Error::new(
+                io::ErrorKind::Other,
+                "a command returned a formatting error",
+            )),
+        },
+    }
+}
+
+/// Forwards formatted output to an `io::Write`, keeping the I/O error which `fmt::Error` drops.
+struct Adapter<'a, W: ?Sized> {
+    writer: &'a mut W,
+    error: io::Result<()>,
+}
+
+impl<W: io::Write + ?Sized> fmt::Write for Adapter<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.writer.write_all(s.as_bytes()).map_err(|err| {
+            self.error = Err(err);
+            fmt::Error
+        })
+    }
+}
+
+/// Writes commands into a writer without flushing it.
+///
+/// The writer may be a value or a mutable reference implementing [`std::io::Write`], and the
+/// commands are anything implementing [`Command`]. Writing stops at the first error, which is
+/// returned as an [`std::io::Result`].
+///
+/// See the [`command`](crate::command) module for an example.
+#[macro_export]
+macro_rules! queue {
+    ($writer:expr $(, $command:expr)* $(,)?) => {{
+        use ::std::io::Write as _;
+        // `by_ref` accepts both `impl Write` and `&mut impl Write` writers.
+        ::std::result::Result::<_, ::std::io::Error>::Ok($writer.by_ref())
+            $(.and_then(|writer| $crate::command::queue(writer, $command).map(|()| writer)))*
+            .map(|_| ())
+    }};
+}
+
+/// Writes commands into a writer like [`queue!`](crate::queue) and then flushes it.
+#[macro_export]
+macro_rules! execute {
+    ($writer:expr $(, $command:expr)* $(,)?) => {{
+        use ::std::io::Write as _;
+        $crate::queue!($writer $(, $command)*).and_then(|()| $writer.by_ref().flush())
+    }};
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::escape::csi::{Cursor, Mode};
+
+    struct Broken;
+
+    impl io::Write for Broken {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            Err(io::ErrorKind::BrokenPipe.into())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn queue_commands() {
+        let mut output = Vec::new();
+        let writer = &mut output;
+        crate::queue!(writer, Esc::SaveCursor, Csi::Cursor(Cursor::Up(1)), 'x').unwrap();
+        crate::execute!(writer, format_args!("{}", 1), &Csi::Mode(Mode::QueryTheme)).unwrap();
+        assert_eq!(output, b"\x1b7\x1b[1Ax1\x1b[?996n");
+
+        let err = crate::queue!(Broken, "text").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+    }
+}
@@ -1,8 +1,10 @@
 //! Typed ANSI escape-sequence helpers.
 //!
-//! Termina models Control Sequence Introducer (CSI), Device Control String (DCS), and Operating
-//! System Command (OSC) sequences it knows how to emit so callers can compose terminal control
-//! payloads through [`Display`] instead of hand-written byte strings.
+//! Termina models Control Sequence Introducer (CSI), Device Control String (DCS), Operating System
+//! Command (OSC) and Application Program Command (APC) sequences it knows how to emit so callers
+//! can compose terminal control payloads through [`Display`] instead of hand-written byte strings.
+//! [`esc`] covers the older escape sequences made of ESC and a single character, and [`sixel`]
+//! encodes images as sixel DCS sequences.
 //!
 //! # Examples
 //!
@@ -32,9 +34,12 @@
 //! [termwiz escape helpers]: https://docs.rs/termwiz/latest/termwiz/escape/index.html
 //! [`Display`]: std::fmt::Display
 
+pub mod apc;
 pub mod csi;
 pub mod dcs;
+pub mod esc;
 pub mod osc;
+pub mod sixel;
 
 /// Control Sequence Introducer (`ESC [`), the prefix for parameterized terminal control functions.
 ///
@@ -48,7 +53,7 @@ pub const CSI: &str = "\x1b[";
 /// Termina models the supported commands in [`osc::Osc`].
 pub const OSC: &str = "\x1b]";
 
-/// String Terminator (`ESC \`), used to end OSC and DCS string controls.
+/// String Terminator (`ESC \`), used to end OSC, DCS and APC string controls.
 ///
 /// Most modern terminal string controls may also be terminated by [`BEL`], but Termina emits the
 /// explicit string terminator form for the sequences it formats.
@@ -64,6 +69,12 @@ pub const SS3: &str = "\x1bO";
 /// Termina models the supported request and response forms in [`dcs::Dcs`].
 pub const DCS: &str = "\x1bP";
 
+/// Application Program Command introducer (`ESC _`), used by terminal extensions such as the kitty
+/// graphics protocol.
+///
+/// Termina formats APC strings with [`apc::Apc`].
+pub const APC: &str = "\x1b_";
+
 /// Bell control character (`BEL`, `0x07`).
 ///
 /// BEL can ring the terminal bell and is also accepted by many terminals as an OSC terminator.
@@ -0,0 +1,39 @@
+//! Application Program Command (APC) escape sequences.
+//!
+//! Application Program Command strings are framed by [`APC`] and [`ST`]. The terminal does not
+//! interpret them itself; extensions define their contents. The most common is the
+//! [kitty graphics protocol], whose commands start with `G` and whose responses arrive as
+//! [`Event::Apc`].
+//!
+//! # Examples
+//!
+//! ```
+//! use termina::escape::apc::Apc;
+//!
+//! // Ask whether the terminal supports the kitty graphics protocol.
+//! let query = Apc("Gi=31,s=1,v=1,a=q,t=d,f=24;AAAA");
+//! assert_eq!(query.to_string(), "\x1b_Gi=31,s=1,v=1,a=q,t=d,f=24;AAAA\x1b\\");
+//! ```
+//!
+//! [`APC`]: super::APC
+//! [`ST`]: super::ST
+//! [`Event::Apc`]: crate::Event::Apc
+//! [kitty graphics protocol]: https://sw.kovidgoyal.net/kitty/graphics-protocol/
+
+use std::fmt::{self, Display};
+
+/// An Application Program Command with the given payload.
+///
+/// Formatting writes the APC introducer, the payload, and the string terminator. The payload must
+/// not contain the string terminator or other control characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Apc<'a>(pub &'a str);
+
+impl Display for Apc<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(super::APC)?;
+        f.write_str(self.0)?;
+        f.write_str(super::ST)
+    }
+}
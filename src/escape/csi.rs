@@ -20,7 +20,10 @@ use std::{
 
 use crate::{
     event::Modifiers,
-    style::{Blink, ColorSpec, CursorStyle, Font, Intensity, RgbaColor, Underline, VerticalAlign},
+    style::{
+        Blink, ColorSpec, CursorStyle, Font, Intensity, RgbColor, RgbaColor, Underline,
+        VerticalAlign,
+    },
     OneBased,
 };
 
@@ -30,12 +33,19 @@ use crate::{
 /// are the main terminal protocol surface for cursor movement, text styling, mode changes, device
 /// reports, mouse reports, and window operations.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub enum Csi {
     /// Select Graphic Rendition commands described by [`Sgr`].
     ///
     /// These `CSI ... m` commands change how subsequent cells are rendered.
     Sgr(Sgr),
 
+    /// xterm's graphic rendition stack commands described by [`SgrStack`].
+    ///
+    /// These `CSI ... # {` and `CSI # }` commands save and restore [`Sgr`] state.
+    SgrStack(SgrStack),
+
     /// Cursor commands described by [`Cursor`].
     ///
     /// This family covers cursor movement, cursor shape, margins, and position reports.
@@ -75,6 +85,39 @@ pub enum Csi {
     /// This family covers window manipulation and reports, mostly from xterm-compatible
     /// extensions.
     Window(Box<Window>),
+
+    /// A CSI sequence without a typed representation.
+    ///
+    /// The parser reports sequences it doesn't understand with this variant rather than dropping
+    /// them, so debug logs can show everything the terminal sent. Formatting writes the sequence
+    /// back unchanged.
+    ///
+    /// ```
+    /// use termina::{escape::csi::Csi, Event, Parser};
+    ///
+    /// let mut parser = Parser::default();
+    /// parser.feed(b"\x1b[>4;1 Z");
+    /// let Some(Event::Csi(csi)) = parser.next_event() else {
+    ///     unreachable!()
+    /// };
+    /// assert_eq!(
+    ///     csi,
+    ///     Csi::Unspecified {
+    ///         params: ">4;1".to_owned(),
+    ///         intermediates: " ".to_owned(),
+    ///         final_byte: b'Z',
+    ///     }
+    /// );
+    /// assert_eq!(csi.to_string(), "\x1b[>4;1 Z");
+    /// ```
+    Unspecified {
+        /// The parameter bytes (`0x30..=0x3F`), including any private marker such as `?` or `>`.
+        params: String,
+        /// The intermediate bytes (`0x20..=0x2F`).
+        intermediates: String,
+        /// The final byte (`0x40..=0x7E`).
+        final_byte: u8,
+    },
 }
 
 impl Display for Csi {
@@ -83,6 +126,7 @@ impl Display for Csi {
         f.write_str(super::CSI)?;
         match self {
             Self::Sgr(sgr) => write!(f, "{sgr}m"),
+            Self::SgrStack(stack) => stack.fmt(f),
             Self::Cursor(cursor) => cursor.fmt(f),
             Self::Edit(edit) => edit.fmt(f),
             Self::Mode(mode) => mode.fmt(f),
@@ -90,10 +134,177 @@ impl Display for Csi {
             Self::Keyboard(keyboard) => keyboard.fmt(f),
             Self::Device(device) => device.fmt(f),
             Self::Window(window) => window.fmt(f),
+            Self::Unspecified {
+                params,
+                intermediates,
+                final_byte,
+            } => write!(f, "{params}{intermediates}{}", *final_byte as char),
+        }
+    }
+}
+
+/// xterm's stack of graphic rendition attributes.
+///
+/// [XTPUSHSGR] saves the current [`Sgr`] state on a stack and [XTPOPSGR] restores the most
+/// recently saved state. A renderer can push before writing a styled span and pop afterwards
+/// instead of re-emitting the full SGR state it had before the span. xterm limits the stack to 10
+/// entries.
+///
+/// # Capability detection
+///
+/// Terminals which don't support the stack ignore these commands, and there is no dedicated query
+/// for them. [`Self::probe`] returns a sequence which detects support by pushing bold text,
+/// resetting it, popping, and asking for the resulting SGR state with DECRQSS. Pass the
+/// terminal's reply to [`Self::probe_result`]. Terminals which answer neither DECRQSS nor the
+/// stack commands send no reply, so wait with a timeout.
+///
+/// ```no_run
+/// use std::{
+///     io::{self, Write as _},
+///     time::Duration,
+/// };
+///
+/// use termina::{escape::csi::SgrStack, PlatformTerminal, Terminal};
+///
+/// fn main() -> io::Result<()> {
+///     let mut terminal = PlatformTerminal::new()?;
+///     terminal.enter_raw_mode()?;
+///     write!(terminal, "{}", SgrStack::probe())?;
+///     terminal.flush()?;
+///     let supported = terminal
+///         .event_reader()
+///         .read_timeout(
+///             |event| SgrStack::probe_result(event).is_some(),
+///             Duration::from_millis(100),
+///         )?
+///         .and_then(|event| SgrStack::probe_result(&event))
+///         .unwrap_or(false);
+///     println!("XTPUSHSGR supported: {supported}");
+///     terminal.enter_cooked_mode()
+/// }
+/// ```
+///
+/// [XTPUSHSGR]: https://invisible-island.net/xterm/ctlseqs/ctlseqs.html#h4-Functions-using-CSI-_-ordered-by-the-final-character-lparen-s-rparen:CSI-Pm-#-{
+/// [XTPOPSGR]: https://invisible-island.net/xterm/ctlseqs/ctlseqs.html#h4-Functions-using-CSI-_-ordered-by-the-final-character-lparen-s-rparen:CSI-#-}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum SgrStack {
+    /// XTPUSHSGR (`CSI Pm # {`): push the selected attributes.
+    ///
+    /// An empty [`SgrStackFlags`] pushes every attribute.
+    Push(SgrStackFlags),
+
+    /// XTPOPSGR (`CSI # }`): restore the attributes saved by the matching push.
+    Pop,
+}
+
+impl SgrStack {
+    /// Returns a sequence which makes a supporting terminal report that it supports the stack.
+    ///
+    /// The sequence resets the SGR state. See the [type-level
+    /// documentation](Self#capability-detection) for how to use it.
+    pub fn probe() -> String {
+        use super::dcs::{Dcs, DcsRequest};
+
+        format!(
+            "{}{}{}{}{}{}{}",
+            Csi::Sgr(Sgr::Reset),
+            Csi::Sgr(Sgr::Intensity(Intensity::Bold)),
+            Csi::SgrStack(Self::Push(SgrStackFlags::empty())),
+            Csi::Sgr(Sgr::Reset),
+            Csi::SgrStack(Self::Pop),
+            Dcs::Request(DcsRequest::GraphicRendition),
+            Csi::Sgr(Sgr::Reset),
+        )
+    }
+
+    /// Interprets the terminal's reply to [`Self::probe`].
+    ///
+    /// Returns `None` if `event` isn't a report of the SGR state. Otherwise returns whether the
+    /// report shows that the pop restored the pushed attributes.
+    pub fn probe_result(event: &crate::Event) -> Option<bool> {
+        use super::dcs::{Dcs, DcsResponse};
+
+        match event {
+            crate::Event::Dcs(Dcs::Response {
+                is_request_valid,
+                value: DcsResponse::GraphicRendition(sgrs),
+            }) => Some(*is_request_valid && sgrs.contains(&Sgr::Intensity(Intensity::Bold))),
+            _ => None,
         }
     }
 }
 
+impl Display for SgrStack {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Push(flags) => {
+                let mut first = true;
+                for (flag, param) in SgrStackFlags::PARAMS {
+                    if flags.contains(flag) {
+                        if !first {
+                            f.write_str(";")?;
+                        }
+                        write!(f, "{param}")?;
+                        first = false;
+                    }
+                }
+                f.write_str("#{")
+            }
+            Self::Pop => f.write_str("#}"),
+        }
+    }
+}
+
+bitflags::bitflags! {
+    /// Attributes saved by [`SgrStack::Push`].
+    ///
+    /// Each flag corresponds to an XTPUSHSGR parameter.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub struct SgrStackFlags: u16 {
+        /// Parameter 1: bold.
+        const BOLD = 1;
+        /// Parameter 2: faint.
+        const FAINT = 1 << 1;
+        /// Parameter 3: italicized.
+        const ITALIC = 1 << 2;
+        /// Parameter 4: underlined.
+        const UNDERLINE = 1 << 3;
+        /// Parameter 5: blinking.
+        const BLINK = 1 << 4;
+        /// Parameter 7: inverse.
+        const INVERSE = 1 << 5;
+        /// Parameter 8: invisible.
+        const INVISIBLE = 1 << 6;
+        /// Parameter 9: crossed-out characters.
+        const STRIKETHROUGH = 1 << 7;
+        /// Parameter 21: doubly-underlined.
+        const DOUBLE_UNDERLINE = 1 << 8;
+        /// Parameter 30: foreground color.
+        const FOREGROUND = 1 << 9;
+        /// Parameter 31: background color.
+        const BACKGROUND = 1 << 10;
+    }
+}
+
+impl SgrStackFlags {
+    const PARAMS: [(Self, u8); 11] = [
+        (Self::BOLD, 1),
+        (Self::FAINT, 2),
+        (Self::ITALIC, 3),
+        (Self::UNDERLINE, 4),
+        (Self::BLINK, 5),
+        (Self::INVERSE, 7),
+        (Self::INVISIBLE, 8),
+        (Self::STRIKETHROUGH, 9),
+        (Self::DOUBLE_UNDERLINE, 21),
+        (Self::FOREGROUND, 30),
+        (Self::BACKGROUND, 31),
+    ];
+}
+
 /// A Select Graphic Rendition (`CSI ... m`) attribute update.
 ///
 /// SGR changes rendering state for text written after the sequence: color, intensity, underline,
@@ -103,6 +314,8 @@ impl Display for Csi {
 ///
 /// [SGR]: https://vt100.net/docs/vt510-rm/SGR.html
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub enum Sgr {
     /// SGR 0: reset all graphic rendition attributes to terminal defaults.
     Reset,
@@ -439,6 +652,7 @@ impl Display for Sgr {
 /// assert_eq!(Csi::Sgr(Sgr::Intensity(Intensity::Bold)).to_string(), "\x1b[1m");
 /// ```
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 // > You can use more than one Ps value to select different character attributes.
 // <https://vt100.net/docs/vt510-rm/SGR>
 pub struct SgrAttributes {
@@ -516,6 +730,7 @@ bitflags::bitflags! {
     /// These flags mirror SGR attributes that can be represented without carrying additional
     /// color or font data.
     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct SgrModifiers: u32 {
         /// No SGR modifiers.
         const NONE = 0;
@@ -600,6 +815,7 @@ impl Default for SgrModifiers {
 /// This represents either a specific [`CursorStyle`] (protocol values 0-6)
 /// or the special "follow main cursor" value (protocol value 29).
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MultiCursorShape {
     /// Use a specific cursor style for secondary cursors.
     Style(CursorStyle),
@@ -613,6 +829,7 @@ pub enum MultiCursorShape {
 /// Returned in the capability query response (`CSI > SP q`). Each variant
 /// corresponds to a protocol operation code the terminal advertises support for.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MultiCursorCapability {
     /// Block cursor shape.
     BlockShape = 1,
@@ -669,6 +886,8 @@ impl TryFrom<u8> for MultiCursorCapability {
 /// assert_eq!(Csi::Cursor(Cursor::default_position()).to_string(), "\x1b[1;1H");
 /// ```
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub enum Cursor {
     /// CBT Moves cursor to the Ps tabs backward. The default value of Ps is 1.
     BackwardTabulation(u32),
@@ -756,7 +975,8 @@ pub enum Cursor {
     RequestActivePositionReport,
 
     /// SCP - Save Cursor Position.
-    /// Only works when DECLRMM is disabled
+    /// Only works when DECLRMM is disabled. [`Esc::SaveCursor`](super::esc::Esc::SaveCursor) is
+    /// more widely supported.
     SaveCursor,
 
     /// RCP - Restore Cursor Position.
@@ -938,6 +1158,7 @@ impl Display for Cursor {
 
 /// Cursor tabulation control actions for CTC.
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CursorTabulationControl {
     /// Set a character tab stop at the active position.
     #[default]
@@ -970,6 +1191,7 @@ impl Display for CursorTabulationControl {
 
 /// Tab-stop clearing actions for TBC.
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TabulationClear {
     /// Clear the character tab stop at the active position.
     #[default]
@@ -1014,6 +1236,8 @@ impl Display for TabulationClear {
 /// );
 /// ```
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub enum Edit {
     /// DCH - DELETE CHARACTER
     /// Deletes Ps characters from the cursor position to the right. The
@@ -1147,6 +1371,7 @@ impl Display for Edit {
 
 /// Erase-in-line modes for EL.
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum EraseInLine {
     /// Erase from the active position to the end of the line.
     #[default]
@@ -1161,6 +1386,7 @@ pub enum EraseInLine {
 
 /// Erase-in-display modes for ED.
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum EraseInDisplay {
     /// the active presentation position and the character positions up to the
     /// end of the page are put into the erased state
@@ -1181,11 +1407,13 @@ pub enum EraseInDisplay {
 /// Terminal mode CSI commands.
 ///
 /// This enum covers Digital Equipment Corporation (DEC) private modes
-/// (`CSI ? ... h/l/s/r/$p`), standard modes, xterm key modifier resources, and terminal theme
-/// query/report extensions.
+/// (`CSI ? ... h/l/s/r/$p`), standard modes (`CSI ... h/l/$p`), xterm key modifier resources, and
+/// terminal theme query/report extensions.
 ///
 /// ```
-/// use termina::escape::csi::{Csi, DecPrivateMode, DecPrivateModeCode, Mode};
+/// use termina::escape::csi::{
+///     Csi, DecPrivateMode, DecPrivateModeCode, Mode, TerminalMode, TerminalModeCode,
+/// };
 ///
 /// let bracketed_paste = DecPrivateMode::Code(DecPrivateModeCode::BracketedPaste);
 /// assert_eq!(
@@ -1196,8 +1424,14 @@ pub enum EraseInDisplay {
 ///     Csi::Mode(Mode::ResetDecPrivateMode(bracketed_paste)).to_string(),
 ///     "\x1b[?2004l",
 /// );
+/// assert_eq!(
+///     Csi::Mode(Mode::QueryMode(TerminalMode::Code(TerminalModeCode::Insert))).to_string(),
+///     "\x1b[4$p",
+/// );
 /// ```
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub enum Mode {
     /// Set a DEC private mode.
     SetDecPrivateMode(DecPrivateMode),
@@ -1223,6 +1457,14 @@ pub enum Mode {
         setting: DecModeSetting,
     },
 
+    /// [DECSCPP] - select the number of columns per page.
+    ///
+    /// DEC terminals accept 80 and 132, and 0 selects 80. Unlike
+    /// [`DecPrivateModeCode::Select132Columns`] this doesn't clear the screen.
+    ///
+    /// [DECSCPP]: https://vt100.net/docs/vt510-rm/DECSCPP.html
+    SelectColumnsPerPage(u16),
+
     /// Set a standard terminal mode.
     SetMode(TerminalMode),
 
@@ -1232,7 +1474,20 @@ pub enum Mode {
     /// Query a standard terminal mode.
     QueryMode(TerminalMode),
 
+    /// Report a standard terminal mode setting.
+    ReportMode {
+        /// The standard terminal mode being reported.
+        mode: TerminalMode,
+
+        /// The current setting state for the mode.
+        setting: DecModeSetting,
+    },
+
     /// Set or query an xterm key modifier resource.
+    ///
+    /// Setting [`XtermKeyModifierResource::OtherKeys`] to 2 enables xterm's modifyOtherKeys, which
+    /// reports modified keys such as Ctrl+Enter as `CSI 27 ; modifiers ; codepoint ~`. Termina
+    /// parses these into [`crate::event::KeyEvent`]s.
     XtermKeyMode {
         /// The xterm key modifier resource.
         resource: XtermKeyModifierResource,
@@ -1245,6 +1500,8 @@ pub enum Mode {
     QueryTheme,
 
     /// Report the current terminal theme.
+    ///
+    /// Incoming reports are parsed as [`crate::Event::ThemeChanged`] rather than as a CSI event.
     ReportTheme(ThemeMode),
 }
 
@@ -1259,9 +1516,11 @@ impl Display for Mode {
             Self::ReportDecPrivateMode { mode, setting } => {
                 write!(f, "?{mode};{}$y", *setting as u8)
             }
+            Self::SelectColumnsPerPage(columns) => write!(f, "{columns}$|"),
             Self::SetMode(mode) => write!(f, "{mode}h"),
             Self::ResetMode(mode) => write!(f, "{mode}l"),
-            Self::QueryMode(mode) => write!(f, "?{mode}$p"),
+            Self::QueryMode(mode) => write!(f, "{mode}$p"),
+            Self::ReportMode { mode, setting } => write!(f, "{mode};{}$y", *setting as u8),
             Self::XtermKeyMode { resource, value } => {
                 write!(f, ">{}", *resource as u8)?;
                 if let Some(value) = value {
@@ -1282,6 +1541,7 @@ impl Display for Mode {
 /// DEC private modes are terminal-specific mode numbers encoded with `CSI ? ...` sequences. Many
 /// modern terminal emulators still use this namespace for xterm-compatible extensions.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DecPrivateMode {
     /// A known DEC private mode code.
     Code(DecPrivateModeCode),
@@ -1290,12 +1550,19 @@ pub enum DecPrivateMode {
     Unspecified(u16),
 }
 
-impl Display for DecPrivateMode {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let code = match *self {
+impl DecPrivateMode {
+    /// Returns the numeric mode code.
+    pub fn code(self) -> u16 {
+        match self {
             Self::Code(code) => code as u16,
             Self::Unspecified(code) => code,
-        };
+        }
+    }
+}
+
+impl Display for DecPrivateMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let code = self.code();
         write!(f, "{code}")
     }
 }
@@ -1305,6 +1572,8 @@ impl Display for DecPrivateMode {
 /// The DEC private-mode namespace started with DEC terminals and now also carries common
 /// xterm-compatible extensions such as mouse tracking, alternate screens, and bracketed paste.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub enum DecPrivateModeCode {
     /// Mode 1: [DECCKM] - Application Cursor Keys.
     ///
@@ -1380,6 +1649,11 @@ pub enum DecPrivateModeCode {
     /// full-screen drawing.
     ShowCursor = 25,
 
+    /// Mode 40: allow switching between 80 and 132 columns.
+    ///
+    /// xterm ignores [`Self::Select132Columns`] unless this mode is set.
+    Allow80To132Columns = 40,
+
     /// Mode 45: reverse-wrap from the left edge to the previous line.
     ///
     /// This xterm extension controls whether cursor-left from column 1 wraps to the previous line.
@@ -1550,6 +1824,7 @@ pub enum DecPrivateModeCode {
 
 /// A standard terminal mode value.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TerminalMode {
     /// A known standard terminal mode code.
     Code(TerminalModeCode),
@@ -1558,18 +1833,27 @@ pub enum TerminalMode {
     Unspecified(u16),
 }
 
-impl Display for TerminalMode {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let code = match *self {
+impl TerminalMode {
+    /// Returns the numeric mode code.
+    pub fn code(self) -> u16 {
+        match self {
             Self::Code(code) => code as u16,
             Self::Unspecified(code) => code,
-        };
+        }
+    }
+}
+
+impl Display for TerminalMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let code = self.code();
         write!(f, "{code}")
     }
 }
 
 /// Known standard terminal mode numbers.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub enum TerminalModeCode {
     /// Mode 2: [KAM] - Keyboard Action Mode.
     ///
@@ -1619,6 +1903,7 @@ pub enum TerminalModeCode {
 
 /// xterm key modifier resources addressed by `CSI > ... m`.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum XtermKeyModifierResource {
     /// Resource 0: xterm keyboard modifier keys.
     ///
@@ -1641,8 +1926,9 @@ pub enum XtermKeyModifierResource {
     OtherKeys = 4,
 }
 
-/// Reported state for a DEC private mode query.
+/// Reported state for a DEC private or standard mode query (DECRPM).
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DecModeSetting {
     /// Report value 0: the terminal does not recognize the requested mode.
     NotRecognized = 0,
@@ -1662,6 +1948,7 @@ pub enum DecModeSetting {
 
 /// Terminal theme values reported by the Contour theme extension.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ThemeMode {
     /// Report value 1: the terminal is using a dark theme.
     Dark = 1,
@@ -1670,10 +1957,38 @@ pub enum ThemeMode {
     Light = 2,
 }
 
+impl ThemeMode {
+    /// The relative luminance below which a background is considered dark.
+    ///
+    /// This is the luminance of a perceptually mid gray, `L* = 50` in CIELAB.
+    pub const DARK_LUMINANCE_THRESHOLD: f32 = 0.1842;
+
+    /// Derives the theme from the terminal's background color, for terminals which don't report
+    /// a theme themselves.
+    ///
+    /// The background color can be queried with OSC 11, see
+    /// [`DynamicColorNumber::TextBackgroundColor`](crate::escape::osc::DynamicColorNumber).
+    ///
+    /// ```
+    /// use termina::{escape::csi::ThemeMode, style::RgbColor};
+    ///
+    /// assert_eq!(ThemeMode::from_background(RgbColor::new(40, 40, 40)), ThemeMode::Dark);
+    /// assert_eq!(ThemeMode::from_background(RgbColor::new(253, 246, 227)), ThemeMode::Light);
+    /// ```
+    pub fn from_background(background: RgbColor) -> Self {
+        if background.luminance() < Self::DARK_LUMINANCE_THRESHOLD {
+            Self::Dark
+        } else {
+            Self::Light
+        }
+    }
+}
+
 // Mouse
 
 /// Mouse reports emitted by terminal mouse tracking modes.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MouseReport {
     /// An SGR 1006 mouse report using text-cell coordinates.
     ///
@@ -1809,6 +2124,7 @@ impl Display for MouseReport {
 
 /// Mouse button actions encoded in SGR mouse reports.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MouseButton {
     /// Button 1 was pressed; encoded with button value 0 and trailer `M`.
     Button1Press,
@@ -1872,6 +2188,7 @@ pub enum MouseButton {
 bitflags::bitflags! {
     /// Feature flags for the Kitty keyboard protocol.
     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct KittyKeyboardFlags: u8 {
         /// No keyboard enhancement flags.
         const NONE = 0;
@@ -1922,6 +2239,8 @@ impl Display for KittyKeyboardFlags {
 /// assert_eq!(Csi::Keyboard(command).to_string(), "\x1b[=2;2u");
 /// ```
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub enum Keyboard {
     /// Query the current values of the flags.
     QueryFlags,
@@ -1961,6 +2280,7 @@ impl Display for Keyboard {
 
 /// Controls how the flags passed in [Keyboard::SetFlags] are interpreted by the terminal.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SetKeyboardFlagsMode {
     /// Request any of the given flags and reset any flags which are not given.
     AssignAll = 1,
@@ -1990,6 +2310,8 @@ impl Display for SetKeyboardFlagsMode {
 /// );
 /// ```
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub enum Device {
     /// A device-attributes response.
     DeviceAttributes(()),
@@ -2011,7 +2333,10 @@ pub enum Device {
     /// Request terminal status.
     StatusReport,
 
-    /// Request the terminal name and version.
+    /// Request the terminal name and version (XTVERSION).
+    ///
+    /// Terminals which support the query reply with `DCS > | text ST`, which is parsed as
+    /// [`Dcs::TerminalVersion`](crate::escape::dcs::Dcs::TerminalVersion).
     ///
     /// Mintty and GNOME VTE discuss this query in [Mintty issue #881] and [GNOME VTE issue #235].
     ///
@@ -2059,6 +2384,8 @@ impl Display for Device {
 /// );
 /// ```
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub enum Window {
     /// De-iconify the window.
     DeIconify,
@@ -2126,21 +2453,64 @@ pub enum Window {
     /// Request the window state.
     ReportWindowState,
 
+    /// Report the window state.
+    ///
+    /// This is the terminal's reply to [`Window::ReportWindowState`]. It is encoded the same way
+    /// as [`Window::DeIconify`] and [`Window::Iconify`].
+    ReportWindowStateResponse {
+        /// Whether the window is iconified (minimized).
+        iconified: bool,
+    },
+
     /// Request the window position.
     ReportWindowPosition,
 
+    /// Report the window or text-area position in pixels.
+    ///
+    /// This is the terminal's reply to [`Window::ReportWindowPosition`] and
+    /// [`Window::ReportTextAreaPosition`]. It is encoded the same way as [`Window::MoveWindow`].
+    ReportWindowPositionResponse {
+        /// The reported x coordinate in pixels.
+        x: i64,
+
+        /// The reported y coordinate in pixels.
+        y: i64,
+    },
+
     /// Request the text-area position.
     ReportTextAreaPosition,
 
     /// Request the text-area size in pixels.
     ReportTextAreaSizePixels,
 
+    /// Report the text-area size in pixels.
+    ///
+    /// This is the terminal's reply to [`Window::ReportTextAreaSizePixels`].
+    ReportTextAreaSizePixelsResponse {
+        /// The reported text-area width in pixels.
+        width: Option<i64>,
+
+        /// The reported text-area height in pixels.
+        height: Option<i64>,
+    },
+
     /// Request the window size in pixels.
     ReportWindowSizePixels,
 
     /// Request the screen size in pixels.
     ReportScreenSizePixels,
 
+    /// Report the screen size in pixels.
+    ///
+    /// This is the terminal's reply to [`Window::ReportScreenSizePixels`].
+    ReportScreenSizePixelsResponse {
+        /// The reported screen width in pixels.
+        width: Option<i64>,
+
+        /// The reported screen height in pixels.
+        height: Option<i64>,
+    },
+
     /// Request the cell size in pixels.
     ReportCellSizePixels,
 
@@ -2156,9 +2526,31 @@ pub enum Window {
     /// Request the text-area size in cells.
     ReportTextAreaSizeCells,
 
+    /// Report the text-area size in cells.
+    ///
+    /// This is the terminal's reply to [`Window::ReportTextAreaSizeCells`].
+    ReportTextAreaSizeCellsResponse {
+        /// The reported text-area width in cells.
+        width: Option<i64>,
+
+        /// The reported text-area height in cells.
+        height: Option<i64>,
+    },
+
     /// Request the screen size in cells.
     ReportScreenSizeCells,
 
+    /// Report the screen size in cells.
+    ///
+    /// This is the terminal's reply to [`Window::ReportScreenSizeCells`].
+    ReportScreenSizeCellsResponse {
+        /// The reported screen width in cells.
+        width: Option<i64>,
+
+        /// The reported screen height in cells.
+        height: Option<i64>,
+    },
+
     /// Request the icon label.
     ReportIconLabel,
 
@@ -2238,17 +2630,33 @@ impl Display for Window {
             Window::ChangeToFullScreenMode => write!(f, "10;1t"),
             Window::ToggleFullScreen => write!(f, "10;2t"),
             Window::ReportWindowState => write!(f, "11t"),
+            Window::ReportWindowStateResponse { iconified } => {
+                write!(f, "{}t", if *iconified { 2 } else { 1 })
+            }
             Window::ReportWindowPosition => write!(f, "13t"),
+            Window::ReportWindowPositionResponse { x, y } => write!(f, "3;{x};{y}t"),
             Window::ReportTextAreaPosition => write!(f, "13;2t"),
             Window::ReportTextAreaSizePixels => write!(f, "14t"),
+            Window::ReportTextAreaSizePixelsResponse { width, height } => {
+                write!(f, "4;{};{}t", NumstrOrEmpty(*height), NumstrOrEmpty(*width))
+            }
             Window::ReportWindowSizePixels => write!(f, "14;2t"),
             Window::ReportScreenSizePixels => write!(f, "15t"),
+            Window::ReportScreenSizePixelsResponse { width, height } => {
+                write!(f, "5;{};{}t", NumstrOrEmpty(*height), NumstrOrEmpty(*width))
+            }
             Window::ReportCellSizePixels => write!(f, "16t"),
             Window::ReportCellSizePixelsResponse { width, height } => {
                 write!(f, "6;{};{}t", NumstrOrEmpty(*height), NumstrOrEmpty(*width))
             }
             Window::ReportTextAreaSizeCells => write!(f, "18t"),
+            Window::ReportTextAreaSizeCellsResponse { width, height } => {
+                write!(f, "8;{};{}t", NumstrOrEmpty(*height), NumstrOrEmpty(*width))
+            }
             Window::ReportScreenSizeCells => write!(f, "19t"),
+            Window::ReportScreenSizeCellsResponse { width, height } => {
+                write!(f, "9;{};{}t", NumstrOrEmpty(*height), NumstrOrEmpty(*width))
+            }
             Window::ReportIconLabel => write!(f, "20t"),
             Window::ReportWindowTitle => write!(f, "21t"),
             Window::PushIconAndWindowTitle => write!(f, "22;0t"),
@@ -2286,6 +2694,44 @@ mod test {
         DecPrivateModeCode::ClearAndEnableAlternateScreen,
     )));
 
+    #[test]
+    fn sgr_stack() {
+        use crate::{escape::dcs, Event};
+
+        assert_eq!(
+            "\x1b[#{",
+            Csi::SgrStack(SgrStack::Push(SgrStackFlags::empty())).to_string()
+        );
+        assert_eq!(
+            "\x1b[1;30;31#{",
+            Csi::SgrStack(SgrStack::Push(
+                SgrStackFlags::BOLD | SgrStackFlags::FOREGROUND | SgrStackFlags::BACKGROUND
+            ))
+            .to_string()
+        );
+        assert_eq!("\x1b[#}", Csi::SgrStack(SgrStack::Pop).to_string());
+
+        assert_eq!(
+            SgrStack::probe(),
+            "\x1b[m\x1b[1m\x1b[#{\x1b[m\x1b[#}\x1bP$qm\x1b\\\x1b[m"
+        );
+        let report = |sgrs| {
+            Event::Dcs(dcs::Dcs::Response {
+                is_request_valid: true,
+                value: dcs::DcsResponse::GraphicRendition(sgrs),
+            })
+        };
+        assert_eq!(
+            SgrStack::probe_result(&report(vec![Sgr::Reset, Sgr::Intensity(Intensity::Bold)])),
+            Some(true)
+        );
+        assert_eq!(
+            SgrStack::probe_result(&report(vec![Sgr::Reset])),
+            Some(false)
+        );
+        assert_eq!(SgrStack::probe_result(&Event::FocusIn), None);
+    }
+
     #[test]
     fn encoding() {
         // Enter the alternate screen using the mode part of CSI.
@@ -1,7 +1,8 @@
 //! Device Control String (DCS) escape sequences.
 //!
 //! Device Control String sequences are framed by [`DCS`] and [`ST`]. Termina currently models the
-//! [DECRQSS] request and [DECRPSS] response forms used for terminal state queries.
+//! [DECRQSS] request and [DECRPSS] response forms used for terminal state queries, and the
+//! XTVERSION reply carrying the terminal's name and version.
 //!
 //! # Examples
 //!
@@ -22,7 +23,7 @@ use std::fmt::{self, Display};
 use crate::style::CursorStyle;
 
 #[cfg(doc)]
-use crate::escape::csi::Sgr;
+use crate::escape::csi::{Device, Sgr};
 
 /// A Device Control String command.
 ///
@@ -30,6 +31,8 @@ use crate::escape::csi::Sgr;
 /// Formatting writes the DCS introducer, the request or response payload, and the string
 /// terminator.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub enum Dcs {
     /// Request a terminal setting with [DECRQSS] using a [`DcsRequest`] selector.
     ///
@@ -51,6 +54,10 @@ pub enum Dcs {
         /// The setting value returned by the terminal.
         value: DcsResponse,
     },
+
+    /// Report the terminal's name and version, in reply to
+    /// [`Device::RequestTerminalNameAndVersion`] (XTVERSION).
+    TerminalVersion(TerminalVersion),
 }
 
 impl Display for Dcs {
@@ -65,6 +72,8 @@ impl Display for Dcs {
                 is_request_valid,
                 value,
             } => write!(f, "{}$r{value}", if *is_request_valid { 1 } else { 0 })?,
+            // DCS > | D...D ST
+            Self::TerminalVersion(version) => write!(f, ">|{version}")?,
         }
         // ST
         f.write_str(super::ST)
@@ -78,6 +87,8 @@ impl Display for Dcs {
 ///
 /// [DECRQSS]: https://vt100.net/docs/vt510-rm/DECRQSS.html
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub enum DcsRequest {
     /// DECRQSS `$}`: request the active status display.
     ActiveStatusDisplay,
@@ -181,6 +192,8 @@ impl Display for DcsRequest {
 /// [DECRPSS]: https://vt100.net/docs/vt510-rm/DECRPSS.html
 /// [DECRQSS]: https://vt100.net/docs/vt510-rm/DECRQSS.html
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub enum DcsResponse {
     /// A DECRPSS response containing [`Sgr`] attributes.
     ///
@@ -217,6 +230,60 @@ impl Display for DcsResponse {
     }
 }
 
+/// A terminal's name and version as reported by XTVERSION.
+///
+/// Terminals format the report differently, for example `XTerm(388)`, `kitty(0.31.0)` or
+/// `tmux 3.4`. The name is the text before the parenthesis or space and the version is the rest,
+/// so the name can be matched to apply terminal-specific workarounds.
+///
+/// ```
+/// use termina::escape::dcs::TerminalVersion;
+///
+/// let version = TerminalVersion::parse("WezTerm 20240203-110809-5046fc22");
+/// assert_eq!(version.name, "WezTerm");
+/// assert_eq!(version.version.as_deref(), Some("20240203-110809-5046fc22"));
+/// assert_eq!(TerminalVersion::parse("kitty(0.31.0)").to_string(), "kitty(0.31.0)");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TerminalVersion {
+    /// The terminal's name, such as `XTerm` or `kitty`.
+    pub name: String,
+    /// The terminal's version, if the report included one.
+    pub version: Option<String>,
+}
+
+impl TerminalVersion {
+    /// Splits the text of an XTVERSION report into the name and version.
+    pub fn parse(text: &str) -> Self {
+        let text = text.trim();
+        let (name, version) = match text.split_once('(') {
+            Some((name, rest)) if rest.ends_with(')') => (name, Some(&rest[..rest.len() - 1])),
+            _ => match text.split_once(' ') {
+                Some((name, version)) => (name, Some(version)),
+                None => (text, None),
+            },
+        };
+        Self {
+            name: name.trim_end().to_owned(),
+            version: version
+                .map(str::trim)
+                .filter(|version| !version.is_empty())
+                .map(str::to_owned),
+        }
+    }
+}
+
+impl Display for TerminalVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)?;
+        match &self.version {
+            Some(version) => write!(f, "({version})"),
+            None => Ok(()),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
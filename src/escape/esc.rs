@@ -0,0 +1,115 @@
+//! Escape sequences made of ESC and a single final character.
+//!
+//! These predate the parameterized [CSI] sequences and are supported by practically every
+//! terminal. In particular [`Esc::SaveCursor`] and [`Esc::RestoreCursor`] (DECSC and DECRC) are
+//! more widely supported than the CSI `s` and `u` forms in [`Cursor`], and also save the
+//! character attributes and origin mode along with the cursor position.
+//!
+//! # Examples
+//!
+//! ```
+//! use termina::escape::esc::Esc;
+//!
+//! let status = format!("{}\x1b[24;1Hready{}", Esc::SaveCursor, Esc::RestoreCursor);
+//! assert_eq!(status, "\x1b7\x1b[24;1Hready\x1b8");
+//! ```
+//!
+//! [CSI]: super::CSI
+//! [`Cursor`]: super::csi::Cursor
+
+use std::fmt::{self, Display};
+
+/// An escape sequence made of ESC and a single final character.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum Esc {
+    /// DECSC - Save Cursor (`ESC 7`).
+    ///
+    /// Saves the cursor position, character attributes, character set and origin mode.
+    SaveCursor,
+
+    /// DECRC - Restore Cursor (`ESC 8`).
+    ///
+    /// Restores the state saved by [`Self::SaveCursor`].
+    RestoreCursor,
+
+    /// IND - Index (`ESC D`).
+    ///
+    /// Moves the cursor down one line, scrolling up at the bottom margin.
+    Index,
+
+    /// RI - Reverse Index (`ESC M`).
+    ///
+    /// Moves the cursor up one line, scrolling down at the top margin.
+    ReverseIndex,
+
+    /// NEL - Next Line (`ESC E`).
+    ///
+    /// Moves the cursor to the start of the next line, scrolling up at the bottom margin.
+    NextLine,
+
+    /// HTS - Horizontal Tab Set (`ESC H`).
+    ///
+    /// Sets a tab stop at the cursor's column.
+    HorizontalTabSet,
+
+    /// DECKPAM - Keypad Application Mode (`ESC =`).
+    ///
+    /// The keypad sends [SS3] sequences instead of the characters printed on its keys.
+    ///
+    /// [SS3]: super::SS3
+    ApplicationKeypad,
+
+    /// DECKPNM - Keypad Numeric Mode (`ESC >`).
+    ///
+    /// The keypad sends the characters printed on its keys, which is the default.
+    NumericKeypad,
+
+    /// RIS - Reset to Initial State (`ESC c`).
+    ///
+    /// Resets the terminal completely, including clearing the screen and the scrollback on many
+    /// terminals.
+    FullReset,
+}
+
+impl Esc {
+    /// Returns the character following ESC.
+    pub const fn final_char(self) -> char {
+        match self {
+            Self::SaveCursor => '7',
+            Self::RestoreCursor => '8',
+            Self::Index => 'D',
+            Self::ReverseIndex => 'M',
+            Self::NextLine => 'E',
+            Self::HorizontalTabSet => 'H',
+            Self::ApplicationKeypad => '=',
+            Self::NumericKeypad => '>',
+            Self::FullReset => 'c',
+        }
+    }
+}
+
+impl Display for Esc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "\x1b{}", self.final_char())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn encoding() {
+        assert_eq!("\x1b7", Esc::SaveCursor.to_string());
+        assert_eq!("\x1b8", Esc::RestoreCursor.to_string());
+        assert_eq!("\x1bD", Esc::Index.to_string());
+        assert_eq!("\x1bM", Esc::ReverseIndex.to_string());
+        assert_eq!("\x1bE", Esc::NextLine.to_string());
+        assert_eq!("\x1bH", Esc::HorizontalTabSet.to_string());
+        assert_eq!("\x1b=", Esc::ApplicationKeypad.to_string());
+        assert_eq!("\x1b>", Esc::NumericKeypad.to_string());
+        assert_eq!("\x1bc", Esc::FullReset.to_string());
+    }
+}
@@ -37,8 +37,13 @@ use crate::{base64, style::RgbColor};
 ///
 /// Formatting writes the OSC introducer, a command number or command letter, the command payload,
 /// and the string terminator. The numbered variants use common xterm-compatible assignments: OSC
-/// 2 sets the window title, OSC 52 manages selections, and OSC 10-19 manage dynamic colors.
+/// 2 sets the window title, OSC 52 manages selections, and OSC 10-19 manage dynamic colors. OSC 9,
+/// 777 and 99 show desktop notifications, which let a long-running application alert the user
+/// while its window is unfocused, and OSC 9;4 shows progress in the taskbar or tab. OSC 133 marks
+/// prompts and commands for shell integration and OSC 7 reports the working directory.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub enum Osc<'a> {
     /// OSC 0: set both the icon name and window title.
     SetIconNameAndWindowTitle(&'a str),
@@ -83,6 +88,82 @@ pub enum Osc<'a> {
     ///
     /// xterm defines reset commands by adding 100 to the dynamic color number.
     ResetDynamicColor(DynamicColorNumber),
+
+    /// OSC 9: show a desktop notification with the given text.
+    ///
+    /// Originally from iTerm2 and also supported by kitty, WezTerm, Windows Terminal and others.
+    /// ConEmu-derived terminals use OSC 9 with a numeric first parameter for other purposes, so
+    /// text starting with a digit followed by `;` may be misinterpreted there.
+    Notify(&'a str),
+
+    /// OSC 777: show a desktop notification with a title and body.
+    ///
+    /// This is the `notify` command from urxvt's notification extension, supported by foot,
+    /// WezTerm, Ghostty and VTE-based terminals. The title must not contain `;`.
+    NotifyWithTitle {
+        /// The notification title.
+        title: &'a str,
+        /// The notification body.
+        body: &'a str,
+    },
+
+    /// OSC 99: show a desktop notification which is reported back when the user activates it.
+    ///
+    /// This uses [kitty's desktop notification protocol], also supported by Ghostty and foot.
+    /// Activating the notification focuses the terminal window and the terminal sends
+    /// [`Self::NotificationActivated`] with the same `id`.
+    ///
+    /// [kitty's desktop notification protocol]: https://sw.kovidgoyal.net/kitty/desktop-notifications/
+    NotifyWithReport {
+        /// An identifier for matching the activation report to the notification.
+        id: u32,
+        /// The notification title.
+        title: &'a str,
+    },
+
+    /// OSC 99 report: the user activated the notification sent with
+    /// [`Self::NotifyWithReport`] with this identifier.
+    NotificationActivated(u32),
+
+    /// OSC 9 ; 4: set the progress shown in the taskbar or tab, described by [`ProgressState`].
+    ///
+    /// This is ConEmu's progress command, supported by Windows Terminal, WezTerm, Ghostty and
+    /// others. Send [`ProgressState::Hidden`] when the work finishes, since terminals keep showing
+    /// the last state otherwise.
+    Progress(ProgressState),
+
+    /// OSC 133: mark a part of a shell prompt or command for shell integration, described by
+    /// [`SemanticPrompt`].
+    ///
+    /// Terminals such as WezTerm, kitty, foot and iTerm2 use these marks to jump between prompts
+    /// in the scrollback, select a command's output and report how long a command ran.
+    SemanticPrompt(SemanticPrompt),
+
+    /// OSC 7: report the current working directory as a `file://` URL.
+    ///
+    /// Terminals use the directory to open new tabs and windows in the same place. `path` should
+    /// be absolute and use `/` separators; it is percent-encoded when formatted. `hostname` is the
+    /// machine the directory is on, which lets the terminal ignore directories reported over SSH.
+    /// It can be empty to mean the local machine.
+    ///
+    /// ```
+    /// use termina::escape::osc::Osc;
+    ///
+    /// let cwd = Osc::SetWorkingDirectory {
+    ///     hostname: "laptop",
+    ///     path: "/home/me/my files",
+    /// };
+    /// assert_eq!(
+    ///     cwd.to_string(),
+    ///     "\x1b]7;file://laptop/home/me/my%20files\x1b\\"
+    /// );
+    /// ```
+    SetWorkingDirectory {
+        /// The host name of the machine.
+        hostname: &'a str,
+        /// The absolute path of the directory.
+        path: &'a str,
+    },
     // TODO: I didn't copy many available commands yet...
 }
 
@@ -108,18 +189,142 @@ impl Display for Osc<'_> {
                 }
             }
             Self::ResetDynamicColor(color) => write!(f, "{}", 100 + *color as u8)?,
+            Self::Notify(text) => write!(f, "9;{text}")?,
+            Self::NotifyWithTitle { title, body } => write!(f, "777;notify;{title};{body}")?,
+            Self::NotifyWithReport { id, title } => write!(f, "99;i={id}:a=focus,report;{title}")?,
+            Self::NotificationActivated(id) => write!(f, "99;i={id};")?,
+            Self::Progress(state) => write!(f, "9;4;{state}")?,
+            Self::SemanticPrompt(mark) => write!(f, "133;{mark}")?,
+            Self::SetWorkingDirectory { hostname, path } => {
+                write!(f, "7;file://{hostname}")?;
+                write_percent_encoded(f, path)?
+            }
         }
         f.write_str(super::ST)?;
         Ok(())
     }
 }
 
+/// Writes `path` with every byte other than unreserved URL characters and `/` percent-encoded.
+fn write_percent_encoded(f: &mut fmt::Formatter<'_>, path: &str) -> fmt::Result {
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                write!(f, "{}", byte as char)?
+            }
+            _ => write!(f, "%{byte:02X}")?,
+        }
+    }
+    Ok(())
+}
+
+/// A progress state for [`Osc::Progress`].
+///
+/// Percentages above 100 are clamped when formatted.
+///
+/// # Examples
+///
+/// ```
+/// use termina::escape::osc::{Osc, ProgressState};
+///
+/// assert_eq!(
+///     Osc::Progress(ProgressState::Normal(40)).to_string(),
+///     "\x1b]9;4;1;40\x1b\\"
+/// );
+/// assert_eq!(
+///     Osc::Progress(ProgressState::Hidden).to_string(),
+///     "\x1b]9;4;0\x1b\\"
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ProgressState {
+    /// State 0: remove the progress indicator.
+    Hidden,
+    /// State 1: show the given percentage as ordinary progress.
+    Normal(u8),
+    /// State 2: show the given percentage in the error state, usually in red.
+    Error(u8),
+    /// State 3: show progress without a known percentage, usually as an animation.
+    Indeterminate,
+    /// State 4: show the given percentage in the paused or warning state, usually in yellow.
+    Paused(u8),
+}
+
+impl Display for ProgressState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Hidden => f.write_str("0"),
+            Self::Normal(percent) => write!(f, "1;{}", percent.min(&100)),
+            Self::Error(percent) => write!(f, "2;{}", percent.min(&100)),
+            Self::Indeterminate => f.write_str("3"),
+            Self::Paused(percent) => write!(f, "4;{}", percent.min(&100)),
+        }
+    }
+}
+
+/// A shell integration mark for [`Osc::SemanticPrompt`].
+///
+/// A shell or REPL sends the marks in order for each command: [`Self::PromptStart`] before drawing
+/// the prompt, [`Self::CommandStart`] after it where the user starts typing,
+/// [`Self::CommandExecuted`] when the command is submitted and its output begins, and
+/// [`Self::CommandFinished`] once it completes.
+///
+/// # Examples
+///
+/// ```
+/// use termina::escape::osc::{Osc, SemanticPrompt};
+///
+/// let prompt = format!(
+///     "{}$ {}",
+///     Osc::SemanticPrompt(SemanticPrompt::PromptStart),
+///     Osc::SemanticPrompt(SemanticPrompt::CommandStart),
+/// );
+/// assert_eq!(prompt, "\x1b]133;A\x1b\\$ \x1b]133;B\x1b\\");
+/// assert_eq!(
+///     Osc::SemanticPrompt(SemanticPrompt::CommandFinished(Some(1))).to_string(),
+///     "\x1b]133;D;1\x1b\\"
+/// );
+/// ```
+///
+/// # Implementation Notes
+///
+/// These are the marks from the [FinalTerm semantic prompt] proposal. Its extension options, such
+/// as `aid=` for nested shells, aren't supported.
+///
+/// [FinalTerm semantic prompt]: https://gitlab.freedesktop.org/Per_Bothner/specifications/blob/master/proposals/semantic-prompts.md
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SemanticPrompt {
+    /// `A`: the prompt starts here.
+    PromptStart,
+    /// `B`: the prompt ends and the command the user types starts here.
+    CommandStart,
+    /// `C`: the command was submitted and its output starts here.
+    CommandExecuted,
+    /// `D`: the command finished, with its exit status if known.
+    CommandFinished(Option<i32>),
+}
+
+impl Display for SemanticPrompt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PromptStart => f.write_str("A"),
+            Self::CommandStart => f.write_str("B"),
+            Self::CommandExecuted => f.write_str("C"),
+            Self::CommandFinished(None) => f.write_str("D"),
+            Self::CommandFinished(Some(status)) => write!(f, "D;{status}"),
+        }
+    }
+}
+
 bitflags::bitflags! {
     /// OSC 52 selection targets.
     ///
     /// Multiple targets can be combined. Formatting concatenates the target letters/numbers in the
     /// order expected by xterm-compatible terminals.
     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct Selection : u16 {
         /// No OSC 52 selection target.
         const NONE = 0;
@@ -212,6 +417,7 @@ impl Display for Selection {
 
 /// Dynamic color slots addressed by OSC 10-19.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum DynamicColorNumber {
     /// OSC 10: the default text foreground color used for normal cells.
@@ -278,6 +484,7 @@ impl DynamicColorNumber {
 /// assert_eq!(set.to_string(), "\x1b]10;rgb:2828/2828/2828\x1b\\");
 /// ```
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ColorOrQuery {
     /// Set the dynamic color to an RGB value.
     ///
@@ -341,4 +548,45 @@ mod test {
             .to_string()
         );
     }
+
+    #[test]
+    fn notifications() {
+        assert_eq!(Osc::Notify("done").to_string(), "\x1b]9;done\x1b\\");
+        assert_eq!(
+            Osc::NotifyWithTitle {
+                title: "build",
+                body: "finished; 0 errors"
+            }
+            .to_string(),
+            "\x1b]777;notify;build;finished; 0 errors\x1b\\"
+        );
+        assert_eq!(
+            Osc::NotifyWithReport {
+                id: 7,
+                title: "done"
+            }
+            .to_string(),
+            "\x1b]99;i=7:a=focus,report;done\x1b\\"
+        );
+    }
+
+    #[test]
+    fn progress() {
+        let progress = |state| Osc::Progress(state).to_string();
+        assert_eq!(progress(ProgressState::Error(150)), "\x1b]9;4;2;100\x1b\\");
+        assert_eq!(progress(ProgressState::Indeterminate), "\x1b]9;4;3\x1b\\");
+        assert_eq!(progress(ProgressState::Paused(5)), "\x1b]9;4;4;5\x1b\\");
+    }
+
+    #[test]
+    fn working_directory() {
+        let cwd = Osc::SetWorkingDirectory {
+            hostname: "",
+            path: "/tmp/a;b/100%/Ünï",
+        };
+        assert_eq!(
+            cwd.to_string(),
+            "\x1b]7;file:///tmp/a%3Bb/100%25/%C3%9Cn%C3%AF\x1b\\"
+        );
+    }
 }
@@ -0,0 +1,454 @@
+//! Sixel graphics, images drawn with a Device Control String.
+//!
+//! A sixel image is a DCS sequence whose payload defines a palette of color registers and then
+//! paints the image in bands six pixels tall. [`SixelEncoder`] converts an RGBA buffer into such
+//! a sequence, quantizing the colors to a palette the terminal can hold. [`SixelHeader`] and
+//! [`Sixel`] are the DCS framing for callers which already have sixel data.
+//!
+//! Where the image is drawn and where the cursor is left afterwards depend on the terminal's
+//! sixel modes; see [`SixelGuard`](crate::SixelGuard).
+//!
+//! # Examples
+//!
+//! ```
+//! use termina::escape::sixel::SixelEncoder;
+//!
+//! // A 2x1 image: one red pixel and one blue pixel.
+//! let rgba = [255, 0, 0, 255, 0, 0, 255, 255];
+//! let sixel = SixelEncoder::new().encode(&rgba, 2, 1).unwrap();
+//! assert_eq!(
+//!     sixel,
+//!     "\x1bP0;0q\"1;1;2;1#0;2;100;0;0#1;2;0;0;100#0@$#1?@\x1b\\",
+//! );
+//! ```
+//!
+//! # Implementation Notes
+//!
+//! The encoding follows the [VT330/VT340 sixel reference][sixel]: colors are defined in RGB
+//! percentages, repeated sixels are run-length encoded with `!` and each color of a band is drawn
+//! in its own pass separated by graphics carriage returns (`$`). Images with more colors than the
+//! palette allows are quantized with the [median cut] algorithm, as [libsixel] does by default.
+//!
+//! [sixel]: https://vt100.net/docs/vt3xx-gp/chapter14.html
+//! [median cut]: https://en.wikipedia.org/wiki/Median_cut
+//! [libsixel]: https://github.com/saitoha/libsixel
+
+use std::{
+    collections::HashMap,
+    fmt::{self, Display, Write as _},
+};
+
+/// How a sixel image treats pixels which are not painted with any color.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SixelBackground {
+    /// Unpainted pixels are drawn with the background color. This is `P2 = 0`.
+    #[default]
+    Opaque,
+    /// Unpainted pixels keep whatever the terminal showed before. This is `P2 = 1`.
+    Transparent,
+}
+
+/// The raster attributes (DECGRA) of a sixel image: its pixel aspect ratio and size.
+///
+/// Terminals use the size to clear the image area up front and the aspect ratio to scale the
+/// image vertically. Most images use an aspect ratio of `1:1`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RasterAttributes {
+    /// The numerator of the pixel aspect ratio, the height of a pixel.
+    pub aspect_numerator: u16,
+    /// The denominator of the pixel aspect ratio, the width of a pixel.
+    pub aspect_denominator: u16,
+    /// The width of the image in pixels.
+    pub width: u32,
+    /// The height of the image in pixels.
+    pub height: u32,
+}
+
+impl RasterAttributes {
+    /// Raster attributes for a `width` by `height` image with square pixels.
+    pub const fn new(width: u32, height: u32) -> Self {
+        Self {
+            aspect_numerator: 1,
+            aspect_denominator: 1,
+            width,
+            height,
+        }
+    }
+}
+
+impl Display for RasterAttributes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "\"{};{};{};{}",
+            self.aspect_numerator, self.aspect_denominator, self.width, self.height
+        )
+    }
+}
+
+/// The start of a sixel DCS sequence: the introducer, its parameters and the raster attributes.
+///
+/// ```
+/// use termina::escape::sixel::{RasterAttributes, SixelBackground, SixelHeader};
+///
+/// let header = SixelHeader {
+///     background: SixelBackground::Transparent,
+///     raster: Some(RasterAttributes::new(16, 12)),
+/// };
+/// assert_eq!(header.to_string(), "\x1bP0;1q\"1;1;16;12");
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SixelHeader {
+    /// How unpainted pixels are drawn.
+    pub background: SixelBackground,
+    /// The aspect ratio and size of the image, if known.
+    pub raster: Option<RasterAttributes>,
+}
+
+impl Display for SixelHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // DCS P1 ; P2 q
+        // P1 selects a pixel aspect ratio, which the raster attributes override.
+        let background = match self.background {
+            SixelBackground::Opaque => 0,
+            SixelBackground::Transparent => 1,
+        };
+        write!(f, "{}0;{background}q", super::DCS)?;
+        if let Some(raster) = self.raster {
+            write!(f, "{raster}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A complete sixel DCS sequence: the header, sixel data and the string terminator.
+///
+/// ```
+/// use termina::escape::sixel::{Sixel, SixelHeader};
+///
+/// let sixel = Sixel {
+///     header: SixelHeader::default(),
+///     data: "#0;2;0;0;0#0~~@@vv@@~~$-",
+/// };
+/// assert_eq!(sixel.to_string(), "\x1bP0;0q#0;2;0;0;0#0~~@@vv@@~~$-\x1b\\");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sixel<'a> {
+    /// The introducer and raster attributes.
+    pub header: SixelHeader,
+    /// Color definitions and sixel data.
+    pub data: &'a str,
+}
+
+impl Display for Sixel<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}{}", self.header, self.data, super::ST)
+    }
+}
+
+/// Error returned by [`SixelEncoder::encode`] when the buffer doesn't match the image size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidImageError;
+
+impl Display for InvalidImageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("RGBA buffer length does not match the image dimensions")
+    }
+}
+
+impl std::error::Error for InvalidImageError {}
+
+/// Encodes RGBA images as sixel sequences.
+///
+/// Pixels with an alpha below the [threshold](Self::alpha_threshold) are left unpainted and make
+/// the image's background [transparent](SixelBackground::Transparent). Images with more distinct
+/// colors than [`Self::max_colors`] are quantized to that many colors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SixelEncoder {
+    max_colors: u16,
+    alpha_threshold: u8,
+}
+
+impl Default for SixelEncoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SixelEncoder {
+    /// The most color registers an encoder uses, the palette size of xterm and most terminals.
+    pub const MAX_COLORS: u16 = 256;
+
+    /// Creates an encoder with a palette of [`Self::MAX_COLORS`] and an alpha threshold of 128.
+    pub const fn new() -> Self {
+        Self {
+            max_colors: Self::MAX_COLORS,
+            alpha_threshold: 128,
+        }
+    }
+
+    /// Sets the number of color registers to use, between 1 and [`Self::MAX_COLORS`].
+    ///
+    /// Terminals with fewer registers, like the VT340's 16, need a smaller palette.
+    pub fn max_colors(mut self, max_colors: u16) -> Self {
+        self.max_colors = max_colors.clamp(1, Self::MAX_COLORS);
+        self
+    }
+
+    /// Sets the alpha below which pixels are left unpainted. `0` paints every pixel.
+    pub fn alpha_threshold(mut self, alpha_threshold: u8) -> Self {
+        self.alpha_threshold = alpha_threshold;
+        self
+    }
+
+    /// Encodes a `width` by `height` image of RGBA pixels, in rows from the top left.
+    ///
+    /// Returns the complete DCS sequence, ready to write to the terminal. Fails if `rgba` isn't
+    /// exactly `width * height * 4` bytes long.
+    pub fn encode(
+        &self,
+        rgba: &[u8],
+        width: u32,
+        height: u32,
+    ) -> Result<String, InvalidImageError> {
+        let len = (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|pixels| pixels.checked_mul(4))
+            .ok_or(InvalidImageError)?;
+        if rgba.len() != len {
+            return Err(InvalidImageError);
+        }
+
+        // Count the distinct colors of the painted pixels in the order they first appear.
+        let mut colors: Vec<([u8; 3], u32)> = Vec::new();
+        let mut color_indices: HashMap<[u8; 3], usize> = HashMap::new();
+        let mut transparent = false;
+        for pixel in rgba.chunks_exact(4) {
+            if pixel[3] < self.alpha_threshold {
+                transparent = true;
+                continue;
+            }
+            let color = [pixel[0], pixel[1], pixel[2]];
+            let index = *color_indices.entry(color).or_insert_with(|| {
+                colors.push((color, 0));
+                colors.len() - 1
+            });
+            colors[index].1 += 1;
+        }
+
+        let palette = if colors.len() <= self.max_colors as usize {
+            colors.into_iter().map(|(color, _)| color).collect()
+        } else {
+            let palette = median_cut(colors, self.max_colors as usize);
+            for (color, index) in color_indices.iter_mut() {
+                *index = nearest(&palette, *color);
+            }
+            palette
+        };
+        let pixels: Vec<Option<usize>> = rgba
+            .chunks_exact(4)
+            .map(|pixel| {
+                (pixel[3] >= self.alpha_threshold)
+                    .then(|| color_indices[&[pixel[0], pixel[1], pixel[2]]])
+            })
+            .collect();
+
+        let mut data = String::new();
+        let percent = |channel: u8| (channel as u32 * 100 + 127) / 255;
+        for (index, [red, green, blue]) in palette.iter().enumerate() {
+            let _ = write!(
+                data,
+                "#{index};2;{};{};{}",
+                percent(*red),
+                percent(*green),
+                percent(*blue)
+            );
+        }
+
+        let (width, height) = (width as usize, height as usize);
+        let mut used = vec![false; palette.len()];
+        for top in (0..height).step_by(6) {
+            let band = &pixels[top * width..(top + 6).min(height) * width];
+            used.fill(false);
+            for index in band.iter().flatten() {
+                used[*index] = true;
+            }
+            let mut first = true;
+            for color in (0..palette.len()).filter(|&color| used[color]) {
+                if !first {
+                    // Return to the start of the band for the next color.
+                    data.push('$');
+                }
+                first = false;
+                let _ = write!(data, "#{color}");
+                let mut run: Option<(char, usize)> = None;
+                for x in 0..width {
+                    let bits = (0..band.len() / width)
+                        .filter(|y| band[y * width + x] == Some(color))
+                        .fold(0, |bits, y| bits | 1 << y);
+                    let sixel = char::from(0x3f + bits as u8);
+                    match &mut run {
+                        Some((ch, count)) if *ch == sixel => *count += 1,
+                        _ => {
+                            if let Some((ch, count)) = run {
+                                push_run(&mut data, ch, count);
+                            }
+                            run = Some((sixel, 1));
+                        }
+                    }
+                }
+                // Unpainted sixels at the end of the line don't need to be written.
+                if let Some((ch, count)) = run.filter(|(ch, _)| *ch != '?') {
+                    push_run(&mut data, ch, count);
+                }
+            }
+            if top + 6 < height {
+                // Move down to the next band.
+                data.push('-');
+            }
+        }
+
+        let header = SixelHeader {
+            background: if transparent {
+                SixelBackground::Transparent
+            } else {
+                SixelBackground::Opaque
+            },
+            raster: Some(RasterAttributes::new(width as u32, height as u32)),
+        };
+        Ok(Sixel {
+            header,
+            data: &data,
+        }
+        .to_string())
+    }
+}
+
+/// Writes `count` repetitions of the sixel `ch`, with a repeat introducer if that is shorter.
+fn push_run(data: &mut String, ch: char, count: usize) {
+    if count > 3 {
+        let _ = write!(data, "!{count}{ch}");
+    } else {
+        data.extend(std::iter::repeat(ch).take(count));
+    }
+}
+
+/// Reduces `colors`, with the number of pixels of each, to a palette of at most `max` colors.
+fn median_cut(colors: Vec<([u8; 3], u32)>, max: usize) -> Vec<[u8; 3]> {
+    fn widest_channel(colors: &[([u8; 3], u32)]) -> (usize, u8) {
+        (0..3)
+            .map(|channel| {
+                let (min, max) = colors.iter().fold((u8::MAX, u8::MIN), |(min, max), c| {
+                    (min.min(c.0[channel]), max.max(c.0[channel]))
+                });
+                (channel, max - min)
+            })
+            .max_by_key(|(_, range)| *range)
+            .unwrap()
+    }
+
+    let mut boxes = vec![colors];
+    while boxes.len() < max {
+        // Split the box with the widest range of colors at the median pixel.
+        let Some((index, channel, _)) = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, colors)| colors.len() > 1)
+            .map(|(index, colors)| {
+                let (channel, range) = widest_channel(colors);
+                (index, channel, range)
+            })
+            .max_by_key(|(_, _, range)| *range)
+        else {
+            break;
+        };
+        let mut colors = boxes.swap_remove(index);
+        colors.sort_unstable_by_key(|(color, _)| color[channel]);
+        let total: u64 = colors.iter().map(|(_, count)| *count as u64).sum();
+        let mut seen = 0;
+        let median = colors
+            .iter()
+            .position(|(_, count)| {
+                seen += *count as u64;
+                seen * 2 >= total
+            })
+            .unwrap_or(0);
+        let rest = colors.split_off((median + 1).clamp(1, colors.len() - 1));
+        boxes.push(colors);
+        boxes.push(rest);
+    }
+
+    boxes
+        .iter()
+        .map(|colors| {
+            let total: u64 = colors.iter().map(|(_, count)| *count as u64).sum();
+            let mut sum = [0u64; 3];
+            for (color, count) in colors {
+                for channel in 0..3 {
+                    sum[channel] += color[channel] as u64 * *count as u64;
+                }
+            }
+            sum.map(|sum| ((sum + total / 2) / total) as u8)
+        })
+        .collect()
+}
+
+/// Returns the index of the palette entry closest to `color`.
+fn nearest(palette: &[[u8; 3]], color: [u8; 3]) -> usize {
+    let distance = |entry: &[u8; 3]| -> u32 {
+        (0..3)
+            .map(|channel| (entry[channel] as i32 - color[channel] as i32).pow(2) as u32)
+            .sum()
+    };
+    (0..palette.len())
+        .min_by_key(|&index| distance(&palette[index]))
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn encode_sixel() {
+        // A 5x7 image: a red column on the left, transparent pixels and a second band.
+        let mut rgba = Vec::new();
+        for y in 0..7 {
+            for x in 0..5 {
+                let pixel = match (x, y) {
+                    (0, _) => [255, 0, 0, 255],
+                    (_, 6) => [0, 0, 255, 255],
+                    _ => [0, 0, 0, 0],
+                };
+                rgba.extend(pixel);
+            }
+        }
+        assert_eq!(
+            SixelEncoder::new().encode(&rgba, 5, 7).unwrap(),
+            "\x1bP0;1q\"1;1;5;7#0;2;100;0;0#1;2;0;0;100#0~-#0@$#1?!4@\x1b\\"
+        );
+        assert_eq!(
+            SixelEncoder::new().encode(&rgba, 5, 6),
+            Err(InvalidImageError)
+        );
+    }
+
+    #[test]
+    fn quantize_palette() {
+        // A gradient with 64 distinct colors reduced to 8.
+        let rgba: Vec<u8> = (0..64u8)
+            .flat_map(|i| [i * 4, 255 - i * 4, 128, 255])
+            .collect();
+        let sixel = SixelEncoder::new()
+            .max_colors(8)
+            .encode(&rgba, 8, 8)
+            .unwrap();
+        let registers = sixel.matches(";2;").count();
+        assert_eq!(registers, 8);
+        assert!(sixel.contains("#7;2;"));
+        assert!(!sixel.contains("#8"));
+    }
+}
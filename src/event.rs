@@ -27,29 +27,44 @@
 //! [`Parser::pop`]: crate::Parser::pop
 //! [`Terminal::read`]: crate::Terminal::read
 
+use std::{fmt, str};
+
 use crate::{
-    escape::{csi::Csi, dcs::Dcs, osc::Osc},
+    escape::{
+        csi::{Csi, Keyboard, KittyKeyboardFlags, ThemeMode},
+        dcs::{Dcs, TerminalVersion},
+        osc::Osc,
+    },
     WindowSize,
 };
 
 #[cfg(doc)]
-use crate::escape::csi::{DecPrivateModeCode, KittyKeyboardFlags};
+use crate::escape::csi::{DecPrivateModeCode, Device, Mode};
 #[cfg(doc)]
 use crate::{EventReader, Parser, Terminal};
 
+#[cfg(feature = "compat-crossterm")]
+pub mod crossterm;
 pub(crate) mod reader;
 pub(crate) mod source;
 #[cfg(feature = "event-stream")]
 pub(crate) mod stream;
 
-pub use source::PlatformWaker;
+pub use reader::{Coalesce, EventSubscription, EventTee, OverflowPolicy, QueueLimit, TeeMode};
+pub use source::{PlatformWaker, WokenUp};
 
 /// A parsed terminal input event or terminal protocol response.
 ///
 /// Values of this type are returned by [`EventReader::read`], [`Terminal::read`], and
 /// [`Parser::pop`]. See [`EventReader`] for the normal terminal-reading flow, including how
 /// filters skip events without losing them.
+///
+/// This enum is `#[non_exhaustive]`: new terminal protocols add new kinds of events, so matches
+/// need a wildcard arm. The same applies to [`KeyCode`], [`MouseEventKind`] and the escape
+/// sequence enums in [`crate::escape`].
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub enum Event {
     /// A keyboard event described by [`KeyEvent`].
     ///
@@ -66,6 +81,9 @@ pub enum Event {
     Mouse(MouseEvent),
 
     /// The terminal window was resized to the given [`WindowSize`].
+    ///
+    /// The size may be degenerate (zero rows or columns) while a window is collapsed. See
+    /// [`WindowSize::is_degenerate`].
     WindowResized(WindowSize),
 
     /// Terminal focus entered the application window.
@@ -90,6 +108,14 @@ pub enum Event {
     /// [bracketed paste mode]: https://invisible-island.net/xterm/ctlseqs/ctlseqs.html#h2-Bracketed-Paste-Mode
     Paste(String),
 
+    /// The terminal's color theme changed or was reported.
+    ///
+    /// Terminals send this after [`DecPrivateModeCode::Theme`] has enabled theme change
+    /// notifications, whenever the theme flips between dark and light. It is also the response to
+    /// [`Mode::QueryTheme`]. The report is sent as `CSI ? 997 ; 1 n` (dark) or `CSI ? 997 ; 2 n`
+    /// (light), which is encoded by [`Mode::ReportTheme`].
+    ThemeChanged(ThemeMode),
+
     /// A parsed CSI response or report described by [`Csi`].
     ///
     /// Applications see this when the terminal sends a Control Sequence Introducer response, such
@@ -101,20 +127,211 @@ pub enum Event {
     ///
     /// Applications see this when the terminal answers an Operating System Command query, such as a
     /// dynamic color query.
-    Osc(Osc<'static>),
+    ///
+    /// With the `serde` feature, OSC commands carrying borrowed strings can be serialized but not
+    /// deserialized as events. The parser never produces those commands.
+    Osc(#[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_osc"))] Osc<'static>),
 
     /// A parsed DCS response described by [`Dcs`].
     ///
     /// Applications see this when the terminal answers a Device Control String query, such as
     /// DECRQSS.
     Dcs(Dcs),
+
+    /// The payload of an Application Program Command received from the terminal.
+    ///
+    /// Holds the bytes between `ESC _` and the string terminator. Terminal extensions use APC for
+    /// responses, most commonly the [kitty graphics protocol], whose responses look like
+    /// `Gi=31;OK`. See [`crate::escape::apc`]. An `ESC _` with no input after it is reported as
+    /// Alt+`_` instead, decided like a lone ESC. See [`EventReader::set_escape_timeout`].
+    ///
+    /// [kitty graphics protocol]: https://sw.kovidgoyal.net/kitty/graphics-protocol/
+    Apc(Vec<u8>),
+
+    /// An escape sequence which the parser abandoned because it exceeded a length limit.
+    ///
+    /// Holds the bytes of the sequence up to the point where it was abandoned; the rest of the
+    /// sequence is discarded. This is a diagnostic: it means the program on the other side of the
+    /// terminal sent a sequence too long to be a real response, usually because of a bug. See
+    /// [`Parser`](crate::Parser#limits) for the limits.
+    Malformed(Vec<u8>),
+
+    /// The process was continued after being stopped, for example by the shell's `fg` after
+    /// Ctrl+Z or `PlatformTerminal::suspend`.
+    ///
+    /// The terminal may have been used by other programs and resized while the process was
+    /// stopped, so applications should redraw the whole screen at its current size. Only Unix
+    /// event readers report this, when they receive `SIGCONT`.
+    Resumed,
+
+    /// The terminal input was closed, for example because the terminal window was closed or an
+    /// SSH connection dropped.
+    ///
+    /// This is reported once, after the events parsed from the last input. The reader stops
+    /// reading the terminal afterwards, so applications should leave their event loop. Signals,
+    /// wakers and timeouts still work. Only Unix event readers report this, when reading the
+    /// terminal reaches end-of-file or fails with `EIO`.
+    Disconnected,
+
+    /// A process signal enabled with `EventReader::enable_signal`.
+    ///
+    /// Only Unix event readers report signals, and only those which were enabled.
+    Signal(Signal),
+
+    /// A frame tick from [`EventReader::set_tick_interval`].
+    ///
+    /// Ticks are generated by the reader rather than read from the terminal, so they never come
+    /// from [`Parser`]. This variant is available with the `tick` feature.
+    #[cfg(feature = "tick")]
+    Tick,
 }
 
 impl Event {
-    /// Returns `true` for CSI, OSC, and DCS protocol responses.
+    /// Returns `true` for CSI, OSC, DCS and APC protocol responses.
+    ///
+    /// This includes responses which Termina surfaces as dedicated variants, such as
+    /// [`Self::ThemeChanged`], and abandoned sequences reported as [`Self::Malformed`].
     #[inline]
     pub fn is_escape(&self) -> bool {
-        matches!(self, Self::Csi(_) | Self::Dcs(_) | Self::Osc(_))
+        matches!(
+            self,
+            Self::Csi(_)
+                | Self::Dcs(_)
+                | Self::Osc(_)
+                | Self::Apc(_)
+                | Self::ThemeChanged(_)
+                | Self::Malformed(_)
+        )
+    }
+}
+
+/// Converts a [`Event::ThemeChanged`] report, returning other events unchanged as the error.
+///
+/// This lets [`Terminal::query`] wait for the reply to [`Mode::QueryTheme`].
+impl TryFrom<Event> for ThemeMode {
+    type Error = Event;
+
+    fn try_from(event: Event) -> Result<Self, Self::Error> {
+        match event {
+            Event::ThemeChanged(mode) => Ok(mode),
+            event => Err(event),
+        }
+    }
+}
+
+/// Converts a [`Keyboard::ReportFlags`] reply, returning other events unchanged as the error.
+///
+/// This lets [`Terminal::query`] wait for the reply to [`Keyboard::QueryFlags`].
+impl TryFrom<Event> for KittyKeyboardFlags {
+    type Error = Event;
+
+    fn try_from(event: Event) -> Result<Self, Self::Error> {
+        match event {
+            Event::Csi(Csi::Keyboard(Keyboard::ReportFlags(flags))) => Ok(flags),
+            event => Err(event),
+        }
+    }
+}
+
+/// Converts a [`Dcs::TerminalVersion`] report, returning other events unchanged as the error.
+///
+/// This lets [`Terminal::query`] wait for the reply to [`Device::RequestTerminalNameAndVersion`].
+impl TryFrom<Event> for TerminalVersion {
+    type Error = Event;
+
+    fn try_from(event: Event) -> Result<Self, Self::Error> {
+        match event {
+            Event::Dcs(Dcs::TerminalVersion(version)) => Ok(version),
+            event => Err(event),
+        }
+    }
+}
+
+/// Formats the event as a one-line, human-readable description for logs and diagnostics.
+///
+/// The description never contains control characters, so it is safe to print to the terminal the
+/// event came from. Protocol responses are described by their parsed form rather than re-encoded
+/// as escape sequences. The format is not stable and should not be parsed.
+///
+/// ```
+/// use termina::event::{Event, KeyCode, KeyEvent, Modifiers};
+///
+/// let event = Event::Key(KeyEvent::new(KeyCode::Char('a'), Modifiers::CONTROL));
+/// assert_eq!(event.to_string(), "key Ctrl+Char('a')");
+/// assert_eq!(
+///     Event::Malformed(b"\x1b]52;".to_vec()).to_string(),
+///     r"malformed \x1b]52;"
+/// );
+/// ```
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Key(key) => {
+                write!(f, "key {}{:?}", DisplayModifiers(key.modifiers), key.code)?;
+                match key.kind {
+                    KeyEventKind::Press => (),
+                    KeyEventKind::Release => f.write_str(" release")?,
+                    KeyEventKind::Repeat => f.write_str(" repeat")?,
+                }
+                if !key.state.is_empty() {
+                    write!(f, " {:?}", key.state)?;
+                }
+                if let Some(text) = key.text {
+                    write!(f, " text {:?}", text.as_str())?;
+                }
+                if let Some(base_code) = key.base_code {
+                    write!(f, " base {base_code:?}")?;
+                }
+                Ok(())
+            }
+            Self::Mouse(mouse) => write!(
+                f,
+                "mouse {}{:?} at {},{}",
+                DisplayModifiers(mouse.modifiers),
+                mouse.kind,
+                mouse.column,
+                mouse.row
+            ),
+            Self::WindowResized(size) => write!(f, "resize {}x{}", size.cols, size.rows),
+            Self::FocusIn => f.write_str("focus in"),
+            Self::FocusOut => f.write_str("focus out"),
+            Self::Resumed => f.write_str("resumed"),
+            Self::Disconnected => f.write_str("disconnected"),
+            Self::Signal(signal) => write!(f, "signal {signal:?}"),
+            Self::Paste(text) => write!(f, "paste {text:?}"),
+            Self::ThemeChanged(mode) => write!(f, "theme {mode:?}"),
+            Self::Csi(csi) => write!(f, "csi {csi:?}"),
+            Self::Osc(osc) => write!(f, "osc {osc:?}"),
+            Self::Dcs(dcs) => write!(f, "dcs {dcs:?}"),
+            Self::Apc(payload) => write!(f, "apc {}", payload.escape_ascii()),
+            Self::Malformed(bytes) => write!(f, "malformed {}", bytes.escape_ascii()),
+            #[cfg(feature = "tick")]
+            Self::Tick => f.write_str("tick"),
+        }
+    }
+}
+
+/// Writes modifiers as a `Ctrl+Shift+` style prefix.
+struct DisplayModifiers(Modifiers);
+
+impl fmt::Display for DisplayModifiers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const NAMES: [(Modifiers, &str); 8] = [
+            (Modifiers::CONTROL, "Ctrl"),
+            (Modifiers::ALT, "Alt"),
+            (Modifiers::SHIFT, "Shift"),
+            (Modifiers::SUPER, "Super"),
+            (Modifiers::HYPER, "Hyper"),
+            (Modifiers::META, "Meta"),
+            (Modifiers::CAPS_LOCK, "CapsLock"),
+            (Modifiers::NUM_LOCK, "NumLock"),
+        ];
+        for (modifier, name) in NAMES {
+            if self.0.contains(modifier) {
+                write!(f, "{name}+")?;
+            }
+        }
+        Ok(())
     }
 }
 
@@ -144,6 +361,7 @@ impl Event {
 /// [crossterm key events]: https://docs.rs/crossterm/latest/crossterm/event/struct.KeyEvent.html
 /// [missing key combinations]: https://github.com/crossterm-rs/crossterm/issues/685
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct KeyEvent {
     /// The key identity.
     pub code: KeyCode,
@@ -167,6 +385,22 @@ pub struct KeyEvent {
     /// This is empty unless the input source reports state outside the ordinary modifier mask,
     /// such as keypad-originated input, Caps Lock, or Num Lock.
     pub state: KeyEventState,
+
+    /// The text this key press produced, when the terminal reports it.
+    ///
+    /// Terminals report text with [`KittyKeyboardFlags::REPORT_ASSOCIATED_TEXT`], which is only
+    /// meaningful together with [`KittyKeyboardFlags::REPORT_ALL_KEYS_AS_ESCAPE_CODES`]. It is the
+    /// text after the keyboard layout, dead keys and the input method have been applied, so it can
+    /// differ from [`Self::code`]. This is `None` for other input.
+    pub text: Option<KeyText>,
+
+    /// The key at the same position in the standard (PC-101, US) keyboard layout.
+    ///
+    /// Terminals report this with [`KittyKeyboardFlags::REPORT_ALTERNATE_KEYS`] when the active
+    /// layout differs from the standard one. Keybindings which should follow key position rather
+    /// than the character typed, so that Ctrl+Z works the same on a Cyrillic layout, can match on
+    /// `base_code.unwrap_or(code)`. This is `None` when the terminal doesn't report a base key.
+    pub base_code: Option<KeyCode>,
 }
 
 impl KeyEvent {
@@ -177,8 +411,96 @@ impl KeyEvent {
             modifiers,
             kind: KeyEventKind::Press,
             state: KeyEventState::NONE,
+            text: None,
+            base_code: None,
         }
     }
+
+    /// Returns this event in a canonical form for comparing against keybindings.
+    ///
+    /// The same key combination can arrive in different shapes depending on the terminal, the
+    /// keyboard protocol and the platform backend. Normalization folds those shapes together:
+    ///
+    /// * A C0 control character such as `Char('\x01')` becomes `Ctrl+<char>` (here Ctrl+a),
+    ///   the same way Termina's parser decodes control bytes. `Char('\t')`, `Char('\r')`,
+    ///   `Char('\x1b')` and `Char('\x7f')` become [`KeyCode::Tab`], [`KeyCode::Enter`],
+    ///   [`KeyCode::Escape`] and [`KeyCode::Backspace`].
+    /// * A letter is uppercase exactly when [`Modifiers::SHIFT`] is set, so Shift+a is
+    ///   `Char('A')` with SHIFT whether the terminal sent `A`, `a` with SHIFT, or `A` without it.
+    /// * [`KeyCode::BackTab`] always carries SHIFT.
+    /// * For characters without case, SHIFT is dropped when the character is already the shifted
+    ///   one. `flags` are the [`KittyKeyboardFlags`] in effect when the event was read and tell
+    ///   which that is: with [`KittyKeyboardFlags::REPORT_ALTERNATE_KEYS`], or without the flags
+    ///   which make terminals encode text keys as `CSI u` sequences, Shift+1 arrives as `!` and
+    ///   SHIFT is dropped. Otherwise the terminal reports the unshifted key, `1` with SHIFT, which
+    ///   is kept as is since the shifted character depends on the keyboard layout.
+    ///
+    /// Some combinations can't be recovered because the terminal never sent them. With legacy
+    /// encoding Ctrl+Shift+a is indistinguishable from Ctrl+a, for example.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use termina::{
+    ///     escape::csi::KittyKeyboardFlags,
+    ///     event::{KeyCode, KeyEvent, Modifiers},
+    /// };
+    ///
+    /// let flags = KittyKeyboardFlags::DISAMBIGUATE_ESCAPE_CODES;
+    /// let binding = KeyEvent::new(KeyCode::Char('A'), Modifiers::CONTROL | Modifiers::SHIFT);
+    /// // `CSI 97 ; 6 u` is decoded as Ctrl+Shift with the unshifted key.
+    /// let event = KeyEvent::new(KeyCode::Char('a'), Modifiers::CONTROL | Modifiers::SHIFT);
+    /// assert_eq!(event.normalize(flags), binding);
+    ///
+    /// let flags = KittyKeyboardFlags::NONE;
+    /// let event = KeyEvent::new(KeyCode::Char('\x1a'), Modifiers::NONE);
+    /// assert_eq!(
+    ///     event.normalize(flags),
+    ///     KeyEvent::new(KeyCode::Char('z'), Modifiers::CONTROL),
+    /// );
+    /// ```
+    pub fn normalize(mut self, flags: KittyKeyboardFlags) -> Self {
+        if let KeyCode::Char(c) = self.code {
+            let (code, control) = match c {
+                '\t' => (KeyCode::Tab, false),
+                '\r' => (KeyCode::Enter, false),
+                '\x1b' => (KeyCode::Escape, false),
+                '\x7f' => (KeyCode::Backspace, false),
+                '\0' => (KeyCode::Char(' '), true),
+                '\x01'..='\x1a' => (KeyCode::Char((c as u8 - 0x1 + b'a') as char), true),
+                '\x1c'..='\x1f' => (KeyCode::Char((c as u8 - 0x1C + b'4') as char), true),
+                _ => (self.code, false),
+            };
+            self.code = code;
+            if control {
+                self.modifiers |= Modifiers::CONTROL;
+            }
+        }
+
+        match self.code {
+            KeyCode::Char(c) if c.is_uppercase() => self.modifiers |= Modifiers::SHIFT,
+            KeyCode::Char(c) if c.is_lowercase() && self.modifiers.contains(Modifiers::SHIFT) => {
+                let mut upper = c.to_uppercase();
+                if let (Some(upper), None) = (upper.next(), upper.next()) {
+                    self.code = KeyCode::Char(upper);
+                }
+            }
+            KeyCode::Char(c) if c.is_lowercase() => (),
+            KeyCode::Char(_) => {
+                let reports_base_keys = flags.intersects(
+                    KittyKeyboardFlags::DISAMBIGUATE_ESCAPE_CODES
+                        | KittyKeyboardFlags::REPORT_ALL_KEYS_AS_ESCAPE_CODES,
+                ) && !flags
+                    .contains(KittyKeyboardFlags::REPORT_ALTERNATE_KEYS);
+                if !reports_base_keys {
+                    self.modifiers.remove(Modifiers::SHIFT);
+                }
+            }
+            KeyCode::BackTab => self.modifiers |= Modifiers::SHIFT,
+            _ => (),
+        }
+        self
+    }
 }
 
 impl From<KeyCode> for KeyEvent {
@@ -188,8 +510,117 @@ impl From<KeyCode> for KeyEvent {
             kind: KeyEventKind::Press,
             modifiers: Modifiers::NONE,
             state: KeyEventState::NONE,
+            text: None,
+            base_code: None,
+        }
+    }
+}
+
+/// Text associated with a [`KeyEvent`].
+///
+/// The text is stored inline so that `KeyEvent` stays `Copy`. It holds up to [`Self::CAPACITY`]
+/// bytes of UTF-8, which covers the few characters a single key press produces; longer text is
+/// truncated at a character boundary. `KeyText` dereferences to `str`.
+///
+/// # Examples
+///
+/// ```
+/// use termina::event::KeyText;
+///
+/// let text = KeyText::new("é");
+/// assert_eq!(text.as_str(), "é");
+/// assert!(text.starts_with('é'));
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyText {
+    len: u8,
+    bytes: [u8; Self::CAPACITY],
+}
+
+impl KeyText {
+    /// The most bytes of text a `KeyText` can hold.
+    pub const CAPACITY: usize = 31;
+
+    /// Creates a `KeyText` from `text`, truncating it to [`Self::CAPACITY`] bytes.
+    pub fn new(text: &str) -> Self {
+        let mut len = text.len().min(Self::CAPACITY);
+        while !text.is_char_boundary(len) {
+            len -= 1;
+        }
+        let mut bytes = [0; Self::CAPACITY];
+        bytes[..len].copy_from_slice(&text.as_bytes()[..len]);
+        Self {
+            len: len as u8,
+            bytes,
         }
     }
+
+    /// Returns the text as a string slice.
+    pub fn as_str(&self) -> &str {
+        str::from_utf8(&self.bytes[..self.len as usize]).expect("constructed from a `str`")
+    }
+}
+
+impl std::ops::Deref for KeyText {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for KeyText {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl fmt::Debug for KeyText {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for KeyText {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "serde")]
+fn deserialize_osc<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Osc<'static>, D::Error> {
+    use serde::{de::Error as _, Deserialize as _};
+
+    match Osc::deserialize(deserializer)? {
+        Osc::ClearSelection(selection) => Ok(Osc::ClearSelection(selection)),
+        Osc::QuerySelection(selection) => Ok(Osc::QuerySelection(selection)),
+        Osc::ChangeDynamicColors(number, colors) => Ok(Osc::ChangeDynamicColors(number, colors)),
+        Osc::ResetDynamicColor(number) => Ok(Osc::ResetDynamicColor(number)),
+        Osc::NotificationActivated(id) => Ok(Osc::NotificationActivated(id)),
+        Osc::Progress(state) => Ok(Osc::Progress(state)),
+        Osc::SemanticPrompt(mark) => Ok(Osc::SemanticPrompt(mark)),
+        _ => Err(D::Error::custom(
+            "OSC commands with string payloads can't be deserialized as events",
+        )),
+    }
+}
+
+// NOTE: `KeyText` is (de)serialized as a plain string rather than its inline buffer.
+#[cfg(feature = "serde")]
+impl serde::Serialize for KeyText {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for KeyText {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        Ok(Self::new(&text))
+    }
 }
 
 /// Whether a key was pressed, released, or repeated.
@@ -204,6 +635,7 @@ impl From<KeyCode> for KeyEvent {
 ///
 /// [missing key combinations]: https://github.com/crossterm-rs/crossterm/issues/685
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum KeyEventKind {
     /// A key was pressed.
     Press,
@@ -225,6 +657,7 @@ bitflags::bitflags! {
     /// Terminals vary in which modifiers they report. Treat these flags as the state Termina
     /// observed, not as proof that every unlisted physical modifier was inactive.
     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct Modifiers: u8 {
         /// No modifier keys were active.
         const NONE = 0;
@@ -266,6 +699,7 @@ bitflags::bitflags! {
     /// These flags are present only when the input source reports them. Ordinary terminal input
     /// often cannot distinguish keypad-originated keys or lock-key state.
     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct KeyEventState: u8 {
         /// No extra key state was reported.
         const NONE = 0;
@@ -283,6 +717,8 @@ bitflags::bitflags! {
 
 /// The key identity reported by the terminal.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub enum KeyCode {
     /// A Unicode character key after terminal decoding.
     ///
@@ -380,6 +816,8 @@ pub enum KeyCode {
 
 /// Physical modifier keys reported as key events.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub enum ModifierKeyCode {
     /// Left Shift key.
     LeftShift,
@@ -413,6 +851,8 @@ pub enum ModifierKeyCode {
 
 /// Media keys reported as key events.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub enum MediaKeyCode {
     /// Play media key.
     Play,
@@ -442,6 +882,25 @@ pub enum MediaKeyCode {
     MuteVolume,
 }
 
+/// A process signal which can be reported as [`Event::Signal`].
+///
+/// Applications in raw mode commonly enable these to shut down cleanly: Ctrl+C is read as a key
+/// press in raw mode, but the signals can still come from `kill`, a closed terminal window or a
+/// service manager.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum Signal {
+    /// `SIGINT`, sent by Ctrl+C in cooked mode.
+    Interrupt,
+    /// `SIGTERM`, the polite request to exit sent by `kill` and service managers.
+    Terminate,
+    /// `SIGHUP`, sent when the controlling terminal is closed.
+    Hangup,
+    /// `SIGQUIT`, sent by Ctrl+\ in cooked mode.
+    Quit,
+}
+
 /// Mouse input event with zero-based terminal cell coordinates.
 ///
 /// Terminal mouse protocols encode cell positions as one-based coordinates, but Termina converts
@@ -449,6 +908,7 @@ pub enum MediaKeyCode {
 /// existing event model. SGR pixel mouse reports are represented separately as
 /// [`crate::escape::csi::MouseReport::Sgr1016`].
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MouseEvent {
     /// The mouse action.
     pub kind: MouseEventKind,
@@ -465,6 +925,8 @@ pub struct MouseEvent {
 
 /// The mouse action reported by the terminal.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub enum MouseEventKind {
     /// A mouse button was pressed.
     Down(MouseButton),
@@ -493,6 +955,8 @@ pub enum MouseEventKind {
 
 /// Mouse buttons reported by terminal mouse tracking.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub enum MouseButton {
     /// Left mouse button.
     Left,
@@ -500,4 +964,96 @@ pub enum MouseButton {
     Right,
     /// Middle mouse button.
     Middle,
+    /// Another mouse button, identified by its X11 button number.
+    ///
+    /// xterm-compatible terminals report buttons 8 through 11 this way. Buttons 8 and 9 are
+    /// commonly the "back" and "forward" side buttons.
+    Other(u8),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Parser;
+
+    #[test]
+    fn normalize_across_encodings() {
+        fn key(input: &[u8], flags: KittyKeyboardFlags) -> KeyEvent {
+            let mut parser = Parser::default();
+            parser.parse(input, false);
+            match parser.pop() {
+                Some(Event::Key(key)) => key.normalize(flags),
+                event => panic!("expected a key event, got {event:?}"),
+            }
+        }
+        let legacy = KittyKeyboardFlags::NONE;
+        let disambiguate = KittyKeyboardFlags::DISAMBIGUATE_ESCAPE_CODES;
+        let alternate = disambiguate | KittyKeyboardFlags::REPORT_ALTERNATE_KEYS;
+
+        // Shift+a.
+        let shift_a = KeyEvent::new(KeyCode::Char('A'), Modifiers::SHIFT);
+        assert_eq!(key(b"A", legacy), shift_a);
+        assert_eq!(key(b"\x1b[97;2u", disambiguate), shift_a);
+        assert_eq!(key(b"\x1b[97:65;2u", alternate), shift_a);
+
+        // Ctrl+Shift+a.
+        let ctrl_shift_a = KeyEvent::new(KeyCode::Char('A'), Modifiers::CONTROL | Modifiers::SHIFT);
+        assert_eq!(key(b"\x1b[97;6u", disambiguate), ctrl_shift_a);
+        assert_eq!(key(b"\x1b[97:65;6u", alternate), ctrl_shift_a);
+
+        // Shift+1 on a US layout.
+        let bang = KeyEvent::new(KeyCode::Char('!'), Modifiers::NONE);
+        assert_eq!(key(b"!", legacy), bang);
+        assert_eq!(key(b"\x1b[49:33;2u", alternate), bang);
+        assert_eq!(
+            key(b"\x1b[49;2u", disambiguate),
+            KeyEvent::new(KeyCode::Char('1'), Modifiers::SHIFT)
+        );
+
+        // Control characters from a backend which reports them as characters.
+        let event = KeyEvent::new(KeyCode::Char('\x01'), Modifiers::CONTROL).normalize(legacy);
+        assert_eq!(event, KeyEvent::new(KeyCode::Char('a'), Modifiers::CONTROL));
+        let event = KeyEvent::new(KeyCode::Char('\t'), Modifiers::SHIFT).normalize(legacy);
+        assert_eq!(event.code, KeyCode::Tab);
+    }
+
+    #[test]
+    fn hyper_and_meta_are_distinct() {
+        fn modifiers(input: &[u8]) -> Modifiers {
+            let mut parser = Parser::default();
+            parser.parse(input, false);
+            match parser.pop() {
+                Some(Event::Key(key)) => key.modifiers,
+                event => panic!("expected a key event, got {event:?}"),
+            }
+        }
+        assert_eq!(modifiers(b"\x1b[97;17u"), Modifiers::HYPER);
+        assert_eq!(modifiers(b"\x1b[97;33u"), Modifiers::META);
+        assert_eq!(
+            modifiers(b"\x1b[97;49u"),
+            Modifiers::HYPER | Modifiers::META
+        );
+        assert!(!Modifiers::HYPER.intersects(Modifiers::META));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let mut parser = Parser::default();
+        // A Kitty key with associated text, an SGR mouse press, a focus report, a paste, and an
+        // OSC 11 color report.
+        parser.parse(
+            b"\x1b[97;2;65u\x1b[<0;10;5M\x1b[I\x1b[200~hi\x1b[201~\x1b]11;rgb:2828/2828/2828\x1b\\",
+            false,
+        );
+        let events: Vec<_> = std::iter::from_fn(|| parser.pop()).collect();
+        assert_eq!(events.len(), 5);
+
+        let json = serde_json::to_string(&events).unwrap();
+        let decoded: Vec<Event> = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, events);
+
+        let title = serde_json::to_string(&Event::Osc(Osc::SetWindowTitle("title"))).unwrap();
+        assert!(serde_json::from_str::<Event>(&title).is_err());
+    }
 }
@@ -0,0 +1,564 @@
+//! Conversions between Termina's and [crossterm]'s input events.
+//!
+//! Applications migrating from crossterm can switch the terminal to Termina first and keep their
+//! crossterm-based input handling for a while. Every crossterm [`Event`](CtEvent) converts into a
+//! Termina [`Event`] with [`From`]. The other direction uses [`TryFrom`] since Termina reports
+//! more than crossterm: terminal responses, signals, ticks and mouse buttons other than left,
+//! middle and right have no crossterm counterpart, and the conversion returns the Termina value as
+//! the error. Key, mouse, focus, paste and resize events all convert.
+//!
+//! [`CrosstermReader`] reads crossterm events from an [`EventReader`], mirroring crossterm's
+//! `event::poll` and `event::read`, for code which is written against those functions.
+//!
+//! This module is available with the `compat-crossterm` feature.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::io;
+//!
+//! use crossterm::event::{Event, KeyCode};
+//! use termina::{event::crossterm::CrosstermReader, PlatformTerminal, Terminal};
+//!
+//! fn main() -> io::Result<()> {
+//!     let mut terminal = PlatformTerminal::new()?;
+//!     terminal.enter_raw_mode()?;
+//!     let reader = CrosstermReader::new(terminal.event_reader());
+//!     loop {
+//!         if let Event::Key(key) = reader.read()? {
+//!             if key.code == KeyCode::Char('q') {
+//!                 break;
+//!             }
+//!         }
+//!     }
+//!     terminal.enter_cooked_mode()
+//! }
+//! ```
+//!
+//! # Implementation Notes
+//!
+//! crossterm's own `EventSource` trait is private, so crossterm's global `event::read` can't be
+//! pointed at Termina's reader. [`CrosstermReader`] offers the same calls on a value instead.
+//!
+//! Termina's [`Modifiers`] also carry the Caps Lock and Num Lock state, which crossterm only
+//! reports in [`KeyEventState`](CtKeyEventState). Converting a key event to crossterm moves them
+//! there. Termina's [`KeyEvent::text`] and [`KeyEvent::base_code`] have no crossterm counterpart
+//! and are dropped.
+//!
+//! [crossterm]: https://docs.rs/crossterm
+
+use std::{io, time::Duration};
+
+use ::crossterm::event::{
+    Event as CtEvent, KeyCode as CtKeyCode, KeyEvent as CtKeyEvent, KeyEventKind as CtKeyEventKind,
+    KeyEventState as CtKeyEventState, KeyModifiers as CtKeyModifiers,
+    MediaKeyCode as CtMediaKeyCode, ModifierKeyCode as CtModifierKeyCode,
+    MouseButton as CtMouseButton, MouseEvent as CtMouseEvent, MouseEventKind as CtMouseEventKind,
+};
+
+use crate::{EventReader, WindowSize};
+
+use super::{
+    Event, KeyCode, KeyEvent, KeyEventKind, KeyEventState, MediaKeyCode, ModifierKeyCode,
+    Modifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+
+/// Reads crossterm events from an [`EventReader`].
+///
+/// Events without a crossterm counterpart are skipped by [`Self::poll`] and [`Self::read`] and
+/// stay buffered in the reader, so they can still be read from it directly.
+#[derive(Debug, Clone)]
+pub struct CrosstermReader {
+    reader: EventReader,
+}
+
+impl CrosstermReader {
+    /// Creates a reader which reads from `reader`.
+    pub fn new(reader: EventReader) -> Self {
+        Self { reader }
+    }
+
+    /// Returns the underlying reader.
+    pub fn reader(&self) -> &EventReader {
+        &self.reader
+    }
+
+    /// Checks whether an event is available within `timeout`, like crossterm's `event::poll`.
+    pub fn poll(&self, timeout: Duration) -> io::Result<bool> {
+        self.reader.poll(Some(timeout), is_convertible)
+    }
+
+    /// Blocks until an event is available, like crossterm's `event::read`.
+    pub fn read(&self) -> io::Result<CtEvent> {
+        let event = self.reader.read(is_convertible)?;
+        Ok(CtEvent::try_from(event).expect("the filter only accepts convertible events"))
+    }
+}
+
+fn is_convertible(event: &Event) -> bool {
+    match event {
+        Event::Key(_)
+        | Event::WindowResized(_)
+        | Event::FocusIn
+        | Event::FocusOut
+        | Event::Paste(_) => true,
+        Event::Mouse(mouse) => CtMouseEventKind::try_from(mouse.kind).is_ok(),
+        _ => false,
+    }
+}
+
+/// Converts the events crossterm also reports, returning other events unchanged as the error.
+impl TryFrom<Event> for CtEvent {
+    type Error = Event;
+
+    fn try_from(event: Event) -> Result<Self, Self::Error> {
+        match event {
+            Event::Key(key) => Ok(Self::Key(key.into())),
+            Event::Mouse(mouse) => CtMouseEvent::try_from(mouse)
+                .map(Self::Mouse)
+                .map_err(Event::Mouse),
+            Event::WindowResized(size) => Ok(Self::Resize(size.cols, size.rows)),
+            Event::FocusIn => Ok(Self::FocusGained),
+            Event::FocusOut => Ok(Self::FocusLost),
+            Event::Paste(text) => Ok(Self::Paste(text)),
+            event => Err(event),
+        }
+    }
+}
+
+impl From<CtEvent> for Event {
+    fn from(event: CtEvent) -> Self {
+        match event {
+            CtEvent::FocusGained => Self::FocusIn,
+            CtEvent::FocusLost => Self::FocusOut,
+            CtEvent::Key(key) => Self::Key(key.into()),
+            CtEvent::Mouse(mouse) => Self::Mouse(mouse.into()),
+            CtEvent::Paste(text) => Self::Paste(text),
+            CtEvent::Resize(cols, rows) => Self::WindowResized(WindowSize {
+                cols,
+                rows,
+                pixel_width: None,
+                pixel_height: None,
+            }),
+        }
+    }
+}
+
+impl From<KeyEvent> for CtKeyEvent {
+    fn from(key: KeyEvent) -> Self {
+        let mut state = CtKeyEventState::from(key.state);
+        state.set(
+            CtKeyEventState::CAPS_LOCK,
+            key.modifiers.contains(Modifiers::CAPS_LOCK),
+        );
+        state.set(
+            CtKeyEventState::NUM_LOCK,
+            key.modifiers.contains(Modifiers::NUM_LOCK),
+        );
+        Self::new_with_kind_and_state(
+            key.code.into(),
+            key.modifiers.into(),
+            key.kind.into(),
+            state,
+        )
+    }
+}
+
+impl From<CtKeyEvent> for KeyEvent {
+    fn from(key: CtKeyEvent) -> Self {
+        Self {
+            code: key.code.into(),
+            kind: key.kind.into(),
+            modifiers: key.modifiers.into(),
+            state: key.state.into(),
+            text: None,
+            base_code: None,
+        }
+    }
+}
+
+impl From<KeyCode> for CtKeyCode {
+    fn from(code: KeyCode) -> Self {
+        match code {
+            KeyCode::Char(ch) => Self::Char(ch),
+            KeyCode::Enter => Self::Enter,
+            KeyCode::Backspace => Self::Backspace,
+            KeyCode::Tab => Self::Tab,
+            KeyCode::Escape => Self::Esc,
+            KeyCode::Left => Self::Left,
+            KeyCode::Right => Self::Right,
+            KeyCode::Up => Self::Up,
+            KeyCode::Down => Self::Down,
+            KeyCode::Home => Self::Home,
+            KeyCode::End => Self::End,
+            KeyCode::BackTab => Self::BackTab,
+            KeyCode::PageUp => Self::PageUp,
+            KeyCode::PageDown => Self::PageDown,
+            KeyCode::Insert => Self::Insert,
+            KeyCode::Delete => Self::Delete,
+            KeyCode::KeypadBegin => Self::KeypadBegin,
+            KeyCode::CapsLock => Self::CapsLock,
+            KeyCode::ScrollLock => Self::ScrollLock,
+            KeyCode::NumLock => Self::NumLock,
+            KeyCode::PrintScreen => Self::PrintScreen,
+            KeyCode::Pause => Self::Pause,
+            KeyCode::Menu => Self::Menu,
+            KeyCode::Null => Self::Null,
+            KeyCode::Function(n) => Self::F(n),
+            KeyCode::Modifier(code) => Self::Modifier(code.into()),
+            KeyCode::Media(code) => Self::Media(code.into()),
+        }
+    }
+}
+
+impl From<CtKeyCode> for KeyCode {
+    fn from(code: CtKeyCode) -> Self {
+        match code {
+            CtKeyCode::Char(ch) => Self::Char(ch),
+            CtKeyCode::Enter => Self::Enter,
+            CtKeyCode::Backspace => Self::Backspace,
+            CtKeyCode::Tab => Self::Tab,
+            CtKeyCode::Esc => Self::Escape,
+            CtKeyCode::Left => Self::Left,
+            CtKeyCode::Right => Self::Right,
+            CtKeyCode::Up => Self::Up,
+            CtKeyCode::Down => Self::Down,
+            CtKeyCode::Home => Self::Home,
+            CtKeyCode::End => Self::End,
+            CtKeyCode::BackTab => Self::BackTab,
+            CtKeyCode::PageUp => Self::PageUp,
+            CtKeyCode::PageDown => Self::PageDown,
+            CtKeyCode::Insert => Self::Insert,
+            CtKeyCode::Delete => Self::Delete,
+            CtKeyCode::KeypadBegin => Self::KeypadBegin,
+            CtKeyCode::CapsLock => Self::CapsLock,
+            CtKeyCode::ScrollLock => Self::ScrollLock,
+            CtKeyCode::NumLock => Self::NumLock,
+            CtKeyCode::PrintScreen => Self::PrintScreen,
+            CtKeyCode::Pause => Self::Pause,
+            CtKeyCode::Menu => Self::Menu,
+            CtKeyCode::Null => Self::Null,
+            CtKeyCode::F(n) => Self::Function(n),
+            CtKeyCode::Modifier(code) => Self::Modifier(code.into()),
+            CtKeyCode::Media(code) => Self::Media(code.into()),
+        }
+    }
+}
+
+impl From<ModifierKeyCode> for CtModifierKeyCode {
+    fn from(code: ModifierKeyCode) -> Self {
+        match code {
+            ModifierKeyCode::LeftShift => Self::LeftShift,
+            ModifierKeyCode::LeftControl => Self::LeftControl,
+            ModifierKeyCode::LeftAlt => Self::LeftAlt,
+            ModifierKeyCode::LeftSuper => Self::LeftSuper,
+            ModifierKeyCode::LeftHyper => Self::LeftHyper,
+            ModifierKeyCode::LeftMeta => Self::LeftMeta,
+            ModifierKeyCode::RightShift => Self::RightShift,
+            ModifierKeyCode::RightControl => Self::RightControl,
+            ModifierKeyCode::RightAlt => Self::RightAlt,
+            ModifierKeyCode::RightSuper => Self::RightSuper,
+            ModifierKeyCode::RightHyper => Self::RightHyper,
+            ModifierKeyCode::RightMeta => Self::RightMeta,
+            ModifierKeyCode::IsoLevel3Shift => Self::IsoLevel3Shift,
+            ModifierKeyCode::IsoLevel5Shift => Self::IsoLevel5Shift,
+        }
+    }
+}
+
+impl From<CtModifierKeyCode> for ModifierKeyCode {
+    fn from(code: CtModifierKeyCode) -> Self {
+        match code {
+            CtModifierKeyCode::LeftShift => Self::LeftShift,
+            CtModifierKeyCode::LeftControl => Self::LeftControl,
+            CtModifierKeyCode::LeftAlt => Self::LeftAlt,
+            CtModifierKeyCode::LeftSuper => Self::LeftSuper,
+            CtModifierKeyCode::LeftHyper => Self::LeftHyper,
+            CtModifierKeyCode::LeftMeta => Self::LeftMeta,
+            CtModifierKeyCode::RightShift => Self::RightShift,
+            CtModifierKeyCode::RightControl => Self::RightControl,
+            CtModifierKeyCode::RightAlt => Self::RightAlt,
+            CtModifierKeyCode::RightSuper => Self::RightSuper,
+            CtModifierKeyCode::RightHyper => Self::RightHyper,
+            CtModifierKeyCode::RightMeta => Self::RightMeta,
+            CtModifierKeyCode::IsoLevel3Shift => Self::IsoLevel3Shift,
+            CtModifierKeyCode::IsoLevel5Shift => Self::IsoLevel5Shift,
+        }
+    }
+}
+
+impl From<MediaKeyCode> for CtMediaKeyCode {
+    fn from(code: MediaKeyCode) -> Self {
+        match code {
+            MediaKeyCode::Play => Self::Play,
+            MediaKeyCode::Pause => Self::Pause,
+            MediaKeyCode::PlayPause => Self::PlayPause,
+            MediaKeyCode::Reverse => Self::Reverse,
+            MediaKeyCode::Stop => Self::Stop,
+            MediaKeyCode::FastForward => Self::FastForward,
+            MediaKeyCode::Rewind => Self::Rewind,
+            MediaKeyCode::TrackNext => Self::TrackNext,
+            MediaKeyCode::TrackPrevious => Self::TrackPrevious,
+            MediaKeyCode::Record => Self::Record,
+            MediaKeyCode::LowerVolume => Self::LowerVolume,
+            MediaKeyCode::RaiseVolume => Self::RaiseVolume,
+            MediaKeyCode::MuteVolume => Self::MuteVolume,
+        }
+    }
+}
+
+impl From<CtMediaKeyCode> for MediaKeyCode {
+    fn from(code: CtMediaKeyCode) -> Self {
+        match code {
+            CtMediaKeyCode::Play => Self::Play,
+            CtMediaKeyCode::Pause => Self::Pause,
+            CtMediaKeyCode::PlayPause => Self::PlayPause,
+            CtMediaKeyCode::Reverse => Self::Reverse,
+            CtMediaKeyCode::Stop => Self::Stop,
+            CtMediaKeyCode::FastForward => Self::FastForward,
+            CtMediaKeyCode::Rewind => Self::Rewind,
+            CtMediaKeyCode::TrackNext => Self::TrackNext,
+            CtMediaKeyCode::TrackPrevious => Self::TrackPrevious,
+            CtMediaKeyCode::Record => Self::Record,
+            CtMediaKeyCode::LowerVolume => Self::LowerVolume,
+            CtMediaKeyCode::RaiseVolume => Self::RaiseVolume,
+            CtMediaKeyCode::MuteVolume => Self::MuteVolume,
+        }
+    }
+}
+
+impl From<KeyEventKind> for CtKeyEventKind {
+    fn from(kind: KeyEventKind) -> Self {
+        match kind {
+            KeyEventKind::Press => Self::Press,
+            KeyEventKind::Release => Self::Release,
+            KeyEventKind::Repeat => Self::Repeat,
+        }
+    }
+}
+
+impl From<CtKeyEventKind> for KeyEventKind {
+    fn from(kind: CtKeyEventKind) -> Self {
+        match kind {
+            CtKeyEventKind::Press => Self::Press,
+            CtKeyEventKind::Release => Self::Release,
+            CtKeyEventKind::Repeat => Self::Repeat,
+        }
+    }
+}
+
+/// Pairs of Termina modifiers and crossterm key modifiers.
+const MODIFIERS: [(Modifiers, CtKeyModifiers); 6] = [
+    (Modifiers::SHIFT, CtKeyModifiers::SHIFT),
+    (Modifiers::CONTROL, CtKeyModifiers::CONTROL),
+    (Modifiers::ALT, CtKeyModifiers::ALT),
+    (Modifiers::SUPER, CtKeyModifiers::SUPER),
+    (Modifiers::HYPER, CtKeyModifiers::HYPER),
+    (Modifiers::META, CtKeyModifiers::META),
+];
+
+/// Converts the modifier keys, dropping [`Modifiers::CAPS_LOCK`] and [`Modifiers::NUM_LOCK`].
+impl From<Modifiers> for CtKeyModifiers {
+    fn from(modifiers: Modifiers) -> Self {
+        MODIFIERS
+            .iter()
+            .filter(|(termina, _)| modifiers.contains(*termina))
+            .fold(Self::NONE, |acc, &(_, crossterm)| acc | crossterm)
+    }
+}
+
+impl From<CtKeyModifiers> for Modifiers {
+    fn from(modifiers: CtKeyModifiers) -> Self {
+        MODIFIERS
+            .iter()
+            .filter(|(_, crossterm)| modifiers.contains(*crossterm))
+            .fold(Self::NONE, |acc, &(termina, _)| acc | termina)
+    }
+}
+
+/// Pairs of Termina and crossterm key states.
+const KEY_EVENT_STATES: [(KeyEventState, CtKeyEventState); 3] = [
+    (KeyEventState::KEYPAD, CtKeyEventState::KEYPAD),
+    (KeyEventState::CAPS_LOCK, CtKeyEventState::CAPS_LOCK),
+    (KeyEventState::NUM_LOCK, CtKeyEventState::NUM_LOCK),
+];
+
+impl From<KeyEventState> for CtKeyEventState {
+    fn from(state: KeyEventState) -> Self {
+        KEY_EVENT_STATES
+            .iter()
+            .filter(|(termina, _)| state.contains(*termina))
+            .fold(Self::NONE, |acc, &(_, crossterm)| acc | crossterm)
+    }
+}
+
+impl From<CtKeyEventState> for KeyEventState {
+    fn from(state: CtKeyEventState) -> Self {
+        KEY_EVENT_STATES
+            .iter()
+            .filter(|(_, crossterm)| state.contains(*crossterm))
+            .fold(Self::NONE, |acc, &(termina, _)| acc | termina)
+    }
+}
+
+/// Converts mouse events with a left, middle or right button, returning others unchanged as the
+/// error.
+impl TryFrom<MouseEvent> for CtMouseEvent {
+    type Error = MouseEvent;
+
+    fn try_from(mouse: MouseEvent) -> Result<Self, Self::Error> {
+        Ok(Self {
+            kind: mouse.kind.try_into().map_err(|_| mouse)?,
+            column: mouse.column,
+            row: mouse.row,
+            modifiers: mouse.modifiers.into(),
+        })
+    }
+}
+
+impl From<CtMouseEvent> for MouseEvent {
+    fn from(mouse: CtMouseEvent) -> Self {
+        Self {
+            kind: mouse.kind.into(),
+            column: mouse.column,
+            row: mouse.row,
+            modifiers: mouse.modifiers.into(),
+        }
+    }
+}
+
+/// Converts mouse actions with a left, middle or right button, returning others unchanged as the
+/// error.
+impl TryFrom<MouseEventKind> for CtMouseEventKind {
+    type Error = MouseEventKind;
+
+    fn try_from(kind: MouseEventKind) -> Result<Self, Self::Error> {
+        let button = |button: MouseButton| CtMouseButton::try_from(button).map_err(|_| kind);
+        Ok(match kind {
+            MouseEventKind::Down(b) => Self::Down(button(b)?),
+            MouseEventKind::Up(b) => Self::Up(button(b)?),
+            MouseEventKind::Drag(b) => Self::Drag(button(b)?),
+            MouseEventKind::Moved => Self::Moved,
+            MouseEventKind::ScrollDown => Self::ScrollDown,
+            MouseEventKind::ScrollUp => Self::ScrollUp,
+            MouseEventKind::ScrollLeft => Self::ScrollLeft,
+            MouseEventKind::ScrollRight => Self::ScrollRight,
+        })
+    }
+}
+
+impl From<CtMouseEventKind> for MouseEventKind {
+    fn from(kind: CtMouseEventKind) -> Self {
+        match kind {
+            CtMouseEventKind::Down(button) => Self::Down(button.into()),
+            CtMouseEventKind::Up(button) => Self::Up(button.into()),
+            CtMouseEventKind::Drag(button) => Self::Drag(button.into()),
+            CtMouseEventKind::Moved => Self::Moved,
+            CtMouseEventKind::ScrollDown => Self::ScrollDown,
+            CtMouseEventKind::ScrollUp => Self::ScrollUp,
+            CtMouseEventKind::ScrollLeft => Self::ScrollLeft,
+            CtMouseEventKind::ScrollRight => Self::ScrollRight,
+        }
+    }
+}
+
+/// Converts the left, middle and right buttons, returning others unchanged as the error.
+impl TryFrom<MouseButton> for CtMouseButton {
+    type Error = MouseButton;
+
+    fn try_from(button: MouseButton) -> Result<Self, Self::Error> {
+        match button {
+            MouseButton::Left => Ok(Self::Left),
+            MouseButton::Right => Ok(Self::Right),
+            MouseButton::Middle => Ok(Self::Middle),
+            button => Err(button),
+        }
+    }
+}
+
+impl From<CtMouseButton> for MouseButton {
+    fn from(button: CtMouseButton) -> Self {
+        match button {
+            CtMouseButton::Left => Self::Left,
+            CtMouseButton::Right => Self::Right,
+            CtMouseButton::Middle => Self::Middle,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let events = [
+            Event::Key(KeyEvent::new(
+                KeyCode::Char('A'),
+                Modifiers::SHIFT | Modifiers::CONTROL,
+            )),
+            Event::Key(KeyEvent {
+                kind: KeyEventKind::Release,
+                state: KeyEventState::KEYPAD,
+                ..KeyEvent::new(KeyCode::Function(5), Modifiers::ALT | Modifiers::META)
+            }),
+            Event::Key(KeyCode::Modifier(ModifierKeyCode::IsoLevel3Shift).into()),
+            Event::Key(KeyCode::Media(MediaKeyCode::PlayPause).into()),
+            Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Drag(MouseButton::Middle),
+                column: 3,
+                row: 7,
+                modifiers: Modifiers::SUPER,
+            }),
+            Event::WindowResized(crate::test_size(80, 24)),
+            Event::FocusIn,
+            Event::Paste("pasted".to_owned()),
+        ];
+        for event in events {
+            let crossterm = CtEvent::try_from(event.clone()).unwrap();
+            assert_eq!(Event::from(crossterm), event);
+        }
+
+        // Modifier bits are laid out differently.
+        let key = CtKeyEvent::from(KeyEvent::new(KeyCode::Escape, Modifiers::CONTROL));
+        assert_eq!(key.code, CtKeyCode::Esc);
+        assert_eq!(key.modifiers, CtKeyModifiers::CONTROL);
+        // Lock keys are key state in crossterm.
+        let key = CtKeyEvent::from(KeyEvent::new(KeyCode::Enter, Modifiers::CAPS_LOCK));
+        assert_eq!(key.modifiers, CtKeyModifiers::NONE);
+        assert_eq!(key.state, CtKeyEventState::CAPS_LOCK);
+    }
+
+    #[test]
+    fn unconvertible_events() {
+        let back = Event::Mouse(MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Other(8)),
+            column: 0,
+            row: 0,
+            modifiers: Modifiers::NONE,
+        });
+        assert_eq!(CtEvent::try_from(back.clone()), Err(back));
+        assert_eq!(CtEvent::try_from(Event::Resumed), Err(Event::Resumed));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn reader_skips_unconvertible_events() {
+        use std::io::Write as _;
+
+        use crate::event::reader::test_reader;
+
+        let (reader, mut input) = test_reader();
+        // A device attributes reply, an SGR mouse press of button 8 and a key press.
+        input.write_all(b"\x1b[?62c\x1b[<128;1;1Mq").unwrap();
+        let crossterm = CrosstermReader::new(reader.clone());
+        assert!(crossterm.poll(Duration::from_secs(1)).unwrap());
+        assert_eq!(
+            crossterm.read().unwrap(),
+            CtEvent::Key(CtKeyEvent::new(CtKeyCode::Char('q'), CtKeyModifiers::NONE))
+        );
+        // The skipped events are still buffered.
+        assert!(matches!(reader.read(|_| true).unwrap(), Event::Csi(_)));
+        assert!(matches!(reader.read(|_| true).unwrap(), Event::Mouse(_)));
+    }
+}
@@ -15,13 +15,26 @@
 //! [crossterm's event reader]: https://docs.rs/crossterm/latest/crossterm/event/index.html
 //! [`Terminal`]: crate::Terminal
 
-use std::{collections::VecDeque, io, sync::Arc, time::Duration};
+use std::{
+    collections::VecDeque,
+    fmt, io,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc,
+    },
+    time::{Duration, Instant},
+};
 
 use parking_lot::Mutex;
 
+use crate::{
+    parse::{ParserStats, RawTap},
+    WindowSize,
+};
+
 use super::{
-    source::{EventSource as _, PlatformEventSource, PlatformWaker, PollTimeout},
-    Event,
+    source::{EventSource as _, PlatformEventSource, PlatformWaker, PollTimeout, WokenUp},
+    Event, MouseEventKind,
 };
 
 /// A reader of events from the terminal's input handle.
@@ -88,19 +101,38 @@ use super::{
 #[derive(Debug, Clone)]
 pub struct EventReader {
     shared: Arc<Mutex<Shared>>,
+    /// The number of buffered events, published by `Shared::publish_len`.
+    len: Arc<AtomicUsize>,
+    /// The latest window size read from the terminal, shared with `Shared::record_size`.
+    size: Arc<Mutex<Option<WindowSize>>>,
     waker: PlatformWaker,
 }
 
 impl EventReader {
     pub(crate) fn new(source: PlatformEventSource) -> Self {
         let waker = source.waker();
+        let len = Arc::new(AtomicUsize::new(0));
+        let size = Arc::new(Mutex::new(None));
         let shared = Shared {
             events: VecDeque::with_capacity(32),
             source,
             skipped_events: Vec::with_capacity(32),
+            tees: Vec::new(),
+            recording: None,
+            replay: VecDeque::new(),
+            limit: None,
+            coalesce: Coalesce::NONE,
+            resize_debounce: None,
+            pending_resize: None,
+            len: len.clone(),
+            size: size.clone(),
+            #[cfg(feature = "tick")]
+            ticker: None,
         };
         Self {
             shared: Arc::new(Mutex::new(shared)),
+            len,
+            size,
             waker,
         }
     }
@@ -111,16 +143,20 @@ impl EventReader {
     /// The waker is cheap to clone and can be freely moved to another thread. Calling its
     /// `wake` method does not require the calling thread to hold the [`EventReader`]'s internal
     /// lock, so it can be called while a [`read`](Self::read) or [`poll`](Self::poll) call is
-    /// blocked on another thread or clone of this reader. A woken [`read`](Self::read) call
-    /// returns `Err` with [`io::ErrorKind::Interrupted`].
+    /// blocked on another thread or clone of this reader. A woken [`poll`](Self::poll) call returns
+    /// `Ok(false)` and a woken [`read`](Self::read) call returns an error for which
+    /// [`WokenUp::matches`] is `true`. A wake-up while no call is blocked is observed by the next
+    /// call which waits for input.
     pub fn waker(&self) -> PlatformWaker {
         self.waker.clone()
     }
 
     /// Polls for availability of an event matching `filter`.
     ///
-    /// When `timeout` is `None`, this call blocks indefinitely. Events rejected by `filter` are
-    /// retained so a later call can still return them. Use the same filter with [`Self::read`] if
+    /// When `timeout` is `None`, this call blocks indefinitely. It returns `Ok(false)` if no
+    /// matching event arrived before the timeout elapsed or if [`Self::waker`]'s `wake` was
+    /// called while waiting. Events rejected by `filter` are retained so a later call can still
+    /// return them. Use the same filter with [`Self::read`] if
     /// the follow-up read should consume the event that made this method return `true`.
     pub fn poll<F>(&self, timeout: Option<Duration>, filter: F) -> io::Result<bool>
     where
@@ -136,7 +172,668 @@ impl EventReader {
         } else {
             (self.shared.lock(), None)
         };
-        reader.poll(timeout, filter)
+        let result = reader.poll(timeout, filter);
+        reader.publish_len();
+        result
+    }
+
+    /// Reads an event matching `filter`, waiting at most `timeout` for one to arrive.
+    ///
+    /// Returns `Ok(None)` if no matching event arrived before the timeout elapsed or if
+    /// [`Self::waker`]'s `wake` was called while waiting. Unlike calling [`Self::poll`] followed
+    /// by [`Self::read`], the wait and the removal of the event happen under one acquisition of
+    /// the reader's lock, so another clone of this reader can't take the event in between.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::{io, time::Duration};
+    ///
+    /// use termina::{escape::csi::Csi, Event, PlatformTerminal, Terminal};
+    ///
+    /// fn main() -> io::Result<()> {
+    ///     let reader = PlatformTerminal::new()?.event_reader();
+    ///     let response = reader.read_timeout(
+    ///         |event| matches!(event, Event::Csi(Csi::Keyboard(_))),
+    ///         Duration::from_millis(100),
+    ///     )?;
+    ///     println!("kitty keyboard supported: {}", response.is_some());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn read_timeout<F>(&self, mut filter: F, timeout: Duration) -> io::Result<Option<Event>>
+    where
+        F: FnMut(&Event) -> bool,
+    {
+        let poll_timeout = PollTimeout::new(Some(timeout));
+        let Some(mut reader) = self.shared.try_lock_for(timeout) else {
+            return Ok(None);
+        };
+        let result = match reader.poll(poll_timeout.leftover(), &mut filter) {
+            Ok(true) => reader.read(filter).map(Some),
+            Ok(false) => Ok(None),
+            Err(err) => Err(err),
+        };
+        reader.publish_len();
+        result
+    }
+
+    /// Reads an event matching `filter` if one is available, without blocking.
+    ///
+    /// This is the non-blocking drain used to integrate the reader with an external event loop:
+    /// when one of the descriptors from [`Self::try_clone_pollable_fds`] becomes readable, call
+    /// this method until it returns `Ok(None)`. It also returns `Ok(None)` if another clone of
+    /// this reader is currently reading, since that call will receive the input.
+    pub fn try_read<F>(&self, filter: F) -> io::Result<Option<Event>>
+    where
+        F: FnMut(&Event) -> bool,
+    {
+        self.read_timeout(filter, Duration::ZERO)
+    }
+
+    /// Starts recording the events returned by [`Self::read`].
+    ///
+    /// This is the building block for keyboard macros: start recording, let the application read
+    /// and handle input as usual, then call [`Self::stop_recording`] to get the events the
+    /// application consumed, in the order it consumed them. Events rejected by read filters are
+    /// not recorded until they are read, and events delivered by [`Self::replay`] are never
+    /// recorded. Starting a recording discards any recording in progress.
+    pub fn start_recording(&self) {
+        self.shared.lock().recording = Some(Vec::new());
+    }
+
+    /// Returns `true` if a recording started by [`Self::start_recording`] is in progress.
+    pub fn is_recording(&self) -> bool {
+        self.shared.lock().recording.is_some()
+    }
+
+    /// Stops recording and returns the recorded events.
+    ///
+    /// Returns an empty `Vec` if no recording was in progress.
+    pub fn stop_recording(&self) -> Vec<Event> {
+        self.shared.lock().recording.take().unwrap_or_default()
+    }
+
+    /// Queues `events` to be returned by [`Self::poll`] and [`Self::read`] before any live input.
+    ///
+    /// Replayed events are delivered in order and ahead of terminal input which is already
+    /// buffered or arrives later, so a replayed macro isn't interleaved with keys typed while it
+    /// runs. Replaying while earlier replayed events are still queued delivers the new events
+    /// first, which gives nested macros the expected order. Filters apply to replayed events as
+    /// they do to live input.
+    ///
+    /// This takes the reader's lock. If another thread is blocked in [`Self::read`] it must be
+    /// woken with [`Self::waker`] first.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::io;
+    ///
+    /// use termina::{
+    ///     event::{KeyCode, KeyEventKind},
+    ///     Event, PlatformTerminal, Terminal,
+    /// };
+    ///
+    /// fn main() -> io::Result<()> {
+    ///     let reader = PlatformTerminal::new()?.event_reader();
+    ///     let is_press = |event: &Event| {
+    ///         matches!(event, Event::Key(key) if key.kind == KeyEventKind::Press)
+    ///     };
+    ///
+    ///     reader.start_recording();
+    ///     loop {
+    ///         let event = reader.read(is_press)?;
+    ///         if matches!(event, Event::Key(key) if key.code == KeyCode::Escape) {
+    ///             break;
+    ///         }
+    ///     }
+    ///     let recorded = reader.stop_recording();
+    ///
+    ///     reader.replay(recorded);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn replay<I>(&self, events: I)
+    where
+        I: IntoIterator<Item = Event>,
+    {
+        let mut shared = self.shared.lock();
+        let events: Vec<_> = events.into_iter().collect();
+        for event in events.into_iter().rev() {
+            shared.replay.push_front(event);
+        }
+        shared.publish_len();
+    }
+
+    /// Queues `events` as if they had been read from the terminal, after any buffered input.
+    ///
+    /// The events are handed to the source without taking the reader's lock, so a read blocked on
+    /// another thread returns them. A resize among them is cached for
+    /// [`Self::cached_size_or`] right away.
+    #[cfg(unix)]
+    pub(crate) fn push_events<I>(&self, events: I)
+    where
+        I: IntoIterator<Item = Event>,
+    {
+        let events: Vec<_> = events.into_iter().collect();
+        if let Some(size) = events.iter().rev().find_map(|event| match event {
+            Event::WindowResized(size) => Some(*size),
+            _ => None,
+        }) {
+            *self.size.lock() = (!size.is_degenerate()).then_some(size);
+        }
+        let count = events.len();
+        self.waker.push_events(events);
+        self.len.fetch_add(count, Ordering::Relaxed);
+    }
+
+    /// Sets the size [`Event::WindowResized`] reports if the terminal's window size can't be read.
+    #[cfg(unix)]
+    pub(crate) fn set_fallback_size(&self, size: Option<WindowSize>) {
+        self.shared.lock().source.set_fallback_size(size);
+    }
+
+    /// Stops reading terminal input until [`Self::resume`] is called.
+    ///
+    /// This lets something else read the terminal for a while, such as a child process or a
+    /// password prompt, without tearing the terminal down. It applies to every clone of this
+    /// reader, including one which is currently blocked in [`Self::poll`] or [`Self::read`], for
+    /// example the helper thread of an `EventStream`: the blocked call keeps waiting but no longer
+    /// reads input. Buffered events, signals such as resizes, ticks and wake-ups are still
+    /// delivered while input is paused.
+    ///
+    /// This doesn't take the reader's lock. Pausing while already paused does nothing. See
+    /// [`Terminal::release`](crate::Terminal::release) to also return the terminal to cooked mode.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::io::{self, BufRead as _};
+    ///
+    /// use termina::{PlatformTerminal, Terminal};
+    ///
+    /// fn main() -> io::Result<()> {
+    ///     let terminal = PlatformTerminal::new()?;
+    ///     let reader = terminal.event_reader();
+    ///     reader.pause()?;
+    ///     let mut line = String::new();
+    ///     io::stdin().lock().read_line(&mut line)?;
+    ///     reader.resume()
+    /// }
+    /// ```
+    pub fn pause(&self) -> io::Result<()> {
+        self.waker.set_input_paused(true)
+    }
+
+    /// Resumes reading terminal input after [`Self::pause`].
+    ///
+    /// Input which arrived while reading was paused and which nothing else read is delivered.
+    pub fn resume(&self) -> io::Result<()> {
+        self.waker.set_input_paused(false)
+    }
+
+    /// Returns `true` between [`Self::pause`] and [`Self::resume`].
+    pub fn is_paused(&self) -> bool {
+        self.waker.is_input_paused()
+    }
+
+    /// Discards buffered events, including replayed ones, and any partially parsed input.
+    ///
+    /// Recordings, tees and subscriptions are kept.
+    pub(crate) fn clear(&self) {
+        let mut shared = self.shared.lock();
+        shared.events.clear();
+        shared.skipped_events.clear();
+        shared.replay.clear();
+        shared.pending_resize = None;
+        shared.source.clear();
+        shared.publish_len();
+        *self.size.lock() = None;
+    }
+
+    /// Returns the latest window size read from the terminal, or stores and returns the size from
+    /// `get_dimensions` if none is known. See [`Terminal::dimensions_cached`].
+    ///
+    /// [`Terminal::dimensions_cached`]: crate::Terminal::dimensions_cached
+    pub(crate) fn cached_size_or<F>(&self, get_dimensions: F) -> io::Result<WindowSize>
+    where
+        F: FnOnce() -> io::Result<WindowSize>,
+    {
+        if let Some(size) = *self.size.lock() {
+            return Ok(size);
+        }
+        // The lock isn't held while asking, so that reading events isn't blocked on it. A resize
+        // read meanwhile is newer and is kept.
+        let dimensions = get_dimensions()?;
+        Ok(*self.size.lock().get_or_insert(dimensions))
+    }
+
+    /// Creates a tee which receives events matching `filter` as they are read from the terminal.
+    ///
+    /// A tee sees events before any [`Self::poll`] or [`Self::read`] filter does, so it observes
+    /// replies even when another part of the application (for example a query helper waiting for a
+    /// specific response) reads them with a narrow filter. `mode` controls whether matched events
+    /// remain available to ordinary reads ([`TeeMode::Copy`]) or are routed only to the tee
+    /// ([`TeeMode::Consume`]). When several tees match an event they each receive a copy.
+    ///
+    /// Only events read from the terminal after the tee is created are observed. The tee is
+    /// removed when the returned [`EventTee`] is dropped.
+    ///
+    /// # Examples
+    ///
+    /// Log every CSI response while leaving it available to the rest of the application:
+    ///
+    /// ```no_run
+    /// use std::io;
+    ///
+    /// use termina::{event::TeeMode, Event, PlatformTerminal, Terminal};
+    ///
+    /// fn main() -> io::Result<()> {
+    ///     let reader = PlatformTerminal::new()?.event_reader();
+    ///     let tee = reader.tee(TeeMode::Copy, |event| matches!(event, Event::Csi(_)));
+    ///     let _event = reader.read(|_| true)?;
+    ///     for csi in tee.try_iter() {
+    ///         eprintln!("CSI: {csi:?}");
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn tee<F>(&self, mode: TeeMode, filter: F) -> EventTee
+    where
+        F: FnMut(&Event) -> bool + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel();
+        self.shared.lock().tees.push(Tee {
+            mode,
+            filter: Box::new(filter),
+            sender,
+        });
+        EventTee { receiver }
+    }
+
+    /// Creates a subscription which receives every event matching `filter` in its own queue.
+    ///
+    /// Events matched by one or more subscriptions are delivered to each matching subscription and
+    /// are not delivered to [`Self::poll`] or [`Self::read`]. Unlike an [`EventTee`], a
+    /// subscription can wait for events itself: [`EventSubscription::read`] reads from the
+    /// terminal when no other caller is, and otherwise waits for the reading caller to route
+    /// events to it. This lets independent parts of an application (a resize handler on one
+    /// thread, key handling on another) each consume their own events without filtering each
+    /// other's events out of a single shared queue.
+    ///
+    /// Only events read from the terminal after the subscription is created are observed. The
+    /// subscription is removed when the returned [`EventSubscription`] is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::{io, thread};
+    ///
+    /// use termina::{Event, PlatformTerminal, Terminal};
+    ///
+    /// fn main() -> io::Result<()> {
+    ///     let reader = PlatformTerminal::new()?.event_reader();
+    ///     let resizes = reader.subscribe(|event| matches!(event, Event::WindowResized(_)));
+    ///     thread::spawn(move || {
+    ///         while let Ok(Event::WindowResized(size)) = resizes.read() {
+    ///             eprintln!("resized to {}x{}", size.cols, size.rows);
+    ///         }
+    ///     });
+    ///     loop {
+    ///         if let Event::Key(key) = reader.read(|event| matches!(event, Event::Key(_)))? {
+    ///             eprintln!("key {:?}", key.code);
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn subscribe<F>(&self, filter: F) -> EventSubscription
+    where
+        F: FnMut(&Event) -> bool + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel();
+        self.shared.lock().tees.push(Tee {
+            mode: TeeMode::Consume,
+            filter: Box::new(filter),
+            sender,
+        });
+        EventSubscription {
+            reader: self.clone(),
+            receiver,
+        }
+    }
+
+    /// Limits the number of events buffered for [`Self::poll`] and [`Self::read`].
+    ///
+    /// Events accumulate in the reader when the application stops reading for a while (during a
+    /// long render, for example) or reads with a narrow filter while other input keeps arriving.
+    /// With mouse tracking modes such as [`DecPrivateModeCode::AnyEventMouse`] enabled, motion
+    /// events can fill the queue quickly. When the queue would grow beyond `limit.capacity`
+    /// events, `limit.policy` decides what happens. Pass `None` to remove the limit, which is the
+    /// default.
+    ///
+    /// The limit applies to events buffered by the reader. Events held by tees, subscriptions and
+    /// [`Self::replay`] are not counted.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::io;
+    ///
+    /// use termina::{
+    ///     event::{OverflowPolicy, QueueLimit},
+    ///     PlatformTerminal, Terminal,
+    /// };
+    ///
+    /// fn main() -> io::Result<()> {
+    ///     let reader = PlatformTerminal::new()?.event_reader();
+    ///     reader.set_queue_limit(Some(QueueLimit {
+    ///         capacity: 1024,
+    ///         policy: OverflowPolicy::CoalesceMouseMoves,
+    ///     }));
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// [`DecPrivateModeCode::AnyEventMouse`]: crate::escape::csi::DecPrivateModeCode::AnyEventMouse
+    pub fn set_queue_limit(&self, limit: Option<QueueLimit>) {
+        let mut shared = self.shared.lock();
+        shared.limit = limit;
+        // Apply a reduced capacity right away rather than on the next buffered event. There is no
+        // read to fail with `OverflowPolicy::Error`, so its newest events are just dropped.
+        while shared.is_over_limit() {
+            let _ = shared.evict();
+        }
+        shared.publish_len();
+    }
+
+    /// Sets which kinds of high-rate events the reader merges.
+    ///
+    /// Mouse motion with [`DecPrivateModeCode::AnyEventMouse`] and resizes while a window is
+    /// dragged can produce events faster than an application renders. With coalescing enabled the
+    /// reader merges a run of such events into the latest one: when a matching event is read, any
+    /// events of the same kind which are already available are read too and only the last is
+    /// returned, and buffered events are merged as they are queued. See [`Coalesce`] for the
+    /// merging rules. Coalescing is disabled by default.
+    ///
+    /// Tees and subscriptions see every event, since they observe events before coalescing.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::io;
+    ///
+    /// use termina::{event::Coalesce, PlatformTerminal, Terminal};
+    ///
+    /// fn main() -> io::Result<()> {
+    ///     let reader = PlatformTerminal::new()?.event_reader();
+    ///     reader.set_coalescing(Coalesce::MOUSE_MOVED | Coalesce::RESIZE);
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// [`DecPrivateModeCode::AnyEventMouse`]: crate::escape::csi::DecPrivateModeCode::AnyEventMouse
+    pub fn set_coalescing(&self, coalesce: Coalesce) {
+        self.shared.lock().coalesce = coalesce;
+    }
+
+    /// Sets how long a lone ESC byte waits for the rest of an escape sequence before it is reported
+    /// as the Escape key, or `None` to decide as soon as the input read so far ends, which is the
+    /// default.
+    ///
+    /// The Escape key sends the same byte that starts escape sequences such as the arrow keys. By
+    /// default input which ends with ESC is taken to be the Escape key, which is right on a local
+    /// terminal but can split a sequence sent over a slow link such as SSH into Escape followed by
+    /// stray keys. Like Vim's `ttimeoutlen`, a timeout waits for the next bytes instead: bytes
+    /// which arrive in time continue the sequence, and otherwise the Escape key is reported once
+    /// the timeout elapses. A few tens of milliseconds is usually enough. Escape key presses, and
+    /// any events which arrive meanwhile, are delayed by up to the timeout. A wakeup or a signal
+    /// ends the wait early. Non-blocking calls such as [`Self::try_read`] don't wait, and take
+    /// input ending in ESC to be the Escape key.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::{io, time::Duration};
+    ///
+    /// use termina::{PlatformTerminal, Terminal};
+    ///
+    /// fn main() -> io::Result<()> {
+    ///     let reader = PlatformTerminal::new()?.event_reader();
+    ///     reader.set_escape_timeout(Some(Duration::from_millis(50)));
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_escape_timeout(&self, timeout: Option<Duration>) {
+        self.shared.lock().source.set_escape_timeout(timeout);
+    }
+
+    /// Sets whether 8-bit C1 controls such as 0x9B (CSI) introduce escape sequences in the
+    /// terminal's input. They are ignored by default.
+    ///
+    /// Enable this for terminals which send 8-bit controls, for example after they were switched
+    /// on with S8C1T (`ESC SP G`). See [`Parser`](crate::Parser#8-bit-controls) for how UTF-8
+    /// text is kept apart from the controls.
+    pub fn set_c1_controls(&self, enabled: bool) {
+        self.shared.lock().source.set_c1_controls(enabled);
+    }
+
+    /// Holds back [`Event::WindowResized`] until no other resize arrived for `delay`, or delivers
+    /// resizes as they arrive with `None`, which is the default.
+    ///
+    /// Dragging a window's border produces a storm of resizes, and a render loop which lays out
+    /// and redraws the whole screen for each of them falls behind the pointer. With a debounce
+    /// delay only the last resize of a burst is delivered, once the burst has settled. Blocked
+    /// calls to [`Self::poll`] and [`Self::read`] wake up to deliver it. Meanwhile
+    /// [`Terminal::dimensions_cached`] already reports the newest size, so a frame drawn for
+    /// another reason uses it.
+    ///
+    /// Tees and subscriptions see every resize, since they observe events before debouncing.
+    /// Turning debouncing off delivers a held resize right away.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::{io, time::Duration};
+    ///
+    /// use termina::{Event, PlatformTerminal, Terminal};
+    ///
+    /// fn main() -> io::Result<()> {
+    ///     let terminal = PlatformTerminal::new()?;
+    ///     let reader = terminal.event_reader();
+    ///     reader.set_resize_debounce(Some(Duration::from_millis(50)));
+    ///     loop {
+    ///         match reader.read(|_| true)? {
+    ///             Event::WindowResized(size) => { /* lay out for `size` and redraw */ }
+    ///             Event::Key(_) => break,
+    ///             _ => (),
+    ///         }
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// [`Terminal::dimensions_cached`]: crate::Terminal::dimensions_cached
+    pub fn set_resize_debounce(&self, delay: Option<Duration>) {
+        let mut shared = self.shared.lock();
+        shared.resize_debounce = delay;
+        if delay.is_none() {
+            if let Some((size, _)) = shared.pending_resize.take() {
+                shared.events.push_back(Event::WindowResized(size));
+            }
+        }
+        shared.publish_len();
+    }
+
+    /// Emits [`Event::Tick`] at a fixed interval, or stops emitting ticks with `None`.
+    ///
+    /// Animation and game loops can then block on a single [`Self::read`] which returns either
+    /// input or the next frame's tick, rather than computing poll timeouts themselves. The first
+    /// tick arrives one interval after this call. Ticks are only generated while a call is
+    /// waiting in [`Self::poll`], [`Self::read`] or [`Self::read_timeout`] and only delivered to
+    /// calls whose filter accepts them.
+    ///
+    /// Like a display's vertical sync, a tick that is due while nobody is reading is delivered
+    /// late rather than queued: a loop which falls behind receives one tick and then continues at
+    /// the original cadence instead of receiving a burst of ticks to catch up. A rejected tick is
+    /// dropped.
+    ///
+    /// This method is available with the `tick` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::{io, time::Duration};
+    ///
+    /// use termina::{Event, PlatformTerminal, Terminal};
+    ///
+    /// fn main() -> io::Result<()> {
+    ///     let reader = PlatformTerminal::new()?.event_reader();
+    ///     reader.set_tick_interval(Some(Duration::from_secs(1) / 60));
+    ///     loop {
+    ///         match reader.read(|_| true)? {
+    ///             Event::Tick => { /* advance the animation and draw a frame */ }
+    ///             Event::Key(_) => break,
+    ///             _ => (),
+    ///         }
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(feature = "tick")]
+    pub fn set_tick_interval(&self, interval: Option<Duration>) {
+        let mut shared = self.shared.lock();
+        shared.ticker = interval.map(|interval| Ticker {
+            interval,
+            next: Instant::now() + interval,
+        });
+    }
+
+    /// Reports `signal` as an [`Event::Signal`] read from this reader.
+    ///
+    /// Once enabled, the signal no longer runs its default action, such as terminating the
+    /// process, so the application is responsible for exiting when it reads the event. Signals are
+    /// delivered through the same pipe as `SIGWINCH`, so a blocked [`Self::read`] or
+    /// [`Self::poll`] wakes up for them and event loops built on
+    /// [`Self::try_clone_pollable_fds`] need no extra descriptors. Enabling a signal again has no
+    /// effect. The signal is reported until the reader and all of its clones are dropped, and its
+    /// default action runs again from then on unless another reader still reports it.
+    ///
+    /// This method is only available on Unix.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::io;
+    ///
+    /// use termina::{event::Signal, Event, PlatformTerminal, Terminal};
+    ///
+    /// fn main() -> io::Result<()> {
+    ///     let mut terminal = PlatformTerminal::new()?;
+    ///     terminal.enter_raw_mode()?;
+    ///     let reader = terminal.event_reader();
+    ///     reader.enable_signal(Signal::Terminate)?;
+    ///     reader.enable_signal(Signal::Hangup)?;
+    ///     loop {
+    ///         if let Event::Signal(_) = reader.read(|_| true)? {
+    ///             break;
+    ///         }
+    ///     }
+    ///     terminal.enter_cooked_mode()
+    /// }
+    /// ```
+    #[cfg(unix)]
+    pub fn enable_signal(&self, signal: super::Signal) -> io::Result<()> {
+        self.shared.lock().source.enable_signal(signal)
+    }
+
+    /// Sets whether key releases and repeats are reported, like
+    /// [`KittyKeyboardFlags::REPORT_EVENT_TYPES`](crate::escape::csi::KittyKeyboardFlags::REPORT_EVENT_TYPES)
+    /// does for terminals supporting the kitty keyboard protocol.
+    ///
+    /// The Windows console reports key presses and releases as separate input records. By default
+    /// the VT input mode reports only presses, and the legacy input mode reports presses and
+    /// releases but reports a held key as repeated presses. While this is enabled, releases are
+    /// reported with [`KeyEventKind::Release`](super::KeyEventKind::Release), pressing a key which
+    /// is already held is reported with [`KeyEventKind::Repeat`](super::KeyEventKind::Repeat), and
+    /// a record standing for several repeated presses is reported as that many events.
+    ///
+    /// The console only writes VT input for key presses, so in the default VT input mode releases
+    /// and repeats are only reported for keys which produce a single byte, such as letters, digits
+    /// and Enter. The legacy input mode reports them for every key.
+    ///
+    /// This method is only available on Windows.
+    #[cfg(windows)]
+    pub fn set_report_event_types(&self, enabled: bool) {
+        self.shared.lock().source.set_report_event_types(enabled);
+    }
+
+    /// Calls `tap` with the raw bytes read from the terminal before they are parsed.
+    ///
+    /// This is a diagnostic for parsing problems: together with [`Self::parser_stats`] and the
+    /// [`Display`](std::fmt::Display) form of [`Event`] it shows what the terminal sent and how
+    /// Termina interpreted it. The `sniff` example prints both and is the quickest way to capture
+    /// this for a bug report. It can also record the input stream, for example to replay a session
+    /// later. `tap` is called from the event source's read loop with each chunk of input before it
+    /// is parsed. Sequences may be split across chunks, and on Windows the tap only sees input in
+    /// the default VT input mode.
+    ///
+    /// The tap replaces any previous one and stays installed until [`Self::remove_raw_tap`]. It
+    /// runs while the reader is locked, so it must not call methods on this reader.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::io;
+    ///
+    /// use termina::{PlatformTerminal, Terminal};
+    ///
+    /// fn main() -> io::Result<()> {
+    ///     let reader = PlatformTerminal::new()?.event_reader();
+    ///     reader.set_raw_tap(|bytes| eprintln!("{}", bytes.escape_ascii()));
+    ///     let event = reader.read(|_| true)?;
+    ///     eprintln!("{event}");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_raw_tap<F>(&self, tap: F)
+    where
+        F: FnMut(&[u8]) + Send + 'static,
+    {
+        self.shared
+            .lock()
+            .source
+            .set_raw_tap(Some(RawTap::new(tap)));
+    }
+
+    /// Removes the tap installed with [`Self::set_raw_tap`].
+    pub fn remove_raw_tap(&self) {
+        self.shared.lock().source.set_raw_tap(None);
+    }
+
+    /// Returns counters from the parser decoding the terminal's input.
+    ///
+    /// See [`ParserStats`] and [`Self::set_raw_tap`].
+    pub fn parser_stats(&self) -> ParserStats {
+        self.shared.lock().source.parser_stats()
+    }
+
+    /// Duplicates the file descriptors the reader waits on, for registration with an event loop.
+    ///
+    /// The descriptors are the terminal input, a pipe written to on `SIGWINCH` and other signals
+    /// (see [`Self::enable_signal`]), and the pipe
+    /// written to by [`Self::waker`]. When any of them is readable, drain the reader with
+    /// [`Self::try_read`]. Register them level-triggered and for readability only; the reader
+    /// performs the reads. The descriptors are in blocking mode and must be left that way, since
+    /// they share their file description with the reader's.
+    ///
+    /// Events which are already buffered in the reader don't make any descriptor readable, so
+    /// drain the reader once after registering.
+    ///
+    /// See the `termina::integration` module for adapters built on this method.
+    #[cfg(unix)]
+    pub fn try_clone_pollable_fds(&self) -> io::Result<[std::os::fd::OwnedFd; 3]> {
+        self.shared.lock().source.try_clone_pollable_fds()
     }
 
     /// Blocks until an event matching `filter` is available.
@@ -145,14 +842,271 @@ impl EventReader {
     /// `Event::Key(key) if key.kind == KeyEventKind::Press` unless the application intentionally
     /// handles release or repeat events.
     ///
-    /// Returns `Err` with [`io::ErrorKind::Interrupted`] if [`Self::waker`]'s `wake` is called
-    /// while this call is blocked.
+    /// Returns an error for which [`WokenUp::matches`] is `true` if [`Self::waker`]'s `wake` is
+    /// called while this call is blocked.
     pub fn read<F>(&self, filter: F) -> io::Result<Event>
     where
         F: FnMut(&Event) -> bool,
     {
         let mut reader = self.shared.lock();
-        reader.read(filter)
+        let result = reader.read(filter);
+        reader.publish_len();
+        result
+    }
+
+    /// Returns the number of events which have been read from the terminal, or queued by
+    /// [`Self::replay`], but not yet returned.
+    ///
+    /// Render loops can use this to batch input handling or skip a frame while input is waiting.
+    /// The count doesn't take the reader's lock or read from the terminal, so it is approximate:
+    /// it is updated when a call on this reader or one of its clones returns, and it doesn't
+    /// include input which the terminal has sent but which hasn't been read yet. Use
+    /// [`Self::poll`] with a zero timeout for an exact answer.
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Relaxed)
+    }
+
+    /// Returns `true` if no events are buffered. See [`Self::len`].
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns `true` if an event can be read without reading from the terminal.
+    ///
+    /// This is the fast path for render loops which drain input before drawing a frame: it
+    /// checks the events this reader has buffered, including input which was read and parsed but
+    /// not yet returned, without making a system call. Unlike [`Self::len`] the answer is exact,
+    /// except while another clone of this reader is reading, in which case it falls back to
+    /// [`Self::len`] rather than waiting for the read. Resizes held back by
+    /// [`Self::set_resize_debounce`] and ticks don't count until they are due.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::{io, time::Duration};
+    ///
+    /// use termina::{PlatformTerminal, Terminal};
+    ///
+    /// fn main() -> io::Result<()> {
+    ///     let reader = PlatformTerminal::new()?.event_reader();
+    ///     loop {
+    ///         if reader.poll(Some(Duration::from_millis(16)), |_| true)? {
+    ///             while reader.has_buffered_events() {
+    ///                 println!("{}", reader.read(|_| true)?);
+    ///             }
+    ///         }
+    ///         // Draw a frame.
+    ///     }
+    /// }
+    /// ```
+    pub fn has_buffered_events(&self) -> bool {
+        match self.shared.try_lock() {
+            Some(shared) => shared.has_buffered_events(),
+            None => !self.is_empty(),
+        }
+    }
+}
+
+/// Whether events matched by an [`EventTee`] stay available to ordinary reads.
+///
+/// See [`EventReader::tee`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TeeMode {
+    /// The tee receives a copy of each matched event and the event is still delivered to
+    /// [`EventReader::poll`] and [`EventReader::read`].
+    Copy,
+
+    /// The tee receives each matched event and it is not delivered to [`EventReader::poll`] or
+    /// [`EventReader::read`].
+    Consume,
+}
+
+/// The receiving side of a tee created by [`EventReader::tee`].
+///
+/// A tee does not read from the terminal itself: events arrive while the [`EventReader`] is being
+/// polled or read. Dropping the tee unregisters it from the reader.
+#[derive(Debug)]
+pub struct EventTee {
+    receiver: mpsc::Receiver<Event>,
+}
+
+impl EventTee {
+    /// Returns the next received event if there is one, without blocking.
+    pub fn try_recv(&self) -> Option<Event> {
+        self.receiver.try_recv().ok()
+    }
+
+    /// Returns an iterator over the received events which doesn't block.
+    pub fn try_iter(&self) -> impl Iterator<Item = Event> + '_ {
+        self.receiver.try_iter()
+    }
+}
+
+/// How long a subscription waits on its queue before trying again to read from the terminal
+/// itself, when another caller holds the reader's lock.
+const SUBSCRIPTION_LOCK_RETRY: Duration = Duration::from_millis(10);
+
+/// An event queue created by [`EventReader::subscribe`].
+///
+/// Dropping the subscription unregisters it from the reader.
+#[derive(Debug)]
+pub struct EventSubscription {
+    reader: EventReader,
+    receiver: mpsc::Receiver<Event>,
+}
+
+impl EventSubscription {
+    /// Blocks until an event for this subscription is available.
+    ///
+    /// Returns an error for which [`WokenUp::matches`] is `true` if the reader's waker is woken
+    /// while this call is reading from the terminal.
+    pub fn read(&self) -> io::Result<Event> {
+        match self.recv(None)? {
+            Some(event) => Ok(event),
+            None => Err(WokenUp.into()),
+        }
+    }
+
+    /// Reads an event for this subscription, waiting at most `timeout` for one to arrive.
+    ///
+    /// Returns `Ok(None)` if no event arrived before the timeout elapsed or if the reader's waker
+    /// was woken while waiting.
+    pub fn read_timeout(&self, timeout: Duration) -> io::Result<Option<Event>> {
+        self.recv(Some(timeout))
+    }
+
+    /// Returns the next queued event if there is one, without reading from the terminal.
+    pub fn try_recv(&self) -> Option<Event> {
+        self.receiver.try_recv().ok()
+    }
+
+    fn recv(&self, timeout: Option<Duration>) -> io::Result<Option<Event>> {
+        let timeout = PollTimeout::new(timeout);
+        loop {
+            if let Ok(event) = self.receiver.try_recv() {
+                return Ok(Some(event));
+            }
+
+            if let Some(mut shared) = self.reader.shared.try_lock() {
+                let result = shared.pump(timeout.leftover());
+                shared.publish_len();
+                match result {
+                    Ok(()) => (),
+                    Err(err) if err.kind() == io::ErrorKind::Interrupted => return Ok(None),
+                    Err(err) => return Err(err),
+                }
+            } else {
+                // Another caller is reading from the terminal and routes our events to the
+                // channel. Wake up periodically in case it stops reading.
+                let wait = timeout
+                    .leftover()
+                    .map_or(SUBSCRIPTION_LOCK_RETRY, |leftover| {
+                        leftover.min(SUBSCRIPTION_LOCK_RETRY)
+                    });
+                if let Ok(event) = self.receiver.recv_timeout(wait) {
+                    return Ok(Some(event));
+                }
+            }
+
+            if timeout.elapsed() {
+                return Ok(self.receiver.try_recv().ok());
+            }
+        }
+    }
+}
+
+/// A maximum length for the [`EventReader`]'s event queue.
+///
+/// See [`EventReader::set_queue_limit`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueLimit {
+    /// The maximum number of buffered events.
+    pub capacity: usize,
+
+    /// What to do when another event arrives while the queue is full.
+    pub policy: OverflowPolicy,
+}
+
+/// What an [`EventReader`] does with new events when its queue is full.
+///
+/// See [`EventReader::set_queue_limit`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum OverflowPolicy {
+    /// Drop the oldest buffered event to make room.
+    DropOldest,
+
+    /// Drop the oldest buffered mouse motion event ([`MouseEventKind::Moved`] or
+    /// [`MouseEventKind::Drag`]) to make room, since a later motion event supersedes it.
+    ///
+    /// If no motion event is buffered the oldest event is dropped instead.
+    CoalesceMouseMoves,
+
+    /// Drop the new event and return an error with [`io::ErrorKind::Other`] from the call that
+    /// read it.
+    Error,
+}
+
+bitflags::bitflags! {
+    /// Kinds of events an [`EventReader`] merges.
+    ///
+    /// See [`EventReader::set_coalescing`].
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Coalesce: u8 {
+        /// Nothing is merged.
+        const NONE = 0;
+
+        /// Consecutive [`MouseEventKind::Moved`] events with the same modifiers are merged into
+        /// the latest one.
+        const MOUSE_MOVED = 1;
+
+        /// Only the latest [`Event::WindowResized`] is kept.
+        const RESIZE = 1 << 1;
+    }
+}
+
+impl Coalesce {
+    /// Returns `true` if `event` is of a kind which is merged.
+    fn applies_to(&self, event: &Event) -> bool {
+        match event {
+            Event::Mouse(mouse) => {
+                self.contains(Self::MOUSE_MOVED) && mouse.kind == MouseEventKind::Moved
+            }
+            Event::WindowResized(_) => self.contains(Self::RESIZE),
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if `next` supersedes `previous`.
+    fn merges(&self, previous: &Event, next: &Event) -> bool {
+        if !self.applies_to(previous) || !self.applies_to(next) {
+            return false;
+        }
+        match (previous, next) {
+            (Event::Mouse(previous), Event::Mouse(next)) => previous.modifiers == next.modifiers,
+            (Event::WindowResized(_), Event::WindowResized(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(feature = "tick")]
+#[derive(Debug)]
+struct Ticker {
+    interval: Duration,
+    /// When the next tick is due.
+    next: Instant,
+}
+
+struct Tee {
+    mode: TeeMode,
+    filter: Box<dyn FnMut(&Event) -> bool + Send>,
+    sender: mpsc::Sender<Event>,
+}
+
+impl fmt::Debug for Tee {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Tee").field("mode", &self.mode).finish()
     }
 }
 
@@ -161,35 +1115,347 @@ struct Shared {
     events: VecDeque<Event>,
     source: PlatformEventSource,
     skipped_events: Vec<Event>,
+    tees: Vec<Tee>,
+    /// Events returned by `read` while a recording is active.
+    recording: Option<Vec<Event>>,
+    /// Replayed events, which are delivered before live input.
+    replay: VecDeque<Event>,
+    /// The limit on `events` and `skipped_events` combined.
+    limit: Option<QueueLimit>,
+    coalesce: Coalesce,
+    /// See `EventReader::set_resize_debounce`.
+    resize_debounce: Option<Duration>,
+    /// The held back resize and when it is due.
+    pending_resize: Option<(WindowSize, Instant)>,
+    /// Shared with `EventReader::len`.
+    len: Arc<AtomicUsize>,
+    /// Shared with `EventReader::cached_size_or`.
+    size: Arc<Mutex<Option<WindowSize>>>,
+    #[cfg(feature = "tick")]
+    ticker: Option<Ticker>,
 }
 
 impl Shared {
+    /// Stores the number of buffered events for [`EventReader::len`].
+    fn publish_len(&self) {
+        let len = self.replay.len()
+            + self.events.len()
+            + self.skipped_events.len()
+            + self.source.queued();
+        self.len.store(len, Ordering::Relaxed);
+    }
+
+    /// Checks whether an event is buffered here or in the source. See
+    /// [`EventReader::has_buffered_events`].
+    fn has_buffered_events(&self) -> bool {
+        !self.replay.is_empty()
+            || !self.events.is_empty()
+            || !self.skipped_events.is_empty()
+            || self.source.queued() != 0
+    }
+
+    /// Updates the cached window size for a freshly read event.
+    ///
+    /// A degenerate size is not cached, and the window may have changed while the process was
+    /// stopped, so both clear the cache.
+    fn record_size(&self, event: &Event) {
+        match event {
+            Event::WindowResized(size) => {
+                *self.size.lock() = (!size.is_degenerate()).then_some(*size);
+            }
+            Event::Resumed => *self.size.lock() = None,
+            _ => (),
+        }
+    }
+
+    /// Holds back a resize while resizes are debounced, returning other events.
+    fn debounce(&mut self, event: Event) -> Option<Event> {
+        match (event, self.resize_debounce) {
+            (Event::WindowResized(size), Some(delay)) => {
+                self.pending_resize = Some((size, Instant::now() + delay));
+                None
+            }
+            (event, _) => Some(event),
+        }
+    }
+
+    /// Returns the time left until the held back resize is due.
+    fn until_resize(&self) -> Option<Duration> {
+        self.pending_resize
+            .map(|(_, due)| due.saturating_duration_since(Instant::now()))
+    }
+
+    /// Returns the held back resize if it is due.
+    fn take_resize(&mut self) -> Option<Event> {
+        let (size, due) = self.pending_resize?;
+        if due > Instant::now() {
+            return None;
+        }
+        self.pending_resize = None;
+        Some(Event::WindowResized(size))
+    }
+
+    /// Returns a held back resize or a tick which is due and accepted by `filter`, buffering a
+    /// rejected resize.
+    fn take_due<F>(&mut self, mut filter: F) -> io::Result<Option<Event>>
+    where
+        F: FnMut(&Event) -> bool,
+    {
+        if let Some(resize) = self.take_resize() {
+            if (filter)(&resize) {
+                return Ok(Some(resize));
+            }
+            self.buffer(resize)?;
+        }
+        #[cfg(feature = "tick")]
+        if let Some(tick) = self.take_tick(&mut filter) {
+            return Ok(Some(tick));
+        }
+        Ok(None)
+    }
+
+    /// Handles an event freshly read from the terminal: caches its size, passes it through the
+    /// tees and holds it back if it is a debounced resize.
+    ///
+    /// Returns `None` if a tee consumed the event or it was held back.
+    fn receive(&mut self, event: Event) -> Option<Event> {
+        self.record_size(&event);
+        let event = self.tee(event)?;
+        self.debounce(event)
+    }
+
+    /// Passes a freshly read event through the registered tees.
+    ///
+    /// Returns `None` if a tee consumed the event.
+    fn tee(&mut self, event: Event) -> Option<Event> {
+        let mut consumed = false;
+        self.tees.retain_mut(|tee| {
+            if !(tee.filter)(&event) {
+                return true;
+            }
+            // The receiving half was dropped: unregister the tee.
+            let connected = tee.sender.send(event.clone()).is_ok();
+            consumed |= connected && tee.mode == TeeMode::Consume;
+            connected
+        });
+        (!consumed).then_some(event)
+    }
+
+    fn is_over_limit(&self) -> bool {
+        self.limit
+            .is_some_and(|limit| self.events.len() + self.skipped_events.len() > limit.capacity)
+    }
+
+    /// Drops a buffered event according to the overflow policy.
+    ///
+    /// The most recently buffered event is the newest, so `OverflowPolicy::Error` drops that one.
+    fn evict(&mut self) -> io::Result<()> {
+        let policy = self
+            .limit
+            .map_or(OverflowPolicy::DropOldest, |limit| limit.policy);
+        match policy {
+            OverflowPolicy::Error => {
+                if self.skipped_events.pop().is_none() {
+                    self.events.pop_back();
+                }
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    "event queue capacity exceeded",
+                ));
+            }
+            OverflowPolicy::CoalesceMouseMoves => {
+                let is_motion = |event: &Event| {
+                    matches!(
+                        event,
+                        Event::Mouse(mouse)
+                            if matches!(mouse.kind, MouseEventKind::Moved | MouseEventKind::Drag(_))
+                    )
+                };
+                if let Some(idx) = self.events.iter().position(is_motion) {
+                    self.events.remove(idx);
+                    return Ok(());
+                }
+                if let Some(idx) = self.skipped_events.iter().position(is_motion) {
+                    self.skipped_events.remove(idx);
+                    return Ok(());
+                }
+            }
+            OverflowPolicy::DropOldest => (),
+        }
+        if self.events.pop_front().is_none() && !self.skipped_events.is_empty() {
+            self.skipped_events.remove(0);
+        }
+        Ok(())
+    }
+
+    /// Reads at most one event from the source on behalf of a subscription, buffering it for
+    /// ordinary reads if no tee consumed it.
+    fn pump(&mut self, timeout: Option<Duration>) -> io::Result<()> {
+        if let Some(event) = self.source.try_read(timeout)? {
+            if let Some(event) = self.receive(event) {
+                let result = self.buffer(event);
+                self.events.extend(self.skipped_events.drain(..));
+                result?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Queues an event which wasn't returned to the caller, merging it with buffered events and
+    /// enforcing the queue limit.
+    fn buffer(&mut self, event: Event) -> io::Result<()> {
+        if self.coalesce.applies_to(&event) {
+            if matches!(event, Event::WindowResized(_)) {
+                let is_resize = |event: &Event| matches!(event, Event::WindowResized(_));
+                self.events.retain(|event| !is_resize(event));
+                self.skipped_events.retain(|event| !is_resize(event));
+            } else if let Some(previous) = self
+                .skipped_events
+                .last_mut()
+                .or_else(|| self.events.back_mut())
+            {
+                if self.coalesce.merges(previous, &event) {
+                    *previous = event;
+                    return Ok(());
+                }
+            }
+        }
+
+        self.skipped_events.push(event);
+        if self.is_over_limit() {
+            if let Err(err) = self.evict() {
+                self.events.extend(self.skipped_events.drain(..));
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+
+    /// Merges events which are already available into `event`, for [`Coalesce`].
+    ///
+    /// Stops at the first event which `event` doesn't merge with, buffering it.
+    fn coalesce_available<F>(&mut self, mut event: Event, mut filter: F) -> io::Result<Event>
+    where
+        F: FnMut(&Event) -> bool,
+    {
+        while self.coalesce.applies_to(&event) {
+            // Errors and wakeups are left for the next call to observe.
+            let Ok(Some(next)) = self.source.try_read(Some(Duration::ZERO)) else {
+                break;
+            };
+            let Some(next) = self.receive(next) else {
+                continue;
+            };
+            if self.coalesce.merges(&event, &next) && (filter)(&next) {
+                event = next;
+            } else {
+                if let Err(err) = self.buffer(next) {
+                    self.events.push_front(event);
+                    return Err(err);
+                }
+                break;
+            }
+        }
+        Ok(event)
+    }
+
+    /// Returns a freshly read event if `filter` accepts it, or buffers it otherwise.
+    fn accept<F>(&mut self, event: Event, mut filter: F) -> io::Result<Option<Event>>
+    where
+        F: FnMut(&Event) -> bool,
+    {
+        match self.receive(event) {
+            Some(event) if (filter)(&event) => Ok(Some(event)),
+            Some(event) => {
+                self.buffer(event)?;
+                Ok(None)
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the time left until the next tick, if ticks are enabled.
+    fn until_tick(&self) -> Option<Duration> {
+        #[cfg(feature = "tick")]
+        if let Some(ticker) = &self.ticker {
+            return Some(ticker.next.saturating_duration_since(Instant::now()));
+        }
+        None
+    }
+
+    /// Returns a tick if one is due and `filter` accepts it.
+    ///
+    /// A due tick is received like an event read from the terminal, so tees see it, and is then
+    /// consumed whether or not it is accepted. Missed ticks are skipped.
+    #[cfg(feature = "tick")]
+    fn take_tick<F>(&mut self, mut filter: F) -> Option<Event>
+    where
+        F: FnMut(&Event) -> bool,
+    {
+        let ticker = self.ticker.as_mut()?;
+        let now = Instant::now();
+        if now < ticker.next {
+            return None;
+        }
+        ticker.next += ticker.interval;
+        if ticker.next <= now {
+            ticker.next = now + ticker.interval;
+        }
+        let tick = self.receive(Event::Tick)?;
+        (filter)(&tick).then_some(tick)
+    }
+
     fn poll<F>(&mut self, timeout: Option<Duration>, mut filter: F) -> io::Result<bool>
     where
         F: FnMut(&Event) -> bool,
     {
-        if self.events.iter().any(&mut (filter)) {
+        if let Some(event) = self.take_due(&mut filter)? {
+            self.events.push_front(event);
+            self.publish_len();
             return Ok(true);
         }
+        self.events.extend(self.skipped_events.drain(..));
+
+        if self.replay.iter().any(&mut filter) || self.events.iter().any(&mut filter) {
+            return Ok(true);
+        }
+
+        // Events which the source has already parsed are examined before it reads from or waits
+        // on the terminal, so a zero timeout returns them without a system call.
+        while let Some(event) = self.source.pop_buffered() {
+            if let Some(event) = self.accept(event, &mut filter)? {
+                let event = self.coalesce_available(event, &mut filter)?;
+                self.events.extend(self.skipped_events.drain(..));
+                self.events.push_front(event);
+                return Ok(true);
+            }
+        }
 
         let timeout = PollTimeout::new(timeout);
 
         loop {
-            let maybe_event = match self.source.try_read(timeout.leftover()) {
-                Ok(None) => None,
-                Ok(Some(event)) => {
-                    if (filter)(&event) {
-                        Some(event)
-                    } else {
-                        self.skipped_events.push(event);
-                        None
-                    }
+            // Once the timeout has elapsed, keep going only while input is already available.
+            let mut drained = false;
+            // Wake up in time for the next tick or held back resize.
+            let wait = [timeout.leftover(), self.until_tick(), self.until_resize()]
+                .into_iter()
+                .flatten()
+                .min();
+            let maybe_event = match self.source.try_read(wait) {
+                Ok(None) => {
+                    drained = true;
+                    self.take_due(&mut filter)?
                 }
+                Ok(Some(event)) => self.accept(event, &mut filter)?,
                 Err(err) if err.kind() == io::ErrorKind::Interrupted => return Ok(false),
                 Err(err) => return Err(err),
             };
 
-            if timeout.elapsed() || maybe_event.is_some() {
+            if (drained && timeout.elapsed()) || maybe_event.is_some() {
+                let maybe_event = match maybe_event {
+                    Some(event) => Some(self.coalesce_available(event, &mut filter)?),
+                    None => None,
+                };
                 self.events.extend(self.skipped_events.drain(..));
 
                 if let Some(event) = maybe_event {
@@ -206,12 +1472,19 @@ impl Shared {
     where
         F: FnMut(&Event) -> bool,
     {
+        if let Some(idx) = self.replay.iter().position(&mut filter) {
+            return Ok(self.replay.remove(idx).unwrap());
+        }
+
         let mut skipped_events = VecDeque::new();
 
         loop {
             while let Some(event) = self.events.pop_front() {
                 if (filter)(&event) {
                     self.events.extend(skipped_events.drain(..));
+                    if let Some(recording) = &mut self.recording {
+                        recording.push(event.clone());
+                    }
                     return Ok(event);
                 } else {
                     skipped_events.push_back(event);
@@ -220,11 +1493,529 @@ impl Shared {
             // With `timeout: None`, `poll` only returns `Ok(false)` when a waker interrupted it
             // (its internal timeout can never elapse), so this unambiguously means "woken up."
             if !self.poll(None, &mut filter)? {
-                return Err(io::Error::new(
-                    io::ErrorKind::Interrupted,
-                    "read operation was woken up",
-                ));
+                return Err(WokenUp.into());
             }
         }
     }
 }
+
+/// Creates a reader over a socket, returning the reader and the socket's input side.
+#[cfg(all(test, unix))]
+pub(crate) fn test_reader() -> (EventReader, std::os::unix::net::UnixStream) {
+    use crate::{event::source::UnixEventSource, terminal::FileDescriptor};
+
+    let (read, write) = std::os::unix::net::UnixStream::pair().unwrap();
+    let source =
+        UnixEventSource::new(FileDescriptor::Owned(read.into()), FileDescriptor::STDOUT).unwrap();
+    (EventReader::new(source), write)
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use std::io::Write as _;
+
+    use super::{test_reader as reader, *};
+    use crate::event::KeyCode;
+
+    fn is_key(event: &Event, c: char) -> bool {
+        matches!(event, Event::Key(key) if key.code == KeyCode::Char(c))
+    }
+
+    #[test]
+    fn zero_timeout_poll_reads_available_input() {
+        let (reader, mut input) = reader();
+        input.write_all(b"ab").unwrap();
+        // The timeout has elapsed once 'a' is read and skipped, but 'b' arrived in the same read
+        // and is still examined.
+        assert!(reader
+            .poll(Some(Duration::ZERO), |event| is_key(event, 'b'))
+            .unwrap());
+        assert!(is_key(
+            &reader.read(|event| is_key(event, 'b')).unwrap(),
+            'b'
+        ));
+        assert!(is_key(&reader.read(|_| true).unwrap(), 'a'));
+        assert!(!reader.poll(Some(Duration::ZERO), |_| true).unwrap());
+    }
+
+    #[test]
+    fn tee_copy_and_consume() {
+        let (reader, mut input) = reader();
+        let copy = reader.tee(TeeMode::Copy, |event| is_key(event, 'a'));
+        let consume = reader.tee(TeeMode::Consume, |event| is_key(event, 'b'));
+
+        input.write_all(b"abc").unwrap();
+        let event = reader.read(|_| true).unwrap();
+        assert!(is_key(&event, 'a'));
+        // 'b' was consumed by the tee.
+        let event = reader.read(|_| true).unwrap();
+        assert!(is_key(&event, 'c'));
+
+        assert!(copy.try_recv().is_some_and(|event| is_key(&event, 'a')));
+        assert!(copy.try_recv().is_none());
+        assert!(consume.try_recv().is_some_and(|event| is_key(&event, 'b')));
+        assert!(consume.try_recv().is_none());
+
+        // Dropped tees no longer consume events.
+        drop(consume);
+        input.write_all(b"b").unwrap();
+        let event = reader.read(|_| true).unwrap();
+        assert!(is_key(&event, 'b'));
+        assert_eq!(reader.shared.lock().tees.len(), 1);
+    }
+
+    #[test]
+    fn signals() {
+        use crate::event::Signal;
+
+        let (reader, mut input) = reader();
+        reader.enable_signal(Signal::Hangup).unwrap();
+        reader.enable_signal(Signal::Hangup).unwrap();
+        input.write_all(b"a").unwrap();
+        signal_hook::low_level::raise(signal_hook::consts::SIGHUP).unwrap();
+
+        let event = reader
+            .read(|event| matches!(event, Event::Signal(_)))
+            .unwrap();
+        assert_eq!(event, Event::Signal(Signal::Hangup));
+        let event = reader.read(|_| true).unwrap();
+        assert!(is_key(&event, 'a'));
+        assert!(!reader.poll(Some(Duration::ZERO), |_| true).unwrap());
+    }
+
+    #[test]
+    fn waker_contract() {
+        let (reader, mut input) = reader();
+        let waker = reader.waker();
+        let timeout = Duration::from_secs(5);
+
+        waker.wake().unwrap();
+        assert!(!reader.poll(Some(timeout), |_| true).unwrap());
+        waker.wake().unwrap();
+        let err = reader.read(|_| true).unwrap_err();
+        assert!(WokenUp::matches(&err));
+        assert_eq!(err.kind(), io::ErrorKind::Interrupted);
+        waker.wake().unwrap();
+        assert_eq!(reader.read_timeout(|_| true, timeout).unwrap(), None);
+        // A blocked read is woken from another thread.
+        let thread = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            waker.wake()
+        });
+        assert!(WokenUp::matches(&reader.read(|_| true).unwrap_err()));
+        thread.join().unwrap().unwrap();
+
+        // Each wake-up is observed once.
+        input.write_all(b"a").unwrap();
+        assert!(is_key(&reader.read(|_| true).unwrap(), 'a'));
+        assert!(!WokenUp::matches(&io::Error::from(
+            io::ErrorKind::Interrupted
+        )));
+    }
+
+    #[test]
+    fn paused_input() {
+        let (reader, mut input) = reader();
+        input.write_all(b"a").unwrap();
+        assert!(reader.poll(Some(Duration::from_secs(1)), |_| true).unwrap());
+
+        // A read blocked on another thread leaves input alone once input is paused.
+        let blocked = reader.clone();
+        let thread = std::thread::spawn(move || {
+            blocked.poll(Some(Duration::from_millis(300)), |event| is_key(event, 'b'))
+        });
+        std::thread::sleep(Duration::from_millis(50));
+        reader.pause().unwrap();
+        assert!(reader.is_paused());
+        input.write_all(b"b").unwrap();
+        assert!(!thread.join().unwrap().unwrap());
+        // Buffered events are still delivered.
+        assert!(is_key(&reader.read(|_| true).unwrap(), 'a'));
+        assert!(!reader.poll(Some(Duration::ZERO), |_| true).unwrap());
+        // So are wake-ups.
+        reader.waker().wake().unwrap();
+        assert!(WokenUp::matches(&reader.read(|_| true).unwrap_err()));
+
+        reader.resume().unwrap();
+        assert!(!reader.is_paused());
+        assert!(is_key(&reader.read(|_| true).unwrap(), 'b'));
+    }
+
+    #[test]
+    fn subscriptions() {
+        let (reader, mut input) = reader();
+        let first = reader.subscribe(|event| is_key(event, 'x') || is_key(event, 'y'));
+        let second = reader.subscribe(|event| is_key(event, 'y'));
+
+        input.write_all(b"axyb").unwrap();
+        // Subscribed events are routed past ordinary reads.
+        let event = reader.read(|_| true).unwrap();
+        assert!(is_key(&event, 'a'));
+        let event = reader.read(|_| true).unwrap();
+        assert!(is_key(&event, 'b'));
+        // Matching subscriptions each receive their own copy.
+        assert!(first.try_recv().is_some_and(|event| is_key(&event, 'x')));
+        assert!(first.try_recv().is_some_and(|event| is_key(&event, 'y')));
+        assert!(second.try_recv().is_some_and(|event| is_key(&event, 'y')));
+        assert!(second.try_recv().is_none());
+
+        // A subscription reads from the terminal itself, buffering other events.
+        input.write_all(b"cx").unwrap();
+        let event = first.read().unwrap();
+        assert!(is_key(&event, 'x'));
+        let event = first.read_timeout(Duration::from_millis(10)).unwrap();
+        assert!(event.is_none());
+        let event = reader.read(|_| true).unwrap();
+        assert!(is_key(&event, 'c'));
+
+        drop(first);
+        drop(second);
+        input.write_all(b"y").unwrap();
+        let event = reader.read(|_| true).unwrap();
+        assert!(is_key(&event, 'y'));
+        assert!(reader.shared.lock().tees.is_empty());
+    }
+
+    #[test]
+    fn queue_limit() {
+        let (reader, mut input) = reader();
+        let limit = |policy| {
+            Some(QueueLimit {
+                capacity: 2,
+                policy,
+            })
+        };
+        let is_move = |event: &Event| matches!(event, Event::Mouse(_));
+
+        reader.set_queue_limit(limit(OverflowPolicy::DropOldest));
+        input.write_all(b"abcz").unwrap();
+        let event = reader.read(|event| is_key(event, 'z')).unwrap();
+        assert!(is_key(&event, 'z'));
+        assert!(is_key(&reader.read(|_| true).unwrap(), 'b'));
+        assert!(is_key(&reader.read(|_| true).unwrap(), 'c'));
+
+        // SGR mouse motion events at (1, 1) and (2, 1).
+        reader.set_queue_limit(limit(OverflowPolicy::CoalesceMouseMoves));
+        input.write_all(b"\x1b[<35;1;1M\x1b[<35;2;1Maz").unwrap();
+        reader.read(|event| is_key(event, 'z')).unwrap();
+        let event = reader.read(|_| true).unwrap();
+        assert!(matches!(event, Event::Mouse(mouse) if mouse.column == 1));
+        assert!(is_key(&reader.read(|_| true).unwrap(), 'a'));
+        assert!(!reader.poll(Some(Duration::ZERO), is_move).unwrap());
+
+        reader.set_queue_limit(limit(OverflowPolicy::Error));
+        input.write_all(b"abcz").unwrap();
+        let err = reader.read(|event| is_key(event, 'z')).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Other);
+        assert!(is_key(&reader.read(|_| true).unwrap(), 'a'));
+        assert!(is_key(&reader.read(|_| true).unwrap(), 'b'));
+        assert!(is_key(&reader.read(|_| true).unwrap(), 'z'));
+
+        // Lowering the capacity applies immediately.
+        input.write_all(b"abcz").unwrap();
+        reader.set_queue_limit(None);
+        reader.read(|event| is_key(event, 'z')).unwrap();
+        reader.set_queue_limit(limit(OverflowPolicy::DropOldest));
+        assert_eq!(reader.shared.lock().events.len(), 2);
+        // With the error policy too, as many events are dropped as needed.
+        reader.set_queue_limit(None);
+        input.write_all(b"dez").unwrap();
+        reader.read(|event| is_key(event, 'z')).unwrap();
+        reader.set_queue_limit(Some(QueueLimit {
+            capacity: 1,
+            policy: OverflowPolicy::Error,
+        }));
+        assert_eq!(reader.len(), 1);
+        reader.read(|_| true).unwrap();
+        assert!(reader.is_empty());
+    }
+
+    #[test]
+    fn buffered_len() {
+        let (reader, mut input) = reader();
+        assert!(reader.is_empty());
+
+        input.write_all(b"abc").unwrap();
+        assert!(is_key(&reader.read(|_| true).unwrap(), 'a'));
+        assert_eq!(reader.len(), 2);
+        assert!(is_key(
+            &reader.read(|event| is_key(event, 'c')).unwrap(),
+            'c'
+        ));
+        assert_eq!(reader.len(), 1);
+
+        reader.replay([Event::FocusIn]);
+        assert_eq!(reader.len(), 2);
+        assert_eq!(reader.read(|_| true).unwrap(), Event::FocusIn);
+        assert!(is_key(&reader.read(|_| true).unwrap(), 'b'));
+        assert!(reader.is_empty());
+    }
+
+    #[test]
+    fn buffered_events() {
+        let (reader, mut input) = reader();
+        assert!(!reader.has_buffered_events());
+
+        input.write_all(b"abc").unwrap();
+        // The input hasn't been read yet.
+        assert!(!reader.has_buffered_events());
+        assert!(is_key(&reader.read(|_| true).unwrap(), 'a'));
+        assert!(reader.has_buffered_events());
+
+        // Parsed events are returned before the terminal is read again.
+        input.write_all(b"d").unwrap();
+        let poll = |c| reader.poll(Some(Duration::ZERO), |event| is_key(event, c));
+        assert!(poll('c').unwrap());
+        assert!(is_key(&reader.read(|_| true).unwrap(), 'c'));
+        assert!(is_key(&reader.read(|_| true).unwrap(), 'b'));
+        assert!(!reader.has_buffered_events());
+        assert!(poll('d').unwrap());
+        assert!(reader.has_buffered_events());
+    }
+
+    #[test]
+    fn coalescing() {
+        let (reader, mut input) = reader();
+        let is_mouse = |event: &Event| matches!(event, Event::Mouse(_));
+        let column = |event: Event| match event {
+            Event::Mouse(mouse) => mouse.column,
+            _ => panic!("expected a mouse event, got {event:?}"),
+        };
+        reader.set_coalescing(Coalesce::MOUSE_MOVED);
+
+        // SGR mouse motion to columns 1, 2 and 3, then a motion with Shift held.
+        input
+            .write_all(b"\x1b[<35;1;1M\x1b[<35;2;1M\x1b[<35;3;1M\x1b[<39;4;1Ma")
+            .unwrap();
+        assert_eq!(column(reader.read(is_mouse).unwrap()), 2);
+        assert_eq!(column(reader.read(is_mouse).unwrap()), 3);
+        assert!(is_key(&reader.read(|_| true).unwrap(), 'a'));
+
+        // Buffered motion is merged too.
+        input.write_all(b"\x1b[<35;1;1M\x1b[<35;2;1Ma").unwrap();
+        assert!(is_key(
+            &reader.read(|event| is_key(event, 'a')).unwrap(),
+            'a'
+        ));
+        assert_eq!(column(reader.read(is_mouse).unwrap()), 1);
+        assert!(!reader.poll(Some(Duration::ZERO), is_mouse).unwrap());
+
+        // Click events are never merged.
+        reader.set_coalescing(Coalesce::MOUSE_MOVED | Coalesce::RESIZE);
+        input.write_all(b"\x1b[<0;1;1M\x1b[<0;2;1M").unwrap();
+        assert_eq!(column(reader.read(is_mouse).unwrap()), 0);
+        assert_eq!(column(reader.read(is_mouse).unwrap()), 1);
+    }
+
+    #[test]
+    fn resize_debounce() {
+        let (reader, mut input) = reader();
+        let size = |cols| crate::test_size(cols, 24);
+        let delay = Duration::from_millis(50);
+        reader.set_resize_debounce(Some(delay));
+
+        // Only the last resize of a burst is delivered, after input which arrived meanwhile.
+        let start = Instant::now();
+        reader.push_events((80..90).map(|cols| Event::WindowResized(size(cols))));
+        input.write_all(b"a").unwrap();
+        assert!(is_key(&reader.read(|_| true).unwrap(), 'a'));
+        assert_eq!(
+            reader.read(|_| true).unwrap(),
+            Event::WindowResized(size(89))
+        );
+        assert!(start.elapsed() >= delay);
+        assert!(!reader.poll(Some(Duration::ZERO), |_| true).unwrap());
+
+        // The size is cached before the resize is delivered.
+        reader.push_events([Event::WindowResized(size(100))]);
+        assert_eq!(reader.cached_size_or(|| unreachable!()).unwrap(), size(100));
+        // A rejected resize is buffered once it is due.
+        let is_key_press = |event: &Event| matches!(event, Event::Key(_));
+        assert!(!reader.poll(Some(delay * 2), is_key_press).unwrap());
+        assert_eq!(reader.len(), 1);
+
+        // Turning debouncing off delivers a held resize right away.
+        reader.push_events([Event::WindowResized(size(110))]);
+        reader.set_resize_debounce(None);
+        let event = reader.read_timeout(|_| true, Duration::ZERO).unwrap();
+        assert_eq!(event, Some(Event::WindowResized(size(100))));
+        let event = reader.read_timeout(|_| true, Duration::ZERO).unwrap();
+        assert_eq!(event, Some(Event::WindowResized(size(110))));
+        // A resize which is due is counted once it is polled.
+        reader.set_resize_debounce(Some(Duration::ZERO));
+        reader.push_events([Event::WindowResized(size(115))]);
+        assert!(reader.poll(Some(Duration::ZERO), |_| true).unwrap());
+        assert_eq!(reader.len(), 1);
+        assert!(reader.read(|_| true).is_ok());
+
+        // A degenerate size isn't cached.
+        reader.push_events([Event::WindowResized(size(0))]);
+        assert_eq!(reader.cached_size_or(|| Ok(size(120))).unwrap(), size(120));
+        // A resize read while asking is newer than the answer.
+        reader.push_events([Event::WindowResized(size(0))]);
+        let cached = reader.cached_size_or(|| {
+            reader.push_events([Event::WindowResized(size(130))]);
+            Ok(size(120))
+        });
+        assert_eq!(cached.unwrap(), size(130));
+    }
+
+    #[test]
+    fn escape_timeout() {
+        let (reader, mut input) = reader();
+        let is_escape =
+            |event: &Event| matches!(event, Event::Key(key) if key.code == KeyCode::Escape);
+
+        // By default input ending in ESC is the Escape key.
+        input.write_all(b"\x1b").unwrap();
+        assert!(is_escape(&reader.read(|_| true).unwrap()));
+
+        // An up arrow split by a slow link.
+        reader.set_escape_timeout(Some(Duration::from_secs(5)));
+        input.write_all(b"a\x1b").unwrap();
+        let writer = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(20));
+            input.write_all(b"[A").unwrap();
+            input
+        });
+        assert!(is_key(&reader.read(|_| true).unwrap(), 'a'));
+        let event = reader.read(|_| true).unwrap();
+        assert!(matches!(event, Event::Key(key) if key.code == KeyCode::Up));
+        let mut input = writer.join().unwrap();
+
+        // Without more input the Escape key is reported once the timeout elapses.
+        let timeout = Duration::from_millis(20);
+        reader.set_escape_timeout(Some(timeout));
+        let start = Instant::now();
+        input.write_all(b"\x1b").unwrap();
+        assert!(is_escape(&reader.read(|_| true).unwrap()));
+        assert!(start.elapsed() >= timeout);
+
+        // A non-blocking read doesn't wait.
+        reader.set_escape_timeout(Some(Duration::from_secs(5)));
+        input.write_all(b"\x1b").unwrap();
+        let start = Instant::now();
+        let event = reader.poll(Some(Duration::ZERO), |_| true).unwrap();
+        assert!(event && is_escape(&reader.read(|_| true).unwrap()));
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        // A wakeup ends the wait, and is reported after the Escape key.
+        let waker = reader.waker();
+        let writer = std::thread::spawn(move || {
+            input.write_all(b"\x1b").unwrap();
+            std::thread::sleep(Duration::from_millis(20));
+            waker.wake().unwrap();
+        });
+        let start = Instant::now();
+        assert!(is_escape(&reader.read(|_| true).unwrap()));
+        assert!(WokenUp::matches(&reader.read(|_| true).unwrap_err()));
+        assert!(start.elapsed() < Duration::from_secs(5));
+        writer.join().unwrap();
+    }
+
+    #[test]
+    fn read_timeout() {
+        let (reader, mut input) = reader();
+        input.write_all(b"ab").unwrap();
+
+        let event = reader
+            .read_timeout(|event| is_key(event, 'b'), Duration::from_millis(100))
+            .unwrap();
+        assert!(event.is_some_and(|event| is_key(&event, 'b')));
+        // Nothing matching arrives: time out.
+        let event = reader
+            .read_timeout(|event| is_key(event, 'b'), Duration::from_millis(10))
+            .unwrap();
+        assert!(event.is_none());
+        // The rejected event is still buffered.
+        let event = reader
+            .read_timeout(|_| true, Duration::from_millis(10))
+            .unwrap();
+        assert!(event.is_some_and(|event| is_key(&event, 'a')));
+    }
+
+    #[cfg(feature = "tick")]
+    #[test]
+    fn ticks() {
+        let (reader, mut input) = reader();
+        let interval = Duration::from_millis(20);
+        let is_tick = |event: &Event| matches!(event, Event::Tick);
+        reader.set_tick_interval(Some(interval));
+
+        let start = Instant::now();
+        assert_eq!(reader.read(|_| true).unwrap(), Event::Tick);
+        assert!(start.elapsed() >= interval);
+        // Input doesn't wait for the next tick.
+        input.write_all(b"a").unwrap();
+        assert!(is_key(&reader.read(|_| true).unwrap(), 'a'));
+        // Rejected ticks are dropped rather than buffered.
+        let event = reader.read_timeout(|event| !is_tick(event), interval * 2);
+        assert!(event.unwrap().is_none());
+        assert!(reader.is_empty());
+        assert_eq!(reader.read(|_| true).unwrap(), Event::Tick);
+        // Tees see ticks like other events.
+        let tee = reader.tee(TeeMode::Copy, is_tick);
+        assert_eq!(reader.read(|_| true).unwrap(), Event::Tick);
+        assert_eq!(tee.try_recv(), Some(Event::Tick));
+
+        reader.set_tick_interval(None);
+        let event = reader.read_timeout(|_| true, interval * 2).unwrap();
+        assert!(event.is_none());
+    }
+
+    #[test]
+    fn record_and_replay() {
+        let (reader, mut input) = reader();
+
+        reader.start_recording();
+        assert!(reader.is_recording());
+        input.write_all(b"abc").unwrap();
+        // 'a' is skipped by the filter and isn't recorded until it is read.
+        let not_a = |event: &Event| !is_key(event, 'a');
+        assert!(is_key(&reader.read(not_a).unwrap(), 'b'));
+        assert!(is_key(&reader.read(not_a).unwrap(), 'c'));
+        let recorded = reader.stop_recording();
+        assert!(!reader.is_recording());
+        assert_eq!(recorded.len(), 2);
+        assert!(is_key(&recorded[0], 'b'));
+        assert!(is_key(&recorded[1], 'c'));
+
+        // Replayed events come before live input, both buffered and new.
+        reader.start_recording();
+        reader.replay(recorded);
+        input.write_all(b"d").unwrap();
+        for c in ['b', 'c', 'a', 'd'] {
+            assert!(is_key(&reader.read(|_| true).unwrap(), c));
+        }
+        // Replayed events aren't recorded.
+        let recorded = reader.stop_recording();
+        assert_eq!(recorded.len(), 2);
+        assert!(is_key(&recorded[0], 'a'));
+        assert!(is_key(&recorded[1], 'd'));
+    }
+
+    #[test]
+    fn raw_tap_and_stats() {
+        let (reader, mut input) = reader();
+        let (sender, receiver) = mpsc::channel();
+        reader.set_raw_tap(move |bytes| sender.send(bytes.to_vec()).unwrap());
+
+        input.write_all(b"a\x1b[99@b").unwrap();
+        assert!(is_key(&reader.read(|_| true).unwrap(), 'a'));
+        assert!(matches!(reader.read(|_| true).unwrap(), Event::Csi(_)));
+        assert!(is_key(&reader.read(|_| true).unwrap(), 'b'));
+        assert_eq!(
+            receiver.try_iter().collect::<Vec<_>>().concat(),
+            b"a\x1b[99@b"
+        );
+        let stats = reader.parser_stats();
+        assert_eq!(stats.bytes, 7);
+        assert_eq!(stats.events, 3);
+        assert_eq!(stats.unrecognized, 1);
+
+        reader.remove_raw_tap();
+        input.write_all(b"c").unwrap();
+        assert!(is_key(&reader.read(|_| true).unwrap(), 'c'));
+        assert_eq!(receiver.try_iter().count(), 0);
+    }
+}
@@ -3,7 +3,12 @@ mod unix;
 #[cfg(windows)]
 mod windows;
 
-use std::time::{Duration, Instant};
+use std::{
+    error, fmt, io,
+    time::{Duration, Instant},
+};
+
+use crate::parse::{ParserStats, RawTap};
 
 #[cfg(unix)]
 pub(crate) use unix::UnixEventSource;
@@ -24,11 +29,86 @@ pub type PlatformWaker = UnixWaker;
 #[cfg(windows)]
 pub type PlatformWaker = WindowsWaker;
 
+/// The error returned by a blocking read which was woken with a [`PlatformWaker`].
+///
+/// Blocking reads such as [`EventReader::read`](crate::EventReader::read) return an
+/// [`io::ErrorKind::Interrupted`] error wrapping this value when the reader's waker is woken.
+/// Calls which can report that nothing was read do so instead:
+/// [`EventReader::poll`](crate::EventReader::poll) returns `Ok(false)` and
+/// [`EventReader::read_timeout`](crate::EventReader::read_timeout) returns `Ok(None)`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::{io, thread};
+///
+/// use termina::{event::WokenUp, PlatformTerminal, Terminal};
+///
+/// fn main() -> io::Result<()> {
+///     let reader = PlatformTerminal::new()?.event_reader();
+///     let waker = reader.waker();
+///     thread::spawn(move || waker.wake());
+///     match reader.read(|_| true) {
+///         Ok(event) => println!("{event}"),
+///         Err(err) if WokenUp::matches(&err) => println!("woken up"),
+///         Err(err) => return Err(err),
+///     }
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct WokenUp;
+
+impl WokenUp {
+    /// Returns `true` if `err` was returned because the reader was woken up.
+    pub fn matches(err: &io::Error) -> bool {
+        err.kind() == io::ErrorKind::Interrupted
+            && err.get_ref().is_some_and(|inner| inner.is::<Self>())
+    }
+}
+
+impl fmt::Display for WokenUp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("read operation was woken up")
+    }
+}
+
+impl error::Error for WokenUp {}
+
+impl From<WokenUp> for io::Error {
+    fn from(woken_up: WokenUp) -> Self {
+        io::Error::new(io::ErrorKind::Interrupted, woken_up)
+    }
+}
+
 // CREDIT: <https://github.com/crossterm-rs/crossterm/blob/36d95b26a26e64b0f8c12edfe11f410a6d56a812/src/event/source.rs#L12-L27>
 pub(crate) trait EventSource: Send + Sync {
     fn try_read(&mut self, timeout: Option<Duration>) -> std::io::Result<Option<crate::Event>>;
 
+    /// Returns an event which has already been parsed, without reading from or waiting on the
+    /// terminal. `try_read` returns these before making any system call.
+    fn pop_buffered(&mut self) -> Option<crate::Event>;
+
+    /// The number of events which have been parsed but not yet returned by `try_read`.
+    fn queued(&self) -> usize;
+
+    /// Discards parsed events which haven't been returned yet and any partially parsed input.
+    fn clear(&mut self);
+
     fn waker(&self) -> PlatformWaker;
+
+    /// Counters from the parser decoding this source's input.
+    fn parser_stats(&self) -> ParserStats;
+
+    /// Sets the function called with the raw bytes read from the terminal.
+    fn set_raw_tap(&mut self, tap: Option<RawTap>);
+
+    /// Sets how long a lone ESC waits for the rest of a sequence. See
+    /// `EventReader::set_escape_timeout`.
+    fn set_escape_timeout(&mut self, timeout: Option<Duration>);
+
+    /// Sets whether the parser recognizes 8-bit C1 controls.
+    fn set_c1_controls(&mut self, enabled: bool);
 }
 
 // CREDIT: <https://github.com/crossterm-rs/crossterm/blob/36d95b26a26e64b0f8c12edfe11f410a6d56a812/src/event/timeout.rs#L5-L40>
@@ -3,32 +3,192 @@
 // WezTerm repo) but reimplementing with rustix instead of libc.
 // Crossterm: <https://github.com/crossterm-rs/crossterm/blob/36d95b26a26e64b0f8c12edfe11f410a6d56a812/src/event/source/unix/tty.rs>
 // Termwiz: <https://github.com/wezterm/wezterm/blob/a87358516004a652ad840bc1661bdf65ffc89b43/filedescriptor/src/unix.rs#L444-L584>
+#[cfg(feature = "event-queue")]
+mod queue;
+
 use std::{
+    collections::VecDeque,
     io::{self, Read, Write as _},
     os::{
         fd::{AsFd, BorrowedFd},
         unix::net::UnixStream,
     },
-    sync::Arc,
-    time::Duration,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
 };
 
 use parking_lot::Mutex;
 use rustix::termios;
 
-use crate::{parse::Parser, terminal::FileDescriptor, Event};
+use crate::{
+    event::Signal,
+    parse::{Parser, ParserStats, RawTap},
+    terminal::{window_size, FileDescriptor},
+    Event, WindowSize,
+};
 
-use super::{EventSource, PollTimeout};
+use super::{EventSource, PollTimeout, WokenUp};
+
+/// The size of the input buffer to start with.
+const MIN_READ_BUF_SIZE: usize = 1024;
+/// The size the input buffer grows to while reads keep filling it, as in a large paste.
+const MAX_READ_BUF_SIZE: usize = 64 * 1024;
 
 #[derive(Debug)]
 pub struct UnixEventSource {
     parser: Parser,
     read: FileDescriptor,
-    write: FileDescriptor,
-    sigwinch_id: signal_hook::SigId,
-    sigwinch_pipe: UnixStream,
+    /// The buffer input is read into. It doubles in size, up to [`MAX_READ_BUF_SIZE`], each time a
+    /// read fills it.
+    read_buf: Vec<u8>,
+    /// The terminal whose window size is reported on `SIGWINCH`, queried like
+    /// `UnixTerminal::get_dimensions` so that both report the same size.
+    size_fd: FileDescriptor,
+    /// Reported as the window size if `size_fd` has none. See `UnixTerminal::new_lenient`.
+    fallback_size: Option<WindowSize>,
+    /// The size last reported for `SIGWINCH`. A signal which finds the size unchanged, as the
+    /// later signals of a burst do once the first has read the final size, isn't reported.
+    last_size: Option<WindowSize>,
+    /// The signals written to `signal_pipe`, in the order their events are reported.
+    signals: Vec<SignalFlag>,
+    signal_pipe: UnixStream,
+    signal_pipe_write: UnixStream,
     wake_pipe: UnixStream,
     wake_pipe_write: Arc<Mutex<UnixStream>>,
+    /// Events queued with `UnixWaker::push_events`, reported after the parsed input.
+    pushed_events: Arc<Mutex<VecDeque<Event>>>,
+    /// Whether input is left unread, shared with the waker. See `EventReader::pause`.
+    input_paused: Arc<AtomicBool>,
+    /// Set when a wakeup ends the wait for the rest of an escape sequence, and reported once the
+    /// events read until then are.
+    wake_pending: bool,
+    /// See `EventReader::set_escape_timeout`.
+    escape_timeout: Option<Duration>,
+    /// The other end of `read` for a source without input, kept open so that `read` never
+    /// reaches end-of-file.
+    idle_peer: Option<UnixStream>,
+    /// Keeps the descriptors registered across waits. `None` if they can't be registered, in
+    /// which case each wait polls them.
+    #[cfg(feature = "event-queue")]
+    queue: Option<queue::EventQueue>,
+    /// Set once the terminal input reached end-of-file or failed with `EIO`. It isn't waited on
+    /// anymore.
+    input_closed: bool,
+    /// Set until [`Event::Disconnected`] has been reported for the closed input.
+    disconnect_pending: bool,
+}
+
+/// A signal which sets `pending` and then writes to the source's signal pipe.
+///
+/// Actions run in the order they are registered, so the flag is set by the time the pipe is
+/// readable. The flag tells apart signals which share the pipe.
+#[derive(Debug)]
+struct SignalFlag {
+    kind: SignalKind,
+    signal: i32,
+    pending: Arc<AtomicBool>,
+    ids: [signal_hook::SigId; 2],
+}
+
+/// The signals which have been reported by a source, with the number of sources reporting each.
+///
+/// Registering an action replaces a signal's default action for the rest of the process, so each
+/// signal also gets an action which emulates the default while no source reports it.
+static DEFAULT_ACTIONS: Mutex<Vec<DefaultAction>> = Mutex::new(Vec::new());
+
+#[derive(Debug)]
+struct DefaultAction {
+    signal: i32,
+    sources: usize,
+    /// Set while no source reports the signal.
+    enabled: Arc<AtomicBool>,
+}
+
+/// Counts a source reporting `signal`, which disables its default action.
+fn disable_default_action(signal: i32) -> io::Result<()> {
+    let mut actions = DEFAULT_ACTIONS.lock();
+    let action = match actions.iter().position(|action| action.signal == signal) {
+        Some(index) => &mut actions[index],
+        None => {
+            let enabled = Arc::new(AtomicBool::new(false));
+            // This stays registered for the rest of the process.
+            signal_hook::flag::register_conditional_default(signal, enabled.clone())?;
+            actions.push(DefaultAction {
+                signal,
+                sources: 0,
+                enabled,
+            });
+            actions.last_mut().unwrap()
+        }
+    };
+    action.sources += 1;
+    action.enabled.store(false, Ordering::SeqCst);
+    Ok(())
+}
+
+/// Stops counting a source reporting `signal`, enabling its default action again once no source
+/// reports it.
+fn enable_default_action(signal: i32) {
+    let mut actions = DEFAULT_ACTIONS.lock();
+    if let Some(action) = actions.iter_mut().find(|action| action.signal == signal) {
+        action.sources -= 1;
+        if action.sources == 0 {
+            action.enabled.store(true, Ordering::SeqCst);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SignalKind {
+    /// `SIGWINCH`, reported as [`Event::WindowResized`].
+    Resize,
+    /// `SIGCONT`, reported as [`Event::Resumed`].
+    Resumed,
+    /// A signal enabled with [`UnixEventSource::enable_signal`].
+    Signal(Signal),
+}
+
+impl SignalFlag {
+    fn register(signal: i32, kind: SignalKind, pipe: &UnixStream) -> io::Result<Self> {
+        disable_default_action(signal)?;
+        let pending = Arc::new(AtomicBool::new(false));
+        let ids = signal_hook::flag::register(signal, pending.clone()).and_then(|flag_id| {
+            let pipe = pipe.try_clone();
+            match pipe.and_then(|pipe| signal_hook::low_level::pipe::register(signal, pipe)) {
+                Ok(pipe_id) => Ok([flag_id, pipe_id]),
+                Err(err) => {
+                    signal_hook::low_level::unregister(flag_id);
+                    Err(err)
+                }
+            }
+        });
+        match ids {
+            Ok(ids) => Ok(Self {
+                kind,
+                signal,
+                pending,
+                ids,
+            }),
+            Err(err) => {
+                enable_default_action(signal);
+                Err(err)
+            }
+        }
+    }
+}
+
+impl Drop for SignalFlag {
+    /// Unregisters the actions. The signal's default action runs again unless another source
+    /// still reports it.
+    fn drop(&mut self) {
+        for id in self.ids {
+            signal_hook::low_level::unregister(id);
+        }
+        enable_default_action(self.signal);
+    }
 }
 
 /// A handle that can unblock a pending [`EventReader::poll`](crate::EventReader::poll) call
@@ -38,114 +198,445 @@ pub struct UnixEventSource {
 #[derive(Debug, Clone)]
 pub struct UnixWaker {
     inner: Arc<Mutex<UnixStream>>,
+    input_paused: Arc<AtomicBool>,
+    pushed_events: Arc<Mutex<VecDeque<Event>>>,
 }
 
+/// Written to the wake pipe by [`UnixWaker::wake`].
+const WAKE: u8 = 0;
+/// Written to the wake pipe when input is paused or resumed, which doesn't end a blocking read.
+const INPUT_PAUSE_CHANGED: u8 = 1;
+/// Written to the wake pipe when events are pushed, which a blocking read then returns.
+const EVENTS_PUSHED: u8 = 2;
+
 impl UnixWaker {
     /// Unblocks a pending [`EventReader::poll`](crate::EventReader::poll) call.
     pub fn wake(&self) -> io::Result<()> {
-        self.inner.lock().write_all(&[0])
+        self.inner.lock().write_all(&[WAKE])
+    }
+
+    /// Stops or resumes reading terminal input, including in a read which is currently blocked.
+    pub(crate) fn set_input_paused(&self, paused: bool) -> io::Result<()> {
+        if self.input_paused.swap(paused, Ordering::SeqCst) == paused {
+            return Ok(());
+        }
+        self.inner.lock().write_all(&[INPUT_PAUSE_CHANGED])
+    }
+
+    /// Returns `true` while terminal input is paused.
+    pub(crate) fn is_input_paused(&self) -> bool {
+        self.input_paused.load(Ordering::SeqCst)
+    }
+
+    /// Queues `events` as if they had been read from the terminal, after any parsed input, and
+    /// returns the number of events queued now.
+    ///
+    /// This doesn't take the reader's lock, so it may be called while a read is blocked, which
+    /// then returns the first of them.
+    pub(crate) fn push_events<I>(&self, events: I) -> usize
+    where
+        I: IntoIterator<Item = Event>,
+    {
+        let mut pushed_events = self.pushed_events.lock();
+        pushed_events.extend(events);
+        // A full pipe wakes the reader all the same.
+        let _ = self.inner.lock().write_all(&[EVENTS_PUSHED]);
+        pushed_events.len()
     }
 }
 
 impl UnixEventSource {
     pub(crate) fn new(read: FileDescriptor, write: FileDescriptor) -> io::Result<Self> {
-        let (sigwinch_pipe, sigwinch_pipe_write) = UnixStream::pair()?;
-        let sigwinch_id = signal_hook::low_level::pipe::register(
-            signal_hook::consts::SIGWINCH,
-            sigwinch_pipe_write,
-        )?;
-        sigwinch_pipe.set_nonblocking(true)?;
+        let mut source = Self::without_signals(read, write)?;
+        source.signals = vec![
+            SignalFlag::register(
+                signal_hook::consts::SIGWINCH,
+                SignalKind::Resize,
+                &source.signal_pipe_write,
+            )?,
+            SignalFlag::register(
+                signal_hook::consts::SIGCONT,
+                SignalKind::Resumed,
+                &source.signal_pipe_write,
+            )?,
+        ];
+        Ok(source)
+    }
+
+    /// Creates a source which doesn't report `SIGWINCH` and `SIGCONT`, for a terminal which isn't
+    /// the process's own, such as a `MemoryTerminal`. Signals enabled with
+    /// [`Self::enable_signal`] are still reported.
+    pub(crate) fn without_signals(read: FileDescriptor, write: FileDescriptor) -> io::Result<Self> {
+        let (signal_pipe, signal_pipe_write) = UnixStream::pair()?;
+        signal_pipe.set_nonblocking(true)?;
         let (wake_pipe, wake_pipe_write) = UnixStream::pair()?;
         wake_pipe.set_nonblocking(true)?;
         wake_pipe_write.set_nonblocking(true)?;
+        #[cfg(feature = "event-queue")]
+        let queue =
+            match queue::EventQueue::new([read.as_fd(), signal_pipe.as_fd(), wake_pipe.as_fd()]) {
+                Ok(queue) => Some(queue),
+                Err(_err) => {
+                    trace!(err = %_err, "can't register descriptors, falling back to poll");
+                    None
+                }
+            };
 
         Ok(Self {
             parser: Default::default(),
             read,
-            write,
-            sigwinch_id,
-            sigwinch_pipe,
+            read_buf: vec![0; MIN_READ_BUF_SIZE],
+            size_fd: write,
+            fallback_size: None,
+            last_size: None,
+            signals: Vec::new(),
+            signal_pipe,
+            signal_pipe_write,
             wake_pipe,
             wake_pipe_write: Arc::new(Mutex::new(wake_pipe_write)),
+            pushed_events: Default::default(),
+            input_paused: Default::default(),
+            wake_pending: false,
+            escape_timeout: None,
+            idle_peer: None,
+            #[cfg(feature = "event-queue")]
+            queue,
+            input_closed: false,
+            disconnect_pending: false,
         })
     }
-}
 
-impl Drop for UnixEventSource {
-    fn drop(&mut self) {
-        signal_hook::low_level::unregister(self.sigwinch_id);
+    /// Creates a source which never reads input, for a terminal which is not interactive.
+    ///
+    /// Signals, wakers and timeouts still work.
+    pub(crate) fn idle(write: FileDescriptor) -> io::Result<Self> {
+        let (read, idle_peer) = UnixStream::pair()?;
+        let mut source = Self::new(FileDescriptor::Owned(read.into()), write)?;
+        source.idle_peer = Some(idle_peer);
+        Ok(source)
+    }
+
+    /// Reports `signal` as [`Event::Signal`] instead of running its default action.
+    ///
+    /// Enabling a signal twice has no effect.
+    pub(crate) fn enable_signal(&mut self, signal: Signal) -> io::Result<()> {
+        let kind = SignalKind::Signal(signal);
+        if self.signals.iter().any(|flag| flag.kind == kind) {
+            return Ok(());
+        }
+        let number = match signal {
+            Signal::Interrupt => signal_hook::consts::SIGINT,
+            Signal::Terminate => signal_hook::consts::SIGTERM,
+            Signal::Hangup => signal_hook::consts::SIGHUP,
+            Signal::Quit => signal_hook::consts::SIGQUIT,
+        };
+        let flag = SignalFlag::register(number, kind, &self.signal_pipe_write)?;
+        self.signals.push(flag);
+        Ok(())
+    }
+
+    /// Sets the size reported for `SIGWINCH` if the terminal has none.
+    pub(crate) fn set_fallback_size(&mut self, size: Option<WindowSize>) {
+        self.fallback_size = size;
+    }
+
+    /// Returns the event for the first signal received since it was last reported.
+    fn take_signal(&mut self) -> io::Result<Option<Event>> {
+        // The window may have been resized while the process was stopped without the size being
+        // read, so a resize reported along with `SIGCONT` isn't compared to the earlier size.
+        let resumed = self
+            .signals
+            .iter()
+            .any(|flag| flag.kind == SignalKind::Resumed && flag.pending.load(Ordering::Relaxed));
+        if resumed {
+            self.last_size = None;
+        }
+        for flag in &self.signals {
+            if flag.pending.swap(false, Ordering::Relaxed) {
+                let event = match flag.kind {
+                    SignalKind::Resize => {
+                        let size = match window_size(self.size_fd.as_fd(), self.fallback_size) {
+                            Ok(size) => size,
+                            // A collapsed window is still reported, with its degenerate size.
+                            Err(err) => termios::tcgetwinsize(&self.size_fd)
+                                .map_err(|_| err)?
+                                .into(),
+                        };
+                        if self.last_size == Some(size) {
+                            continue;
+                        }
+                        self.last_size = Some(size);
+                        Event::WindowResized(size)
+                    }
+                    SignalKind::Resumed => Event::Resumed,
+                    SignalKind::Signal(signal) => Event::Signal(signal),
+                };
+                return Ok(Some(event));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Reads the available input into the parser.
+    ///
+    /// The input is read until no more is available, so that a large paste is taken in at once and
+    /// a reactor watching for new input isn't left with unread input. With an escape timeout, input ending in a lone ESC waits up to the timeout for the rest of
+    /// a sequence which a slow link may have split, before the ESC is resolved to the Escape key.
+    /// The wait ends early for a signal or a wakeup, and is skipped unless `wait_for_escape`.
+    fn read_input(&mut self, wait_for_escape: bool) -> io::Result<()> {
+        loop {
+            let buffer = &mut self.read_buf;
+            let read_count = match read_complete(&mut self.read, buffer) {
+                // `poll` reported the read side ready but no bytes are available. On a blocking
+                // fd (the `fionbio` call in the terminal module is disabled) that means
+                // end-of-file: the terminal input was closed, e.g. the pty master went away. A
+                // tty whose controlling terminal hung up fails with `EIO` instead.
+                Ok(0) => None,
+                Err(err)
+                    if rustix::io::Errno::from_io_error(&err) == Some(rustix::io::Errno::IO) =>
+                {
+                    None
+                }
+                result => Some(result?),
+            };
+            let Some(read_count) = read_count else {
+                self.close_input();
+                return Ok(());
+            };
+            let filled = read_count == buffer.len();
+            let more = self.has_more_input()?;
+            let escape_timeout = self.escape_timeout.filter(|_| wait_for_escape);
+            self.parser.parse(
+                &self.read_buf[..read_count],
+                more || escape_timeout.is_some(),
+            );
+            self.grow_read_buf(filled);
+            if more {
+                continue;
+            }
+            let Some(escape_timeout) = escape_timeout else {
+                return Ok(());
+            };
+            if !self.parser.is_escape_pending() {
+                return Ok(());
+            }
+            let deadline = Instant::now() + escape_timeout;
+            let read_ready = loop {
+                // A signal or wakeup is reported after the Escape key.
+                let signal_pending = self
+                    .signals
+                    .iter()
+                    .any(|flag| flag.pending.load(Ordering::Relaxed));
+                if signal_pending || self.wake_pending || self.input_paused.load(Ordering::SeqCst) {
+                    break false;
+                }
+                let leftover = deadline.saturating_duration_since(Instant::now());
+                match self.wait(Some(leftover)) {
+                    Ok([true, ..]) => break true,
+                    Ok([false, signal_ready, wake_ready]) => {
+                        if signal_ready {
+                            while read_complete(&self.signal_pipe, &mut [0; 1024])? != 0 {}
+                        }
+                        if wake_ready {
+                            self.wake_pending |= self.drain_wake_pipe()?;
+                        }
+                        if leftover.is_zero() {
+                            break false;
+                        }
+                    }
+                    Err(err) if err.kind() == io::ErrorKind::Interrupted => (),
+                    Err(err) => return Err(err),
+                }
+            };
+            if !read_ready {
+                self.parser.parse(&[], false);
+                return Ok(());
+            }
+        }
+    }
+
+    /// Returns `true` if more input can be read without blocking.
+    ///
+    /// The terminal input is a blocking fd (see the `fionbio` note in the terminal module), so
+    /// this stands in for reading until `EAGAIN`.
+    fn has_more_input(&self) -> io::Result<bool> {
+        if self.input_paused.load(Ordering::SeqCst) {
+            return Ok(false);
+        }
+        let input = self.read.as_fd();
+        loop {
+            match poll([input, input, input], Some(Duration::ZERO)) {
+                Ok([ready, ..]) => return Ok(ready),
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => (),
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Stops reading the closed terminal input and queues [`Event::Disconnected`].
+    ///
+    /// `poll` keeps reporting a closed input as readable, so waiting on it would busy-loop.
+    fn close_input(&mut self) {
+        // Resolve a lone ESC which can't be completed anymore.
+        self.parser.parse(&[], false);
+        self.input_closed = true;
+        self.disconnect_pending = true;
+    }
+
+    /// Doubles the input buffer after a read `filled` it, so that more input is pending and the
+    /// next read takes more of it in one call.
+    fn grow_read_buf(&mut self, filled: bool) {
+        if filled && self.read_buf.len() < MAX_READ_BUF_SIZE {
+            let len = (self.read_buf.len() * 2).min(MAX_READ_BUF_SIZE);
+            self.read_buf.resize(len, 0);
+        }
+    }
+
+    /// Drains the wake pipe, returning `true` if [`UnixWaker::wake`] was called.
+    ///
+    /// Pausing or resuming input only makes the next wait use the new set of descriptors, and
+    /// pushed events are returned by `pop_buffered`.
+    fn drain_wake_pipe(&self) -> io::Result<bool> {
+        let mut woken = false;
+        let mut buffer = [0; 1024];
+        loop {
+            let read_count = read_complete(&self.wake_pipe, &mut buffer)?;
+            if read_count == 0 {
+                return Ok(woken);
+            }
+            woken |= buffer[..read_count].contains(&WAKE);
+        }
+    }
+
+    /// Waits up to `timeout` for input, a signal or a wakeup, returning which of them are ready.
+    ///
+    /// Input is never reported as ready while it is paused or after it was closed.
+    fn wait(&mut self, timeout: Option<Duration>) -> io::Result<[bool; 3]> {
+        let paused = self.input_paused.load(Ordering::SeqCst) || self.input_closed;
+        #[cfg(feature = "event-queue")]
+        if let Some(queue) = &mut self.queue {
+            queue.set_input_enabled(self.read.as_fd(), !paused)?;
+            return queue.wait(timeout);
+        }
+        // While input is paused the signal pipe stands in for the input so it isn't polled.
+        let input = if paused {
+            self.signal_pipe.as_fd()
+        } else {
+            self.read.as_fd()
+        };
+        let [read_ready, signal_ready, wake_ready] = poll(
+            [input, self.signal_pipe.as_fd(), self.wake_pipe.as_fd()],
+            timeout,
+        )?;
+        Ok([read_ready && !paused, signal_ready, wake_ready])
+    }
+
+    /// Duplicates the file descriptors which `try_read` waits on.
+    ///
+    /// These are registered with an async reactor so that readiness of any of them wakes the
+    /// task driving an `EventStream`.
+    pub(crate) fn try_clone_pollable_fds(&self) -> io::Result<[std::os::fd::OwnedFd; 3]> {
+        Ok([
+            self.read.as_fd().try_clone_to_owned()?,
+            self.signal_pipe.as_fd().try_clone_to_owned()?,
+            self.wake_pipe.as_fd().try_clone_to_owned()?,
+        ])
     }
 }
 
 impl EventSource for UnixEventSource {
+    fn queued(&self) -> usize {
+        self.parser.queued()
+            + self.pushed_events.lock().len()
+            + usize::from(self.disconnect_pending)
+    }
+
+    fn clear(&mut self) {
+        self.parser.clear();
+        self.pushed_events.lock().clear();
+    }
+
+    fn parser_stats(&self) -> ParserStats {
+        self.parser.stats()
+    }
+
+    fn set_raw_tap(&mut self, tap: Option<RawTap>) {
+        self.parser.set_tap(tap);
+    }
+
+    fn set_escape_timeout(&mut self, timeout: Option<Duration>) {
+        self.escape_timeout = timeout;
+    }
+
+    fn set_c1_controls(&mut self, enabled: bool) {
+        self.parser.set_c1_controls(enabled);
+    }
+
     fn waker(&self) -> UnixWaker {
         UnixWaker {
             inner: self.wake_pipe_write.clone(),
+            input_paused: self.input_paused.clone(),
+            pushed_events: self.pushed_events.clone(),
         }
     }
 
+    fn pop_buffered(&mut self) -> Option<Event> {
+        if let Some(event) = self.parser.pop() {
+            return Some(event);
+        }
+        if let Some(event) = self.pushed_events.lock().pop_front() {
+            return Some(event);
+        }
+        // The disconnect is reported once the parser and pushed events are drained.
+        std::mem::take(&mut self.disconnect_pending).then_some(Event::Disconnected)
+    }
+
     fn try_read(&mut self, timeout: Option<Duration>) -> io::Result<Option<Event>> {
+        // A non-blocking read doesn't wait out the escape timeout.
+        let wait_for_escape = timeout != Some(Duration::ZERO);
         let timeout = PollTimeout::new(timeout);
 
         loop {
-            if let Some(event) = self.parser.pop() {
+            if let Some(event) = self.pop_buffered() {
                 return Ok(Some(event));
             }
+            // More than one signal may have been received since the pipe was drained.
+            if let Some(event) = self.take_signal()? {
+                return Ok(Some(event));
+            }
+            if std::mem::take(&mut self.wake_pending) {
+                return Err(WokenUp.into());
+            }
 
-            let [read_ready, sigwinch_ready, wake_ready] = match poll(
-                [
-                    self.read.as_fd(),
-                    self.sigwinch_pipe.as_fd(),
-                    self.wake_pipe.as_fd(),
-                ],
-                timeout.leftover(),
-            ) {
+            let [read_ready, signal_ready, wake_ready] = match self.wait(timeout.leftover()) {
                 Ok(ready) => ready,
                 Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
                 Err(err) => return Err(err),
             };
+            trace!(read_ready, signal_ready, wake_ready, "poll woke up");
 
-            // The input/read pipe has data.
-            if read_ready {
-                let mut buffer = [0u8; 1024];
-                let read_count = read_complete(&mut self.read, &mut buffer)?;
-                if read_count == 0 {
-                    // `poll` reported the read side ready but no bytes are available. On a blocking
-                    // fd (the `fionbio` call in the terminal module is disabled) that means
-                    // end-of-file: the terminal input was closed, e.g. the pty master went away.
-                    // Returning `Ok(None)` here would busy-loop at 100% CPU because `poll` keeps
-                    // reporting EOF as readable, so surface it as an error and let the caller stop.
-                    return Err(io::Error::new(
-                        io::ErrorKind::UnexpectedEof,
-                        "terminal input reached end-of-file",
-                    ));
-                }
-                self.parser
-                    .parse(&buffer[..read_count], read_count == buffer.len());
+            // The input/read pipe has data. Input which arrived after it was paused is left unread
+            // even if it woke this poll.
+            if read_ready && !self.input_paused.load(Ordering::SeqCst) {
+                self.read_input(wait_for_escape)?;
                 if let Some(event) = self.parser.pop() {
                     return Ok(Some(event));
                 }
             }
 
-            // SIGWINCH received.
-            if sigwinch_ready {
+            // A signal was received.
+            if signal_ready {
                 // Drain the pipe.
-                while read_complete(&self.sigwinch_pipe, &mut [0; 1024])? != 0 {}
+                while read_complete(&self.signal_pipe, &mut [0; 1024])? != 0 {}
 
-                let winsize = termios::tcgetwinsize(&self.write)?;
-                let event = Event::WindowResized(winsize.into());
-                return Ok(Some(event));
+                if let Some(event) = self.take_signal()? {
+                    return Ok(Some(event));
+                }
             }
 
             // Waker has awoken.
-            if wake_ready {
-                // Drain the pipe.
-                while read_complete(&self.wake_pipe, &mut [0; 1024])? != 0 {}
-
-                return Err(io::Error::new(
-                    io::ErrorKind::Interrupted,
-                    "Poll operation was woken up",
-                ));
+            if wake_ready && self.drain_wake_pipe()? {
+                return Err(WokenUp.into());
             }
 
             if timeout.leftover().is_some_and(|t| t.is_zero()) {
@@ -180,6 +671,10 @@ fn read_complete<F: Read>(mut file: F, buf: &mut [u8]) -> io::Result<usize> {
 /// This module is not meant to be generic. We consider `POLLIN` to be "ready" and do not look at
 /// other poll flags. For the sake of simplicity we also only allow polling exactly three FDs at
 /// a time - the exact amount we need for the event source.
+///
+/// With the `event-queue` feature the event source waits with epoll or kqueue instead, keeping
+/// the FDs registered, and only falls back to this where they can't be registered, such as
+/// `/dev/tty` on macOS.
 fn poll(fds: [BorrowedFd<'_>; 3], timeout: Option<Duration>) -> std::io::Result<[bool; 3]> {
     use rustix::event::Timespec;
 
@@ -234,3 +729,129 @@ fn poll(fds: [BorrowedFd<'_>; 3], timeout: Option<Duration>) -> std::io::Result<
     let timespec = timeout.map(|timeout| timeout.try_into().unwrap());
     poll_impl(fds, timespec.as_ref())
 }
+
+#[cfg(test)]
+mod test {
+    use std::thread;
+
+    use crate::event::{KeyCode, KeyEvent, Modifiers};
+
+    use super::*;
+
+    #[test]
+    fn read_large_paste() {
+        let (read, mut write) = UnixStream::pair().unwrap();
+        let mut source = UnixEventSource::new(
+            FileDescriptor::Owned(read.into()),
+            FileDescriptor::Borrowed(rustix::stdio::stdout()),
+        )
+        .unwrap();
+        let text = "a".repeat(256 * 1024);
+        let input = format!("\x1b[200~{text}\x1b[201~");
+        let writer = thread::spawn(move || write.write_all(input.as_bytes()));
+
+        let event = source.try_read(Some(Duration::from_secs(10))).unwrap();
+        writer.join().unwrap().unwrap();
+        assert_eq!(event, Some(Event::Paste(text)));
+        assert_eq!(source.read_buf.len(), MAX_READ_BUF_SIZE);
+    }
+
+    #[test]
+    fn read_until_no_input_is_left() {
+        let (read, mut write) = UnixStream::pair().unwrap();
+        let mut source = UnixEventSource::new(
+            FileDescriptor::Owned(read.into()),
+            FileDescriptor::Borrowed(rustix::stdio::stdout()),
+        )
+        .unwrap();
+        // More than the input buffer holds at first.
+        write.write_all(&[b'a'; 4 * MIN_READ_BUF_SIZE]).unwrap();
+
+        let event = source.try_read(Some(Duration::ZERO)).unwrap();
+        assert_eq!(
+            event,
+            Some(Event::Key(KeyEvent::new(
+                KeyCode::Char('a'),
+                Modifiers::NONE
+            )))
+        );
+        assert_eq!(source.queued(), 4 * MIN_READ_BUF_SIZE - 1);
+    }
+
+    #[test]
+    fn restore_default_action() {
+        let signal = signal_hook::consts::SIGQUIT;
+        let default_enabled = || {
+            let actions = DEFAULT_ACTIONS.lock();
+            let action = actions.iter().find(|action| action.signal == signal);
+            action.unwrap().enabled.load(Ordering::SeqCst)
+        };
+        let source = || UnixEventSource::idle(FileDescriptor::STDOUT).unwrap();
+
+        let mut first = source();
+        let mut second = source();
+        first.enable_signal(Signal::Quit).unwrap();
+        second.enable_signal(Signal::Quit).unwrap();
+        assert!(!default_enabled());
+        drop(first);
+        assert!(!default_enabled());
+        drop(second);
+        assert!(default_enabled());
+    }
+
+    #[test]
+    fn report_disconnect() {
+        let (read, mut write) = UnixStream::pair().unwrap();
+        let mut source = UnixEventSource::new(
+            FileDescriptor::Owned(read.into()),
+            FileDescriptor::Borrowed(rustix::stdio::stdout()),
+        )
+        .unwrap();
+        write.write_all(b"a\x1b").unwrap();
+        drop(write);
+
+        let timeout = Some(Duration::from_millis(10));
+        let key = |code| Some(Event::Key(KeyEvent::new(code, Modifiers::NONE)));
+        assert_eq!(source.try_read(timeout).unwrap(), key(KeyCode::Char('a')));
+        assert_eq!(source.try_read(timeout).unwrap(), key(KeyCode::Escape));
+        assert_eq!(source.try_read(timeout).unwrap(), Some(Event::Disconnected));
+        // The closed input isn't read again.
+        assert_eq!(source.try_read(timeout).unwrap(), None);
+    }
+
+    #[test]
+    fn coalesce_resizes() {
+        let (read, size_fd) = UnixStream::pair().unwrap();
+        let mut source = UnixEventSource::idle(FileDescriptor::Owned(size_fd.into())).unwrap();
+        drop(read);
+        let size = crate::test_size(80, 24);
+        // The socket has no window size, so the fallback is reported.
+        source.set_fallback_size(Some(size));
+        let raise = |source: &UnixEventSource, kind| {
+            let flag = source
+                .signals
+                .iter()
+                .find(|flag| flag.kind == kind)
+                .unwrap();
+            flag.pending.store(true, Ordering::Relaxed);
+        };
+
+        raise(&source, SignalKind::Resize);
+        assert_eq!(
+            source.take_signal().unwrap(),
+            Some(Event::WindowResized(size))
+        );
+        // A later signal of the same burst finds the size unchanged.
+        raise(&source, SignalKind::Resize);
+        assert_eq!(source.take_signal().unwrap(), None);
+
+        // The size is reported again after the process was stopped.
+        raise(&source, SignalKind::Resumed);
+        raise(&source, SignalKind::Resize);
+        assert_eq!(
+            source.take_signal().unwrap(),
+            Some(Event::WindowResized(size))
+        );
+        assert_eq!(source.take_signal().unwrap(), Some(Event::Resumed));
+    }
+}
@@ -0,0 +1,259 @@
+//! Waiting with epoll on Linux and Android, or kqueue on the BSDs and Apple platforms.
+//!
+//! `poll(2)` and `select(2)` are handed the descriptors on every call. An [`EventQueue`] keeps
+//! them registered with the kernel across reads instead, and only changes the registration of the
+//! terminal input when input is paused or resumed.
+//!
+//! Registrations are edge-triggered, so a descriptor is only reported again once more data
+//! arrives. The event source drains every descriptor which is reported: it reads the terminal
+//! input until no more is available and empties the signal and wake pipes. Input which is left
+//! unread while input is paused is reported when the input is registered again.
+//!
+//! kqueue can't register `/dev/tty` on macOS, so a source reading it falls back to `select(2)`.
+
+use std::{
+    io,
+    os::fd::{BorrowedFd, OwnedFd},
+    time::Duration,
+};
+
+/// The index of the terminal input among the descriptors of an [`EventQueue`].
+const INPUT: usize = 0;
+
+/// The terminal input, signal pipe and wake pipe of a `UnixEventSource`, registered with an epoll
+/// or kqueue instance.
+#[derive(Debug)]
+pub(super) struct EventQueue {
+    queue: OwnedFd,
+    /// Whether the terminal input is registered. It is not while input is paused.
+    input_enabled: bool,
+}
+
+impl EventQueue {
+    /// Registers `fds`, which [`Self::wait`] reports in the same order. The first is the terminal
+    /// input.
+    ///
+    /// Fails if the platform has neither epoll nor kqueue, or if a descriptor can't be registered,
+    /// such as a regular file which stdin was redirected from.
+    pub(super) fn new(fds: [BorrowedFd<'_>; 3]) -> io::Result<Self> {
+        let queue = Self {
+            queue: sys::create()?,
+            input_enabled: true,
+        };
+        for (index, fd) in fds.into_iter().enumerate() {
+            sys::add(&queue.queue, fd, index)?;
+        }
+        Ok(queue)
+    }
+
+    /// Registers the terminal input `input` again, or takes it out of the queue while input is
+    /// paused so that pending input doesn't end every wait.
+    pub(super) fn set_input_enabled(
+        &mut self,
+        input: BorrowedFd<'_>,
+        enabled: bool,
+    ) -> io::Result<()> {
+        if enabled == self.input_enabled {
+            return Ok(());
+        }
+        if enabled {
+            sys::add(&self.queue, input, INPUT)?;
+        } else {
+            sys::remove(&self.queue, input)?;
+        }
+        self.input_enabled = enabled;
+        Ok(())
+    }
+
+    /// Waits up to `timeout` for any of the descriptors to become readable, returning which are.
+    ///
+    /// A descriptor which was hung up or failed counts as readable so that reading it reports the
+    /// end-of-file or error.
+    pub(super) fn wait(&mut self, timeout: Option<Duration>) -> io::Result<[bool; 3]> {
+        sys::wait(&self.queue, timeout)
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod sys {
+    use std::{
+        io,
+        os::fd::{BorrowedFd, OwnedFd},
+        time::Duration,
+    };
+
+    use rustix::event::{
+        epoll::{self, Event, EventData, EventFlags},
+        Timespec,
+    };
+
+    pub(super) fn create() -> io::Result<OwnedFd> {
+        Ok(epoll::create(epoll::CreateFlags::CLOEXEC)?)
+    }
+
+    pub(super) fn add(queue: &OwnedFd, fd: BorrowedFd<'_>, index: usize) -> io::Result<()> {
+        let data = EventData::new_u64(index as u64);
+        // Adding a descriptor which is already readable reports it on the next wait.
+        Ok(epoll::add(
+            queue,
+            fd,
+            data,
+            EventFlags::IN | EventFlags::ET,
+        )?)
+    }
+
+    pub(super) fn remove(queue: &OwnedFd, fd: BorrowedFd<'_>) -> io::Result<()> {
+        // Modifying the registration to no events would still report hang-ups.
+        Ok(epoll::delete(queue, fd)?)
+    }
+
+    pub(super) fn wait(queue: &OwnedFd, timeout: Option<Duration>) -> io::Result<[bool; 3]> {
+        let timespec: Option<Timespec> = timeout.map(|timeout| timeout.try_into().unwrap());
+        let mut events = [Event {
+            flags: EventFlags::empty(),
+            data: EventData::new_u64(0),
+        }; 3];
+        let count = epoll::wait(queue, &mut events[..], timespec.as_ref())?;
+        let mut ready = [false; 3];
+        for event in &events[..count] {
+            let flags = event.flags;
+            if flags.intersects(EventFlags::IN | EventFlags::HUP | EventFlags::ERR) {
+                let data = event.data;
+                ready[data.u64() as usize] = true;
+            }
+        }
+        Ok(ready)
+    }
+}
+
+#[cfg(any(
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "dragonfly"
+))]
+mod sys {
+    use std::{
+        io,
+        os::fd::{AsRawFd as _, BorrowedFd, OwnedFd},
+        ptr,
+        time::Duration,
+    };
+
+    use rustix::event::kqueue::{self, Event, EventFilter, EventFlags};
+
+    pub(super) fn create() -> io::Result<OwnedFd> {
+        Ok(kqueue::kqueue()?)
+    }
+
+    /// Applies `flags` to the read filter of `fd`.
+    fn change(
+        queue: &OwnedFd,
+        fd: BorrowedFd<'_>,
+        flags: EventFlags,
+        index: usize,
+    ) -> io::Result<()> {
+        let change = Event::new(
+            EventFilter::Read(fd.as_raw_fd()),
+            flags,
+            index as *mut std::ffi::c_void,
+        );
+        // SAFETY: the descriptors belong to the event source, which also owns the queue.
+        unsafe { kqueue::kevent(queue, &[change], &mut [] as &mut [Event], None) }?;
+        Ok(())
+    }
+
+    pub(super) fn add(queue: &OwnedFd, fd: BorrowedFd<'_>, index: usize) -> io::Result<()> {
+        // Adding a filter which is already registered enables it again, and reports it on the
+        // next wait if it is readable.
+        let flags = EventFlags::ADD | EventFlags::ENABLE | EventFlags::CLEAR;
+        change(queue, fd, flags, index)
+    }
+
+    pub(super) fn remove(queue: &OwnedFd, fd: BorrowedFd<'_>) -> io::Result<()> {
+        // Only the terminal input is removed, so its index is known.
+        change(queue, fd, EventFlags::DISABLE, super::INPUT)
+    }
+
+    pub(super) fn wait(queue: &OwnedFd, timeout: Option<Duration>) -> io::Result<[bool; 3]> {
+        let mut events =
+            [Event::new(EventFilter::Read(0), EventFlags::empty(), ptr::null_mut()); 3];
+        // SAFETY: as in `change`.
+        let count = unsafe { kqueue::kevent(queue, &[], &mut events[..], timeout) }?;
+        let mut ready = [false; 3];
+        // Readable, end-of-file and errors are all reported as events.
+        for event in &events[..count] {
+            ready[event.udata() as usize] = true;
+        }
+        Ok(ready)
+    }
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "dragonfly"
+)))]
+mod sys {
+    use std::{
+        io,
+        os::fd::{BorrowedFd, OwnedFd},
+        time::Duration,
+    };
+
+    pub(super) fn create() -> io::Result<OwnedFd> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    pub(super) fn add(_: &OwnedFd, _: BorrowedFd<'_>, _: usize) -> io::Result<()> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    pub(super) fn remove(_: &OwnedFd, _: BorrowedFd<'_>) -> io::Result<()> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    pub(super) fn wait(_: &OwnedFd, _: Option<Duration>) -> io::Result<[bool; 3]> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        io::Write as _,
+        os::{fd::AsFd as _, unix::net::UnixStream},
+    };
+
+    use super::*;
+
+    #[test]
+    fn wait_for_registered_descriptors() {
+        let pairs = [(); 3].map(|()| UnixStream::pair().unwrap());
+        let fds = [pairs[0].0.as_fd(), pairs[1].0.as_fd(), pairs[2].0.as_fd()];
+        let mut queue = EventQueue::new(fds).unwrap();
+        let zero = Some(Duration::ZERO);
+        assert_eq!(queue.wait(zero).unwrap(), [false; 3]);
+
+        (&pairs[2].1).write_all(b"x").unwrap();
+        assert_eq!(queue.wait(zero).unwrap(), [false, false, true]);
+        // Unread data isn't reported again until more arrives.
+        assert_eq!(queue.wait(zero).unwrap(), [false; 3]);
+        (&pairs[2].1).write_all(b"x").unwrap();
+        assert_eq!(queue.wait(zero).unwrap(), [false, false, true]);
+
+        // Paused input doesn't end waits, and is reported again once resumed.
+        (&pairs[0].1).write_all(b"x").unwrap();
+        queue.set_input_enabled(pairs[0].0.as_fd(), false).unwrap();
+        assert_eq!(queue.wait(zero).unwrap(), [false; 3]);
+        queue.set_input_enabled(pairs[0].0.as_fd(), true).unwrap();
+        assert_eq!(queue.wait(zero).unwrap(), [true, false, false]);
+    }
+}
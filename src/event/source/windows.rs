@@ -3,19 +3,46 @@
 // Crossterm: <https://github.com/crossterm-rs/crossterm/blob/36d95b26a26e64b0f8c12edfe11f410a6d56a812/src/event/source/windows.rs>
 // Also see the necessary methods on the handle from the terminal module and the credit comment
 // there.
-use std::{io, os::windows::prelude::*, ptr, sync::Arc, time::Duration};
+use std::{
+    io,
+    os::windows::prelude::*,
+    ptr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 
 use windows_sys::Win32::System::Threading;
 
-use crate::{event::Event, parse::Parser, terminal::InputHandle, windows::InputReaderMode};
+use crate::{
+    event::Event,
+    parse::{Parser, ParserStats, RawTap},
+    terminal::InputHandle,
+    windows::InputReaderMode,
+};
 
-use super::{EventSource, PollTimeout};
+use super::{EventSource, PollTimeout, WokenUp};
+
+/// How often the pipe of an MSYS pty is checked for input. Unlike console handles, pipes can't be
+/// waited on.
+const PIPE_POLL_INTERVAL: Duration = Duration::from_millis(10);
 
 #[derive(Debug)]
 pub struct WindowsEventSource {
     input: InputHandle,
     parser: Parser,
     waker: Arc<EventHandle>,
+    /// Whether input is left unread, shared with the waker. See `EventReader::pause`.
+    input_paused: Arc<AtomicBool>,
+    /// Signaled when input is paused or resumed.
+    input_pause_changed: Arc<EventHandle>,
+    /// Set when a wakeup ends the wait for the rest of an escape sequence, and reported once the
+    /// events read until then are.
+    wake_pending: bool,
+    /// See `EventReader::set_escape_timeout`.
+    escape_timeout: Option<Duration>,
 }
 
 impl WindowsEventSource {
@@ -24,46 +51,212 @@ impl WindowsEventSource {
             input,
             parser: Parser::with_mode(mode),
             waker: Arc::new(EventHandle::new()?),
+            input_paused: Default::default(),
+            input_pause_changed: Arc::new(EventHandle::new()?),
+            wake_pending: false,
+            escape_timeout: None,
         })
     }
+
+    /// Sets whether key releases and repeats are reported.
+    pub(crate) fn set_report_event_types(&mut self, enabled: bool) {
+        self.parser.set_report_event_types(enabled);
+    }
+
+    /// Resolves a trailing ESC to the Escape key once no more input is queued.
+    ///
+    /// With an escape timeout, the ESC first waits up to the timeout for the rest of a sequence
+    /// which a slow link may have split. The wait ends early for a wakeup, or when input is paused,
+    /// and is skipped unless `wait_for_escape`.
+    fn resolve_escape(&mut self, wait_for_escape: bool) -> io::Result<()> {
+        loop {
+            // A sequence split across reads, such as a bracketed paste marker, continues in the
+            // next batch of records.
+            if self.input.has_pending_input_events()? {
+                return Ok(());
+            }
+            let Some(escape_timeout) = self
+                .escape_timeout
+                .filter(|_| wait_for_escape && self.parser.is_escape_pending())
+            else {
+                self.parser.parse(&[], false);
+                return Ok(());
+            };
+            if !self.wait_for_input(escape_timeout)? || self.input_paused.load(Ordering::SeqCst) {
+                self.parser.parse(&[], false);
+                return Ok(());
+            }
+            self.read_input()?;
+        }
+    }
+
+    /// Waits up to `timeout` for input to be queued. The pipe of an MSYS pty is polled instead.
+    ///
+    /// Returns `false` if the timeout elapses, or if a wakeup or a pause of the input ends the wait
+    /// first. A wakeup is kept in `wake_pending` to be reported later.
+    fn wait_for_input(&mut self, timeout: Duration) -> io::Result<bool> {
+        use windows_sys::Win32::Foundation::{WAIT_FAILED, WAIT_OBJECT_0};
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            if self.input.has_pending_input_events()? {
+                return Ok(true);
+            }
+            let leftover = deadline.saturating_duration_since(Instant::now());
+            if leftover.is_zero() {
+                return Ok(false);
+            }
+            let mut handles = [
+                self.waker.as_raw_handle(),
+                self.input_pause_changed.as_raw_handle(),
+                self.input.as_raw_handle(),
+            ];
+            let (count, wait) = if self.input.is_pipe() {
+                (2, leftover.min(PIPE_POLL_INTERVAL))
+            } else {
+                (3, leftover)
+            };
+            let result = unsafe {
+                Threading::WaitForMultipleObjects(
+                    count,
+                    handles.as_mut_ptr(),
+                    0,
+                    wait.as_millis() as u32,
+                )
+            };
+            if result == WAIT_OBJECT_0 {
+                self.wake_pending = true;
+                return Ok(false);
+            } else if result == WAIT_OBJECT_0 + 1 {
+                return Ok(false);
+            } else if result == WAIT_FAILED {
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    format!(
+                        "failed to poll input handles: {}",
+                        io::Error::last_os_error()
+                    ),
+                ));
+            }
+        }
+    }
+
+    /// Reads queued input records, or bytes from the pipe of an MSYS pty, into the parser.
+    ///
+    /// This blocks if nothing is queued. The read may also come back empty.
+    fn read_input(&mut self) -> io::Result<()> {
+        if self.input.is_pipe() {
+            let bytes = self.input.read_pipe()?;
+            self.parser.parse(bytes, true);
+        } else {
+            let records = self.input.read_console_input()?;
+            self.parser.decode_input_records(records);
+        }
+        Ok(())
+    }
 }
 
 impl EventSource for WindowsEventSource {
+    fn queued(&self) -> usize {
+        self.parser.queued()
+    }
+
+    fn clear(&mut self) {
+        self.parser.clear();
+    }
+
+    fn parser_stats(&self) -> ParserStats {
+        self.parser.stats()
+    }
+
+    fn set_raw_tap(&mut self, tap: Option<RawTap>) {
+        self.parser.set_tap(tap);
+    }
+
+    fn set_escape_timeout(&mut self, timeout: Option<Duration>) {
+        self.escape_timeout = timeout;
+    }
+
+    fn set_c1_controls(&mut self, enabled: bool) {
+        self.parser.set_c1_controls(enabled);
+    }
+
     fn waker(&self) -> WindowsWaker {
         WindowsWaker {
             handle: self.waker.clone(),
+            input_paused: self.input_paused.clone(),
+            input_pause_changed: self.input_pause_changed.clone(),
         }
     }
 
+    fn pop_buffered(&mut self) -> Option<Event> {
+        self.parser.pop()
+    }
+
     fn try_read(&mut self, timeout: Option<Duration>) -> io::Result<Option<Event>> {
         use windows_sys::Win32::Foundation::{WAIT_FAILED, WAIT_OBJECT_0};
         use Threading::{WaitForMultipleObjects, INFINITE};
 
+        // A non-blocking read doesn't wait out the escape timeout.
+        let wait_for_escape = timeout != Some(Duration::ZERO);
         let timeout = PollTimeout::new(timeout);
 
         loop {
-            if let Some(event) = self.parser.pop() {
+            if let Some(event) = self.pop_buffered() {
                 return Ok(Some(event));
             }
+            if std::mem::take(&mut self.wake_pending) {
+                return Err(WokenUp.into());
+            }
 
-            if !self.input.has_pending_input_events()? {
-                let mut handles = [self.input.as_raw_handle(), self.waker.as_raw_handle()];
-                let wait = timeout
-                    .leftover()
-                    .map(|timeout| timeout.as_millis() as u32)
-                    .unwrap_or(INFINITE);
-                let result = unsafe {
-                    WaitForMultipleObjects(handles.len() as u32, handles.as_mut_ptr(), 0, wait)
+            let input_paused = self.input_paused.load(Ordering::SeqCst);
+            if input_paused || !self.input.has_pending_input_events()? {
+                let mut handles = [
+                    self.waker.as_raw_handle(),
+                    self.input_pause_changed.as_raw_handle(),
+                    self.input.as_raw_handle(),
+                ];
+                // While input is paused the input handle is left out so it isn't read. A pipe
+                // can't be waited on at all, so it is checked again after a short wait instead.
+                let poll_pipe = self.input.is_pipe() && !input_paused;
+                let count = if input_paused || self.input.is_pipe() {
+                    2
+                } else {
+                    3
+                };
+                let wait = match timeout.leftover() {
+                    Some(timeout) if poll_pipe => {
+                        timeout.min(PIPE_POLL_INTERVAL).as_millis() as u32
+                    }
+                    Some(timeout) => timeout.as_millis() as u32,
+                    None if poll_pipe => PIPE_POLL_INTERVAL.as_millis() as u32,
+                    None => INFINITE,
                 };
+                let result =
+                    unsafe { WaitForMultipleObjects(count, handles.as_mut_ptr(), 0, wait) };
+                trace!(result, "poll woke up");
 
                 if result == WAIT_OBJECT_0 {
-                    // The input handle is signaled: there is input ready to be read. Fall through
-                    // to `read_console_input` below.
+                    return Err(WokenUp.into());
                 } else if result == WAIT_OBJECT_0 + 1 {
-                    return Err(io::Error::new(
-                        io::ErrorKind::Interrupted,
-                        "Poll operation was woken up",
-                    ));
+                    // Input was paused or resumed: wait again with the new set of handles.
+                    if timeout.elapsed() {
+                        return Ok(None);
+                    }
+                    continue;
+                } else if result == WAIT_OBJECT_0 + 2 {
+                    // The input handle is signaled: there is input ready to be read. Another
+                    // reader of the console in this process may take it first though, and reading
+                    // an empty buffer would block without honoring the timeout or the waker. Input
+                    // may also have been paused since the wait began.
+                    if self.input_paused.load(Ordering::SeqCst)
+                        || !self.input.has_pending_input_events()?
+                    {
+                        if timeout.elapsed() {
+                            return Ok(None);
+                        }
+                        continue;
+                    }
                 } else if result == WAIT_FAILED {
                     return Err(io::Error::new(
                         io::ErrorKind::Other,
@@ -72,15 +265,18 @@ impl EventSource for WindowsEventSource {
                             io::Error::last_os_error()
                         ),
                     ));
+                } else if poll_pipe && !timeout.elapsed() {
+                    // The poll interval passed: check the pipe again.
+                    continue;
                 } else {
                     // `WAIT_TIMEOUT` (or an abandoned handle): no event arrived within the timeout.
                     return Ok(None);
                 }
             }
 
-            let records = self.input.read_console_input()?;
-
-            self.parser.decode_input_records(records);
+            // This may be empty, in which case we wait again.
+            self.read_input()?;
+            self.resolve_escape(wait_for_escape)?;
 
             // Decoding the records may have produced an event (a key press, a resize, a parsed VT
             // sequence). Return it before honoring a zero timeout, otherwise a non-blocking poll
@@ -128,15 +324,34 @@ impl AsRawHandle for EventHandle {
 #[derive(Debug, Clone)]
 pub struct WindowsWaker {
     handle: Arc<EventHandle>,
+    input_paused: Arc<AtomicBool>,
+    input_pause_changed: Arc<EventHandle>,
 }
 
 impl WindowsWaker {
     /// Unblocks a pending [`EventReader::poll`](crate::EventReader::poll) call.
     pub fn wake(&self) -> io::Result<()> {
-        if unsafe { Threading::SetEvent(self.handle.as_raw_handle()) } == 0 {
-            Err(io::Error::last_os_error())
-        } else {
-            Ok(())
+        set_event(&self.handle)
+    }
+
+    /// Stops or resumes reading console input, including in a read which is currently blocked.
+    pub(crate) fn set_input_paused(&self, paused: bool) -> io::Result<()> {
+        if self.input_paused.swap(paused, Ordering::SeqCst) == paused {
+            return Ok(());
         }
+        set_event(&self.input_pause_changed)
+    }
+
+    /// Returns `true` while console input is paused.
+    pub(crate) fn is_input_paused(&self) -> bool {
+        self.input_paused.load(Ordering::SeqCst)
+    }
+}
+
+fn set_event(event: &EventHandle) -> io::Result<()> {
+    if unsafe { Threading::SetEvent(event.as_raw_handle()) } == 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(())
     }
 }
@@ -4,6 +4,12 @@
 //! [`EventReader`] API into a stream by parking a helper thread on the platform event source and
 //! waking the async task when matching input arrives.
 //!
+//! With the `tokio` feature on Unix, streams created inside a Tokio runtime instead register the
+//! terminal input, the `SIGWINCH` pipe and the waker pipe with Tokio's reactor. No helper thread
+//! is spawned and the task is only woken when one of those becomes readable. If registration
+//! fails, for example because the platform's reactor can't watch `/dev/tty` (as with kqueue on
+//! macOS), the stream falls back to the helper thread.
+//!
 //! # Implementation Notes
 //!
 //! This is intentionally close to [crossterm's event stream]. The Termina-specific part is the
@@ -26,6 +32,8 @@ use std::{
 };
 
 use futures_core::Stream;
+#[cfg(all(unix, feature = "tokio"))]
+use tokio::io::unix::AsyncFd;
 
 use super::{reader::EventReader, source::PlatformWaker, Event};
 
@@ -36,6 +44,9 @@ use super::{reader::EventReader, source::PlatformWaker, Event};
 /// Create an event stream for a terminal by passing the reader from
 /// [`crate::Terminal::event_reader`] into [`EventStream::new`] with a filter.
 ///
+/// The filter is stored type-erased, so `EventStream` has no type parameters and is `Send` and
+/// `Sync`. It can be stored in structs and moved between async tasks.
+///
 /// # Examples
 ///
 /// Requires the `event-stream` feature and an async runtime.
@@ -56,12 +67,23 @@ use super::{reader::EventReader, source::PlatformWaker, Event};
 /// # }
 /// ```
 pub struct EventStream {
-    waker: PlatformWaker,
-    filter: Arc<dyn Fn(&Event) -> bool>,
+    filter: Arc<dyn Fn(&Event) -> bool + Send + Sync>,
     reader: EventReader,
-    stream_wake_task_executed: Arc<AtomicBool>,
-    stream_wake_task_should_shutdown: Arc<AtomicBool>,
-    task_sender: SyncSender<Task>,
+    backend: Backend,
+}
+
+/// How the stream learns that the reader may have new events.
+enum Backend {
+    /// A helper thread blocks in [`EventReader::poll`] and wakes the task.
+    Thread {
+        waker: PlatformWaker,
+        stream_wake_task_executed: Arc<AtomicBool>,
+        stream_wake_task_should_shutdown: Arc<AtomicBool>,
+        task_sender: SyncSender<Task>,
+    },
+    /// The event source's file descriptors are registered with Tokio's reactor.
+    #[cfg(all(unix, feature = "tokio"))]
+    Reactor(Vec<AsyncFd<std::os::fd::OwnedFd>>),
 }
 
 /// Internal task handed to the helper thread managing the blocking poll.
@@ -78,13 +100,50 @@ impl EventStream {
     where
         F: Fn(&Event) -> bool + Send + Sync + 'static,
     {
-        let filter = Arc::new(filter);
+        let filter: Arc<dyn Fn(&Event) -> bool + Send + Sync> = Arc::new(filter);
+
+        #[cfg(all(unix, feature = "tokio"))]
+        if let Some(fds) = register_with_reactor(&reader) {
+            return Self {
+                filter,
+                reader,
+                backend: Backend::Reactor(fds),
+            };
+        }
+
+        let backend = Backend::spawn_thread(&reader, filter.clone());
+        Self {
+            filter,
+            reader,
+            backend,
+        }
+    }
+}
+
+/// Registers the reader's file descriptors with the current Tokio runtime's reactor.
+///
+/// Returns `None` when called outside of a runtime or when the reactor can't watch one of the
+/// descriptors.
+#[cfg(all(unix, feature = "tokio"))]
+fn register_with_reactor(reader: &EventReader) -> Option<Vec<AsyncFd<std::os::fd::OwnedFd>>> {
+    tokio::runtime::Handle::try_current().ok()?;
+    let fds = reader.try_clone_pollable_fds().ok()?;
+    fds.into_iter()
+        .map(|fd| AsyncFd::with_interest(fd, tokio::io::Interest::READABLE).ok())
+        .collect()
+}
+
+impl Backend {
+    fn spawn_thread(
+        reader: &EventReader,
+        filter: Arc<dyn Fn(&Event) -> bool + Send + Sync>,
+    ) -> Self {
         let waker = reader.waker();
 
         let (task_sender, receiver) = mpsc::sync_channel::<Task>(1);
 
         let task_reader = reader.clone();
-        let task_filter = filter.clone();
+        let task_filter = filter;
         thread::spawn(move || {
             while let Ok(task) = receiver.recv() {
                 loop {
@@ -101,22 +160,79 @@ impl EventStream {
             }
         });
 
-        Self {
+        Self::Thread {
             waker,
-            filter,
-            reader,
             stream_wake_task_executed: Default::default(),
             stream_wake_task_should_shutdown: Default::default(),
             task_sender,
         }
     }
+
+    /// Arranges for the task in `cx` to be woken when the reader may have a new event.
+    ///
+    /// Returns `Poll::Ready(Ok(()))` if the reader should be polled again immediately.
+    fn poll_wake(&self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self {
+            Self::Thread {
+                stream_wake_task_executed,
+                stream_wake_task_should_shutdown,
+                task_sender,
+                ..
+            } => {
+                if !stream_wake_task_executed
+                    .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+                    .unwrap_or_else(|x| x)
+                {
+                    stream_wake_task_should_shutdown.store(false, Ordering::SeqCst);
+                    let _ = task_sender.send(Task {
+                        stream_waker: cx.waker().clone(),
+                        stream_wake_task_executed: stream_wake_task_executed.clone(),
+                        stream_wake_task_should_shutdown: stream_wake_task_should_shutdown.clone(),
+                    });
+                }
+                Poll::Pending
+            }
+            #[cfg(all(unix, feature = "tokio"))]
+            Self::Reactor(fds) => {
+                let mut ready = false;
+                for fd in fds {
+                    match fd.poll_read_ready(cx) {
+                        // The reader reads until no input is left and found no event, so the
+                        // readiness is stale. Clearing it only discards readiness observed before
+                        // the guard was created, so input arriving concurrently still wakes the
+                        // task.
+                        Poll::Ready(Ok(mut guard)) => {
+                            guard.clear_ready();
+                            ready = true;
+                        }
+                        Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                        Poll::Pending => (),
+                    }
+                }
+                if ready {
+                    Poll::Ready(Ok(()))
+                } else {
+                    Poll::Pending
+                }
+            }
+        }
+    }
 }
 
 impl Drop for EventStream {
     fn drop(&mut self) {
-        self.stream_wake_task_should_shutdown
-            .store(true, Ordering::SeqCst);
-        let _ = self.waker.wake();
+        match &self.backend {
+            Backend::Thread {
+                waker,
+                stream_wake_task_should_shutdown,
+                ..
+            } => {
+                stream_wake_task_should_shutdown.store(true, Ordering::SeqCst);
+                let _ = waker.wake();
+            }
+            #[cfg(all(unix, feature = "tokio"))]
+            Backend::Reactor(_) => (),
+        }
     }
 }
 
@@ -124,33 +240,91 @@ impl Stream for EventStream {
     type Item = io::Result<Event>;
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        match self
-            .reader
-            .poll(Some(Duration::from_secs(0)), &*self.filter)
-        {
-            Ok(true) => match self.reader.read(&*self.filter) {
-                Ok(event) => Poll::Ready(Some(Ok(event))),
-                Err(err) => Poll::Ready(Some(Err(err))),
-            },
-            Ok(false) => {
-                if !self
-                    .stream_wake_task_executed
-                    .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
-                    .unwrap_or_else(|x| x)
-                {
-                    self.stream_wake_task_should_shutdown
-                        .store(false, Ordering::SeqCst);
-                    let _ = self.task_sender.send(Task {
-                        stream_waker: cx.waker().clone(),
-                        stream_wake_task_executed: self.stream_wake_task_executed.clone(),
-                        stream_wake_task_should_shutdown: self
-                            .stream_wake_task_should_shutdown
-                            .clone(),
-                    });
+        loop {
+            match self
+                .reader
+                .poll(Some(Duration::from_secs(0)), &*self.filter)
+            {
+                Ok(true) => {
+                    return match self.reader.read(&*self.filter) {
+                        Ok(event) => Poll::Ready(Some(Ok(event))),
+                        Err(err) => Poll::Ready(Some(Err(err))),
+                    }
                 }
-                Poll::Pending
+                Ok(false) => match self.backend.poll_wake(cx) {
+                    Poll::Ready(Ok(())) => continue,
+                    Poll::Ready(Err(err)) => return Poll::Ready(Some(Err(err))),
+                    Poll::Pending => return Poll::Pending,
+                },
+                Err(err) => return Poll::Ready(Some(Err(err))),
             }
-            Err(err) => Poll::Ready(Some(Err(err))),
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn event_stream_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<EventStream>();
+    }
+
+    #[cfg(all(unix, feature = "tokio"))]
+    #[test]
+    fn reactor_stream() {
+        use std::{future::poll_fn, io::Write as _};
+
+        use crate::event::{reader::test_reader, KeyCode};
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_io()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let (reader, mut input) = test_reader();
+            let waker = reader.waker();
+            let mut stream = EventStream::new(reader, |event| matches!(event, Event::Key(_)));
+            assert!(matches!(stream.backend, Backend::Reactor(_)));
+
+            // Spurious wakeups don't end the stream.
+            waker.wake().unwrap();
+            input.write_all(b"x").unwrap();
+            let event = poll_fn(|cx| Pin::new(&mut stream).poll_next(cx))
+                .await
+                .unwrap()
+                .unwrap();
+            assert!(matches!(event, Event::Key(key) if key.code == KeyCode::Char('x')));
+        });
+    }
+
+    #[cfg(all(unix, feature = "tokio"))]
+    #[test]
+    fn reactor_stream_large_paste() {
+        use std::{future::poll_fn, io::Write as _};
+
+        use crate::event::reader::test_reader;
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_io()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let (reader, mut input) = test_reader();
+            // Larger than the input buffer, so it takes several reads after a single wakeup.
+            let text = "a".repeat(100 * 1024);
+            let paste = format!("\x1b[200~{text}\x1b[201~");
+            input.write_all(paste.as_bytes()).unwrap();
+
+            let mut stream = EventStream::new(reader, |_| true);
+            assert!(matches!(stream.backend, Backend::Reactor(_)));
+            let event = poll_fn(|cx| Pin::new(&mut stream).poll_next(cx))
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(event, Event::Paste(text));
+        });
+    }
+}
@@ -0,0 +1,32 @@
+//! Adapters for driving an [`EventReader`] from an existing event loop.
+//!
+//! Applications which already run an event loop, such as GUI-adjacent programs built on
+//! [calloop] or async programs built on [smol], can't dedicate a thread to a blocking
+//! [`EventReader::read`]. Instead they wait for the reader's input to become readable and then
+//! drain it without blocking. On Unix the reader exposes the two pieces needed for that:
+//!
+//! - `EventReader::try_clone_pollable_fds` returns the file descriptors to wait on.
+//! - [`EventReader::try_read`] returns the next available event without blocking.
+//!
+//! The adapters in this module package those pieces for specific event loops. Each is behind a
+//! feature flag named after the event loop and is only available on Unix:
+//!
+//! - `calloop`: `calloop::EventSourceAdapter`, a calloop event source.
+//! - `smol`: `smol::AsyncEventReader`, an async reader for the smol (async-io) reactor.
+//!
+//! The tokio reactor is supported by `EventStream` with the `tokio` feature.
+//!
+//! Don't mix an adapter with blocking reads of the same reader on another thread: input consumed
+//! by the blocking read doesn't make the adapter's descriptors readable again.
+//!
+//! [calloop]: https://docs.rs/calloop
+//! [smol]: https://docs.rs/smol
+
+#[cfg(doc)]
+use crate::EventReader;
+
+#[cfg(all(unix, feature = "calloop"))]
+pub mod calloop;
+
+#[cfg(all(unix, feature = "smol"))]
+pub mod smol;
@@ -0,0 +1,187 @@
+//! A [calloop] event source for terminal events.
+//!
+//! This module is available with the `calloop` feature on Unix.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::io;
+//!
+//! use calloop::EventLoop;
+//! use termina::{integration::calloop::EventSourceAdapter, Event, PlatformTerminal, Terminal};
+//!
+//! fn main() -> io::Result<()> {
+//!     let mut terminal = PlatformTerminal::new()?;
+//!     terminal.enter_raw_mode()?;
+//!
+//!     let error = |err| io::Error::new(io::ErrorKind::Other, err);
+//!     let mut event_loop: EventLoop<bool> = EventLoop::try_new().map_err(error)?;
+//!     let source = EventSourceAdapter::new(terminal.event_reader(), |_| true)?;
+//!     event_loop
+//!         .handle()
+//!         .insert_source(source, |event, _, done| {
+//!             *done = matches!(event, Event::Key(_));
+//!         })
+//!         .map_err(|err| error(err.error))?;
+//!
+//!     let mut done = false;
+//!     while !done {
+//!         event_loop
+//!             .dispatch(None, &mut done)
+//!             .map_err(error)?;
+//!     }
+//!     terminal.enter_cooked_mode()
+//! }
+//! ```
+//!
+//! [calloop]: https://docs.rs/calloop
+
+use std::{io, os::fd::OwnedFd};
+
+use calloop::{
+    generic::Generic, EventSource, Interest, Mode, Poll, PostAction, Readiness, Token, TokenFactory,
+};
+
+use crate::{Event, EventReader};
+
+/// A calloop [`EventSource`] which produces terminal events.
+///
+/// The adapter registers the reader's input, `SIGWINCH` pipe and waker pipe with the event loop.
+/// When any of them is readable it drains every available event matching its filter and passes
+/// each to the callback. Events rejected by the filter stay buffered in the [`EventReader`].
+///
+/// Events already buffered in the reader when the adapter is inserted are delivered the next time
+/// input arrives.
+pub struct EventSourceAdapter {
+    reader: EventReader,
+    filter: Box<dyn FnMut(&Event) -> bool>,
+    sources: Vec<Generic<OwnedFd>>,
+}
+
+impl std::fmt::Debug for EventSourceAdapter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventSourceAdapter")
+            .field("reader", &self.reader)
+            .finish_non_exhaustive()
+    }
+}
+
+impl EventSourceAdapter {
+    /// Creates an event source which delivers events from `reader` matching `filter`.
+    pub fn new(
+        reader: EventReader,
+        filter: impl FnMut(&Event) -> bool + 'static,
+    ) -> io::Result<Self> {
+        let sources = reader
+            .try_clone_pollable_fds()?
+            .into_iter()
+            .map(|fd| Generic::new(fd, Interest::READ, Mode::Level))
+            .collect();
+        Ok(Self {
+            reader,
+            filter: Box::new(filter),
+            sources,
+        })
+    }
+
+    /// Returns the reader this source drains.
+    pub fn reader(&self) -> &EventReader {
+        &self.reader
+    }
+}
+
+impl EventSource for EventSourceAdapter {
+    type Event = Event;
+    type Metadata = ();
+    type Ret = ();
+    type Error = io::Error;
+
+    fn process_events<F>(
+        &mut self,
+        readiness: Readiness,
+        token: Token,
+        mut callback: F,
+    ) -> io::Result<PostAction>
+    where
+        F: FnMut(Self::Event, &mut Self::Metadata) -> Self::Ret,
+    {
+        let mut ready = false;
+        for source in &mut self.sources {
+            source.process_events(readiness, token, |_, _| {
+                ready = true;
+                Ok(PostAction::Continue)
+            })?;
+        }
+        if ready {
+            while let Some(event) = self.reader.try_read(&mut self.filter)? {
+                callback(event, &mut ());
+            }
+        }
+        Ok(PostAction::Continue)
+    }
+
+    fn register(
+        &mut self,
+        poll: &mut Poll,
+        token_factory: &mut TokenFactory,
+    ) -> calloop::Result<()> {
+        for source in &mut self.sources {
+            source.register(poll, token_factory)?;
+        }
+        Ok(())
+    }
+
+    fn reregister(
+        &mut self,
+        poll: &mut Poll,
+        token_factory: &mut TokenFactory,
+    ) -> calloop::Result<()> {
+        for source in &mut self.sources {
+            source.reregister(poll, token_factory)?;
+        }
+        Ok(())
+    }
+
+    fn unregister(&mut self, poll: &mut Poll) -> calloop::Result<()> {
+        for source in &mut self.sources {
+            source.unregister(poll)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{io::Write as _, time::Duration};
+
+    use calloop::EventLoop;
+
+    use super::*;
+    use crate::event::{reader::test_reader, KeyCode};
+
+    #[test]
+    fn dispatch_events() {
+        let (reader, mut input) = test_reader();
+        let mut event_loop: EventLoop<Vec<Event>> = EventLoop::try_new().unwrap();
+        let source =
+            EventSourceAdapter::new(reader, |event| matches!(event, Event::Key(_))).unwrap();
+        event_loop
+            .handle()
+            .insert_source(source, |event, _, events| events.push(event))
+            .unwrap();
+
+        input.write_all(b"ab").unwrap();
+        let mut events = Vec::new();
+        event_loop
+            .dispatch(Some(Duration::from_secs(1)), &mut events)
+            .unwrap();
+        let codes: Vec<_> = events
+            .iter()
+            .map(|event| match event {
+                Event::Key(key) => key.code,
+                _ => unreachable!(),
+            })
+            .collect();
+        assert_eq!(codes, [KeyCode::Char('a'), KeyCode::Char('b')]);
+    }
+}
@@ -0,0 +1,104 @@
+//! An async terminal event reader for the [smol] runtime.
+//!
+//! This module is available with the `smol` feature on Unix. The reader waits for input with the
+//! [async-io] reactor which smol and async-std run on, so no helper thread is needed.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::io;
+//!
+//! use termina::{integration::smol::AsyncEventReader, Event, PlatformTerminal, Terminal};
+//!
+//! async fn wait_for_key() -> io::Result<Event> {
+//!     let terminal = PlatformTerminal::new()?;
+//!     let reader = AsyncEventReader::new(terminal.event_reader())?;
+//!     reader.read(|event| matches!(event, Event::Key(_))).await
+//! }
+//! ```
+//!
+//! [smol]: https://docs.rs/smol
+//! [async-io]: https://docs.rs/async-io
+
+use std::{future, io, os::fd::OwnedFd, task::Poll};
+
+use async_io::Async;
+
+use crate::{Event, EventReader};
+
+/// An [`EventReader`] driven by the async-io reactor.
+#[derive(Debug)]
+pub struct AsyncEventReader {
+    reader: EventReader,
+    fds: Vec<Async<OwnedFd>>,
+}
+
+impl AsyncEventReader {
+    /// Registers `reader`'s descriptors with the async-io reactor.
+    pub fn new(reader: EventReader) -> io::Result<Self> {
+        let fds = reader
+            .try_clone_pollable_fds()?
+            .into_iter()
+            // The descriptors share their file description with the reader, which expects
+            // blocking reads.
+            .map(Async::new_nonblocking)
+            .collect::<io::Result<_>>()?;
+        Ok(Self { reader, fds })
+    }
+
+    /// Returns the underlying reader.
+    pub fn reader(&self) -> &EventReader {
+        &self.reader
+    }
+
+    /// Waits for an event matching `filter`.
+    ///
+    /// Events rejected by `filter` are retained for later reads, as with [`EventReader::read`].
+    /// Dropping the returned future before it completes doesn't lose any events.
+    pub async fn read<F>(&self, mut filter: F) -> io::Result<Event>
+    where
+        F: FnMut(&Event) -> bool,
+    {
+        loop {
+            if let Some(event) = self.reader.try_read(&mut filter)? {
+                return Ok(event);
+            }
+            future::poll_fn(|cx| {
+                for fd in &self.fds {
+                    if let Poll::Ready(result) = fd.poll_readable(cx) {
+                        return Poll::Ready(result);
+                    }
+                }
+                Poll::Pending
+            })
+            .await?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Write as _;
+
+    use super::*;
+    use crate::event::{reader::test_reader, KeyCode};
+
+    #[test]
+    fn read_events() {
+        let (reader, mut input) = test_reader();
+        let reader = AsyncEventReader::new(reader).unwrap();
+        let writer = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            input.write_all(b"ab").unwrap();
+            input
+        });
+        let is_b =
+            |event: &Event| matches!(event, Event::Key(key) if key.code == KeyCode::Char('b'));
+        let event = async_io::block_on(reader.read(is_b)).unwrap();
+        assert!(is_b(&event));
+        let _input = writer.join().unwrap();
+        // The rejected event stays buffered.
+        let event = async_io::block_on(reader.read(|_| true)).unwrap();
+        assert!(matches!(event, Event::Key(key) if key.code == KeyCode::Char('a')));
+    }
+}
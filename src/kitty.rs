@@ -0,0 +1,294 @@
+//! Reference data for the Kitty keyboard protocol.
+//!
+//! [`KEY_TEST_VECTORS`] pairs raw input sequences with the [`KeyEvent`] that Termina's [`Parser`]
+//! produces for them. The vectors are public so that downstream crates can check their own key
+//! handling (keymaps, key-to-string conversions, test harnesses that synthesize input) against the
+//! same data Termina's parser is tested with.
+//!
+//! Each vector records the [`KittyKeyboardFlags`] which must be pushed for a terminal to send that
+//! sequence. Sequences which are valid without any enhancement flags use
+//! [`KittyKeyboardFlags::NONE`].
+//!
+//! # Examples
+//!
+//! ```
+//! use termina::{kitty::KEY_TEST_VECTORS, Event, Parser};
+//!
+//! for vector in KEY_TEST_VECTORS {
+//!     let mut parser = Parser::default();
+//!     parser.parse(vector.sequence.as_bytes(), false);
+//!     assert_eq!(parser.pop(), Some(Event::Key(vector.event)));
+//! }
+//! ```
+//!
+//! # Implementation Notes
+//!
+//! The sequences follow the examples and tables in the [Kitty keyboard protocol] specification.
+//!
+//! [Kitty keyboard protocol]: https://sw.kovidgoyal.net/kitty/keyboard-protocol/
+
+#[cfg(doc)]
+use crate::Parser;
+
+use crate::{
+    escape::csi::KittyKeyboardFlags,
+    event::{
+        KeyCode, KeyEvent, KeyEventKind, KeyEventState, MediaKeyCode, ModifierKeyCode, Modifiers,
+    },
+};
+
+/// An input sequence and the key event it decodes to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyTestVector {
+    /// The bytes sent by the terminal, as a string.
+    pub sequence: &'static str,
+
+    /// The keyboard enhancement flags a terminal must have pushed to send [`Self::sequence`].
+    pub flags: KittyKeyboardFlags,
+
+    /// The key event expected from parsing [`Self::sequence`].
+    pub event: KeyEvent,
+}
+
+const fn vector(
+    sequence: &'static str,
+    flags: KittyKeyboardFlags,
+    code: KeyCode,
+    modifiers: Modifiers,
+    kind: KeyEventKind,
+    state: KeyEventState,
+) -> KeyTestVector {
+    KeyTestVector {
+        sequence,
+        flags,
+        event: KeyEvent {
+            code,
+            kind,
+            modifiers,
+            state,
+            text: None,
+            base_code: None,
+        },
+    }
+}
+
+const PRESS: KeyEventKind = KeyEventKind::Press;
+const DISAMBIGUATE: KittyKeyboardFlags = KittyKeyboardFlags::DISAMBIGUATE_ESCAPE_CODES;
+
+/// Kitty keyboard protocol sequences and the key events Termina decodes them to.
+pub const KEY_TEST_VECTORS: &[KeyTestVector] = &[
+    // Plain and modified text keys.
+    vector(
+        "\x1b[97u",
+        DISAMBIGUATE,
+        KeyCode::Char('a'),
+        Modifiers::NONE,
+        PRESS,
+        KeyEventState::NONE,
+    ),
+    vector(
+        "\x1b[97;5u",
+        DISAMBIGUATE,
+        KeyCode::Char('a'),
+        Modifiers::CONTROL,
+        PRESS,
+        KeyEventState::NONE,
+    ),
+    vector(
+        "\x1b[97;3u",
+        DISAMBIGUATE,
+        KeyCode::Char('a'),
+        Modifiers::ALT,
+        PRESS,
+        KeyEventState::NONE,
+    ),
+    vector(
+        "\x1b[97;7u",
+        DISAMBIGUATE,
+        KeyCode::Char('a'),
+        Modifiers::CONTROL.union(Modifiers::ALT),
+        PRESS,
+        KeyEventState::NONE,
+    ),
+    vector(
+        "\x1b[97;9u",
+        DISAMBIGUATE,
+        KeyCode::Char('a'),
+        Modifiers::SUPER,
+        PRESS,
+        KeyEventState::NONE,
+    ),
+    // Functional keys with Unicode code points.
+    vector(
+        "\x1b[27u",
+        DISAMBIGUATE,
+        KeyCode::Escape,
+        Modifiers::NONE,
+        PRESS,
+        KeyEventState::NONE,
+    ),
+    vector(
+        "\x1b[13;5u",
+        DISAMBIGUATE,
+        KeyCode::Enter,
+        Modifiers::CONTROL,
+        PRESS,
+        KeyEventState::NONE,
+    ),
+    vector(
+        "\x1b[9;2u",
+        DISAMBIGUATE,
+        KeyCode::BackTab,
+        Modifiers::SHIFT,
+        PRESS,
+        KeyEventState::NONE,
+    ),
+    vector(
+        "\x1b[127;3u",
+        DISAMBIGUATE,
+        KeyCode::Backspace,
+        Modifiers::ALT,
+        PRESS,
+        KeyEventState::NONE,
+    ),
+    // Legacy functional keys which keep their `CSI 1 ; mods X` and `CSI n ; mods ~` forms.
+    vector(
+        "\x1b[1;5A",
+        DISAMBIGUATE,
+        KeyCode::Up,
+        Modifiers::CONTROL,
+        PRESS,
+        KeyEventState::NONE,
+    ),
+    vector(
+        "\x1b[1;2H",
+        DISAMBIGUATE,
+        KeyCode::Home,
+        Modifiers::SHIFT,
+        PRESS,
+        KeyEventState::NONE,
+    ),
+    vector(
+        "\x1b[3;5~",
+        DISAMBIGUATE,
+        KeyCode::Delete,
+        Modifiers::CONTROL,
+        PRESS,
+        KeyEventState::NONE,
+    ),
+    vector(
+        "\x1b[15~",
+        KittyKeyboardFlags::NONE,
+        KeyCode::Function(5),
+        Modifiers::NONE,
+        PRESS,
+        KeyEventState::NONE,
+    ),
+    // Functional keys in the Unicode private use area.
+    vector(
+        "\x1b[57376u",
+        DISAMBIGUATE,
+        KeyCode::Function(13),
+        Modifiers::NONE,
+        PRESS,
+        KeyEventState::NONE,
+    ),
+    vector(
+        "\x1b[57399u",
+        DISAMBIGUATE,
+        KeyCode::Char('0'),
+        Modifiers::NONE,
+        PRESS,
+        KeyEventState::KEYPAD,
+    ),
+    vector(
+        "\x1b[57414u",
+        DISAMBIGUATE,
+        KeyCode::Enter,
+        Modifiers::NONE,
+        PRESS,
+        KeyEventState::KEYPAD,
+    ),
+    vector(
+        "\x1b[57428u",
+        DISAMBIGUATE,
+        KeyCode::Media(MediaKeyCode::Play),
+        Modifiers::NONE,
+        PRESS,
+        KeyEventState::NONE,
+    ),
+    // Event types.
+    vector(
+        "\x1b[97;1:2u",
+        KittyKeyboardFlags::REPORT_EVENT_TYPES,
+        KeyCode::Char('a'),
+        Modifiers::NONE,
+        KeyEventKind::Repeat,
+        KeyEventState::NONE,
+    ),
+    vector(
+        "\x1b[97;1:3u",
+        KittyKeyboardFlags::REPORT_EVENT_TYPES,
+        KeyCode::Char('a'),
+        Modifiers::NONE,
+        KeyEventKind::Release,
+        KeyEventState::NONE,
+    ),
+    // Alternate keys: the shifted key replaces the base key and consumes Shift.
+    vector(
+        "\x1b[97:65;2u",
+        KittyKeyboardFlags::REPORT_ALTERNATE_KEYS,
+        KeyCode::Char('A'),
+        Modifiers::NONE,
+        PRESS,
+        KeyEventState::NONE,
+    ),
+    // Lock key state is reported in `KeyEvent::state` rather than the modifiers.
+    vector(
+        "\x1b[97;65u",
+        DISAMBIGUATE,
+        KeyCode::Char('a'),
+        Modifiers::NONE,
+        PRESS,
+        KeyEventState::CAPS_LOCK,
+    ),
+    // Modifier keys themselves, which are only reported with all keys as escape codes.
+    vector(
+        "\x1b[57441;2u",
+        KittyKeyboardFlags::REPORT_ALL_KEYS_AS_ESCAPE_CODES,
+        KeyCode::Modifier(ModifierKeyCode::LeftShift),
+        Modifiers::SHIFT,
+        PRESS,
+        KeyEventState::NONE,
+    ),
+    vector(
+        "\x1b[57448;5:3u",
+        KittyKeyboardFlags::REPORT_ALL_KEYS_AS_ESCAPE_CODES
+            .union(KittyKeyboardFlags::REPORT_EVENT_TYPES),
+        KeyCode::Modifier(ModifierKeyCode::RightControl),
+        Modifiers::CONTROL,
+        KeyEventKind::Release,
+        KeyEventState::NONE,
+    ),
+];
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Event, Parser};
+
+    #[test]
+    fn key_test_vectors_parse() {
+        for vector in KEY_TEST_VECTORS {
+            let mut parser = Parser::default();
+            parser.parse(vector.sequence.as_bytes(), false);
+            assert_eq!(
+                parser.pop(),
+                Some(Event::Key(vector.event)),
+                "sequence {:?}",
+                vector.sequence
+            );
+            assert_eq!(parser.pop(), None);
+        }
+    }
+}
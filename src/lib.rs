@@ -48,22 +48,60 @@
 //! parser.parse(b"\x1b[5~", false);
 //! assert!(matches!(parser.pop(), Some(Event::Key(_))));
 //! ```
+//!
+//! # Tracing
+//!
+//! With the `tracing` feature enabled, Termina logs the raw bytes it reads, the events it parses,
+//! the mode changes it writes and the wake-ups of its event sources at the trace level. Recording
+//! a trace with a [`tracing`](https://docs.rs/tracing) subscriber shows exactly what a terminal
+//! sent when input is misparsed.
+
+/// Logs a trace-level event when the `tracing` feature is enabled.
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::trace!($($arg)*)
+    };
+}
 
 pub(crate) mod base64;
+pub mod command;
 pub mod escape;
 pub mod event;
+pub mod integration;
+pub mod kitty;
+#[cfg(feature = "line")]
+pub mod line;
 pub(crate) mod parse;
+pub mod query;
+pub mod session;
 pub mod style;
+#[cfg(feature = "surface")]
+pub mod surface;
 mod terminal;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "width")]
+pub mod width;
 
 use std::{fmt, num::NonZeroU16};
 
 pub use event::{reader::EventReader, Event, PlatformWaker};
 #[cfg(windows)]
 pub use parse::windows;
-pub use parse::Parser;
+pub use parse::{Parser, ParserStats};
+pub use session::Session;
+
+pub use terminal::{
+    OutputTarget, PlatformHandle, PlatformTerminal, ReleasedTerminal, ScrollRegion, SixelGuard,
+    SizeRequest, SyncGuard, Terminal, TerminalInput, TerminalOutput, TerminalWriter, ThemeReport,
+    ThemeSource,
+};
 
-pub use terminal::{PlatformHandle, PlatformTerminal, Terminal};
+#[cfg(windows)]
+pub use terminal::{ConsoleKind, Handle};
+#[cfg(unix)]
+pub use terminal::{FileDescriptor, MemoryTerminal};
 
 #[cfg(feature = "event-stream")]
 pub use event::stream::EventStream;
@@ -93,6 +131,7 @@ pub use event::stream::EventStream;
 ///
 /// [termwiz escape helpers]: https://docs.rs/termwiz/latest/termwiz/escape/index.html
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct OneBased(NonZeroU16);
 
 impl OneBased {
@@ -151,7 +190,20 @@ impl From<NonZeroU16> for OneBased {
 /// cursor positioning and layout code. Pixel dimensions are available when the platform reports
 /// them. On Unix, Termina reads those optional pixel fields from the `TIOCGWINSZ` window-size
 /// query when the terminal fills them in. Windows currently reports `None` for both pixel fields.
+///
+/// # Degenerate sizes
+///
+/// A window can transiently report zero rows or columns, for example while a tiling window
+/// manager lays out windows or when a pane is collapsed. Termina handles these sizes the same way
+/// on every platform:
+///
+/// - [`Event::WindowResized`] is delivered for every size change, including degenerate sizes, so
+///   applications can stop rendering until the window has a usable size again. Use
+///   [`Self::is_degenerate`] to check.
+/// - [`Terminal::get_dimensions`] returns an error rather than a degenerate size, since callers
+///   of that method usually need a size to lay out into.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WindowSize {
     /// The width in terminal cells.
     #[doc(alias = "width")]
@@ -167,3 +219,38 @@ pub struct WindowSize {
     /// The height of the window in pixels, if the platform reports it.
     pub pixel_height: Option<u16>,
 }
+
+impl WindowSize {
+    /// Returns `true` if the window has no rows or no columns.
+    ///
+    /// Nothing can be drawn into a degenerate window. See the [type-level
+    /// documentation](Self#degenerate-sizes) for how Termina reports these sizes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use termina::WindowSize;
+    ///
+    /// let size = WindowSize {
+    ///     cols: 80,
+    ///     rows: 0,
+    ///     pixel_width: None,
+    ///     pixel_height: None,
+    /// };
+    /// assert!(size.is_degenerate());
+    /// ```
+    pub const fn is_degenerate(&self) -> bool {
+        self.cols == 0 || self.rows == 0
+    }
+}
+
+/// Creates a window size with the given cells and no pixel dimensions.
+#[cfg(test)]
+pub(crate) const fn test_size(cols: u16, rows: u16) -> WindowSize {
+    WindowSize {
+        cols,
+        rows,
+        pixel_width: None,
+        pixel_height: None,
+    }
+}
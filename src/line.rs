@@ -0,0 +1,492 @@
+//! A minimal line editor for prompts and REPLs.
+//!
+//! [`LineEditor`] reads a single line of input from [`Event`]s with the editing keys most shell
+//! users expect: cursor movement by character and word, history navigation, and an Emacs-style
+//! kill ring. It is meant for small interactive tools which would otherwise need a full readline
+//! crate just to ask for a line of input.
+//!
+//! This module is available with the `line` feature.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::io;
+//!
+//! use termina::{
+//!     line::{LineEditor, Outcome},
+//!     PlatformTerminal,
+//! };
+//!
+//! fn main() -> io::Result<()> {
+//!     let mut terminal = PlatformTerminal::new()?;
+//!     let mut editor = LineEditor::new("> ");
+//!     loop {
+//!         match editor.read_line(&mut terminal)? {
+//!             Outcome::Line(line) => println!("you said {line:?}"),
+//!             Outcome::Interrupted => continue,
+//!             Outcome::Eof => break,
+//!         }
+//!     }
+//!     Ok(())
+//! }
+//! ```
+//!
+//! Applications which already own an event loop can feed events with
+//! [`LineEditor::handle_event`] and draw the line with [`LineEditor::render`] instead.
+//!
+//! # Key bindings
+//!
+//! | Keys | Action |
+//! |------|--------|
+//! | Left, Ctrl-b / Right, Ctrl-f | Move by one character |
+//! | Ctrl-Left, Alt-b / Ctrl-Right, Alt-f | Move by one word |
+//! | Home, Ctrl-a / End, Ctrl-e | Move to the start or end of the line |
+//! | Up, Ctrl-p / Down, Ctrl-n | Previous or next history entry |
+//! | Backspace, Ctrl-h / Delete | Delete the character before or under the cursor |
+//! | Ctrl-d | Delete the character under the cursor, or end input on an empty line |
+//! | Ctrl-k / Ctrl-u | Kill to the end or start of the line |
+//! | Ctrl-w, Alt-Backspace / Alt-d | Kill the word before or after the cursor |
+//! | Ctrl-y / Alt-y | Yank the last kill, then rotate through older kills |
+//! | Enter | Submit the line |
+//! | Ctrl-c | Abandon the line |
+//!
+//! # Implementation Notes
+//!
+//! Key bindings and kill ring behavior follow [GNU Readline's Emacs mode][readline]: consecutive
+//! kills are joined into one kill ring entry and yank-pop only applies directly after a yank.
+//! Every character is assumed to occupy one column and the line is assumed to fit in the width of
+//! the terminal.
+//!
+//! [readline]: https://tiswww.case.edu/php/chet/readline/rluserman.html#Readline-Movement-Commands
+
+use std::{
+    borrow::Cow,
+    io::{self, Write},
+};
+
+use crate::{
+    escape::csi::{Csi, Cursor, Edit, EraseInLine, KittyKeyboardFlags},
+    event::{KeyCode, KeyEventKind, Modifiers},
+    style::Style,
+    Event, OneBased, Terminal,
+};
+
+/// The number of kills remembered by the kill ring.
+const KILL_RING_CAPACITY: usize = 16;
+
+/// The result of editing a line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// The line was submitted with Enter.
+    Line(String),
+    /// The line was abandoned with Ctrl-c.
+    Interrupted,
+    /// Ctrl-d was pressed on an empty line.
+    Eof,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LastAction {
+    Kill { forward: bool },
+    Yank { start: usize, index: usize },
+    Other,
+}
+
+/// An editable line of input with history and a kill ring.
+///
+/// History and the kill ring are kept across lines so one editor should be reused for a whole
+/// session. Submitted lines are added to the history unless they are empty or repeat the previous
+/// entry.
+#[derive(Debug, Clone)]
+pub struct LineEditor {
+    prompt: String,
+    prompt_style: Style,
+    buffer: String,
+    /// Byte offset of the cursor into `buffer`.
+    cursor: usize,
+    history: Vec<String>,
+    /// The history entry being shown, or `None` while editing a new line.
+    history_index: Option<usize>,
+    /// The new line being edited before history navigation started.
+    draft: String,
+    kill_ring: Vec<String>,
+    last_action: LastAction,
+}
+
+impl LineEditor {
+    /// Creates an editor which shows the given prompt before the line.
+    pub fn new(prompt: impl Into<String>) -> Self {
+        Self {
+            prompt: prompt.into(),
+            prompt_style: Style::new(),
+            buffer: String::new(),
+            cursor: 0,
+            history: Vec::new(),
+            history_index: None,
+            draft: String::new(),
+            kill_ring: Vec::new(),
+            last_action: LastAction::Other,
+        }
+    }
+
+    /// Sets the style the prompt is painted with.
+    pub fn with_prompt_style(mut self, style: Style) -> Self {
+        self.prompt_style = style;
+        self
+    }
+
+    /// Replaces the prompt.
+    pub fn set_prompt(&mut self, prompt: impl Into<String>) {
+        self.prompt = prompt.into();
+    }
+
+    /// The text of the line being edited.
+    pub fn buffer(&self) -> &str {
+        &self.buffer
+    }
+
+    /// The cursor position as a byte offset into [`Self::buffer`].
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Previously submitted lines, oldest first.
+    pub fn history(&self) -> &[String] {
+        &self.history
+    }
+
+    /// Adds a line to the end of the history.
+    pub fn add_history(&mut self, line: impl Into<String>) {
+        let line = line.into();
+        if !line.is_empty() && self.history.last() != Some(&line) {
+            self.history.push(line);
+        }
+    }
+
+    /// Updates the line for an event.
+    ///
+    /// Returns `Some` once the line is finished, after which the editor is ready for a new line.
+    /// Key releases and events unrelated to editing are ignored. Pasted line breaks are inserted
+    /// as spaces rather than submitting the line.
+    pub fn handle_event(&mut self, event: &Event) -> Option<Outcome> {
+        match event {
+            Event::Key(key) if key.kind != KeyEventKind::Release => {
+                let key = key.normalize(KittyKeyboardFlags::empty());
+                self.handle_key(key.code, key.modifiers)
+            }
+            Event::Paste(text) => {
+                let text: String = text
+                    .chars()
+                    .map(|c| if c == '\r' || c == '\n' { ' ' } else { c })
+                    .filter(|c| !c.is_control())
+                    .collect();
+                self.insert(&text);
+                self.last_action = LastAction::Other;
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn handle_key(&mut self, code: KeyCode, modifiers: Modifiers) -> Option<Outcome> {
+        const NONE: Modifiers = Modifiers::NONE;
+        const CTRL: Modifiers = Modifiers::CONTROL;
+        const ALT: Modifiers = Modifiers::ALT;
+
+        let last_action = std::mem::replace(&mut self.last_action, LastAction::Other);
+        match (code, modifiers) {
+            (KeyCode::Enter, _) => {
+                let line = self.take_line();
+                self.add_history(line.clone());
+                return Some(Outcome::Line(line));
+            }
+            (KeyCode::Char('c'), CTRL) => {
+                self.take_line();
+                return Some(Outcome::Interrupted);
+            }
+            (KeyCode::Char('d'), CTRL) if self.buffer.is_empty() => {
+                self.take_line();
+                return Some(Outcome::Eof);
+            }
+
+            (KeyCode::Left, NONE) | (KeyCode::Char('b'), CTRL) => {
+                self.cursor = self.prev_char();
+            }
+            (KeyCode::Right, NONE) | (KeyCode::Char('f'), CTRL) => {
+                self.cursor = self.next_char();
+            }
+            (KeyCode::Left, CTRL) | (KeyCode::Char('b'), ALT) => {
+                self.cursor = self.prev_word();
+            }
+            (KeyCode::Right, CTRL) | (KeyCode::Char('f'), ALT) => {
+                self.cursor = self.next_word();
+            }
+            (KeyCode::Home, NONE) | (KeyCode::Char('a'), CTRL) => self.cursor = 0,
+            (KeyCode::End, NONE) | (KeyCode::Char('e'), CTRL) => self.cursor = self.buffer.len(),
+
+            (KeyCode::Up, NONE) | (KeyCode::Char('p'), CTRL) => self.history_prev(),
+            (KeyCode::Down, NONE) | (KeyCode::Char('n'), CTRL) => self.history_next(),
+
+            (KeyCode::Backspace, NONE) | (KeyCode::Char('h'), CTRL) => {
+                let start = self.prev_char();
+                self.buffer.drain(start..self.cursor);
+                self.cursor = start;
+            }
+            (KeyCode::Delete, NONE) | (KeyCode::Char('d'), CTRL) => {
+                self.buffer.drain(self.cursor..self.next_char());
+            }
+
+            (KeyCode::Char('k'), CTRL) => self.kill(self.cursor..self.buffer.len(), last_action),
+            (KeyCode::Char('u'), CTRL) => self.kill(0..self.cursor, last_action),
+            (KeyCode::Char('w'), CTRL) | (KeyCode::Backspace, ALT) => {
+                self.kill(self.prev_word()..self.cursor, last_action)
+            }
+            (KeyCode::Char('d'), ALT) => self.kill(self.cursor..self.next_word(), last_action),
+            (KeyCode::Char('y'), CTRL) => self.yank(0),
+            (KeyCode::Char('y'), ALT) => {
+                if let LastAction::Yank { start, index } = last_action {
+                    self.buffer.drain(start..self.cursor);
+                    self.cursor = start;
+                    self.yank(index + 1);
+                }
+            }
+
+            (KeyCode::Char(c), NONE | Modifiers::SHIFT) if !c.is_control() => {
+                self.insert(c.encode_utf8(&mut [0; 4]));
+            }
+            _ => self.last_action = last_action,
+        }
+        None
+    }
+
+    /// Draws the prompt and line over the current terminal line and places the cursor.
+    ///
+    /// Nothing is flushed, so callers can combine this with other output.
+    pub fn render(&self, w: &mut impl Write) -> io::Result<()> {
+        let column = self.prompt.chars().count() + self.buffer[..self.cursor].chars().count();
+        let column = OneBased::from_zero_based(column.min(u16::MAX as usize - 1) as u16);
+        write!(
+            w,
+            "\r{}{}{}{}",
+            self.prompt_style.paint(Cow::Borrowed(self.prompt.as_str())),
+            self.buffer,
+            Csi::Edit(Edit::EraseInLine(EraseInLine::EraseToEndOfLine)),
+            Csi::Cursor(Cursor::CharacterAbsolute(column)),
+        )
+    }
+
+    /// Reads a line from the terminal.
+    ///
+    /// The terminal is switched to raw mode while editing and back to cooked mode afterwards,
+    /// including when reading fails. The cursor is left at the start of the line after the input.
+    pub fn read_line<T: Terminal>(&mut self, terminal: &mut T) -> io::Result<Outcome> {
+        terminal.enter_raw_mode()?;
+        let outcome = self.edit(terminal);
+        let cooked = terminal.enter_cooked_mode();
+        let outcome = outcome?;
+        cooked?;
+        Ok(outcome)
+    }
+
+    fn edit<T: Terminal>(&mut self, terminal: &mut T) -> io::Result<Outcome> {
+        loop {
+            self.render(terminal)?;
+            terminal.flush()?;
+            let event = terminal.read(|event| {
+                matches!(
+                    event,
+                    Event::Key(_) | Event::Paste(_) | Event::WindowResized(_)
+                )
+            })?;
+            if let Some(outcome) = self.handle_event(&event) {
+                self.render(terminal)?;
+                write!(terminal, "\r\n")?;
+                terminal.flush()?;
+                return Ok(outcome);
+            }
+        }
+    }
+
+    fn take_line(&mut self) -> String {
+        self.cursor = 0;
+        self.history_index = None;
+        self.draft.clear();
+        std::mem::take(&mut self.buffer)
+    }
+
+    fn insert(&mut self, text: &str) {
+        self.buffer.insert_str(self.cursor, text);
+        self.cursor += text.len();
+    }
+
+    fn prev_char(&self) -> usize {
+        self.buffer[..self.cursor]
+            .char_indices()
+            .next_back()
+            .map_or(0, |(i, _)| i)
+    }
+
+    fn next_char(&self) -> usize {
+        self.buffer[self.cursor..]
+            .chars()
+            .next()
+            .map_or(self.cursor, |c| self.cursor + c.len_utf8())
+    }
+
+    fn prev_word(&self) -> usize {
+        let before = self.buffer[..self.cursor].trim_end_matches(|c: char| !c.is_alphanumeric());
+        before
+            .char_indices()
+            .rev()
+            .find(|(_, c)| !c.is_alphanumeric())
+            .map_or(0, |(i, c)| i + c.len_utf8())
+    }
+
+    fn next_word(&self) -> usize {
+        let after = &self.buffer[self.cursor..];
+        let start = after.len()
+            - after
+                .trim_start_matches(|c: char| !c.is_alphanumeric())
+                .len();
+        after[start..]
+            .char_indices()
+            .find(|(_, c)| !c.is_alphanumeric())
+            .map_or(self.buffer.len(), |(i, _)| self.cursor + start + i)
+    }
+
+    fn history_prev(&mut self) {
+        let index = match self.history_index {
+            Some(0) => return,
+            Some(index) => index - 1,
+            None if self.history.is_empty() => return,
+            None => {
+                self.draft = std::mem::take(&mut self.buffer);
+                self.history.len() - 1
+            }
+        };
+        self.history_index = Some(index);
+        self.buffer = self.history[index].clone();
+        self.cursor = self.buffer.len();
+    }
+
+    fn history_next(&mut self) {
+        let Some(index) = self.history_index else {
+            return;
+        };
+        if index + 1 < self.history.len() {
+            self.history_index = Some(index + 1);
+            self.buffer = self.history[index + 1].clone();
+        } else {
+            self.history_index = None;
+            self.buffer = std::mem::take(&mut self.draft);
+        }
+        self.cursor = self.buffer.len();
+    }
+
+    fn kill(&mut self, range: std::ops::Range<usize>, last_action: LastAction) {
+        let forward = range.start == self.cursor;
+        let killed: String = self.buffer.drain(range.clone()).collect();
+        self.cursor = range.start;
+        self.last_action = LastAction::Kill { forward };
+
+        if killed.is_empty() {
+            return;
+        }
+        match (last_action, self.kill_ring.last_mut()) {
+            (LastAction::Kill { .. }, Some(last)) if forward => last.push_str(&killed),
+            (LastAction::Kill { .. }, Some(last)) => last.insert_str(0, &killed),
+            _ => {
+                if self.kill_ring.len() == KILL_RING_CAPACITY {
+                    self.kill_ring.remove(0);
+                }
+                self.kill_ring.push(killed);
+            }
+        }
+    }
+
+    /// Inserts the kill `index` entries back from the most recent one.
+    fn yank(&mut self, index: usize) {
+        if self.kill_ring.is_empty() {
+            return;
+        }
+        let index = index % self.kill_ring.len();
+        let text = self.kill_ring[self.kill_ring.len() - 1 - index].clone();
+        let start = self.cursor;
+        self.insert(&text);
+        self.last_action = LastAction::Yank { start, index };
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::event::KeyEvent;
+
+    fn type_keys(editor: &mut LineEditor, keys: &[(KeyCode, Modifiers)]) -> Option<Outcome> {
+        keys.iter()
+            .filter_map(|&(code, modifiers)| {
+                editor.handle_event(&Event::Key(KeyEvent::new(code, modifiers)))
+            })
+            .last()
+    }
+
+    fn type_str(editor: &mut LineEditor, text: &str) {
+        for c in text.chars() {
+            editor.handle_event(&Event::Key(KeyCode::Char(c).into()));
+        }
+    }
+
+    #[test]
+    fn edit_and_kill() {
+        let ctrl = |c| (KeyCode::Char(c), Modifiers::CONTROL);
+        let alt = |c| (KeyCode::Char(c), Modifiers::ALT);
+        let mut editor = LineEditor::new("> ");
+
+        type_str(&mut editor, "hello wörld");
+        type_keys(&mut editor, &[alt('b'), (KeyCode::Left, Modifiers::NONE)]);
+        assert_eq!(editor.cursor(), "hello".len());
+        type_str(&mut editor, ",");
+        assert_eq!(editor.buffer(), "hello, wörld");
+
+        // Consecutive kills join into one entry which yanks back as a whole.
+        type_keys(&mut editor, &[ctrl('e'), ctrl('w'), ctrl('w')]);
+        assert_eq!(editor.buffer(), "");
+        type_keys(&mut editor, &[ctrl('y')]);
+        assert_eq!(editor.buffer(), "hello, wörld");
+
+        type_keys(
+            &mut editor,
+            &[ctrl('a'), alt('d'), (KeyCode::Delete, Modifiers::NONE)],
+        );
+        type_keys(&mut editor, &[ctrl('e'), ctrl('y'), alt('y')]);
+        assert_eq!(editor.buffer(), " wörldhello, wörld");
+
+        let outcome = type_keys(&mut editor, &[(KeyCode::Enter, Modifiers::NONE)]);
+        assert_eq!(outcome, Some(Outcome::Line(" wörldhello, wörld".into())));
+        assert_eq!(editor.buffer(), "");
+        assert_eq!(type_keys(&mut editor, &[ctrl('d')]), Some(Outcome::Eof));
+    }
+
+    #[test]
+    fn history_navigation() {
+        let up = (KeyCode::Up, Modifiers::NONE);
+        let down = (KeyCode::Down, Modifiers::NONE);
+        let mut editor = LineEditor::new("> ");
+        editor.add_history("first");
+        editor.add_history("second");
+        editor.add_history("second");
+        assert_eq!(editor.history(), ["first", "second"]);
+
+        type_str(&mut editor, "draft");
+        type_keys(&mut editor, &[up, up, up]);
+        assert_eq!(editor.buffer(), "first");
+        type_keys(&mut editor, &[down]);
+        assert_eq!(editor.buffer(), "second");
+        type_keys(&mut editor, &[down]);
+        assert_eq!(editor.buffer(), "draft");
+
+        let mut output = Vec::new();
+        editor.render(&mut output).unwrap();
+        assert_eq!(output, b"\r> draft\x1b[0K\x1b[8G");
+    }
+}
@@ -1,20 +1,28 @@
 //! Streaming parser for terminal input bytes.
 //!
 //! [`Parser`] turns bytes from a terminal or PTY into [`Event`] values. It accepts partial input:
-//! callers append bytes with [`Parser::parse`], then drain completed events with [`Parser::pop`].
-//! This is the same parser used behind [`EventReader`], exposed for code that owns its input source
-//! directly.
+//! callers append bytes with [`Parser::feed`], then drain completed events with
+//! [`Parser::next_event`]. This is the same parser used behind [`EventReader`], exposed for code
+//! that owns its input source directly, such as SSH clients, terminal multiplexers and test
+//! harnesses. No terminal handle is needed.
 //!
 //! # Implementation Notes
 //!
-//! The parser is adapted from [crossterm's Unix event parser], with additions for Termina-specific
-//! escape sequences and Windows input modes. Crossterm comments call this style of parser a bit
-//! scary and probably in need of a refactor. I like the approach though, because it is quite easy
-//! to read and test. The main uncertainty is performance: `process_bytes` considers the bytes as an
-//! increasing slice of the buffer until that slice becomes valid or invalid. WezTerm and Alacritty
-//! use more formal parsers, [`vtparse`] and [`vte`] respectively, but those terminal-emulator
-//! parsers may be larger or more complex than an application needs.
+//! Sequence framing follows the VT500-series state machine described by [Paul Williams]: each
+//! byte moves the parser between ground, escape, CSI parameter/intermediate, OSC string and DCS
+//! passthrough states in constant time, so input is processed in linear time no matter how it is
+//! split. Only once a sequence is complete is it decoded, using functions adapted from
+//! [crossterm's Unix event parser] with additions for Termina-specific escape sequences and Windows
+//! input modes. Crossterm comments call this style of decoder a bit scary and probably in need of
+//! a refactor. I like the approach though, because it is quite easy to read and test. WezTerm and
+//! Alacritty use full parsers, [`vtparse`] and [`vte`] respectively, but those terminal-emulator
+//! parsers handle far more than an application reading input needs.
 //!
+//! The state machine also defines recovery. C0 controls within a CSI sequence are reported as
+//! keys without interrupting the sequence, CAN and SUB abort the sequence, ESC starts a new one,
+//! and a CSI sequence with misplaced parameter bytes is consumed up to its final byte and dropped.
+//!
+//! [Paul Williams]: https://vt100.net/emu/dec_ansi_parser
 //! [crossterm's Unix event parser]: https://docs.rs/crossterm/latest/crossterm/event/index.html
 //! [`vtparse`]: https://docs.rs/vtparse/latest/vtparse/
 //! [`vte`]: https://docs.rs/vte/latest/vte/
@@ -39,51 +47,126 @@ use crate::{
         dcs, osc,
     },
     event::{
-        KeyCode, KeyEvent, KeyEventKind, KeyEventState, MediaKeyCode, ModifierKeyCode, Modifiers,
-        MouseButton, MouseEvent, MouseEventKind,
+        KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyText, MediaKeyCode, ModifierKeyCode,
+        Modifiers, MouseButton, MouseEvent, MouseEventKind,
     },
     style, Event,
 };
 
 /// An incremental parser for terminal input.
 ///
-/// The parser keeps incomplete escape sequences in an internal buffer. Pass `maybe_more = true`
-/// when the current byte slice may end in the middle of a sequence. Completed events are queued
-/// until [`Self::pop`] removes them.
+/// The parser keeps incomplete escape sequences in an internal buffer. Completed events are
+/// queued until [`Self::next_event`] (or [`Self::pop`]) removes them.
+///
+/// # Partial sequences
+///
+/// Input may be split anywhere, including in the middle of an escape sequence or a UTF-8
+/// character: [`Self::feed`] holds the incomplete tail and completes it when the following bytes
+/// arrive. Bytes which can't start or continue a valid sequence are discarded.
+///
+/// A lone ESC byte is ambiguous until more bytes arrive or it is clear that none will: it is either
+/// the Escape key or the start of a sequence. After feeding, call [`Self::flush`] when the input
+/// source has no more bytes available (for example after a read returned less than a full buffer,
+/// or after a short timeout) to resolve it as a key press. [`Self::has_pending`] reports whether
+/// any input is waiting.
+///
+/// # Limits
+///
+/// A buggy or hostile program on the other side of a PTY could send the start of a sequence and
+/// never finish it. The parser bounds what it buffers for one sequence: a CSI sequence may have at
+/// most 64 parameters (counting sub-parameters) of at most 20 digits each and at most 512 bytes in
+/// total, and an OSC, DCS or APC string at most 1 MiB. A sequence which exceeds a limit is
+/// abandoned and reported as [`Event::Malformed`], and the rest of it is discarded as it arrives.
+/// Bracketed pastes are not limited since a paste of any size is legitimate input.
+///
+/// # 8-bit controls
+///
+/// Terminals normally introduce sequences with ESC, but some terminals and older applications use
+/// the single-byte C1 controls instead, such as 0x9B for CSI. These bytes are also UTF-8
+/// continuation bytes, so they are only recognized once enabled with [`Self::set_c1_controls`].
+/// The parser then accepts SS3 (0x8F), DCS (0x90), CSI (0x9B), OSC (0x9D) and APC (0x9F) between
+/// characters, and ST (0x9C) to terminate a string unless it continues a UTF-8 encoded character
+/// in the string.
+///
+/// [`Self::parse`] is the lower-level form of [`Self::feed`]: `parse(bytes, true)` is the same as
+/// `feed(bytes)`, and `parse(bytes, false)` additionally resolves a trailing lone ESC but keeps
+/// other incomplete sequences.
 ///
 /// # Examples
 ///
 /// ```
-/// use termina::{Event, Parser};
+/// use termina::{
+///     event::{KeyCode, KeyEvent},
+///     Event, Parser,
+/// };
 ///
 /// let mut parser = Parser::default();
-/// parser.parse(b"\x1b[5~", false);
-/// assert!(matches!(parser.pop(), Some(Event::Key(_))));
+/// // A sequence split across two reads.
+/// parser.feed(b"\x1b[5");
+/// assert_eq!(parser.next_event(), None);
+/// parser.feed(b"~");
+/// assert!(matches!(parser.next_event(), Some(Event::Key(_))));
+///
+/// // A lone ESC is only reported once the input is flushed.
+/// parser.feed(b"\x1b");
+/// assert!(parser.has_pending());
+/// parser.flush();
+/// assert_eq!(
+///     parser.next_event(),
+///     Some(Event::Key(KeyEvent::from(KeyCode::Escape)))
+/// );
 /// ```
 #[derive(Debug)]
 pub struct Parser {
+    /// Bytes of the sequence currently being framed.
     buffer: Vec<u8>,
+    state: State,
+    /// The number of parameter separators in the current CSI sequence.
+    csi_separators: usize,
+    /// The number of digits in the current CSI parameter.
+    csi_param_len: usize,
     /// Events which have been parsed. Pop out with [`Self::pop`].
     events: VecDeque<Event>,
     #[cfg(windows)]
     mode: InputReaderMode,
+    /// Whether key releases and repeats are reported. See [`EventReader::set_report_event_types`].
+    #[cfg(windows)]
+    report_event_types: bool,
+    /// The virtual key code of the key which was last pressed and not yet released.
+    #[cfg(windows)]
+    held_key: Option<u16>,
     #[cfg(all(windows, feature = "windows-legacy"))]
     surrogate_buffer: Option<u16>,
     #[cfg(all(windows, feature = "windows-legacy"))]
     mouse_buttons_pressed: legacy::MouseButtonsPressed,
+    stats: ParserStats,
+    /// Called with every chunk of input. See [`EventReader::set_raw_tap`].
+    tap: Option<RawTap>,
+    /// Whether 8-bit C1 controls introduce sequences.
+    c1_controls: bool,
 }
 
 impl Default for Parser {
     fn default() -> Self {
         Self {
             buffer: Vec::with_capacity(256),
+            state: State::Ground,
+            csi_separators: 0,
+            csi_param_len: 0,
             events: VecDeque::with_capacity(32),
             #[cfg(windows)]
             mode: InputReaderMode::Vte,
+            #[cfg(windows)]
+            report_event_types: false,
+            #[cfg(windows)]
+            held_key: None,
             #[cfg(all(windows, feature = "windows-legacy"))]
             surrogate_buffer: None,
             #[cfg(all(windows, feature = "windows-legacy"))]
             mouse_buttons_pressed: legacy::MouseButtonsPressed::default(),
+            stats: ParserStats::default(),
+            tap: None,
+            c1_controls: false,
         }
     }
 }
@@ -104,34 +187,613 @@ impl Parser {
         self.events.pop_front()
     }
 
+    /// Adds bytes to the parser, queueing the events they complete.
+    ///
+    /// An incomplete sequence at the end of `bytes` is kept until later input completes it or
+    /// [`Self::flush`] is called. See the [type-level documentation](Self#partial-sequences).
+    pub fn feed(&mut self, bytes: &[u8]) {
+        self.parse(bytes, true);
+    }
+
+    /// Treats the buffered input as complete.
+    ///
+    /// Ambiguous input such as a lone ESC is resolved into key events and any other incomplete
+    /// sequence is discarded.
+    pub fn flush(&mut self) {
+        self.resolve_escape();
+        self.reset();
+    }
+
+    /// Sets whether 8-bit C1 controls such as 0x9B (CSI) introduce sequences. They are ignored by
+    /// default. See the [type-level documentation](Self#8-bit-controls).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use termina::{
+    ///     event::{KeyCode, Modifiers},
+    ///     Event, Parser,
+    /// };
+    ///
+    /// let mut parser = Parser::default();
+    /// parser.set_c1_controls(true);
+    /// // Ctrl+Up with an 8-bit CSI.
+    /// parser.feed(b"\x9b1;5A");
+    /// let Some(Event::Key(key)) = parser.next_event() else {
+    ///     panic!("expected a key event");
+    /// };
+    /// assert_eq!(key.code, KeyCode::Up);
+    /// assert_eq!(key.modifiers, Modifiers::CONTROL);
+    /// ```
+    pub fn set_c1_controls(&mut self, enabled: bool) {
+        self.c1_controls = enabled;
+    }
+
+    /// Returns `true` if the parser holds input which hasn't produced an event yet.
+    pub fn has_pending(&self) -> bool {
+        self.state != State::Ground
+    }
+
+    /// Returns `true` if the input ends with a lone ESC, which is either the Escape key or the
+    /// start of a sequence.
+    pub(crate) fn is_escape_pending(&self) -> bool {
+        matches!(self.state, State::Escape | State::ApcEntry)
+    }
+
+    /// The number of completed events which haven't been removed yet.
+    pub(crate) fn queued(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Discards completed events and any partially parsed sequence.
+    pub(crate) fn clear(&mut self) {
+        self.reset();
+        self.events.clear();
+        #[cfg(windows)]
+        {
+            self.held_key = None;
+        }
+        #[cfg(all(windows, feature = "windows-legacy"))]
+        {
+            self.surrogate_buffer = None;
+        }
+    }
+
+    /// Returns counters describing the input parsed so far.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use termina::Parser;
+    ///
+    /// let mut parser = Parser::default();
+    /// // A key press and a CSI sequence with an unknown final byte.
+    /// parser.feed(b"a\x1b[99@");
+    /// let stats = parser.stats();
+    /// assert_eq!(stats.bytes, 6);
+    /// assert_eq!(stats.events, 2);
+    /// assert_eq!(stats.unrecognized, 1);
+    /// ```
+    pub fn stats(&self) -> ParserStats {
+        self.stats
+    }
+
+    /// Sets the function called with every chunk of input before it is parsed.
+    pub(crate) fn set_tap(&mut self, tap: Option<RawTap>) {
+        self.tap = tap;
+    }
+
+    /// Removes and returns the oldest completed event.
+    ///
+    /// This is the same as [`Self::pop`].
+    pub fn next_event(&mut self) -> Option<Event> {
+        self.pop()
+    }
+
     /// Adds bytes to the parser and queues any completed events.
     ///
     /// Set `maybe_more` to `true` when the input source may provide more bytes for the same
     /// escape sequence later. Set it to `false` when the buffer should be treated as complete for
     /// now; malformed or incomplete sequences can then be discarded instead of held indefinitely.
     pub fn parse(&mut self, bytes: &[u8], maybe_more: bool) {
-        if bytes.is_empty() {
-            self.process_bytes(maybe_more);
-            return;
+        if let Some(tap) = &mut self.tap {
+            if !bytes.is_empty() {
+                tap.0.get_mut()(bytes);
+            }
         }
-        for (idx, b) in bytes.iter().enumerate() {
-            self.buffer.push(*b);
-            self.process_bytes(maybe_more || idx + 1 < bytes.len());
+        if !bytes.is_empty() {
+            trace!(bytes = %bytes.escape_ascii(), maybe_more, "read input");
         }
+        self.stats.bytes += bytes.len() as u64;
+        let mut rest = bytes;
+        while let Some((&byte, tail)) = rest.split_first() {
+            // Runs of bytes which can't change the state are consumed in bulk. Anything else, such
+            // as a terminator or a byte which would exceed a limit, goes through `advance`.
+            let consumed = match self.state {
+                State::Paste => self.paste(rest),
+                State::String => self.string(rest),
+                State::StringIgnore => string_len(rest),
+                State::CsiEntry | State::CsiParam => self.csi_params(rest),
+                _ => 0,
+            };
+            if consumed > 0 {
+                rest = &rest[consumed..];
+            } else if self.state == State::Escape && byte == b'_' && tail.is_empty() {
+                // Terminals send an APC string in one write, so an `ESC _` which ends the input is
+                // Alt+`_` rather than an APC string swallowing the keys typed after it. If more
+                // input may follow, this is decided like a lone ESC.
+                if maybe_more {
+                    self.push(byte, State::ApcEntry);
+                } else {
+                    self.alt_underscore();
+                }
+                rest = tail;
+            } else {
+                self.advance(byte);
+                rest = tail;
+            }
+        }
+        if !maybe_more {
+            self.resolve_escape();
+        }
+    }
+
+    /// Moves the state machine forward by one byte.
+    fn advance(&mut self, byte: u8) {
+        match self.state {
+            State::Ground => self.ground(byte),
+            State::ApcEntry => {
+                self.state = State::String;
+                self.advance(byte);
+            }
+            State::Escape => match byte {
+                b'[' => self.push(byte, State::CsiEntry),
+                b']' | b'P' | b'_' => self.push(byte, State::String),
+                b'O' => self.push(byte, State::Final),
+                b'\x1B' => {
+                    self.buffer.push(byte);
+                    self.dispatch();
+                }
+                // Any other key is reported with the Alt modifier.
+                _ => self.ground(byte),
+            },
+            State::Utf8(remaining) => {
+                if byte & 0b1100_0000 == 0b1000_0000 {
+                    self.buffer.push(byte);
+                    if remaining == 1 {
+                        self.dispatch();
+                    } else {
+                        self.state = State::Utf8(remaining - 1);
+                    }
+                } else {
+                    self.reset();
+                    self.advance(byte);
+                }
+            }
+            State::Final => {
+                self.buffer.push(byte);
+                self.dispatch();
+            }
+            State::CsiEntry if byte == b'[' => self.push(byte, State::Final),
+            State::CsiEntry if byte == b'M' => self.push(byte, State::MouseBytes(3)),
+            State::CsiEntry | State::CsiParam | State::CsiIntermediate | State::CsiIgnore => {
+                self.csi(byte)
+            }
+            State::MouseBytes(remaining) => {
+                self.buffer.push(byte);
+                if remaining == 1 {
+                    self.dispatch();
+                } else {
+                    self.state = State::MouseBytes(remaining - 1);
+                }
+            }
+            State::String => match byte {
+                b'\x1B' => self.push(byte, State::StringEscape),
+                // OSC strings may also be terminated by BEL, see `parse_osc`.
+                b'\x07' if self.buffer[1] == b']' => {
+                    self.buffer.push(byte);
+                    self.dispatch();
+                }
+                b'\x18' | b'\x1A' => {
+                    self.reset();
+                    self.ground(byte);
+                }
+                // An 8-bit ST, unless the string is in the middle of a UTF-8 encoded character.
+                0x9C if self.c1_controls && incomplete_char_len(&self.buffer[2..]) == 0 => {
+                    self.buffer.extend_from_slice(b"\x1B\\");
+                    self.dispatch();
+                }
+                _ => {
+                    self.buffer.push(byte);
+                    if self.buffer.len() >= MAX_STRING_LEN {
+                        self.abandon(State::StringIgnore);
+                    }
+                }
+            },
+            State::StringIgnore => match byte {
+                b'\x1B' => self.state = State::StringEscape,
+                b'\x07' => self.reset(),
+                b'\x18' | b'\x1A' => {
+                    self.reset();
+                    self.ground(byte);
+                }
+                _ => (),
+            },
+            State::StringEscape => {
+                if byte == b'\\' {
+                    if self.buffer.is_empty() {
+                        // The end of an abandoned string.
+                        self.reset();
+                    } else {
+                        self.buffer.push(byte);
+                        self.dispatch();
+                    }
+                } else {
+                    // An ESC which doesn't form ST abandons the string and starts a new sequence.
+                    self.reset();
+                    self.push(b'\x1B', State::Escape);
+                    self.advance(byte);
+                }
+            }
+            State::Paste => {
+                self.paste(&[byte]);
+            }
+        }
+    }
+
+    /// Adds bracketed paste contents up to and including the end marker, returning the number of
+    /// bytes consumed.
+    ///
+    /// Pastes can be megabytes long, so the contents are copied a chunk at a time rather than
+    /// passed through the state machine byte by byte.
+    fn paste(&mut self, bytes: &[u8]) -> usize {
+        // The end marker may have begun in an earlier chunk, but not within the start marker.
+        let search_from = self
+            .buffer
+            .len()
+            .saturating_sub(PASTE_END.len() - 1)
+            .max(PASTE_START.len());
+        self.buffer.extend_from_slice(bytes);
+        let Some(pos) = memchr::memmem::find(&self.buffer[search_from..], PASTE_END) else {
+            return bytes.len();
+        };
+        let end = search_from + pos + PASTE_END.len();
+        let consumed = bytes.len() - (self.buffer.len() - end);
+        self.buffer.truncate(end);
+        self.dispatch_paste();
+        consumed
+    }
+
+    /// Adds the OSC, DCS or APC string contents at the start of `bytes`, returning the number of
+    /// bytes consumed.
+    ///
+    /// Stops before any byte which may end the string and before the byte which would reach
+    /// `MAX_STRING_LEN`.
+    fn string(&mut self, bytes: &[u8]) -> usize {
+        let room = (MAX_STRING_LEN - 1).saturating_sub(self.buffer.len());
+        let len = string_len(&bytes[..bytes.len().min(room)]);
+        self.buffer.extend_from_slice(&bytes[..len]);
+        len
     }
 
-    fn process_bytes(&mut self, maybe_more: bool) {
-        match parse_event(&self.buffer, maybe_more) {
-            Ok(Some(event)) => {
-                self.events.push_back(event);
-                self.buffer.clear();
+    /// Adds the CSI parameter bytes at the start of `bytes`, returning the number of bytes
+    /// consumed.
+    ///
+    /// Stops before any other byte and before any byte which would exceed a CSI limit, so that
+    /// `csi` can handle it.
+    fn csi_params(&mut self, bytes: &[u8]) -> usize {
+        // Leave room for the final byte, as in `push_csi`.
+        let room = (MAX_CSI_LEN - 1).saturating_sub(self.buffer.len());
+        let mut len = 0;
+        for &byte in &bytes[..bytes.len().min(room)] {
+            match byte {
+                b';' | b':' if self.csi_separators + 1 < MAX_CSI_PARAMS => {
+                    self.csi_separators += 1;
+                    self.csi_param_len = 0;
+                }
+                b'0'..=b'9' if self.csi_param_len < MAX_CSI_PARAM_LEN => self.csi_param_len += 1,
+                b'<'..=b'?' => (),
+                _ => break,
             }
-            Ok(None) => {}
-            Err(_) => self.buffer.clear(),
+            len += 1;
         }
+        if len > 0 {
+            self.buffer.extend_from_slice(&bytes[..len]);
+            self.state = State::CsiParam;
+        }
+        len
+    }
+
+    /// Reports the completed bracketed paste in the buffer, reusing the buffer's allocation for
+    /// the pasted text.
+    fn dispatch_paste(&mut self) {
+        let mut bytes = std::mem::replace(&mut self.buffer, Vec::with_capacity(256));
+        bytes.truncate(bytes.len() - PASTE_END.len());
+        bytes.drain(..PASTE_START.len());
+        let paste = String::from_utf8(bytes)
+            .unwrap_or_else(|err| String::from_utf8_lossy(err.as_bytes()).into_owned());
+        self.emit(Event::Paste(paste));
+        self.reset();
     }
+
+    fn ground(&mut self, byte: u8) {
+        match byte {
+            b'\x1B' => self.push(byte, State::Escape),
+            // 8-bit controls are stored as their 7-bit equivalents for decoding.
+            0x8F if self.c1_controls => self.push_c1(b'O', State::Final),
+            0x90 if self.c1_controls => self.push_c1(b'P', State::String),
+            0x9B if self.c1_controls => self.push_c1(b'[', State::CsiEntry),
+            0x9D if self.c1_controls => self.push_c1(b']', State::String),
+            0x9F if self.c1_controls => self.push_c1(b'_', State::String),
+            0xC0..=0xDF => self.push(byte, State::Utf8(1)),
+            0xE0..=0xEF => self.push(byte, State::Utf8(2)),
+            0xF0..=0xF7 => self.push(byte, State::Utf8(3)),
+            // ASCII. Invalid UTF-8 lead bytes are discarded by `dispatch`.
+            _ => {
+                self.buffer.push(byte);
+                self.dispatch();
+            }
+        }
+    }
+
+    fn csi(&mut self, byte: u8) {
+        match byte {
+            // CAN and SUB abort the sequence and ESC starts a new one.
+            b'\x18' | b'\x1A' | b'\x1B' => {
+                self.reset();
+                self.ground(byte);
+            }
+            // Other C0 controls are executed without interrupting the sequence.
+            0x00..=0x1F => {
+                if let Ok(Some(event)) = parse_event(&[byte], false) {
+                    self.emit(event);
+                }
+            }
+            // The rest of a malformed or abandoned sequence.
+            0x20..=0x7F if self.state == State::CsiIgnore => {
+                if (0x40..=0x7E).contains(&byte) {
+                    self.reset();
+                }
+            }
+            // Intermediate bytes.
+            0x20..=0x2F => self.push_csi(byte, State::CsiIntermediate),
+            // Parameter bytes. These may not follow intermediate bytes.
+            0x30..=0x3F if self.state == State::CsiIntermediate => {
+                self.reset();
+                self.state = State::CsiIgnore;
+            }
+            0x30..=0x3F => {
+                if matches!(byte, b';' | b':') {
+                    self.csi_separators += 1;
+                    self.csi_param_len = 0;
+                } else if byte.is_ascii_digit() {
+                    self.csi_param_len += 1;
+                }
+                if self.csi_separators >= MAX_CSI_PARAMS || self.csi_param_len > MAX_CSI_PARAM_LEN {
+                    self.buffer.push(byte);
+                    self.abandon(State::CsiIgnore);
+                } else {
+                    self.push_csi(byte, State::CsiParam);
+                }
+            }
+            // Final bytes.
+            0x40..=0x7E => {
+                self.buffer.push(byte);
+                if self.buffer == PASTE_START {
+                    self.state = State::Paste;
+                } else {
+                    self.dispatch();
+                }
+            }
+            // DEL is ignored.
+            0x7F => (),
+            0x80..=0xFF => {
+                self.reset();
+                self.ground(byte);
+            }
+        }
+    }
+
+    fn push(&mut self, byte: u8, state: State) {
+        self.buffer.push(byte);
+        self.state = state;
+    }
+
+    /// Starts the sequence introduced by an 8-bit control, as `ESC` followed by `byte`.
+    fn push_c1(&mut self, byte: u8, state: State) {
+        self.buffer.extend_from_slice(&[b'\x1B', byte]);
+        self.state = state;
+    }
+
+    fn push_csi(&mut self, byte: u8, state: State) {
+        self.push(byte, state);
+        // Leave room for the final byte.
+        if self.buffer.len() >= MAX_CSI_LEN {
+            self.abandon(State::CsiIgnore);
+        }
+    }
+
+    /// Reports the buffered sequence as [`Event::Malformed`] and discards the rest of it in
+    /// `state`.
+    fn abandon(&mut self, state: State) {
+        let bytes = std::mem::replace(&mut self.buffer, Vec::with_capacity(256));
+        self.stats.malformed += 1;
+        trace!(bytes = %bytes.escape_ascii(), "abandoned malformed sequence");
+        self.emit(Event::Malformed(bytes));
+        self.reset();
+        self.state = state;
+    }
+
+    /// Decodes the complete sequence in the buffer.
+    fn dispatch(&mut self) {
+        match parse_event(&self.buffer, false) {
+            Ok(Some(event)) => self.emit(event),
+            _ => {
+                self.stats.unrecognized += 1;
+                trace!(bytes = %self.buffer.escape_ascii(), "unrecognized sequence");
+                if let Some(csi) = unspecified_csi(&self.buffer) {
+                    self.emit(Event::Csi(csi));
+                }
+            }
+        }
+        self.reset();
+    }
+
+    /// Queues a completed event.
+    fn emit(&mut self, event: Event) {
+        self.stats.events += 1;
+        trace!(?event, "parsed event");
+        self.events.push_back(event);
+    }
+
+    /// Reports a lone ESC as the Escape key.
+    fn resolve_escape(&mut self) {
+        match self.state {
+            State::Escape => self.dispatch(),
+            State::ApcEntry => self.alt_underscore(),
+            _ => (),
+        }
+    }
+
+    /// Reports the `ESC _` being parsed as Alt+`_`.
+    fn alt_underscore(&mut self) {
+        self.reset();
+        self.emit(Event::Key(KeyEvent::new(
+            KeyCode::Char('_'),
+            Modifiers::ALT,
+        )));
+    }
+
+    fn reset(&mut self) {
+        self.buffer.clear();
+        self.state = State::Ground;
+        self.csi_separators = 0;
+        self.csi_param_len = 0;
+    }
+}
+
+/// Returns the number of bytes at the end of `bytes` which begin a UTF-8 encoded character without
+/// completing it, so that the next byte continues the character rather than being a C1 control.
+pub(crate) fn incomplete_char_len(bytes: &[u8]) -> usize {
+    for (back, &byte) in bytes.iter().rev().take(4).enumerate() {
+        let len = match byte {
+            0x80..=0xBF => continue,
+            0xC0..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF7 => 4,
+            _ => return 0,
+        };
+        return if back + 1 < len { back + 1 } else { 0 };
+    }
+    0
+}
+
+/// Counters describing the input a [`Parser`] has processed, returned by [`Parser::stats`] and
+/// [`EventReader::parser_stats`].
+///
+/// These are diagnostics for tracking down input which a terminal sends but the parser doesn't
+/// understand: a growing [`Self::unrecognized`] count while pressing a key means the terminal's
+/// encoding of that key isn't supported.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ParserStats {
+    /// Bytes of input passed to the parser.
+    pub bytes: u64,
+    /// Events produced, including [`Event::Malformed`] reports.
+    pub events: u64,
+    /// Sequences abandoned for exceeding a length limit and reported as [`Event::Malformed`].
+    pub malformed: u64,
+    /// Complete sequences which don't decode to a typed event. Unknown CSI sequences are reported
+    /// as [`Csi::Unspecified`] and other sequences are discarded.
+    pub unrecognized: u64,
 }
 
+/// A function called with the raw bytes of input, installed with [`EventReader::set_raw_tap`].
+///
+/// The function is behind a mutex only so that the parser stays `Sync`; it is always called
+/// through `&mut self`.
+pub(crate) struct RawTap(parking_lot::Mutex<TapFn>);
+
+type TapFn = Box<dyn FnMut(&[u8]) + Send>;
+
+impl RawTap {
+    pub(crate) fn new<F>(tap: F) -> Self
+    where
+        F: FnMut(&[u8]) + Send + 'static,
+    {
+        Self(parking_lot::Mutex::new(Box::new(tap)))
+    }
+}
+
+impl std::fmt::Debug for RawTap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RawTap").finish_non_exhaustive()
+    }
+}
+
+/// The position of a [`Parser`] within the sequence it is framing.
+///
+/// These are the states of the VT500-series parser, narrowed to those which occur in terminal
+/// input, plus states for the few input sequences which don't follow ECMA-48 framing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// Between sequences. The buffer is empty.
+    Ground,
+    /// After ESC.
+    Escape,
+    /// After an `ESC _` which ended the input: an APC string if more input follows, otherwise
+    /// Alt+`_`.
+    ApcEntry,
+    /// Within a UTF-8 encoded character, with this many continuation bytes left.
+    Utf8(u8),
+    /// After `ESC O` (SS3) or `ESC [ [` (Linux console function keys): the next byte completes
+    /// the sequence.
+    Final,
+    /// After CSI.
+    CsiEntry,
+    CsiParam,
+    CsiIntermediate,
+    /// A malformed or overlong CSI sequence, which is discarded up to its final byte.
+    CsiIgnore,
+    /// The raw `Cb Cx Cy` bytes of a normal mouse report (`CSI M Cb Cx Cy`), with this many
+    /// bytes left.
+    MouseBytes(u8),
+    /// An OSC, DCS or APC string.
+    String,
+    /// An overlong OSC, DCS or APC string, which is discarded up to its terminator.
+    StringIgnore,
+    /// After ESC within an OSC, DCS or APC string. The buffer is empty if the string is being
+    /// discarded.
+    StringEscape,
+    /// Bracketed paste contents, up to `CSI 201 ~`.
+    Paste,
+}
+
+/// Returns the length of the string contents at the start of `bytes`, up to the first byte which
+/// may end an OSC, DCS or APC string: ESC, BEL, CAN, SUB or an 8-bit ST.
+///
+/// Other C0 controls also stop the run. They are rare in strings and `advance` handles them the
+/// same either way.
+fn string_len(bytes: &[u8]) -> usize {
+    bytes
+        .iter()
+        .position(|&byte| byte < 0x20 || byte == 0x9C)
+        .unwrap_or(bytes.len())
+}
+
+/// Starts a bracketed paste.
+const PASTE_START: &[u8] = b"\x1B[200~";
+/// Ends a bracketed paste.
+const PASTE_END: &[u8] = b"\x1B[201~";
+
+/// The most parameters a CSI sequence may have, counting sub-parameters.
+const MAX_CSI_PARAMS: usize = 64;
+/// The most digits a CSI parameter may have. This fits any `u64`.
+const MAX_CSI_PARAM_LEN: usize = 20;
+/// The most bytes a CSI sequence may have, including the introducer and the final byte.
+const MAX_CSI_LEN: usize = 512;
+/// The most bytes an OSC, DCS or APC string may have, including the introducer and the terminator.
+const MAX_STRING_LEN: usize = 1 << 20;
+
 #[derive(Debug)]
 struct MalformedSequenceError;
 
@@ -150,6 +812,32 @@ macro_rules! bail {
     };
 }
 
+/// Splits a complete CSI sequence into its parameter, intermediate and final bytes.
+///
+/// Returns `None` for sequences which don't follow the ECMA-48 CSI syntax, such as the Linux
+/// console's `CSI [ A` keys and X10 mouse reports.
+fn unspecified_csi(buffer: &[u8]) -> Option<Csi> {
+    let (&final_byte, body) = buffer.strip_prefix(b"\x1B[")?.split_last()?;
+    let split = body
+        .iter()
+        .position(|byte| !(0x30..=0x3F).contains(byte))
+        .unwrap_or(body.len());
+    let (params, intermediates) = body.split_at(split);
+    if !(0x40..=0x7E).contains(&final_byte)
+        || !intermediates
+            .iter()
+            .all(|byte| (0x20..=0x2F).contains(byte))
+    {
+        return None;
+    }
+    // Both parts are ASCII.
+    Some(Csi::Unspecified {
+        params: str::from_utf8(params).ok()?.to_owned(),
+        intermediates: str::from_utf8(intermediates).ok()?.to_owned(),
+        final_byte,
+    })
+}
+
 fn parse_event(buffer: &[u8], maybe_more: bool) -> Result<Option<Event>> {
     if buffer.is_empty() {
         return Ok(None);
@@ -188,6 +876,7 @@ fn parse_event(buffer: &[u8], maybe_more: bool) -> Result<Option<Event>> {
                     b'[' => parse_csi(buffer),
                     b']' => parse_osc(buffer),
                     b'P' => parse_dcs(buffer),
+                    b'_' => parse_apc(buffer),
                     b'\x1B' => Ok(Some(Event::Key(KeyCode::Escape.into()))),
                     _ => parse_event(&buffer[1..], maybe_more).map(|event_option| {
                         event_option.map(|event| {
@@ -284,6 +973,8 @@ fn parse_csi(buffer: &[u8]) -> Result<Option<Event>> {
             modifiers: Modifiers::SHIFT,
             kind: KeyEventKind::Press,
             state: KeyEventState::NONE,
+            text: None,
+            base_code: None,
         })),
         b'M' => return parse_csi_normal_mouse(buffer),
         b'<' => return parse_csi_sgr_mouse(buffer),
@@ -327,6 +1018,8 @@ fn parse_csi(buffer: &[u8]) -> Result<Option<Event>> {
                         b'~' => return parse_csi_special_key_code(buffer),
                         b'u' => return parse_csi_u_encoded_key_code(buffer),
                         b'R' => return parse_csi_cursor_position(buffer),
+                        b'y' => return parse_csi_ansi_mode(buffer),
+                        b't' => return parse_csi_window_report(buffer),
                         _ => return parse_csi_modifier_key_code(buffer),
                     }
                 }
@@ -349,6 +1042,9 @@ fn parse_osc(buffer: &[u8]) -> Result<Option<Event>> {
         return Ok(None);
     };
     let s = str::from_utf8(&buffer[2..buffer.len()])?;
+    if let Some(notification) = s.strip_prefix("99;") {
+        return parse_notification_report(notification);
+    }
     let mut split = s.split(';');
     let index = next_parsed::<u8>(&mut split)?;
     let Some(color_number) = osc::DynamicColorNumber::from_index(index) else {
@@ -368,6 +1064,25 @@ fn parse_osc(buffer: &[u8]) -> Result<Option<Event>> {
     ))))
 }
 
+/// Parses kitty's notification activation report, `OSC 99 ; i=<id> ; ST`.
+///
+/// The payload after the metadata is empty or the index of the button the user clicked.
+fn parse_notification_report(s: &str) -> Result<Option<Event>> {
+    let metadata = s.split(';').next().unwrap_or_default();
+    let mut id = None;
+    for entry in metadata.split(':') {
+        match entry.split_once('=') {
+            Some(("i", value)) => id = Some(value.parse().map_err(|_| MalformedSequenceError)?),
+            // Other payload types are close notifications and capability replies, which are only
+            // sent when requested.
+            Some(("p", _)) => bail!(),
+            _ => (),
+        }
+    }
+    let Some(id) = id else { bail!() };
+    Ok(Some(Event::Osc(osc::Osc::NotificationActivated(id))))
+}
+
 fn next_parsed<T>(iter: &mut dyn Iterator<Item = &str>) -> Result<T>
 where
     T: str::FromStr,
@@ -434,26 +1149,7 @@ fn parse_csi_u_encoded_key_code(buffer: &[u8]) -> Result<Option<Event>> {
             (special_key_code, state)
         } else if let Some(c) = char::from_u32(codepoint) {
             (
-                match c {
-                    '\x1B' => KeyCode::Escape,
-                    '\r' => KeyCode::Enter,
-                    /*
-                    // Issue #371: \n = 0xA, which is also the keycode for Ctrl+J. The only reason we get
-                    // newlines as input is because the terminal converts \r into \n for us. When we
-                    // enter raw mode, we disable that, so \n no longer has any meaning - it's better to
-                    // use Ctrl+J. Waiting to handle it here means it gets picked up later
-                    '\n' if !crate::terminal::sys::is_raw_mode_enabled() => KeyCode::Enter,
-                    */
-                    '\t' => {
-                        if modifiers.contains(Modifiers::SHIFT) {
-                            KeyCode::BackTab
-                        } else {
-                            KeyCode::Tab
-                        }
-                    }
-                    '\x7F' => KeyCode::Backspace,
-                    _ => KeyCode::Char(c),
-                },
+                translate_char_key_code(c, modifiers),
                 KeyEventState::empty(),
             )
         } else {
@@ -489,27 +1185,91 @@ fn parse_csi_u_encoded_key_code(buffer: &[u8]) -> Result<Option<Event>> {
     // and the terminal sends a keyboard event containing shift, the sequence will
     // contain an additional codepoint separated by a ':' character which contains
     // the shifted character according to the keyboard layout.
+    let shifted_c = codepoints
+        .next()
+        .and_then(|codepoint| codepoint.parse::<u32>().ok())
+        .and_then(char::from_u32);
     if modifiers.contains(Modifiers::SHIFT) {
-        if let Some(shifted_c) = codepoints
-            .next()
-            .and_then(|codepoint| codepoint.parse::<u32>().ok())
-            .and_then(char::from_u32)
-        {
+        if let Some(shifted_c) = shifted_c {
             code = KeyCode::Char(shifted_c);
             modifiers.set(Modifiers::SHIFT, false);
         }
     }
 
+    // The flag also adds the key's codepoint in the standard (PC-101) layout after the shifted
+    // key, which is left empty when there is no shifted key: `CSI 1089::99 ; 5 u` for Ctrl+С on a
+    // Cyrillic layout, in the position of Ctrl+C on a US layout.
+    let base_code = codepoints
+        .next()
+        .and_then(|codepoint| codepoint.parse::<u32>().ok())
+        .and_then(|codepoint| match translate_functional_key_code(codepoint) {
+            Some((code, _)) => Some(code),
+            None => char::from_u32(codepoint).map(KeyCode::Char),
+        });
+
+    // With the "report associated text" flag the third section holds the text the key produced,
+    // as colon-separated codepoints.
+    let text = match split.next() {
+        Some(codepoints) if !codepoints.is_empty() => {
+            // Encoded on the stack rather than in a `String`: key events are frequent and
+            // `KeyText` truncates longer text anyway.
+            let mut text = [0; KeyText::CAPACITY];
+            let mut len = 0;
+            let mut truncated = false;
+            for codepoint in codepoints.split(':') {
+                let c = codepoint
+                    .parse::<u32>()
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or(MalformedSequenceError)?;
+                truncated |= len + c.len_utf8() > text.len();
+                if !truncated {
+                    len += c.encode_utf8(&mut text[len..]).len();
+                }
+            }
+            Some(KeyText::new(
+                str::from_utf8(&text[..len]).expect("encoded from `char`s"),
+            ))
+        }
+        _ => None,
+    };
+
     let event = Event::Key(KeyEvent {
         code,
         modifiers,
         kind,
         state: state_from_keycode | state_from_modifiers,
+        text,
+        base_code,
     });
 
     Ok(Some(event))
 }
 
+/// Translates the codepoint of a key reported by `CSI u` or modifyOtherKeys into a key code.
+fn translate_char_key_code(c: char, modifiers: Modifiers) -> KeyCode {
+    match c {
+        '\x1B' => KeyCode::Escape,
+        '\r' => KeyCode::Enter,
+        /*
+        // Issue #371: \n = 0xA, which is also the keycode for Ctrl+J. The only reason we get
+        // newlines as input is because the terminal converts \r into \n for us. When we
+        // enter raw mode, we disable that, so \n no longer has any meaning - it's better to
+        // use Ctrl+J. Waiting to handle it here means it gets picked up later
+        '\n' if !crate::terminal::sys::is_raw_mode_enabled() => KeyCode::Enter,
+        */
+        '\t' => {
+            if modifiers.contains(Modifiers::SHIFT) {
+                KeyCode::BackTab
+            } else {
+                KeyCode::Tab
+            }
+        }
+        '\x7F' => KeyCode::Backspace,
+        _ => KeyCode::Char(c),
+    }
+}
+
 fn parse_modifiers(mask: u8) -> Modifiers {
     let modifier_mask = mask.saturating_sub(1);
     let mut modifiers = Modifiers::empty();
@@ -601,6 +1361,8 @@ fn parse_csi_modifier_key_code(buffer: &[u8]) -> Result<Option<Event>> {
         modifiers,
         kind,
         state: KeyEventState::NONE,
+        text: None,
+        base_code: None,
     });
 
     Ok(Some(event))
@@ -615,6 +1377,9 @@ fn parse_csi_special_key_code(buffer: &[u8]) -> Result<Option<Event>> {
 
     // This CSI sequence can be a list of semicolon-separated numbers.
     let first = next_parsed::<u8>(&mut split)?;
+    if first == 27 {
+        return parse_csi_modify_other_keys(&mut split);
+    }
 
     let (modifiers, kind, state) =
         if let Ok((modifier_mask, kind_code)) = modifier_and_kind_parsed(&mut split) {
@@ -647,6 +1412,32 @@ fn parse_csi_special_key_code(buffer: &[u8]) -> Result<Option<Event>> {
         modifiers,
         kind,
         state,
+        text: None,
+        base_code: None,
+    });
+
+    Ok(Some(event))
+}
+
+/// Parses xterm's modifyOtherKeys encoding of a modified key, `CSI 27 ; modifiers ; codepoint ~`,
+/// after the leading `27`.
+///
+/// xterm and mintty send it for keys such as Ctrl+Enter or Ctrl+Shift+A which have no traditional
+/// encoding, once modifyOtherKeys is enabled with [`csi::Mode::XtermKeyMode`]. Unlike `CSI u`, the
+/// encoding has no event types, associated text or alternate keys.
+fn parse_csi_modify_other_keys(split: &mut dyn Iterator<Item = &str>) -> Result<Option<Event>> {
+    let modifier_mask = next_parsed::<u8>(split)?;
+    let codepoint = next_parsed::<u32>(split)?;
+    let c = char::from_u32(codepoint).ok_or(MalformedSequenceError)?;
+    let modifiers = parse_modifiers(modifier_mask);
+
+    let event = Event::Key(KeyEvent {
+        code: translate_char_key_code(c, modifiers),
+        modifiers,
+        kind: KeyEventKind::Press,
+        state: parse_modifiers_to_state(modifier_mask),
+        text: None,
+        base_code: None,
     });
 
     Ok(Some(event))
@@ -880,7 +1671,8 @@ fn parse_cb(cb: u8) -> Result<(MouseEventKind, Modifiers)> {
         (5, false) => MouseEventKind::ScrollDown,
         (6, false) => MouseEventKind::ScrollLeft,
         (7, false) => MouseEventKind::ScrollRight,
-        // We do not support other buttons.
+        (8..=11, false) => MouseEventKind::Down(MouseButton::Other(button_number)),
+        (8..=11, true) => MouseEventKind::Drag(MouseButton::Other(button_number)),
         _ => bail!(),
     };
 
@@ -932,6 +1724,61 @@ fn parse_csi_cursor_position(buffer: &[u8]) -> Result<Option<Event>> {
     ))))
 }
 
+fn parse_csi_window_report(buffer: &[u8]) -> Result<Option<Event>> {
+    // window state:          CSI 1 t / CSI 2 t
+    // window position:       CSI 3 ; x ; y t
+    // text-area size pixels: CSI 4 ; height ; width t
+    // screen size pixels:    CSI 5 ; height ; width t
+    // cell size pixels:      CSI 6 ; height ; width t
+    // text-area size cells:  CSI 8 ; height ; width t
+    // screen size cells:     CSI 9 ; height ; width t
+    // See <https://invisible-island.net/xterm/ctlseqs/ctlseqs.html#h3-Functions-using-CSI-_-ordered-by-the-final-character_s_>
+    assert!(buffer.starts_with(b"\x1B[")); // CSI
+    assert!(buffer.ends_with(b"t"));
+
+    let s = str::from_utf8(&buffer[2..buffer.len() - 1])?;
+    let mut split = s.split(';');
+
+    // Sizes may be reported with empty parameters, for example when the terminal doesn't know
+    // its pixel dimensions.
+    fn next_size(iter: &mut dyn Iterator<Item = &str>) -> Result<Option<i64>> {
+        match iter.next() {
+            Some("") => Ok(None),
+            Some(n) => n.parse().map(Some).map_err(|_| MalformedSequenceError),
+            None => bail!(),
+        }
+    }
+
+    let window = match next_parsed::<u8>(&mut split)? {
+        1 => csi::Window::ReportWindowStateResponse { iconified: false },
+        2 => csi::Window::ReportWindowStateResponse { iconified: true },
+        3 => {
+            let x = next_parsed(&mut split)?;
+            let y = next_parsed(&mut split)?;
+            csi::Window::ReportWindowPositionResponse { x, y }
+        }
+        n @ (4 | 5 | 6 | 8 | 9) => {
+            let height = next_size(&mut split)?;
+            let width = next_size(&mut split)?;
+            match n {
+                4 => csi::Window::ReportTextAreaSizePixelsResponse { width, height },
+                5 => csi::Window::ReportScreenSizePixelsResponse { width, height },
+                6 => csi::Window::ReportCellSizePixelsResponse { width, height },
+                8 => csi::Window::ReportTextAreaSizeCellsResponse { width, height },
+                9 => csi::Window::ReportScreenSizeCellsResponse { width, height },
+                _ => unreachable!(),
+            }
+        }
+        _ => bail!(),
+    };
+
+    if split.next().is_some() {
+        bail!();
+    }
+
+    Ok(Some(Event::Csi(Csi::Window(Box::new(window)))))
+}
+
 fn parse_csi_cursor_shape_query_response(buffer: &[u8]) -> Result<Option<Event>> {
     assert!(buffer.starts_with(b"\x1B[>")); // CSI >
     assert!(buffer.ends_with(b" q"));
@@ -1033,14 +1880,13 @@ fn parse_csi_theme_mode(buffer: &[u8]) -> Result<Option<Event>> {
         _ => bail!(),
     };
 
-    Ok(Some(Event::Csi(Csi::Mode(csi::Mode::ReportTheme(
-        theme_mode,
-    )))))
+    Ok(Some(Event::ThemeChanged(theme_mode)))
 }
 
 fn parse_csi_mode(buffer: &[u8]) -> Result<Option<Event>> {
     // sync output mode:       CSI ? 2026 ; 0 $ y
     // grapheme clustering:    CSI ? 2027 ; 1 $ y
+    // other modes:            CSI ? 2004 ; 2 $ y
     assert!(buffer.starts_with(b"\x1B[?"));
     assert!(buffer.ends_with(b"y"));
 
@@ -1055,7 +1901,7 @@ fn parse_csi_mode(buffer: &[u8]) -> Result<Option<Event>> {
     let mode = match next_parsed::<u16>(&mut split)? {
         2026 => csi::DecPrivateMode::Code(csi::DecPrivateModeCode::SynchronizedOutput),
         2027 => csi::DecPrivateMode::Code(csi::DecPrivateModeCode::GraphemeClustering),
-        _ => bail!(),
+        code => csi::DecPrivateMode::Unspecified(code),
     };
 
     let setting = match next_parsed::<u8>(&mut split)? {
@@ -1079,11 +1925,64 @@ fn parse_csi_mode(buffer: &[u8]) -> Result<Option<Event>> {
     ))))
 }
 
+fn parse_csi_ansi_mode(buffer: &[u8]) -> Result<Option<Event>> {
+    // insert mode:            CSI 4 ; 1 $ y
+    assert!(buffer.starts_with(b"\x1B["));
+    assert!(buffer.ends_with(b"y"));
+
+    let s = str::from_utf8(&buffer[2..buffer.len() - 1])?;
+    let s = match s.strip_suffix('$') {
+        Some(s) => s,
+        None => bail!(),
+    };
+
+    let mut split = s.split(';');
+
+    let mode = match next_parsed::<u16>(&mut split)? {
+        2 => csi::TerminalMode::Code(csi::TerminalModeCode::KeyboardAction),
+        4 => csi::TerminalMode::Code(csi::TerminalModeCode::Insert),
+        8 => csi::TerminalMode::Code(csi::TerminalModeCode::BiDirectionalSupportMode),
+        12 => csi::TerminalMode::Code(csi::TerminalModeCode::SendReceive),
+        20 => csi::TerminalMode::Code(csi::TerminalModeCode::AutomaticNewline),
+        25 => csi::TerminalMode::Code(csi::TerminalModeCode::ShowCursor),
+        code => csi::TerminalMode::Unspecified(code),
+    };
+
+    let setting = match next_parsed::<u8>(&mut split)? {
+        0 => csi::DecModeSetting::NotRecognized,
+        1 => csi::DecModeSetting::Set,
+        2 => csi::DecModeSetting::Reset,
+        3 => csi::DecModeSetting::PermanentlySet,
+        4 => csi::DecModeSetting::PermanentlyReset,
+        _ => bail!(),
+    };
+
+    Ok(Some(Event::Csi(Csi::Mode(csi::Mode::ReportMode {
+        mode,
+        setting,
+    }))))
+}
+
+fn parse_apc(buffer: &[u8]) -> Result<Option<Event>> {
+    assert!(buffer.starts_with(escape::APC.as_bytes()));
+    let Some(payload) = buffer.strip_suffix(escape::ST.as_bytes()) else {
+        return Ok(None);
+    };
+    Ok(Some(Event::Apc(payload[2..].to_vec())))
+}
+
 fn parse_dcs(buffer: &[u8]) -> Result<Option<Event>> {
     assert!(buffer.starts_with(escape::DCS.as_bytes()));
     if !buffer.ends_with(escape::ST.as_bytes()) {
         return Ok(None);
     }
+    // XTVERSION response: DCS > | D...D ST
+    if let Some(text) = buffer[2..buffer.len() - 2].strip_prefix(b">|") {
+        let text = str::from_utf8(text)?;
+        return Ok(Some(Event::Dcs(dcs::Dcs::TerminalVersion(
+            dcs::TerminalVersion::parse(text),
+        ))));
+    }
     match buffer[buffer.len() - 3] {
         // SGR response: DCS Ps $ r SGR m ST
         b'm' => {
@@ -1225,6 +2124,246 @@ fn parse_sgr(buffer: &str) -> Result<csi::Sgr> {
 mod test {
     use super::*;
 
+    #[test]
+    fn feed_split_input() {
+        let input = "a\x1b[1;5A\u{e9}\x1b[97;5u\x1b]11;rgb:0000/0000/0000\x1b\\\x1b[200~p\x1b[201~"
+            .as_bytes();
+        let mut expected = Parser::default();
+        expected.parse(input, false);
+        let expected: Vec<_> = std::iter::from_fn(|| expected.pop()).collect();
+        assert_eq!(expected.len(), 6);
+
+        for split in 0..input.len() {
+            let mut parser = Parser::default();
+            parser.feed(&input[..split]);
+            parser.feed(&input[split..]);
+            let events: Vec<_> = std::iter::from_fn(|| parser.next_event()).collect();
+            assert_eq!(events, expected, "split at {split}");
+            assert!(!parser.has_pending());
+        }
+
+        // An incomplete sequence is dropped by a flush.
+        let mut parser = Parser::default();
+        parser.feed(b"\x1b[1;");
+        assert!(parser.has_pending());
+        parser.flush();
+        assert!(!parser.has_pending());
+        assert_eq!(parser.next_event(), None);
+    }
+
+    #[test]
+    fn state_machine_recovery() {
+        fn events(input: &[u8]) -> Vec<Event> {
+            let mut parser = Parser::default();
+            parser.feed(input);
+            let events = std::iter::from_fn(|| parser.next_event()).collect();
+            assert!(!parser.has_pending());
+            events
+        }
+        let up = Event::Key(KeyCode::Up.into());
+        let key = |c| Event::Key(KeyCode::Char(c).into());
+        let ctrl = |c| Event::Key(KeyEvent::new(KeyCode::Char(c), Modifiers::CONTROL));
+
+        // C0 controls within CSI are executed without interrupting the sequence.
+        assert_eq!(events(b"\x1b[\x011A"), vec![ctrl('a'), up.clone()]);
+        // CAN aborts the sequence.
+        assert_eq!(events(b"\x1b[1\x18a"), vec![ctrl('x'), key('a')]);
+        // ESC starts a new sequence.
+        assert_eq!(events(b"\x1b[1;\x1b[A"), vec![up.clone()]);
+        // Parameter bytes after intermediate bytes make the sequence malformed.
+        assert_eq!(events(b"\x1b[1$2qa"), vec![key('a')]);
+        // Unknown sequences are reported at their final byte rather than held.
+        let unknown = Event::Csi(Csi::Unspecified {
+            params: "?1;2".to_owned(),
+            intermediates: String::new(),
+            final_byte: b'x',
+        });
+        assert_eq!(events(b"\x1b[?1;2xa"), vec![unknown, key('a')]);
+        // An ESC within a string which doesn't form ST starts a new sequence.
+        assert_eq!(events(b"\x1b]11;rgb\x1b[A"), vec![up.clone()]);
+        // A broken UTF-8 character doesn't swallow the following byte.
+        assert_eq!(events(b"\xc3a"), vec![key('a')]);
+
+        // Long input is handled in a single pass.
+        let mut paste = b"\x1b[200~".to_vec();
+        paste.extend(std::iter::repeat(b'x').take(1 << 20));
+        paste.extend(b"\x1b[201~");
+        assert_eq!(events(&paste), vec![Event::Paste("x".repeat(1 << 20))]);
+    }
+
+    #[test]
+    fn sequence_limits() {
+        fn events(input: &[u8]) -> Vec<Event> {
+            let mut parser = Parser::default();
+            parser.feed(input);
+            let events = std::iter::from_fn(|| parser.next_event()).collect();
+            assert!(!parser.has_pending());
+            events
+        }
+        let key = Event::Key(KeyCode::Char('a').into());
+        let malformed = |events: &[Event]| match events {
+            [Event::Malformed(bytes), event] if *event == key => bytes.len(),
+            _ => panic!("expected a malformed sequence and a key, got {events:?}"),
+        };
+
+        // Sequences within the limits are parsed.
+        let mut input = b"\x1b[1".to_vec();
+        input.extend(b";1".repeat(MAX_CSI_PARAMS - 1));
+        input.push(b'u');
+        assert!(matches!(events(&input)[..], [Event::Key(_)]));
+
+        // Too many parameters.
+        let mut input = b"\x1b[1".to_vec();
+        input.extend(b";1".repeat(MAX_CSI_PARAMS));
+        input.extend(b"ua");
+        assert_eq!(malformed(&events(&input)), 2 * MAX_CSI_PARAMS + 2);
+
+        // A long parameter, sent in pieces to show that the rest isn't buffered.
+        let mut parser = Parser::default();
+        parser.feed(b"\x1b[");
+        for _ in 0..1000 {
+            parser.feed(&[b'1'; 1000]);
+        }
+        assert!(parser.buffer.is_empty());
+        parser.feed(b"~a");
+        let long: Vec<_> = std::iter::from_fn(|| parser.next_event()).collect();
+        assert_eq!(malformed(&long), MAX_CSI_PARAM_LEN + 3);
+
+        // A long sequence of short parameters.
+        let mut input = b"\x1b[".to_vec();
+        input.extend(b"<".repeat(MAX_CSI_LEN));
+        input.extend(b"Ma");
+        assert_eq!(malformed(&events(&input)), MAX_CSI_LEN);
+
+        // Long strings, terminated by ST and BEL.
+        let mut input = b"\x1b]11;".to_vec();
+        input.extend(b"x".repeat(MAX_STRING_LEN));
+        let mut st = input.clone();
+        st.extend(b"\x1b\\a");
+        assert_eq!(malformed(&events(&st)), MAX_STRING_LEN);
+        input.extend(b"\x07a");
+        assert_eq!(malformed(&events(&input)), MAX_STRING_LEN);
+    }
+
+    #[test]
+    fn parse_kitty_base_layout_key() {
+        fn key(input: &[u8]) -> KeyEvent {
+            let mut parser = Parser::default();
+            parser.parse(input, false);
+            match parser.pop() {
+                Some(Event::Key(key)) => key,
+                event => panic!("expected a key event, got {event:?}"),
+            }
+        }
+
+        // Ctrl+С on a Cyrillic layout, without and with a shifted key.
+        let event = key(b"\x1b[1089::99;5u");
+        assert_eq!(event.code, KeyCode::Char('\u{441}'));
+        assert_eq!(event.base_code, Some(KeyCode::Char('c')));
+        let event = key(b"\x1b[1089:1057:99;6u");
+        assert_eq!(event.code, KeyCode::Char('\u{421}'));
+        assert_eq!(event.modifiers, Modifiers::CONTROL);
+        assert_eq!(event.base_code, Some(KeyCode::Char('c')));
+
+        assert_eq!(key(b"\x1b[97:65;2u").base_code, None);
+    }
+
+    #[test]
+    fn parse_modify_other_keys() {
+        fn key(input: &[u8]) -> KeyEvent {
+            let mut parser = Parser::default();
+            parser.parse(input, false);
+            match parser.pop() {
+                Some(Event::Key(key)) => key,
+                event => panic!("expected a key event, got {event:?}"),
+            }
+        }
+
+        // Ctrl+Enter, Ctrl+Shift+A, Alt+Shift+Tab and Ctrl+Backspace.
+        let event = key(b"\x1b[27;5;13~");
+        assert_eq!(event.code, KeyCode::Enter);
+        assert_eq!(event.modifiers, Modifiers::CONTROL);
+        let event = key(b"\x1b[27;6;65~");
+        assert_eq!(event.code, KeyCode::Char('A'));
+        assert_eq!(event.modifiers, Modifiers::CONTROL | Modifiers::SHIFT);
+        let event = key(b"\x1b[27;4;9~");
+        assert_eq!(event.code, KeyCode::BackTab);
+        assert_eq!(event.modifiers, Modifiers::ALT | Modifiers::SHIFT);
+        assert_eq!(key(b"\x1b[27;5;127~").code, KeyCode::Backspace);
+        assert_eq!(key(b"\x1b[27;5;32~").code, KeyCode::Char(' '));
+
+        // Other special keys are unaffected.
+        assert_eq!(key(b"\x1b[23;5~").code, KeyCode::Function(11));
+
+        let mut parser = Parser::default();
+        parser.parse(b"\x1b[27;5~", false);
+        assert!(!matches!(parser.pop(), Some(Event::Key(_))));
+    }
+
+    #[test]
+    fn parse_c1_controls() {
+        fn events(input: &[u8], c1_controls: bool) -> Vec<Event> {
+            let mut parser = Parser::default();
+            parser.set_c1_controls(c1_controls);
+            parser.parse(input, false);
+            std::iter::from_fn(|| parser.pop()).collect()
+        }
+
+        assert_eq!(
+            events(b"\x9b5~\x8fP", true),
+            [
+                Event::Key(KeyCode::PageUp.into()),
+                Event::Key(KeyCode::Function(1).into())
+            ]
+        );
+        let osc = events(b"\x9d11;rgb:ffff/0000/0000\x9c", true);
+        assert!(matches!(osc[..], [Event::Osc(_)]));
+        assert_eq!(osc, events(b"\x1b]11;rgb:ffff/0000/0000\x1b\\", true));
+        // Disabled by default.
+        assert_eq!(events(b"\x9b5~", false), events(b"5~", false));
+        // UTF-8 text is unaffected: U+015B is encoded as C5 9B.
+        assert_eq!(
+            events("\u{15b}".as_bytes(), true),
+            [Event::Key(KeyCode::Char('\u{15b}').into())]
+        );
+    }
+
+    #[test]
+    fn incomplete_chars() {
+        assert_eq!(incomplete_char_len(b""), 0);
+        assert_eq!(incomplete_char_len(b"rgb"), 0);
+        assert_eq!(incomplete_char_len(b"a\xc5"), 1);
+        assert_eq!(incomplete_char_len(b"\xe2\x80"), 2);
+        assert_eq!(incomplete_char_len(b"\xe2\x80\xa6"), 0);
+        // Invalid UTF-8 isn't part of a character.
+        assert_eq!(incomplete_char_len(b"a\x80\x80"), 0);
+        assert_eq!(incomplete_char_len(b"\x80\x80\x80\x80"), 0);
+    }
+
+    #[test]
+    fn parse_kitty_associated_text() {
+        fn text(input: &[u8]) -> Option<KeyText> {
+            let mut parser = Parser::default();
+            parser.parse(input, false);
+            match parser.pop() {
+                Some(Event::Key(key)) => key.text,
+                event => panic!("expected a key event, got {event:?}"),
+            }
+        }
+
+        assert_eq!(text(b"\x1b[97u"), None);
+        assert_eq!(text(b"\x1b[97;1;97u"), Some(KeyText::new("a")));
+        // A dead key followed by 'e' with the default modifiers omitted.
+        assert_eq!(text(b"\x1b[101;;233u"), Some(KeyText::new("\u{e9}")));
+        assert_eq!(text(b"\x1b[97;2:1;65:66u").as_deref(), Some("AB"),);
+
+        // Text is truncated to the capacity at a character boundary.
+        let long = KeyText::new(&"\u{e9}".repeat(20));
+        assert_eq!(long.len(), KeyText::CAPACITY - 1);
+        let input = format!("\x1b[101;;{}u", ["233"; 20].join(":"));
+        assert_eq!(text(input.as_bytes()), Some(long));
+    }
+
     #[test]
     fn parse_dcs_sgr_response() {
         // Example from <https://vt100.net/docs/vt510-rm/DECRPSS.html>
@@ -1250,6 +2389,27 @@ mod test {
         );
     }
 
+    #[test]
+    fn parse_dcs_terminal_version() {
+        let event = parse_event(b"\x1bP>|XTerm(388)\x1b\\", false)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            event,
+            Event::Dcs(dcs::Dcs::TerminalVersion(dcs::TerminalVersion {
+                name: "XTerm".to_owned(),
+                version: Some("388".to_owned()),
+            }))
+        );
+        let event = parse_event(b"\x1bP>|tmux 3.4\x1b\\", false)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            event.to_string(),
+            r#"dcs TerminalVersion(TerminalVersion { name: "tmux", version: Some("3.4") })"#
+        );
+    }
+
     #[test]
     fn parse_dcs_sgr_curly_underline() {
         // A DECRPSS reply describing a curly (`4:3`) underline must round-trip through `parse_sgr`.
@@ -1288,6 +2448,64 @@ mod test {
         );
     }
 
+    #[test]
+    fn parse_osc_notification_activated() {
+        let activated = Event::Osc(osc::Osc::NotificationActivated(7));
+        assert_eq!(
+            parse_event(b"\x1b]99;i=7;\x1b\\", false).unwrap(),
+            Some(activated.clone())
+        );
+        // A click on the second button.
+        assert_eq!(
+            parse_event(b"\x1b]99;i=7;2\x07", false).unwrap(),
+            Some(activated.clone())
+        );
+        assert_eq!(
+            parse_event(
+                osc::Osc::NotificationActivated(7).to_string().as_bytes(),
+                false
+            )
+            .unwrap(),
+            Some(activated)
+        );
+        assert!(parse_event(b"\x1b]99;i=7:p=close;\x1b\\", false).is_err());
+    }
+
+    #[test]
+    fn parse_apc() {
+        let mut parser = Parser::default();
+        parser.feed(b"\x1b_Gi=31;OK\x1b");
+        assert_eq!(parser.pop(), None);
+        parser.feed(b"\\a");
+        assert_eq!(parser.pop(), Some(Event::Apc(b"Gi=31;OK".to_vec())));
+        assert_eq!(parser.pop(), Some(Event::Key(KeyCode::Char('a').into())));
+    }
+
+    #[test]
+    fn parse_alt_underscore() {
+        let alt = Event::Key(KeyEvent::new(KeyCode::Char('_'), Modifiers::ALT));
+        let mut parser = Parser::default();
+        parser.parse(b"\x1b_", false);
+        parser.parse(b"a", false);
+        assert_eq!(parser.pop(), Some(alt.clone()));
+        assert_eq!(parser.pop(), Some(Event::Key(KeyCode::Char('a').into())));
+        assert_eq!(parser.pop(), None);
+
+        // With more input to come it is decided like a lone ESC.
+        parser.parse(b"\x1b_", true);
+        assert!(parser.is_escape_pending());
+        assert_eq!(parser.pop(), None);
+        parser.flush();
+        assert_eq!(parser.pop(), Some(alt));
+
+        // An APC string split after its introducer.
+        parser.parse(b"\x1b_", true);
+        parser.parse(b"Gi=31;OK", true);
+        parser.parse(b"\x1b\\", true);
+        assert_eq!(parser.pop(), Some(Event::Apc(b"Gi=31;OK".to_vec())));
+        assert_eq!(parser.pop(), None);
+    }
+
     #[test]
     fn parse_cursor_shape_query() {
         // CSI > SP q with no parameters is a query.
@@ -1357,6 +2575,44 @@ mod test {
         );
     }
 
+    #[test]
+    fn parse_ansi_mode_report() {
+        for (mode, setting) in [
+            (csi::TerminalModeCode::Insert, csi::DecModeSetting::Set),
+            (
+                csi::TerminalModeCode::AutomaticNewline,
+                csi::DecModeSetting::Reset,
+            ),
+            (
+                csi::TerminalModeCode::SendReceive,
+                csi::DecModeSetting::PermanentlySet,
+            ),
+        ] {
+            let report = Csi::Mode(csi::Mode::ReportMode {
+                mode: csi::TerminalMode::Code(mode),
+                setting,
+            });
+            let event = parse_event(report.to_string().as_bytes(), false)
+                .unwrap()
+                .unwrap();
+            assert_eq!(event, Event::Csi(report));
+        }
+        // Only `$y` reports are mode reports.
+        assert!(parse_event(b"\x1b[1;2y", false).is_err());
+    }
+
+    #[test]
+    fn parse_other_mode_report() {
+        let event = parse_event(b"\x1b[?2004;2$y", false).unwrap().unwrap();
+        assert_eq!(
+            event,
+            Event::Csi(Csi::Mode(csi::Mode::ReportDecPrivateMode {
+                mode: csi::DecPrivateMode::Unspecified(2004),
+                setting: csi::DecModeSetting::Reset,
+            }))
+        );
+    }
+
     #[test]
     fn parse_bracketed_paste() {
         // Incomplete input is not considered a paste.
@@ -1366,5 +2622,133 @@ mod test {
         assert_eq!(event, Some(Event::Paste("Hello, world!".to_string())));
         let event = parse_event(b"\x1b[200~\x1b[201~", false).unwrap();
         assert_eq!(event, Some(Event::Paste("".to_string())));
+
+        // Split anywhere, including within the markers, with invalid UTF-8 replaced.
+        let input = b"\x1b[200~a\x1b[201\xff\x1b[201~b";
+        for split in 0..input.len() {
+            let mut parser = Parser::default();
+            parser.feed(&input[..split]);
+            parser.feed(&input[split..]);
+            let events: Vec<_> = std::iter::from_fn(|| parser.next_event()).collect();
+            assert_eq!(
+                events,
+                [
+                    Event::Paste("a\x1b[201\u{fffd}".to_string()),
+                    Event::Key(KeyCode::Char('b').into())
+                ],
+                "split at {split}"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_window_reports() {
+        let event = parse_event(b"\x1b[4;600;800t", false).unwrap().unwrap();
+        assert_eq!(
+            event,
+            Event::Csi(Csi::Window(Box::new(
+                csi::Window::ReportTextAreaSizePixelsResponse {
+                    width: Some(800),
+                    height: Some(600),
+                }
+            )))
+        );
+        let event = parse_event(b"\x1b[8;24;80t", false).unwrap().unwrap();
+        assert_eq!(
+            event,
+            Event::Csi(Csi::Window(Box::new(
+                csi::Window::ReportTextAreaSizeCellsResponse {
+                    width: Some(80),
+                    height: Some(24),
+                }
+            )))
+        );
+        let event = parse_event(b"\x1b[6;;t", false).unwrap().unwrap();
+        assert_eq!(
+            event,
+            Event::Csi(Csi::Window(Box::new(
+                csi::Window::ReportCellSizePixelsResponse {
+                    width: None,
+                    height: None,
+                }
+            )))
+        );
+        let event = parse_event(b"\x1b[3;10;20t", false).unwrap().unwrap();
+        assert_eq!(
+            event,
+            Event::Csi(Csi::Window(Box::new(
+                csi::Window::ReportWindowPositionResponse { x: 10, y: 20 }
+            )))
+        );
+        let event = parse_event(b"\x1b[2t", false).unwrap().unwrap();
+        assert_eq!(
+            event,
+            Event::Csi(Csi::Window(Box::new(
+                csi::Window::ReportWindowStateResponse { iconified: true }
+            )))
+        );
+
+        // Requests and incomplete reports aren't valid input.
+        assert!(parse_event(b"\x1b[14t", false).is_err());
+        assert!(parse_event(b"\x1b[4;600t", false).is_err());
+    }
+
+    #[test]
+    fn window_report_round_trip() {
+        for report in [
+            "\x1b[1t",
+            "\x1b[3;-5;40t",
+            "\x1b[4;600;800t",
+            "\x1b[5;1080;1920t",
+            "\x1b[6;17;8t",
+            "\x1b[8;24;80t",
+            "\x1b[9;60;240t",
+        ] {
+            let event = parse_event(report.as_bytes(), false).unwrap().unwrap();
+            let Event::Csi(csi) = event else {
+                panic!("expected a CSI event for {report:?}");
+            };
+            assert_eq!(csi.to_string(), report);
+        }
+    }
+
+    #[test]
+    fn parse_theme_change() {
+        let event = parse_event(b"\x1b[?997;1n", false).unwrap().unwrap();
+        assert_eq!(event, Event::ThemeChanged(ThemeMode::Dark));
+        let event = parse_event(b"\x1b[?997;2n", false).unwrap().unwrap();
+        assert_eq!(event, Event::ThemeChanged(ThemeMode::Light));
+        assert!(parse_event(b"\x1b[?997;3n", false).is_err());
+
+        // The report round-trips through its CSI encoding.
+        assert_eq!(
+            Csi::Mode(csi::Mode::ReportTheme(ThemeMode::Light)).to_string(),
+            "\x1b[?997;2n"
+        );
+    }
+
+    #[test]
+    fn parse_extra_mouse_buttons() {
+        // Button 8 ("back") press and release in SGR encoding.
+        let event = parse_event(b"\x1b[<128;5;7M", false).unwrap().unwrap();
+        assert_eq!(
+            event,
+            Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Down(MouseButton::Other(8)),
+                column: 4,
+                row: 6,
+                modifiers: Modifiers::NONE,
+            })
+        );
+        let event = parse_event(b"\x1b[<129;5;7m", false).unwrap().unwrap();
+        assert_eq!(
+            event,
+            Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Up(MouseButton::Other(9)),
+                column: 4,
+                row: 6,
+                modifiers: Modifiers::NONE,
+            })
+        );
     }
 }
@@ -39,12 +39,32 @@ pub enum InputReaderMode {
 }
 
 impl Parser {
+    /// Decodes a batch of records read with `ReadConsoleInput`.
+    ///
+    /// In VT mode the key records carry the bytes of the input stream, which are fed to the same
+    /// state machine as Unix input. Sequences may be split across batches, for example when a
+    /// bracketed paste is larger than the console input buffer, so a trailing ESC is left pending
+    /// like [`Parser::parse`] with `maybe_more`. Resolve it with `parse(&[], false)` once no more
+    /// records are queued.
+    ///
+    /// The VT bytes of consecutive key records are parsed together, so the raw tap sees them in
+    /// the same chunks as it would on Unix. While key releases and repeats are reported, each
+    /// record is decoded on its own instead.
     pub(crate) fn decode_input_records(&mut self, records: &[Console::INPUT_RECORD]) {
+        let mut bytes = Vec::new();
         for record in records {
+            // Parse the bytes read so far before emitting any other event to keep them in order.
+            if !bytes.is_empty() && !self.is_batched_key_record(record) {
+                self.parse(&std::mem::take(&mut bytes), true);
+            }
             match record.EventType as u32 {
                 Console::KEY_EVENT => {
                     let record = unsafe { record.Event.KeyEvent };
                     match self.mode {
+                        InputReaderMode::Vte if self.report_event_types => {
+                            let byte = unsafe { record.uChar.AsciiChar } as u8;
+                            self.decode_vt_key_record(&record, byte);
+                        }
                         InputReaderMode::Vte => {
                             // This skips 'down's. IIRC Termwiz skips 'down's and Crossterm skips
                             // 'up's. If we skip 'up's we don't seem to get key events at all.
@@ -58,42 +78,35 @@ impl Parser {
                             }
                             // `read_console_input` uses `ReadConsoleInputA` so we should treat the
                             // key code as a byte and add it to the buffer.
-                            self.buffer.push(byte);
-                            self.process_bytes(true);
+                            bytes.push(byte);
                         }
                         InputReaderMode::Legacy => {
                             #[cfg(feature = "windows-legacy")]
-                            if let Some(event) =
+                            if let Some(Event::Key(key)) =
                                 legacy::handle_key_event(record, &mut self.surrogate_buffer)
                             {
-                                self.events.push_back(event);
+                                self.emit_key(key, &record);
                             }
                         }
                     }
                 }
                 Console::WINDOW_BUFFER_SIZE_EVENT => {
                     // NOTE: the `WINDOW_BUFFER_SIZE_EVENT` coordinates are one-based, even
-                    // though `GetConsoleScreenBufferInfo` is zero-based.
+                    // though `GetConsoleScreenBufferInfo` is zero-based. Degenerate sizes are
+                    // delivered too, matching SIGWINCH handling on Unix.
 
-                    use crate::{OneBased, WindowSize};
+                    use crate::WindowSize;
                     let record = unsafe { record.Event.WindowBufferSizeEvent };
-                    let Some(rows) = OneBased::new(record.dwSize.Y as u16) else {
-                        continue;
-                    };
-                    let Some(cols) = OneBased::new(record.dwSize.X as u16) else {
-                        continue;
-                    };
-                    self.events.push_back(Event::WindowResized(WindowSize {
-                        rows: rows.get(),
-                        cols: cols.get(),
+                    self.emit(Event::WindowResized(WindowSize {
+                        rows: u16::try_from(record.dwSize.Y).unwrap_or(0),
+                        cols: u16::try_from(record.dwSize.X).unwrap_or(0),
                         pixel_width: None,
                         pixel_height: None,
                     }));
                 }
                 Console::FOCUS_EVENT => {
                     #[cfg(feature = "windows-legacy")]
-                    self.events
-                        .push_back(legacy::handle_focus(unsafe { record.Event.FocusEvent }));
+                    self.emit(legacy::handle_focus(unsafe { record.Event.FocusEvent }));
                 }
                 Console::MOUSE_EVENT => {
                     #[cfg(feature = "windows-legacy")]
@@ -108,15 +121,95 @@ impl Parser {
                             middle: button_state.middle_button(),
                         };
                         if let Some(event) = mouse_event {
-                            self.events.push_back(event);
+                            self.emit(event);
                         }
                     }
                 }
                 _ => (),
             }
         }
-        if self.mode == InputReaderMode::Vte {
-            self.process_bytes(false);
+        self.parse(&bytes, true);
+    }
+
+    /// Sets whether key releases and repeats are reported.
+    pub(crate) fn set_report_event_types(&mut self, enabled: bool) {
+        self.report_event_types = enabled;
+        self.held_key = None;
+    }
+
+    /// Returns `true` if the VT bytes of `record` are parsed together with those of its neighbors.
+    fn is_batched_key_record(&self, record: &Console::INPUT_RECORD) -> bool {
+        self.mode == InputReaderMode::Vte
+            && !self.report_event_types
+            && record.EventType as u32 == Console::KEY_EVENT
+    }
+
+    /// Decodes a key record in VT mode while key releases and repeats are reported.
+    ///
+    /// The console only writes VT input for key presses, so releases and repeats are only
+    /// reported for keys which produce a single byte, such as letters, digits and Enter.
+    fn decode_vt_key_record(&mut self, record: &Console::KEY_EVENT_RECORD, byte: u8) {
+        // The zero byte is sent when the input record is not VT.
+        if byte == 0 {
+            return;
+        }
+        if record.bKeyDown == 0 {
+            // Translate the byte the key produced when it was pressed.
+            if self.state == State::Ground {
+                if let Ok(Some(Event::Key(key))) = parse_event(&[byte], false) {
+                    self.emit_key(
+                        KeyEvent {
+                            kind: KeyEventKind::Release,
+                            ..key
+                        },
+                        record,
+                    );
+                }
+            }
+            return;
+        }
+        let queued = self.events.len();
+        self.parse(&[byte], true);
+        // Only a key completed by this byte alone belongs to the record.
+        if self.events.len() == queued + 1 && matches!(self.events.back(), Some(Event::Key(_))) {
+            if let Some(Event::Key(key)) = self.events.pop_back() {
+                self.stats.events -= 1;
+                self.emit_key(key, record);
+            }
+        }
+    }
+
+    /// Queues a key event decoded from `record`.
+    ///
+    /// While key releases and repeats are reported, a press of the key which is already held is
+    /// reported as a repeat, and a record's `wRepeatCount` is reported as that many events.
+    /// Otherwise the key is queued as decoded, which reports releases in the legacy input mode.
+    fn emit_key(&mut self, mut key: KeyEvent, record: &Console::KEY_EVENT_RECORD) {
+        let virtual_key_code = record.wVirtualKeyCode;
+        let held = self.held_key == Some(virtual_key_code);
+        if record.bKeyDown != 0 {
+            self.held_key = Some(virtual_key_code);
+        } else if held {
+            self.held_key = None;
+        }
+
+        if !self.report_event_types {
+            self.emit(Event::Key(key));
+            return;
+        }
+        if key.kind == KeyEventKind::Press && held {
+            key.kind = KeyEventKind::Repeat;
+        }
+        let repeats = match key.kind {
+            KeyEventKind::Release => 0,
+            _ => record.wRepeatCount.saturating_sub(1),
+        };
+        self.emit(Event::Key(key));
+        for _ in 0..repeats {
+            self.emit(Event::Key(KeyEvent {
+                kind: KeyEventKind::Repeat,
+                ..key
+            }));
         }
     }
 }
@@ -320,6 +413,8 @@ pub(crate) mod legacy {
                         modifiers,
                         kind,
                         state: KeyEventState::empty(),
+                        text: None,
+                        base_code: None,
                     };
                     return Some(WindowsKeyEvent::KeyEvent(key_event));
                 }
@@ -388,6 +483,8 @@ pub(crate) mod legacy {
                 modifiers,
                 kind,
                 state: KeyEventState::empty(),
+                text: None,
+                base_code: None,
             };
             return Some(WindowsKeyEvent::KeyEvent(key_event));
         }
@@ -658,3 +755,104 @@ pub(crate) mod legacy {
         }))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Builds the key-down records which Windows Terminal sends for VT input `bytes`.
+    fn vt_records(bytes: &[u8]) -> Vec<Console::INPUT_RECORD> {
+        bytes
+            .iter()
+            .map(|&byte| Console::INPUT_RECORD {
+                EventType: Console::KEY_EVENT as _,
+                Event: Console::INPUT_RECORD_0 {
+                    KeyEvent: Console::KEY_EVENT_RECORD {
+                        bKeyDown: 1,
+                        wRepeatCount: 1,
+                        wVirtualKeyCode: 0,
+                        wVirtualScanCode: 0,
+                        uChar: Console::KEY_EVENT_RECORD_0 {
+                            AsciiChar: byte as _,
+                        },
+                        dwControlKeyState: 0,
+                    },
+                },
+            })
+            .collect()
+    }
+
+    #[test]
+    fn bracketed_paste_across_batches() {
+        let mut parser = Parser::with_mode(InputReaderMode::Vte);
+        // The paste markers and the pasted text are split at awkward points, including right
+        // after the ESC which starts each marker.
+        for batch in [&b"\x1b"[..], b"[200~hello\x1b", b"[20", b"1~"] {
+            parser.decode_input_records(&vt_records(batch));
+            assert_eq!(parser.pop(), None);
+        }
+        parser.parse(&[], false);
+        assert_eq!(parser.pop(), Some(Event::Paste("hello".to_string())));
+
+        // A lone ESC is the Escape key once no more input follows.
+        parser.decode_input_records(&vt_records(b"\x1b"));
+        assert_eq!(parser.pop(), None);
+        parser.parse(&[], false);
+        assert!(matches!(
+            parser.pop(),
+            Some(Event::Key(key)) if key.code == KeyCode::Escape
+        ));
+    }
+
+    #[test]
+    fn report_event_types() {
+        fn record(down: bool, repeat_count: u16) -> Console::INPUT_RECORD {
+            Console::INPUT_RECORD {
+                EventType: Console::KEY_EVENT as _,
+                Event: Console::INPUT_RECORD_0 {
+                    KeyEvent: Console::KEY_EVENT_RECORD {
+                        bKeyDown: down as _,
+                        wRepeatCount: repeat_count,
+                        wVirtualKeyCode: b'A' as u16,
+                        wVirtualScanCode: 0,
+                        uChar: Console::KEY_EVENT_RECORD_0 {
+                            AsciiChar: b'a' as _,
+                        },
+                        dwControlKeyState: 0,
+                    },
+                },
+            }
+        }
+        fn kinds(parser: &mut Parser) -> Vec<KeyEventKind> {
+            std::iter::from_fn(|| parser.pop())
+                .map(|event| match event {
+                    Event::Key(key) => {
+                        assert_eq!(key.code, KeyCode::Char('a'));
+                        key.kind
+                    }
+                    event => panic!("expected a key event, got {event:?}"),
+                })
+                .collect()
+        }
+        let records = [record(true, 1), record(true, 2), record(false, 1)];
+
+        let mut parser = Parser::with_mode(InputReaderMode::Vte);
+        parser.decode_input_records(&records);
+        assert_eq!(
+            kinds(&mut parser),
+            [KeyEventKind::Press, KeyEventKind::Press]
+        );
+
+        parser.set_report_event_types(true);
+        parser.decode_input_records(&records);
+        assert_eq!(
+            kinds(&mut parser),
+            [
+                KeyEventKind::Press,
+                KeyEventKind::Repeat,
+                KeyEventKind::Repeat,
+                KeyEventKind::Release,
+            ]
+        );
+    }
+}
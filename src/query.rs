@@ -0,0 +1,324 @@
+//! Several terminal queries written at once and answered with a single deadline.
+//!
+//! Applications often detect features at startup by asking for the cursor position, the kitty
+//! keyboard flags, the theme, the terminal's name and version and a handful of DEC private modes.
+//! Asking one at a time costs a round trip per query, and unsupported queries are never answered,
+//! so each one waits for its own timeout. A [`QueryBatch`] writes every query in one flush followed
+//! by the primary device attributes query, which every terminal answers, and sorts the replies into
+//! [`QueryResults`] in whatever order they arrive.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::{io, time::Duration};
+//!
+//! use termina::{
+//!     escape::csi::{DecModeSetting, DecPrivateMode, DecPrivateModeCode},
+//!     query::QueryBatch,
+//!     PlatformTerminal, Terminal,
+//! };
+//!
+//! fn main() -> io::Result<()> {
+//!     let mut terminal = PlatformTerminal::new()?;
+//!     terminal.enter_raw_mode()?;
+//!     let sync = DecPrivateMode::Code(DecPrivateModeCode::SynchronizedOutput);
+//!     let results = QueryBatch::new()
+//!         .cursor_position()
+//!         .kitty_keyboard_flags()
+//!         .dec_mode(sync)
+//!         .run(&mut terminal, Duration::from_millis(200))?;
+//!     terminal.enter_cooked_mode()?;
+//!
+//!     let synchronized = matches!(
+//!         results.dec_mode(sync),
+//!         Some(DecModeSetting::Set | DecModeSetting::Reset)
+//!     );
+//!     println!("kitty keyboard: {}", results.kitty_keyboard_flags.is_some());
+//!     println!("synchronized output: {synchronized}");
+//!     Ok(())
+//! }
+//! ```
+//!
+//! # Implementation Notes
+//!
+//! Terminals process input in order, so the device attributes reply usually marks the end of the
+//! replies. Some terminals answer out of order though, so replies which are already buffered
+//! when the device attributes arrive are still collected.
+
+use std::{
+    fmt, io,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    escape::{
+        csi::{
+            Csi, Cursor, DecModeSetting, DecPrivateMode, Device, Keyboard, KittyKeyboardFlags,
+            Mode, ThemeMode,
+        },
+        dcs::{Dcs, TerminalVersion},
+    },
+    Event, OneBased, Terminal,
+};
+
+/// The cursor position reported in reply to [`Cursor::RequestActivePositionReport`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CursorPosition {
+    /// The cursor's line.
+    pub line: OneBased,
+    /// The cursor's column.
+    pub col: OneBased,
+}
+
+/// Converts a [`Cursor::ActivePositionReport`], returning other events unchanged as the error.
+impl TryFrom<Event> for CursorPosition {
+    type Error = Event;
+
+    fn try_from(event: Event) -> Result<Self, Self::Error> {
+        match event {
+            Event::Csi(Csi::Cursor(Cursor::ActivePositionReport { line, col })) => {
+                Ok(Self { line, col })
+            }
+            event => Err(event),
+        }
+    }
+}
+
+/// A set of queries written together, created with [`QueryBatch::new`].
+///
+/// Displaying the batch writes its queries followed by the primary device attributes query, so it
+/// can also be sent on its own and the replies read with [`QueryResults::record`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct QueryBatch {
+    cursor_position: bool,
+    kitty_keyboard_flags: bool,
+    theme: bool,
+    terminal_version: bool,
+    dec_modes: Vec<DecPrivateMode>,
+}
+
+impl QueryBatch {
+    /// Creates a batch which only asks for the primary device attributes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Asks for the cursor position.
+    pub fn cursor_position(mut self) -> Self {
+        self.cursor_position = true;
+        self
+    }
+
+    /// Asks for the kitty keyboard protocol flags, which only terminals supporting the protocol
+    /// report.
+    pub fn kitty_keyboard_flags(mut self) -> Self {
+        self.kitty_keyboard_flags = true;
+        self
+    }
+
+    /// Asks for the dark or light theme, which only terminals supporting
+    /// [`DecPrivateModeCode::Theme`](crate::escape::csi::DecPrivateModeCode::Theme) report.
+    pub fn theme(mut self) -> Self {
+        self.theme = true;
+        self
+    }
+
+    /// Asks for the terminal's name and version (XTVERSION).
+    pub fn terminal_version(mut self) -> Self {
+        self.terminal_version = true;
+        self
+    }
+
+    /// Asks for the setting of a DEC private mode.
+    pub fn dec_mode(mut self, mode: DecPrivateMode) -> Self {
+        if !self
+            .dec_modes
+            .iter()
+            .any(|asked| asked.code() == mode.code())
+        {
+            self.dec_modes.push(mode);
+        }
+        self
+    }
+
+    /// Writes the queries and waits up to `timeout` for the replies.
+    ///
+    /// Waiting ends early once the device attributes are answered. Other events stay buffered for
+    /// later reads. Call this in raw mode, since replies are otherwise only delivered after Enter
+    /// is pressed.
+    pub fn run<T: Terminal>(
+        &self,
+        terminal: &mut T,
+        timeout: Duration,
+    ) -> io::Result<QueryResults> {
+        write!(terminal, "{self}")?;
+        terminal.flush()?;
+
+        let deadline = Instant::now() + timeout;
+        let mut results = QueryResults::default();
+        let is_reply = |event: &Event| self.is_reply(event);
+        loop {
+            // Only collect replies which are already buffered once the device attributes arrive.
+            let wait = if results.device_attributes {
+                Duration::ZERO
+            } else {
+                deadline.saturating_duration_since(Instant::now())
+            };
+            if !terminal.poll(is_reply, Some(wait))? {
+                break;
+            }
+            results.record(terminal.read(is_reply)?);
+        }
+        Ok(results)
+    }
+
+    /// Returns `true` if `event` replies to one of the batch's queries.
+    pub fn is_reply(&self, event: &Event) -> bool {
+        match event {
+            Event::Csi(Csi::Device(Device::DeviceAttributes(_))) => true,
+            Event::Csi(Csi::Cursor(Cursor::ActivePositionReport { .. })) => self.cursor_position,
+            Event::Csi(Csi::Keyboard(Keyboard::ReportFlags(_))) => self.kitty_keyboard_flags,
+            Event::ThemeChanged(_) => self.theme,
+            Event::Dcs(Dcs::TerminalVersion(_)) => self.terminal_version,
+            Event::Csi(Csi::Mode(Mode::ReportDecPrivateMode { mode, .. })) => self
+                .dec_modes
+                .iter()
+                .any(|asked| asked.code() == mode.code()),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for QueryBatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.cursor_position {
+            write!(f, "{}", Csi::Cursor(Cursor::RequestActivePositionReport))?;
+        }
+        if self.kitty_keyboard_flags {
+            write!(f, "{}", Csi::Keyboard(Keyboard::QueryFlags))?;
+        }
+        if self.theme {
+            write!(f, "{}", Csi::Mode(Mode::QueryTheme))?;
+        }
+        if self.terminal_version {
+            write!(f, "{}", Csi::Device(Device::RequestTerminalNameAndVersion))?;
+        }
+        for &mode in &self.dec_modes {
+            write!(f, "{}", Csi::Mode(Mode::QueryDecPrivateMode(mode)))?;
+        }
+        write!(f, "{}", Csi::Device(Device::RequestPrimaryDeviceAttributes))
+    }
+}
+
+/// The replies to a [`QueryBatch`].
+///
+/// A reply is missing if the terminal didn't answer the query or it wasn't asked.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct QueryResults {
+    /// The cursor position.
+    pub cursor_position: Option<CursorPosition>,
+    /// The kitty keyboard protocol flags.
+    pub kitty_keyboard_flags: Option<KittyKeyboardFlags>,
+    /// The dark or light theme.
+    pub theme: Option<ThemeMode>,
+    /// The terminal's name and version.
+    pub terminal_version: Option<TerminalVersion>,
+    /// The reported DEC private mode settings, in the order they arrived.
+    pub dec_modes: Vec<(DecPrivateMode, DecModeSetting)>,
+    /// Whether the terminal answered the primary device attributes query.
+    ///
+    /// Every terminal answers it, so `false` means the terminal didn't answer before the timeout.
+    pub device_attributes: bool,
+}
+
+impl QueryResults {
+    /// Returns the reported setting of `mode`.
+    ///
+    /// Modes are compared by their code, so a mode which Termina reports as
+    /// [`DecPrivateMode::Unspecified`] is also found through [`DecPrivateMode::Code`].
+    pub fn dec_mode(&self, mode: DecPrivateMode) -> Option<DecModeSetting> {
+        self.dec_modes
+            .iter()
+            .find(|(reported, _)| reported.code() == mode.code())
+            .map(|&(_, setting)| setting)
+    }
+
+    /// Stores `event` if it replies to one of the queries, returning `false` otherwise.
+    pub fn record(&mut self, event: Event) -> bool {
+        match event {
+            Event::Csi(Csi::Device(Device::DeviceAttributes(_))) => self.device_attributes = true,
+            Event::Csi(Csi::Cursor(Cursor::ActivePositionReport { line, col })) => {
+                self.cursor_position = Some(CursorPosition { line, col });
+            }
+            Event::Csi(Csi::Keyboard(Keyboard::ReportFlags(flags))) => {
+                self.kitty_keyboard_flags = Some(flags);
+            }
+            Event::ThemeChanged(mode) => self.theme = Some(mode),
+            Event::Dcs(Dcs::TerminalVersion(version)) => self.terminal_version = Some(version),
+            Event::Csi(Csi::Mode(Mode::ReportDecPrivateMode { mode, setting })) => {
+                match self
+                    .dec_modes
+                    .iter_mut()
+                    .find(|(reported, _)| reported.code() == mode.code())
+                {
+                    Some((_, reported)) => *reported = setting,
+                    None => self.dec_modes.push((mode, setting)),
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use super::*;
+    use crate::{escape::csi::DecPrivateModeCode, terminal::test_terminal};
+
+    #[test]
+    fn out_of_order_replies() {
+        let mut terminal = test_terminal();
+        let sync = DecPrivateMode::Code(DecPrivateModeCode::SynchronizedOutput);
+        let paste = DecPrivateMode::Code(DecPrivateModeCode::BracketedPaste);
+        let batch = QueryBatch::new()
+            .cursor_position()
+            .kitty_keyboard_flags()
+            .theme()
+            .terminal_version()
+            .dec_mode(sync)
+            .dec_mode(paste);
+
+        // The mode report arrives after the device attributes and a key press is interleaved.
+        terminal
+            .push_input(
+                b"\x1b[?2026;2$ya\x1b[3;7R\x1bP>|kitty(0.31.0)\x1b\\\x1b[?62;22c\x1b[?2004;1$y",
+            )
+            .unwrap();
+        let results = batch.run(&mut terminal, Duration::from_secs(1)).unwrap();
+        assert_eq!(
+            terminal.take_output(),
+            b"\x1b[6n\x1b[?u\x1b[?996n\x1b[>q\x1b[?2026$p\x1b[?2004$p\x1b[c"
+        );
+        assert_eq!(
+            results.cursor_position,
+            Some(CursorPosition {
+                line: OneBased::new(3).unwrap(),
+                col: OneBased::new(7).unwrap(),
+            })
+        );
+        assert_eq!(results.kitty_keyboard_flags, None);
+        assert_eq!(results.theme, None);
+        assert_eq!(
+            results.terminal_version,
+            Some(TerminalVersion::parse("kitty(0.31.0)"))
+        );
+        assert_eq!(results.dec_mode(sync), Some(DecModeSetting::Reset));
+        assert_eq!(results.dec_mode(paste), Some(DecModeSetting::Set));
+        assert!(results.device_attributes);
+        // The key press is left for the application.
+        assert!(matches!(terminal.read(|_| true).unwrap(), Event::Key(_)));
+    }
+}
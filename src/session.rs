@@ -0,0 +1,474 @@
+//! A full-screen application session which sets up the terminal and restores it afterwards.
+//!
+//! Most TUIs enter raw mode, switch to the alternate screen and enable a few input modes, and must
+//! undo all of it in the reverse order when they exit, including when they panic. A [`Session`]
+//! does both: [`SessionBuilder::build`] applies the chosen setup and installs a panic hook with
+//! [`Terminal::set_panic_hook`], and [`Session::end`] or dropping the session tears it down.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::io::{self, Write as _};
+//!
+//! use termina::{
+//!     escape::csi::KittyKeyboardFlags, session::MouseMode, PlatformTerminal, Session, Terminal,
+//! };
+//!
+//! fn main() -> io::Result<()> {
+//!     let mut session = Session::builder()
+//!         .raw()
+//!         .alternate_screen()
+//!         .bracketed_paste()
+//!         .mouse(MouseMode::Any)
+//!         .kitty_keyboard(KittyKeyboardFlags::DISAMBIGUATE_ESCAPE_CODES)
+//!         .build(PlatformTerminal::new()?)?;
+//!
+//!     write!(session, "Hello from the alternate screen")?;
+//!     session.flush()?;
+//!     session.read(|_| true)?;
+//!
+//!     session.end()?;
+//!     Ok(())
+//! }
+//! ```
+//!
+//! # Implementation Notes
+//!
+//! Teardown runs in the reverse order of setup: keyboard flags are popped and input modes disabled
+//! while still on the alternate screen, then the main screen is restored and finally the terminal
+//! returns to cooked mode. This is the order [crossterm's docs] and [ratatui's docs] recommend when
+//! doing the same steps by hand.
+//!
+//! [crossterm's docs]: https://docs.rs/crossterm/latest/crossterm/terminal/index.html
+//! [ratatui's docs]: https://docs.rs/ratatui/latest/ratatui/terminal/index.html
+
+use std::{
+    io::{self, Write as _},
+    ops::{Deref, DerefMut},
+};
+
+use crate::{
+    escape::csi::{Csi, DecPrivateMode, DecPrivateModeCode, Keyboard, KittyKeyboardFlags, Mode},
+    ReleasedTerminal, Terminal,
+};
+
+/// Which mouse events the terminal reports, see [`SessionBuilder::mouse`].
+///
+/// Every mode uses the SGR encoding ([`DecPrivateModeCode::SGRMouse`]), which Termina's parser
+/// expects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseMode {
+    /// Report button presses and releases ([`DecPrivateModeCode::MouseTracking`]).
+    Click,
+    /// Also report motion while a button is held ([`DecPrivateModeCode::ButtonEventMouse`]).
+    Drag,
+    /// Also report motion without a button held ([`DecPrivateModeCode::AnyEventMouse`]).
+    Any,
+}
+
+impl MouseMode {
+    fn codes(self) -> &'static [DecPrivateModeCode] {
+        match self {
+            Self::Click => &[DecPrivateModeCode::MouseTracking],
+            Self::Drag => &[
+                DecPrivateModeCode::MouseTracking,
+                DecPrivateModeCode::ButtonEventMouse,
+            ],
+            Self::Any => &[
+                DecPrivateModeCode::MouseTracking,
+                DecPrivateModeCode::ButtonEventMouse,
+                DecPrivateModeCode::AnyEventMouse,
+            ],
+        }
+    }
+}
+
+/// Configures a [`Session`], created with [`Session::builder`].
+///
+/// Nothing is enabled by default.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SessionBuilder {
+    raw: bool,
+    alternate_screen: bool,
+    hide_cursor: bool,
+    bracketed_paste: bool,
+    focus_events: bool,
+    mouse: Option<MouseMode>,
+    kitty_keyboard: Option<KittyKeyboardFlags>,
+    no_panic_hook: bool,
+}
+
+impl SessionBuilder {
+    /// Enters raw mode with [`Terminal::enter_raw_mode`].
+    pub fn raw(mut self) -> Self {
+        self.raw = true;
+        self
+    }
+
+    /// Switches to the alternate screen with
+    /// [`DecPrivateModeCode::ClearAndEnableAlternateScreen`].
+    pub fn alternate_screen(mut self) -> Self {
+        self.alternate_screen = true;
+        self
+    }
+
+    /// Hides the cursor ([`DecPrivateModeCode::ShowCursor`]).
+    pub fn hide_cursor(mut self) -> Self {
+        self.hide_cursor = true;
+        self
+    }
+
+    /// Enables [`DecPrivateModeCode::BracketedPaste`], so pastes arrive as
+    /// [`Event::Paste`](crate::Event::Paste).
+    pub fn bracketed_paste(mut self) -> Self {
+        self.bracketed_paste = true;
+        self
+    }
+
+    /// Enables [`DecPrivateModeCode::FocusTracking`], so focus changes arrive as
+    /// [`Event::FocusIn`](crate::Event::FocusIn) and [`Event::FocusOut`](crate::Event::FocusOut).
+    pub fn focus_events(mut self) -> Self {
+        self.focus_events = true;
+        self
+    }
+
+    /// Enables mouse reporting.
+    pub fn mouse(mut self, mode: MouseMode) -> Self {
+        self.mouse = Some(mode);
+        self
+    }
+
+    /// Pushes `flags` onto the terminal's [Kitty keyboard protocol] stack with
+    /// [`Keyboard::PushFlags`].
+    ///
+    /// [Kitty keyboard protocol]: https://sw.kovidgoyal.net/kitty/keyboard-protocol/
+    pub fn kitty_keyboard(mut self, flags: KittyKeyboardFlags) -> Self {
+        self.kitty_keyboard = Some(flags);
+        self
+    }
+
+    /// Sets whether a panic hook restores the terminal, which is the default.
+    ///
+    /// The hook is installed with [`Terminal::set_panic_hook`] and writes the teardown sequences
+    /// before the terminal's own cleanup. Disable it when the application installs a hook which
+    /// restores the terminal itself.
+    pub fn panic_hook(mut self, enabled: bool) -> Self {
+        self.no_panic_hook = !enabled;
+        self
+    }
+
+    /// Applies the setup to `terminal` and flushes it.
+    ///
+    /// If a step fails, the steps which already ran are undone before the error is returned.
+    pub fn build<T: Terminal>(self, mut terminal: T) -> io::Result<Session<T>> {
+        if !self.no_panic_hook {
+            let teardown = self.teardown_sequences();
+            terminal.set_panic_hook(move |handle| {
+                let _ = handle.write_all(teardown.as_bytes());
+                let _ = handle.flush();
+            });
+        }
+
+        let mut session = Session {
+            terminal: Some(terminal),
+            builder: self,
+            ended: false,
+        };
+        match session.setup() {
+            Ok(()) => Ok(session),
+            Err(err) => {
+                session.ended = true;
+                Err(err)
+            }
+        }
+    }
+
+    fn setup_sequences(&self) -> String {
+        let mut out = String::new();
+        if self.alternate_screen {
+            set(&mut out, DecPrivateModeCode::ClearAndEnableAlternateScreen);
+        }
+        if self.hide_cursor {
+            reset(&mut out, DecPrivateModeCode::ShowCursor);
+        }
+        if self.bracketed_paste {
+            set(&mut out, DecPrivateModeCode::BracketedPaste);
+        }
+        if self.focus_events {
+            set(&mut out, DecPrivateModeCode::FocusTracking);
+        }
+        if let Some(mouse) = self.mouse {
+            for &code in mouse.codes() {
+                set(&mut out, code);
+            }
+            set(&mut out, DecPrivateModeCode::SGRMouse);
+        }
+        if let Some(flags) = self.kitty_keyboard {
+            out += &Csi::Keyboard(Keyboard::PushFlags(flags)).to_string();
+        }
+        out
+    }
+
+    fn teardown_sequences(&self) -> String {
+        let mut out = String::new();
+        if self.kitty_keyboard.is_some() {
+            out += &Csi::Keyboard(Keyboard::PopFlags(1)).to_string();
+        }
+        if let Some(mouse) = self.mouse {
+            reset(&mut out, DecPrivateModeCode::SGRMouse);
+            for &code in mouse.codes().iter().rev() {
+                reset(&mut out, code);
+            }
+        }
+        if self.focus_events {
+            reset(&mut out, DecPrivateModeCode::FocusTracking);
+        }
+        if self.bracketed_paste {
+            reset(&mut out, DecPrivateModeCode::BracketedPaste);
+        }
+        if self.hide_cursor {
+            set(&mut out, DecPrivateModeCode::ShowCursor);
+        }
+        if self.alternate_screen {
+            reset(&mut out, DecPrivateModeCode::ClearAndEnableAlternateScreen);
+        }
+        out
+    }
+}
+
+fn set(out: &mut String, code: DecPrivateModeCode) {
+    *out += &Csi::Mode(Mode::SetDecPrivateMode(DecPrivateMode::Code(code))).to_string();
+}
+
+fn reset(out: &mut String, code: DecPrivateModeCode) {
+    *out += &Csi::Mode(Mode::ResetDecPrivateMode(DecPrivateMode::Code(code))).to_string();
+}
+
+/// A terminal set up for a full-screen application, created with [`SessionBuilder::build`].
+///
+/// The session dereferences to the terminal, so output and input go through it directly. Ending
+/// or dropping the session restores the terminal. When the session is dropped while panicking the
+/// restoration is left to the panic hook, which already ran.
+///
+/// Prefer [`Self::end`] over dropping the session when errors should be reported. Errors from the
+/// drop-time teardown are ignored.
+#[derive(Debug)]
+pub struct Session<T: Terminal> {
+    /// Always `Some` until the session is ended.
+    terminal: Option<T>,
+    builder: SessionBuilder,
+    ended: bool,
+}
+
+// NOTE: the builder is only attached to one terminal type so that `Session::builder()` doesn't need
+// a type annotation. `SessionBuilder::build` accepts any terminal.
+impl Session<crate::PlatformTerminal> {
+    /// Returns a builder for a session. Nothing is enabled by default.
+    pub fn builder() -> SessionBuilder {
+        SessionBuilder::default()
+    }
+
+    /// Stops the process like Ctrl+Z in a shell, restoring the terminal while it is stopped.
+    ///
+    /// This tears the session down, stops the process with `PlatformTerminal::suspend` and sets the
+    /// session up again once the shell continues it. Entering the alternate screen again clears it,
+    /// so redraw afterwards. This method is only available on Unix.
+    ///
+    /// ```no_run
+    /// use std::io;
+    ///
+    /// use termina::{
+    ///     event::{KeyCode, Modifiers},
+    ///     Event, PlatformTerminal, Session, Terminal,
+    /// };
+    ///
+    /// fn main() -> io::Result<()> {
+    ///     let mut session = Session::builder()
+    ///         .raw()
+    ///         .alternate_screen()
+    ///         .build(PlatformTerminal::new()?)?;
+    ///     loop {
+    ///         match session.read(|_| true)? {
+    ///             Event::Key(key)
+    ///                 if key.code == KeyCode::Char('z') && key.modifiers == Modifiers::CONTROL =>
+    ///             {
+    ///                 session.suspend()?;
+    ///                 // Redraw here.
+    ///             }
+    ///             Event::Key(key) if key.code == KeyCode::Char('q') => break,
+    ///             _ => (),
+    ///         }
+    ///     }
+    ///     session.end()?;
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(unix)]
+    pub fn suspend(&mut self) -> io::Result<()> {
+        let teardown = self.builder.teardown_sequences();
+        let setup = self.builder.setup_sequences();
+        let terminal = self.terminal.as_mut().unwrap();
+        terminal.write_all(teardown.as_bytes())?;
+        terminal.suspend()?;
+        terminal.write_all(setup.as_bytes())?;
+        terminal.flush()
+    }
+}
+
+impl<T: Terminal> Session<T> {
+    fn setup(&mut self) -> io::Result<()> {
+        let terminal = self.terminal.as_mut().unwrap();
+        if self.builder.raw {
+            terminal.enter_raw_mode()?;
+        }
+        let setup = self.builder.setup_sequences();
+        trace!(sequences = %setup.escape_debug(), "writing session setup");
+        let written = terminal
+            .write_all(setup.as_bytes())
+            .and_then(|()| terminal.flush());
+        if written.is_err() {
+            let _ = self.teardown();
+        }
+        written
+    }
+
+    /// Hands the terminal over to a child process until [`ReleasedTerminal::reacquire`] is called.
+    ///
+    /// Like [`Terminal::release`], but the session is also torn down while the terminal is
+    /// released, leaving the alternate screen and disabling the input modes, and set up again
+    /// when it is reacquired. Entering the alternate screen again clears it, so redraw afterwards.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::{io, process::Command};
+    ///
+    /// use termina::{PlatformTerminal, Session};
+    ///
+    /// fn main() -> io::Result<()> {
+    ///     let mut session = Session::builder()
+    ///         .raw()
+    ///         .alternate_screen()
+    ///         .build(PlatformTerminal::new()?)?;
+    ///     // ...
+    ///     let released = session.release()?;
+    ///     Command::new("sh").arg("-c").arg("ls; read line").status()?;
+    ///     released.reacquire()?;
+    ///     // Redraw here.
+    ///     session.end()?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn release(&mut self) -> io::Result<ReleasedTerminal<'_, T>> {
+        let teardown = self.builder.teardown_sequences();
+        let setup = self.builder.setup_sequences();
+        let raw = self.builder.raw;
+        ReleasedTerminal::new(self.terminal.as_mut().unwrap(), &teardown, setup, raw)
+    }
+
+    /// Restores the terminal and returns it.
+    ///
+    /// Every teardown step runs even if an earlier one fails, and the first error is returned.
+    pub fn end(mut self) -> io::Result<T> {
+        let result = self.teardown();
+        self.ended = true;
+        result.map(|()| self.terminal.take().unwrap())
+    }
+
+    fn teardown(&mut self) -> io::Result<()> {
+        let terminal = self.terminal.as_mut().unwrap();
+        let teardown = self.builder.teardown_sequences();
+        trace!(sequences = %teardown.escape_debug(), "writing session teardown");
+        let written = terminal
+            .write_all(teardown.as_bytes())
+            .and_then(|()| terminal.flush());
+        let cooked = if self.builder.raw {
+            terminal.enter_cooked_mode()
+        } else {
+            Ok(())
+        };
+        written.and(cooked)
+    }
+}
+
+impl<T: Terminal> Deref for Session<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.terminal.as_ref().unwrap()
+    }
+}
+
+impl<T: Terminal> DerefMut for Session<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.terminal.as_mut().unwrap()
+    }
+}
+
+impl<T: Terminal> Drop for Session<T> {
+    fn drop(&mut self) {
+        if !self.ended && (self.builder.no_panic_hook || !std::thread::panicking()) {
+            let _ = self.teardown();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn setup_and_teardown() {
+        use crate::terminal::test_terminal;
+
+        let session = Session::builder()
+            .raw()
+            .alternate_screen()
+            .bracketed_paste()
+            .mouse(MouseMode::Drag)
+            .kitty_keyboard(KittyKeyboardFlags::DISAMBIGUATE_ESCAPE_CODES)
+            .build(test_terminal())
+            .unwrap();
+        assert!(session.is_raw_mode());
+        let mut terminal = session.end().unwrap();
+        assert!(!terminal.is_raw_mode());
+        assert_eq!(
+            String::from_utf8(terminal.take_output()).unwrap(),
+            concat!(
+                "\x1b[?1049h\x1b[?2004h\x1b[?1000h\x1b[?1002h\x1b[?1006h\x1b[>1u",
+                "\x1b[<1u\x1b[?1006l\x1b[?1002l\x1b[?1000l\x1b[?2004l\x1b[?1049l",
+            )
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn release() {
+        use std::time::Duration;
+
+        use crate::terminal::test_terminal;
+
+        let mut session = Session::builder()
+            .raw()
+            .alternate_screen()
+            .build(test_terminal())
+            .unwrap();
+        session.take_output();
+
+        // Input is left for the child while the terminal is released.
+        let reader = session.event_reader();
+        session.push_input(b"x").unwrap();
+        let released = session.release().unwrap();
+        assert!(!reader
+            .poll(Some(Duration::from_millis(50)), |_| true)
+            .unwrap());
+        released.reacquire().unwrap();
+        assert!(session.is_raw_mode());
+        assert_eq!(session.take_output(), b"\x1b[?1049l\x1b[?1049h");
+        assert!(reader.poll(Some(Duration::from_secs(1)), |_| true).unwrap());
+
+        drop(session.release().unwrap());
+        assert!(session.is_raw_mode());
+    }
+}
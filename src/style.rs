@@ -2,8 +2,10 @@
 //!
 //! Terminal styling is controlled by [`Sgr`] commands, the `CSI ... m` escape sequences that set
 //! foreground color, background color, intensity, underline, and related text attributes. This
-//! module provides those low-level SGR attribute types and a small [`StyleExt`] convenience trait
-//! for formatting styled text.
+//! module provides those low-level SGR attribute types, a small [`StyleExt`] convenience trait
+//! for formatting styled text, a [`Theme`] of semantic styles which degrade to the terminal's
+//! [`ColorLevel`], and the complete [`Attributes`] of a cell with the minimal SGR transition
+//! between two of them.
 //!
 //! # Examples
 //!
@@ -27,8 +29,12 @@
 use std::{
     borrow::Cow,
     fmt::{self, Display},
+    io::{self, IsTerminal as _},
     str::FromStr,
-    sync::atomic::{AtomicBool, Ordering},
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        OnceLock,
+    },
 };
 
 use crate::escape::{
@@ -36,6 +42,13 @@ use crate::escape::{
     csi::{Csi, Sgr},
 };
 
+mod attributes;
+mod names;
+mod theme;
+
+pub use attributes::{Attributes, AttributesDiff};
+pub use theme::{ColorLevel, Role, Style, Theme};
+
 /// Styling of a cell's underline according to the [Kitty underline extension].
 ///
 /// Single and double underlines are widely understood SGR attributes. Curly, dotted, dashed, and
@@ -52,6 +65,7 @@ use crate::escape::{
 ///
 /// [kitty underline extension]: https://sw.kovidgoyal.net/kitty/underlines/
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Underline {
     /// No underline
     #[default]
@@ -73,6 +87,52 @@ pub enum Underline {
     Dashed = 5,
 }
 
+impl Underline {
+    const NAMES: [(Self, &'static str); 6] = [
+        (Self::None, "none"),
+        (Self::Single, "single"),
+        (Self::Double, "double"),
+        (Self::Curly, "curly"),
+        (Self::Dotted, "dotted"),
+        (Self::Dashed, "dashed"),
+    ];
+}
+
+/// Formats the underline style as its lowercase name, for example `curly`.
+impl Display for Underline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(Self::NAMES[*self as usize].1)
+    }
+}
+
+/// Parses the underline style names written by [`Display`]: `none`, `single`, `double`, `curly`,
+/// `dotted` or `dashed`.
+///
+/// ```
+/// use termina::style::Underline;
+///
+/// assert_eq!("curly".parse(), Ok(Underline::Curly));
+/// assert_eq!(
+///     "wavy".parse::<Underline>().unwrap_err().to_string(),
+///     r#"invalid underline "wavy": expected none, single, double, curly, dotted or dashed"#,
+/// );
+/// ```
+impl FromStr for Underline {
+    type Err = ParseStyleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::NAMES
+            .iter()
+            .find(|(_, name)| name.eq_ignore_ascii_case(s))
+            .map(|(underline, _)| *underline)
+            .ok_or_else(|| ParseStyleError {
+                kind: "underline",
+                input: s.to_string(),
+                expected: "none, single, double, curly, dotted or dashed",
+            })
+    }
+}
+
 /// Cursor shape values for [DECSCUSR].
 ///
 /// DECSCUSR is the DEC-style cursor shape setting used by many modern terminals. The numeric
@@ -91,6 +151,7 @@ pub enum Underline {
 ///
 /// [DECSCUSR]: https://vt100.net/docs/vt510-rm/DECSCUSR.html
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CursorStyle {
     /// DECSCUSR value 0: use the terminal's configured cursor style.
     #[default]
@@ -135,12 +196,14 @@ impl Display for CursorStyle {
 ///
 /// [ANSI 8-bit color]: https://en.wikipedia.org/wiki/ANSI_escape_code#8-bit
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WebColor(pub u8);
 
 /// Red, green, and blue color with 8-bit channels.
 ///
 /// Use [`Self::new`] for byte channels, [`Self::new_f32`] for normalized floating-point channels,
-/// or [`str::parse`] for xterm-style color strings:
+/// [`Self::from_named`] for X11 color names, or [`str::parse`] for color strings in any of the
+/// forms xterm accepts:
 ///
 /// ```
 /// use termina::style::{ColorSpec, RgbColor};
@@ -148,14 +211,17 @@ pub struct WebColor(pub u8);
 /// let from_bytes = RgbColor::new(40, 80, 120);
 /// let from_floats = RgbColor::new_f32(0.5, 0.0, 1.0);
 /// let from_hex: RgbColor = "#285078".parse().unwrap();
+/// let from_name: RgbColor = "steel blue".parse().unwrap();
 ///
 /// assert_eq!(from_bytes, from_hex);
+/// assert_eq!(from_name, "rgbi:0.275/0.51/0.706".parse().unwrap());
 /// assert_eq!(from_floats, RgbColor::new(127, 0, 255));
 ///
 /// let color_spec: ColorSpec = from_bytes.into();
 /// assert!(matches!(color_spec, ColorSpec::TrueColor(_)));
 /// ```
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RgbColor {
     /// Red channel.
     pub red: u8,
@@ -183,6 +249,87 @@ impl RgbColor {
         Self { red, green, blue }
     }
 
+    /// Looks up an X11 color name such as `"rebeccapurple"` or `"Light Goldenrod"`.
+    ///
+    /// Names are those of X.Org's `rgb.txt`, which terminals accept wherever they take a color.
+    /// Case and spaces are ignored and "grey" may be spelled either way. Where X11 and CSS
+    /// disagree, the X11 color is used: `gray`, `green`, `maroon` and `purple` are the X11 colors,
+    /// and the CSS ones are named `webgray`, `webgreen`, `webmaroon` and `webpurple`.
+    ///
+    /// ```
+    /// use termina::style::RgbColor;
+    ///
+    /// assert_eq!(
+    ///     RgbColor::from_named("rebeccapurple"),
+    ///     Some(RgbColor::new(102, 51, 153))
+    /// );
+    /// assert_eq!(
+    ///     RgbColor::from_named("Dark Slate Grey"),
+    ///     Some(RgbColor::new(47, 79, 79))
+    /// );
+    /// assert_eq!(RgbColor::from_named("no such color"), None);
+    /// ```
+    pub fn from_named(name: &str) -> Option<Self> {
+        names::lookup(name)
+    }
+
+    /// Returns the closest color in the 256-color palette, for terminals without true color.
+    ///
+    /// Only the 6x6x6 color cube and the grayscale ramp (indices 16-255) are considered, since
+    /// terminals commonly remap the 16 ANSI colors. Colors are compared by an approximation of
+    /// perceived difference rather than raw RGB distance.
+    ///
+    /// ```
+    /// use termina::style::RgbColor;
+    ///
+    /// assert_eq!(RgbColor::new(255, 135, 0).to_palette_256(), 208);
+    /// assert_eq!(RgbColor::new(128, 128, 128).to_palette_256(), 244);
+    /// ```
+    pub fn to_palette_256(self) -> PaletteIndex {
+        theme::nearest_palette_index(self)
+    }
+
+    /// Returns the closest of the 16 ANSI colors, assuming xterm's default palette.
+    ///
+    /// ```
+    /// use termina::style::{AnsiColor, RgbColor};
+    ///
+    /// assert_eq!(RgbColor::new(255, 135, 0).to_ansi16(), AnsiColor::Yellow);
+    /// assert_eq!(RgbColor::new(100, 100, 110).to_ansi16(), AnsiColor::BrightBlack);
+    /// ```
+    pub fn to_ansi16(self) -> AnsiColor {
+        AnsiColor::from_index(theme::nearest_ansi_color(self))
+    }
+
+    /// Returns the relative luminance of the color, from `0.0` for black to `1.0` for white.
+    ///
+    /// This is the [WCAG relative luminance]: the channels are converted from sRGB to linear light
+    /// and weighted by how bright each appears to the eye.
+    ///
+    /// ```
+    /// use termina::style::RgbColor;
+    ///
+    /// assert_eq!(RgbColor::new(0, 0, 0).luminance(), 0.0);
+    /// assert_eq!(RgbColor::new(255, 255, 255).luminance(), 1.0);
+    /// // Green appears much brighter than blue.
+    /// assert!(RgbColor::new(0, 255, 0).luminance() > RgbColor::new(0, 0, 255).luminance());
+    /// ```
+    ///
+    /// [WCAG relative luminance]: https://www.w3.org/TR/WCAG21/#dfn-relative-luminance
+    pub fn luminance(&self) -> f32 {
+        fn linear(channel: u8) -> f32 {
+            let channel = channel as f32 / 255.;
+            if channel <= 0.04045 {
+                channel / 12.92
+            } else {
+                ((channel + 0.055) / 1.055).powf(2.4)
+            }
+        }
+        let luminance =
+            0.2126 * linear(self.red) + 0.7152 * linear(self.green) + 0.0722 * linear(self.blue);
+        luminance.min(1.)
+    }
+
     fn channel_from_hex(s: &str) -> Result<u8, InvalidFormatError> {
         if s.is_empty() || s.len() > 4 {
             return Err(InvalidFormatError);
@@ -203,6 +350,36 @@ impl RgbColor {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct InvalidFormatError;
 
+impl Display for InvalidFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid color format")
+    }
+}
+
+impl std::error::Error for InvalidFormatError {}
+
+/// Error returned when parsing a [`ColorSpec`] or [`Underline`] from a configuration string fails.
+///
+/// The message names the rejected input and the accepted forms.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseStyleError {
+    kind: &'static str,
+    input: String,
+    expected: &'static str,
+}
+
+impl Display for ParseStyleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid {} {:?}: expected {}",
+            self.kind, self.input, self.expected
+        )
+    }
+}
+
+impl std::error::Error for ParseStyleError {}
+
 impl FromStr for RgbColor {
     type Err = InvalidFormatError;
 
@@ -212,8 +389,23 @@ impl FromStr for RgbColor {
     // 1. rgb:<red>/<green>/<blue>
     //    <red>, <green>, <blue> := h | hh | hhh | hhhh
     //    h := single hexadecimal digits (case insignificant)
-    // 2. #RGB, #RRGGBB, #RRRGGGBBB, #RRRRGGGGBBBB
+    // 2. rgbi:<red>/<green>/<blue>
+    //    <red>, <green>, <blue> := floating point values between 0.0 and 1.0
+    // 3. #RGB, #RRGGBB, #RRRGGGBBB, #RRRRGGGGBBBB
+    // 4. X11 color names, see `RgbColor::from_named`.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(rgbi) = s.strip_prefix("rgbi:") {
+            let mut parts = rgbi.split('/').map(|part| match part.parse::<f32>() {
+                Ok(value) if (0.0..=1.0).contains(&value) => Ok((value * 255.).round() as u8),
+                _ => Err(InvalidFormatError),
+            });
+            let (Some(r), Some(g), Some(b), None) =
+                (parts.next(), parts.next(), parts.next(), parts.next())
+            else {
+                return Err(InvalidFormatError);
+            };
+            return Ok(Self::new(r?, g?, b?));
+        }
         if let Some(rgb) = s.strip_prefix("rgb:") {
             let mut parts = rgb.split('/').map(Self::channel_from_hex);
             let Some(r) = parts.next().transpose()? else {
@@ -255,7 +447,7 @@ impl FromStr for RgbColor {
             };
             Ok(Self::new(r, g, b))
         } else {
-            Err(InvalidFormatError)
+            Self::from_named(s).ok_or(InvalidFormatError)
         }
     }
 }
@@ -275,6 +467,7 @@ impl FromStr for RgbColor {
 /// assert_eq!(RgbColor::from(rgba), rgb);
 /// ```
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RgbaColor {
     /// Red channel.
     pub red: u8,
@@ -327,6 +520,7 @@ impl From<RgbColor> for RgbaColor {
 /// [ANSI color table]: https://en.wikipedia.org/wiki/ANSI_escape_code#Colors
 /// [Ratatui color docs]: https://docs.rs/ratatui/latest/ratatui/style/enum.Color.html
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AnsiColor {
     /// The standard black palette entry.
     Black = 0,
@@ -370,6 +564,51 @@ pub enum AnsiColor {
     BrightWhite,
 }
 
+impl AnsiColor {
+    /// Names used by [`ColorSpec`]'s `Display` and `FromStr` implementations, in palette order.
+    const NAMES: [&'static str; 16] = [
+        "black",
+        "red",
+        "green",
+        "yellow",
+        "blue",
+        "magenta",
+        "cyan",
+        "white",
+        "bright-black",
+        "bright-red",
+        "bright-green",
+        "bright-yellow",
+        "bright-blue",
+        "bright-magenta",
+        "bright-cyan",
+        "bright-white",
+    ];
+
+    /// Returns the color with the given palette index, which must be below 16.
+    fn from_index(index: PaletteIndex) -> Self {
+        const COLORS: [AnsiColor; 16] = [
+            AnsiColor::Black,
+            AnsiColor::Red,
+            AnsiColor::Green,
+            AnsiColor::Yellow,
+            AnsiColor::Blue,
+            AnsiColor::Magenta,
+            AnsiColor::Cyan,
+            AnsiColor::White,
+            AnsiColor::BrightBlack,
+            AnsiColor::BrightRed,
+            AnsiColor::BrightGreen,
+            AnsiColor::BrightYellow,
+            AnsiColor::BrightBlue,
+            AnsiColor::BrightMagenta,
+            AnsiColor::BrightCyan,
+            AnsiColor::BrightWhite,
+        ];
+        COLORS[index as usize]
+    }
+}
+
 /// Index into the terminal's 256-color palette.
 pub type PaletteIndex = u8;
 
@@ -391,6 +630,7 @@ pub type PaletteIndex = u8;
 /// assert_eq!(Csi::Sgr(Sgr::Foreground(blue)).to_string(), "\x1b[38;2;0;0;255m");
 /// ```
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ColorSpec {
     /// Reset the color back to the terminal default.
     ///
@@ -444,6 +684,115 @@ impl ColorSpec {
     pub const BRIGHT_CYAN: Self = Self::PaletteIndex(AnsiColor::BrightCyan as PaletteIndex);
     /// Bright white palette color.
     pub const BRIGHT_WHITE: Self = Self::PaletteIndex(AnsiColor::BrightWhite as PaletteIndex);
+
+    /// Converts the color into the closest color a terminal with the given [`ColorLevel`] can
+    /// display, or `None` when colors are disabled.
+    ///
+    /// Pair this with [`ColorLevel::from_env`] or capability detection to render the same style
+    /// data at whatever fidelity the terminal supports. [`Self::Reset`] is passed through unchanged
+    /// unless colors are disabled.
+    ///
+    /// ```
+    /// use termina::style::{ColorLevel, ColorSpec, RgbColor};
+    ///
+    /// let purple = ColorSpec::from(RgbColor::new(128, 0, 128));
+    /// assert_eq!(purple.degrade(ColorLevel::Ansi256), Some(ColorSpec::PaletteIndex(90)));
+    /// assert_eq!(purple.degrade(ColorLevel::Ansi16), Some(ColorSpec::MAGENTA));
+    /// ```
+    pub fn degrade(self, level: ColorLevel) -> Option<Self> {
+        level.degrade(self)
+    }
+}
+
+/// Formats the color in the form accepted by [`FromStr`]: `reset`, an ANSI color name such as
+/// `bright-blue`, a palette index from 16 to 255, or a hex color such as `#ff8800`. Colors which
+/// are not fully opaque add the alpha channel, as in `#ff880080`.
+impl Display for ColorSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Reset => f.write_str("reset"),
+            Self::PaletteIndex(index) if index < 16 => {
+                f.write_str(AnsiColor::NAMES[index as usize])
+            }
+            Self::PaletteIndex(index) => write!(f, "{index}"),
+            Self::TrueColor(RgbaColor {
+                red,
+                green,
+                blue,
+                alpha: 255,
+            }) => write!(f, "#{red:02x}{green:02x}{blue:02x}"),
+            Self::TrueColor(RgbaColor {
+                red,
+                green,
+                blue,
+                alpha,
+            }) => write!(f, "#{red:02x}{green:02x}{blue:02x}{alpha:02x}"),
+        }
+    }
+}
+
+/// Parses colors from configuration strings.
+///
+/// This accepts:
+///
+/// * `reset` or `default` for the terminal's default color.
+/// * The sixteen ANSI color names, such as `red` or `bright-blue`. `_` may be used in place of
+///   `-` and case is ignored.
+/// * A palette index from 0 to 255, such as `250`.
+/// * `#RRGGBBAA` for a color with an alpha channel.
+/// * Any form accepted by [`RgbColor`]'s `FromStr`, such as `#ff8800`, `rgb:ff/88/00` or an X11
+///   color name like `orange`. ANSI color names take precedence over X11 names.
+///
+/// ```
+/// use termina::style::{ColorSpec, RgbColor};
+///
+/// assert_eq!("bright-blue".parse(), Ok(ColorSpec::BRIGHT_BLUE));
+/// assert_eq!("250".parse(), Ok(ColorSpec::PaletteIndex(250)));
+/// assert_eq!("#ff8800".parse(), Ok(ColorSpec::from(RgbColor::new(255, 136, 0))));
+/// assert_eq!(ColorSpec::BRIGHT_BLUE.to_string(), "bright-blue");
+///
+/// let err = "blu".parse::<ColorSpec>().unwrap_err();
+/// assert_eq!(
+///     err.to_string(),
+///     r#"invalid color "blu": expected reset, a color name, a palette index from 0 to 255 or a hex color like #ff8800"#,
+/// );
+/// ```
+impl FromStr for ColorSpec {
+    type Err = ParseStyleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.to_ascii_lowercase().replace('_', "-");
+        if name == "reset" || name == "default" {
+            return Ok(Self::Reset);
+        }
+        if let Some(index) = AnsiColor::NAMES.iter().position(|&ansi| ansi == name) {
+            return Ok(Self::PaletteIndex(index as PaletteIndex));
+        }
+        if s.bytes().all(|b| b.is_ascii_digit()) {
+            if let Ok(index) = s.parse() {
+                return Ok(Self::PaletteIndex(index));
+            }
+        }
+        if let Some(hex) = s.strip_prefix('#').filter(|hex| hex.len() == 8) {
+            if let Ok(rgba) = u32::from_str_radix(hex, 16) {
+                let [red, green, blue, alpha] = rgba.to_be_bytes();
+                return Ok(Self::TrueColor(RgbaColor {
+                    red,
+                    green,
+                    blue,
+                    alpha,
+                }));
+            }
+        }
+        s.parse::<RgbColor>()
+            .map(Self::from)
+            .map_err(|_| ParseStyleError {
+                kind: "color",
+                input: s.to_string(),
+                expected: "reset, a color name, a palette index from 0 to 255 or a hex color like \
+                           #ff8800",
+            })
+    }
 }
 
 impl From<AnsiColor> for ColorSpec {
@@ -487,6 +836,7 @@ impl From<RgbaColor> for ColorSpec {
 /// assert_eq!("warn".bold().to_string(), "\x1b[0;1mwarn\x1b[m");
 /// ```
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Intensity {
     /// SGR 22: normal text intensity.
     #[default]
@@ -511,6 +861,7 @@ pub enum Intensity {
 /// assert_eq!(Csi::Sgr(Sgr::Blink(Blink::Slow)).to_string(), "\x1b[5m");
 /// ```
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Blink {
     /// SGR 25: disable blinking text.
     #[default]
@@ -535,6 +886,7 @@ pub enum Blink {
 /// assert_eq!(Csi::Sgr(Sgr::Font(Font::Alternate(1))).to_string(), "\x1b[11m");
 /// ```
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Font {
     /// SGR 10: use the default font.
     #[default]
@@ -560,6 +912,7 @@ pub enum Font {
 /// assert_eq!(superscript.to_string(), "\x1b[73m");
 /// ```
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum VerticalAlign {
     /// SGR 75: baseline text alignment.
     #[default]
@@ -594,44 +947,128 @@ pub struct Stylized<'a> {
     /// The text rendered between the opening SGR sequence and reset sequence.
     pub content: Cow<'a, str>,
     styles: Vec<Sgr>,
+    stream: Option<Stream>,
 }
 
+/// A standard output stream which [`Stylized`] text is written to.
+///
+/// See [`Stylized::for_stream`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Stream {
+    /// The process's standard output.
+    Stdout,
+    /// The process's standard error.
+    Stderr,
+}
+
+impl Stream {
+    /// Checks whether this stream is connected to a terminal rather than a file or pipe.
+    ///
+    /// The stream is checked once and the answer is cached, since [`Stylized`] text asks each time
+    /// it is displayed.
+    pub fn is_terminal(self) -> bool {
+        static STDOUT: OnceLock<bool> = OnceLock::new();
+        static STDERR: OnceLock<bool> = OnceLock::new();
+        match self {
+            Self::Stdout => *STDOUT.get_or_init(|| io::stdout().is_terminal()),
+            Self::Stderr => *STDERR.get_or_init(|| io::stderr().is_terminal()),
+        }
+    }
+}
+
+/// Detect colors from the environment and the stream.
+const COLOR_AUTO: u8 = 0;
+/// Always write escape sequences.
+const COLOR_ALWAYS: u8 = 1;
+/// Never write color escape sequences.
+const COLOR_NEVER: u8 = 2;
+
 static INITIALIZER: parking_lot::Once = parking_lot::Once::new();
-static NO_COLOR: AtomicBool = AtomicBool::new(false);
+static COLOR: AtomicU8 = AtomicU8::new(COLOR_AUTO);
+
+/// Reads the color conventions from the environment.
+///
+/// A `CLICOLOR_FORCE` or `FORCE_COLOR` other than `0` forces escape sequences, even when the
+/// output is not a terminal. Otherwise a non-empty `NO_COLOR` or a `CLICOLOR` of `0` disables
+/// colors.
+fn color_from_env() -> u8 {
+    let var = |name| std::env::var(name).ok().filter(|value| !value.is_empty());
+    let forced = |name| var(name).is_some_and(|value| value != "0" && value != "false");
+
+    if forced("CLICOLOR_FORCE") || forced("FORCE_COLOR") {
+        COLOR_ALWAYS
+    } else if var("NO_COLOR").is_some() || var("CLICOLOR").is_some_and(|value| value == "0") {
+        COLOR_NEVER
+    } else {
+        COLOR_AUTO
+    }
+}
 
 impl Stylized<'_> {
+    fn color() -> u8 {
+        INITIALIZER.call_once(|| COLOR.store(color_from_env(), Ordering::SeqCst));
+        COLOR.load(Ordering::SeqCst)
+    }
+
     /// Checks whether ANSI color sequences were turned off in the environment.
     ///
     /// This follows the guidance on [no-color.org][no-color]: if the `NO_COLOR` environment
     /// variable is present and non-empty, color escape sequences will be omitted when rendering
-    /// this struct. This behavior can be overridden with [Self::force_ansi_color].
+    /// this struct. A `CLICOLOR` of `0` also disables colors, following the [CLICOLOR]
+    /// conventions, unless `CLICOLOR_FORCE` or `FORCE_COLOR` is set to a value other than `0`.
+    /// This behavior can be overridden with [Self::force_ansi_color].
     ///
     /// [no-color]: https://no-color.org/
+    /// [CLICOLOR]: https://bixense.com/clicolors/
     pub fn is_ansi_color_disabled() -> bool {
-        // Guidance on disabling colors comes from the no-color.org recommendations.
-        INITIALIZER.call_once(|| {
-            NO_COLOR.store(
-                std::env::var("NO_COLOR").is_ok_and(|e| !e.is_empty()),
-                Ordering::SeqCst,
-            );
-        });
-        NO_COLOR.load(Ordering::SeqCst)
+        Self::color() == COLOR_NEVER
+    }
+
+    fn with(mut self, sgr: Sgr) -> Self {
+        self.styles.push(sgr);
+        self
+    }
+
+    /// Sets the stream this text is written to.
+    ///
+    /// When the stream is not a terminal, for example because it is piped to a file, the text is
+    /// displayed without any escape sequences. `CLICOLOR_FORCE`, `FORCE_COLOR` and
+    /// [Self::force_ansi_color] override this.
+    ///
+    /// ```
+    /// use termina::style::{Stream, StyleExt as _};
+    ///
+    /// eprintln!("{}: unknown flag", "error".red().bold().for_stream(Stream::Stderr));
+    /// ```
+    pub fn for_stream(mut self, stream: Stream) -> Self {
+        self.stream = Some(stream);
+        self
     }
 
-    /// Overrides detection of the `NO_COLOR` environment variable.
+    /// Overrides detection of colors from the environment and the stream.
     ///
     /// Pass `true` to ensure that ANSI color codes are always included when displaying this type
     /// or `false` to ensure ANSI color codes are never included.
     pub fn force_ansi_color(enable_color: bool) {
         // Run the `Once` first so this override is not later overwritten by the `Once` fn.
-        let _ = Self::is_ansi_color_disabled();
-        NO_COLOR.store(!enable_color, Ordering::SeqCst);
+        let _ = Self::color();
+        let color = if enable_color {
+            COLOR_ALWAYS
+        } else {
+            COLOR_NEVER
+        };
+        COLOR.store(color, Ordering::SeqCst);
     }
 }
 
 impl Display for Stylized<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let no_color = Self::is_ansi_color_disabled();
+        let color = Self::color();
+        if color != COLOR_ALWAYS && self.stream.is_some_and(|stream| !stream.is_terminal()) {
+            return write!(f, "{}", self.content);
+        }
+
+        let no_color = color == COLOR_NEVER;
         let mut styles = self
             .styles
             .iter()
@@ -674,39 +1111,225 @@ impl Display for Stylized<'_> {
 ///     "\x1b[0;38;2;0;0;255;1mblue\x1b[m",
 /// );
 /// ```
+///
+/// Backgrounds use the `on_` prefix and the text attributes chain in the same way:
+///
+/// ```
+/// use termina::style::{ColorSpec, StyleExt as _, Stylized};
+///
+/// Stylized::force_ansi_color(true);
+///
+/// assert_eq!(
+///     "typo".curly_underlined().underline_color(ColorSpec::RED).to_string(),
+///     "\x1b[0;4:3;58:5:1mtypo\x1b[m",
+/// );
+/// assert_eq!(
+///     "note".bright_white().on_blue().italic().to_string(),
+///     "\x1b[0;97;44;3mnote\x1b[m",
+/// );
+/// ```
+///
+/// When color is disabled (see [`Stylized::is_ansi_color_disabled`]) the foreground, background
+/// and underline colors are left out and only the text attributes are written.
 pub trait StyleExt<'a>: Sized {
     /// Wraps this value in [`Stylized`] without adding styles.
     fn stylized(self) -> Stylized<'a>;
 
     /// Adds a foreground color.
     fn foreground(self, color: impl Into<ColorSpec>) -> Stylized<'a> {
-        let mut this = self.stylized();
-        this.styles.push(Sgr::Foreground(color.into()));
-        this
+        self.stylized().with(Sgr::Foreground(color.into()))
+    }
+    /// Adds a background color.
+    fn background(self, color: impl Into<ColorSpec>) -> Stylized<'a> {
+        self.stylized().with(Sgr::Background(color.into()))
+    }
+    /// Adds an underline color. Terminals which don't support underline colors ignore it.
+    fn underline_color(self, color: impl Into<ColorSpec>) -> Stylized<'a> {
+        self.stylized().with(Sgr::UnderlineColor(color.into()))
+    }
+    /// Adds a true-color foreground color.
+    fn rgb(self, red: u8, green: u8, blue: u8) -> Stylized<'a> {
+        self.foreground(RgbColor::new(red, green, blue))
+    }
+    /// Adds a true-color background color.
+    fn on_rgb(self, red: u8, green: u8, blue: u8) -> Stylized<'a> {
+        self.background(RgbColor::new(red, green, blue))
+    }
+    /// Adds the standard black foreground color.
+    fn black(self) -> Stylized<'a> {
+        self.foreground(ColorSpec::BLACK)
     }
     /// Adds the standard red foreground color.
     fn red(self) -> Stylized<'a> {
         self.foreground(ColorSpec::RED)
     }
+    /// Adds the standard green foreground color.
+    fn green(self) -> Stylized<'a> {
+        self.foreground(ColorSpec::GREEN)
+    }
     /// Adds the standard yellow foreground color.
     fn yellow(self) -> Stylized<'a> {
         self.foreground(ColorSpec::YELLOW)
     }
-    /// Adds the standard green foreground color.
-    fn green(self) -> Stylized<'a> {
-        self.foreground(ColorSpec::GREEN)
+    /// Adds the standard blue foreground color.
+    fn blue(self) -> Stylized<'a> {
+        self.foreground(ColorSpec::BLUE)
     }
-    /// Adds a single underline.
-    fn underlined(self) -> Stylized<'a> {
-        let mut this = self.stylized();
-        this.styles.push(Sgr::Underline(Underline::Single));
-        this
+    /// Adds the standard magenta foreground color.
+    fn magenta(self) -> Stylized<'a> {
+        self.foreground(ColorSpec::MAGENTA)
+    }
+    /// Adds the standard cyan foreground color.
+    fn cyan(self) -> Stylized<'a> {
+        self.foreground(ColorSpec::CYAN)
+    }
+    /// Adds the standard white foreground color.
+    fn white(self) -> Stylized<'a> {
+        self.foreground(ColorSpec::WHITE)
+    }
+    /// Adds the bright black foreground color.
+    fn bright_black(self) -> Stylized<'a> {
+        self.foreground(ColorSpec::BRIGHT_BLACK)
+    }
+    /// Adds the bright red foreground color.
+    fn bright_red(self) -> Stylized<'a> {
+        self.foreground(ColorSpec::BRIGHT_RED)
+    }
+    /// Adds the bright green foreground color.
+    fn bright_green(self) -> Stylized<'a> {
+        self.foreground(ColorSpec::BRIGHT_GREEN)
+    }
+    /// Adds the bright yellow foreground color.
+    fn bright_yellow(self) -> Stylized<'a> {
+        self.foreground(ColorSpec::BRIGHT_YELLOW)
+    }
+    /// Adds the bright blue foreground color.
+    fn bright_blue(self) -> Stylized<'a> {
+        self.foreground(ColorSpec::BRIGHT_BLUE)
+    }
+    /// Adds the bright magenta foreground color.
+    fn bright_magenta(self) -> Stylized<'a> {
+        self.foreground(ColorSpec::BRIGHT_MAGENTA)
+    }
+    /// Adds the bright cyan foreground color.
+    fn bright_cyan(self) -> Stylized<'a> {
+        self.foreground(ColorSpec::BRIGHT_CYAN)
+    }
+    /// Adds the bright white foreground color.
+    fn bright_white(self) -> Stylized<'a> {
+        self.foreground(ColorSpec::BRIGHT_WHITE)
+    }
+    /// Adds the standard black background color.
+    fn on_black(self) -> Stylized<'a> {
+        self.background(ColorSpec::BLACK)
+    }
+    /// Adds the standard red background color.
+    fn on_red(self) -> Stylized<'a> {
+        self.background(ColorSpec::RED)
+    }
+    /// Adds the standard green background color.
+    fn on_green(self) -> Stylized<'a> {
+        self.background(ColorSpec::GREEN)
+    }
+    /// Adds the standard yellow background color.
+    fn on_yellow(self) -> Stylized<'a> {
+        self.background(ColorSpec::YELLOW)
+    }
+    /// Adds the standard blue background color.
+    fn on_blue(self) -> Stylized<'a> {
+        self.background(ColorSpec::BLUE)
+    }
+    /// Adds the standard magenta background color.
+    fn on_magenta(self) -> Stylized<'a> {
+        self.background(ColorSpec::MAGENTA)
+    }
+    /// Adds the standard cyan background color.
+    fn on_cyan(self) -> Stylized<'a> {
+        self.background(ColorSpec::CYAN)
+    }
+    /// Adds the standard white background color.
+    fn on_white(self) -> Stylized<'a> {
+        self.background(ColorSpec::WHITE)
+    }
+    /// Adds the bright black background color.
+    fn on_bright_black(self) -> Stylized<'a> {
+        self.background(ColorSpec::BRIGHT_BLACK)
+    }
+    /// Adds the bright red background color.
+    fn on_bright_red(self) -> Stylized<'a> {
+        self.background(ColorSpec::BRIGHT_RED)
+    }
+    /// Adds the bright green background color.
+    fn on_bright_green(self) -> Stylized<'a> {
+        self.background(ColorSpec::BRIGHT_GREEN)
+    }
+    /// Adds the bright yellow background color.
+    fn on_bright_yellow(self) -> Stylized<'a> {
+        self.background(ColorSpec::BRIGHT_YELLOW)
+    }
+    /// Adds the bright blue background color.
+    fn on_bright_blue(self) -> Stylized<'a> {
+        self.background(ColorSpec::BRIGHT_BLUE)
+    }
+    /// Adds the bright magenta background color.
+    fn on_bright_magenta(self) -> Stylized<'a> {
+        self.background(ColorSpec::BRIGHT_MAGENTA)
+    }
+    /// Adds the bright cyan background color.
+    fn on_bright_cyan(self) -> Stylized<'a> {
+        self.background(ColorSpec::BRIGHT_CYAN)
+    }
+    /// Adds the bright white background color.
+    fn on_bright_white(self) -> Stylized<'a> {
+        self.background(ColorSpec::BRIGHT_WHITE)
     }
     /// Adds bold intensity.
     fn bold(self) -> Stylized<'a> {
-        let mut this = self.stylized();
-        this.styles.push(Sgr::Intensity(Intensity::Bold));
-        this
+        self.stylized().with(Sgr::Intensity(Intensity::Bold))
+    }
+    /// Adds dim intensity.
+    fn dim(self) -> Stylized<'a> {
+        self.stylized().with(Sgr::Intensity(Intensity::Dim))
+    }
+    /// Adds italic text.
+    fn italic(self) -> Stylized<'a> {
+        self.stylized().with(Sgr::Italic(true))
+    }
+    /// Adds an underline with the given style.
+    fn underline(self, underline: Underline) -> Stylized<'a> {
+        self.stylized().with(Sgr::Underline(underline))
+    }
+    /// Adds a single underline.
+    fn underlined(self) -> Stylized<'a> {
+        self.underline(Underline::Single)
+    }
+    /// Adds a double underline.
+    fn double_underlined(self) -> Stylized<'a> {
+        self.underline(Underline::Double)
+    }
+    /// Adds a curly underline.
+    fn curly_underlined(self) -> Stylized<'a> {
+        self.underline(Underline::Curly)
+    }
+    /// Adds a dotted underline.
+    fn dotted_underlined(self) -> Stylized<'a> {
+        self.underline(Underline::Dotted)
+    }
+    /// Adds a dashed underline.
+    fn dashed_underlined(self) -> Stylized<'a> {
+        self.underline(Underline::Dashed)
+    }
+    /// Adds slow blinking.
+    fn blink(self) -> Stylized<'a> {
+        self.stylized().with(Sgr::Blink(Blink::Slow))
+    }
+    /// Adds strikethrough text.
+    fn strikethrough(self) -> Stylized<'a> {
+        self.stylized().with(Sgr::StrikeThrough(true))
+    }
+    /// Swaps the foreground and background colors.
+    fn reversed(self) -> Stylized<'a> {
+        self.stylized().with(Sgr::Reverse(true))
     }
 }
 
@@ -715,6 +1338,7 @@ impl<'a> StyleExt<'a> for Cow<'a, str> {
         Stylized {
             content: self,
             styles: Vec::with_capacity(2),
+            stream: None,
         }
     }
 }
@@ -747,6 +1371,45 @@ mod test {
         assert_eq!("#282828".parse(), Ok(RgbColor::new(40, 40, 40)));
         assert_eq!("rgb:28/28/28".parse(), Ok(RgbColor::new(40, 40, 40)));
         assert_eq!("rgb:2828/2828/2828".parse(), Ok(RgbColor::new(40, 40, 40)));
+        assert_eq!("rgbi:1/0.5/0".parse(), Ok(RgbColor::new(255, 128, 0)));
+        assert_eq!("rgbi:1/0/2".parse::<RgbColor>(), Err(InvalidFormatError));
+        assert_eq!("rgbi:1/0/0/0".parse::<RgbColor>(), Err(InvalidFormatError));
+        assert_eq!("RebeccaPurple".parse(), Ok(RgbColor::new(102, 51, 153)));
+        assert_eq!("grey50".parse(), Ok(RgbColor::new(127, 127, 127)));
+        assert_eq!("navy blue".parse(), Ok(RgbColor::new(0, 0, 128)));
+        assert_eq!("purple".parse(), Ok(RgbColor::new(160, 32, 240)));
+        assert_eq!("".parse::<RgbColor>(), Err(InvalidFormatError));
+    }
+
+    #[test]
+    fn color_spec_round_trip() {
+        let colors = [
+            ColorSpec::Reset,
+            ColorSpec::BLACK,
+            ColorSpec::BRIGHT_WHITE,
+            ColorSpec::PaletteIndex(16),
+            ColorSpec::PaletteIndex(255),
+            RgbColor::new(255, 136, 0).into(),
+            ColorSpec::TrueColor(RgbaColor {
+                red: 1,
+                green: 2,
+                blue: 3,
+                alpha: 4,
+            }),
+        ];
+        for color in colors {
+            assert_eq!(color.to_string().parse(), Ok(color));
+        }
+        assert_eq!("Bright_Red".parse(), Ok(ColorSpec::BRIGHT_RED));
+        assert_eq!("9".parse(), Ok(ColorSpec::BRIGHT_RED));
+        assert_eq!("default".parse(), Ok(ColorSpec::Reset));
+        assert!("256".parse::<ColorSpec>().is_err());
+        assert!("-1".parse::<ColorSpec>().is_err());
+        assert!("#ff88".parse::<ColorSpec>().is_err());
+
+        for underline in Underline::NAMES.map(|(underline, _)| underline) {
+            assert_eq!(underline.to_string().parse(), Ok(underline));
+        }
     }
 
     #[test]
@@ -754,4 +1417,71 @@ mod test {
         assert_eq!("#é2".parse::<RgbColor>(), Err(InvalidFormatError));
         assert_eq!("#ééé".parse::<RgbColor>(), Err(InvalidFormatError));
     }
+
+    #[test]
+    fn degrade_colors() {
+        let rgb = |r, g, b| ColorSpec::from(RgbColor::new(r, g, b));
+
+        // Grays prefer the grayscale ramp over the color cube.
+        assert_eq!(
+            ColorLevel::Ansi256.degrade(rgb(128, 128, 128)),
+            Some(ColorSpec::PaletteIndex(244))
+        );
+        assert_eq!(
+            ColorLevel::Ansi256.degrade(rgb(0, 0, 255)),
+            Some(ColorSpec::PaletteIndex(21))
+        );
+        assert_eq!(
+            ColorLevel::Ansi16.degrade(ColorSpec::PaletteIndex(196)),
+            Some(ColorSpec::BRIGHT_RED)
+        );
+        assert_eq!(
+            ColorLevel::Ansi16.degrade(ColorSpec::BLUE),
+            Some(ColorSpec::BLUE)
+        );
+        assert_eq!(
+            ColorLevel::Ansi16.degrade(ColorSpec::Reset),
+            Some(ColorSpec::Reset)
+        );
+        assert_eq!(ColorLevel::None.degrade(ColorSpec::RED), None);
+
+        let style = Style::new()
+            .foreground(RgbColor::new(255, 0, 0))
+            .background(WebColor(240))
+            .intensity(Intensity::Bold);
+        assert_eq!(
+            style.degrade(ColorLevel::Ansi16).sgr(),
+            vec![
+                Sgr::Foreground(ColorSpec::BRIGHT_RED),
+                Sgr::Background(ColorSpec::BRIGHT_BLACK),
+                Sgr::Intensity(Intensity::Bold),
+            ]
+        );
+        assert_eq!(
+            style.degrade(ColorLevel::None).sgr(),
+            vec![Sgr::Intensity(Intensity::Bold)]
+        );
+    }
+
+    #[test]
+    fn color_detection() {
+        let styled = "x"
+            .on_rgb(1, 2, 3)
+            .dotted_underlined()
+            .underline_color(ColorSpec::RED)
+            .dim();
+        Stylized::force_ansi_color(true);
+        assert_eq!(styled.to_string(), "\x1b[0;48;2;1;2;3;4:4;58:5:1;2mx\x1b[m");
+        Stylized::force_ansi_color(false);
+        assert_eq!(styled.to_string(), "\x1b[0;4:4;2mx\x1b[m");
+        assert_eq!("x".bright_cyan().on_black().to_string(), "x");
+
+        // Streams which aren't terminals get plain text unless colors are forced.
+        let piped = "x".bold().for_stream(Stream::Stdout);
+        if !Stream::Stdout.is_terminal() {
+            assert_eq!(piped.to_string(), "x");
+        }
+        Stylized::force_ansi_color(true);
+        assert_eq!(piped.to_string(), "\x1b[0;1mx\x1b[m");
+    }
 }
@@ -0,0 +1,260 @@
+//! A complete set of text attributes and the SGR sequences which move between them.
+//!
+//! [`Attributes`] holds everything SGR can set for a cell. Renderers track the attributes the
+//! terminal currently uses and call [`Attributes::diff`] to write only the SGR parameters which
+//! change, rather than resetting and setting every attribute for each run of text.
+//!
+//! # Examples
+//!
+//! ```
+//! use termina::style::{Attributes, ColorSpec, Intensity};
+//!
+//! let plain = Attributes::default();
+//! let error = Attributes {
+//!     foreground: ColorSpec::RED,
+//!     intensity: Intensity::Bold,
+//!     ..plain
+//! };
+//! let emphasis = Attributes {
+//!     intensity: Intensity::Normal,
+//!     italic: true,
+//!     ..error
+//! };
+//!
+//! assert_eq!(plain.diff(&error).to_string(), "\x1b[31;1m");
+//! assert_eq!(error.diff(&emphasis).to_string(), "\x1b[22;3m");
+//! // Going back to the defaults is shorter as a reset.
+//! assert_eq!(emphasis.diff(&plain).to_string(), "\x1b[m");
+//! ```
+
+use std::fmt::{self, Display, Write as _};
+
+use crate::escape::csi::{Csi, Sgr, SgrAttributes, SgrModifiers};
+
+use super::{Blink, ColorSpec, Intensity, Underline};
+
+/// Every text attribute SGR can set.
+///
+/// Colors are [`ColorSpec::Reset`] when the terminal's default color is used. The default value
+/// is the terminal's style after an SGR reset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Attributes {
+    /// The color used to paint text.
+    pub foreground: ColorSpec,
+    /// The color used to paint the cell.
+    pub background: ColorSpec,
+    /// The color of the underline.
+    pub underline_color: ColorSpec,
+    /// Bold, dim or normal text.
+    pub intensity: Intensity,
+    /// The underline style.
+    pub underline: Underline,
+    /// The blink mode.
+    pub blink: Blink,
+    /// Italic text.
+    pub italic: bool,
+    /// Reverse video.
+    pub reverse: bool,
+    /// Invisible text.
+    pub invisible: bool,
+    /// Strikethrough text.
+    pub strike_through: bool,
+    /// Overlined text.
+    pub overline: bool,
+}
+
+impl Default for Attributes {
+    fn default() -> Self {
+        Self {
+            foreground: ColorSpec::Reset,
+            background: ColorSpec::Reset,
+            underline_color: ColorSpec::Reset,
+            intensity: Intensity::Normal,
+            underline: Underline::None,
+            blink: Blink::None,
+            italic: false,
+            reverse: false,
+            invisible: false,
+            strike_through: false,
+            overline: false,
+        }
+    }
+}
+
+impl Attributes {
+    /// Returns the SGR sequences which change the terminal's style from `self` to `to`.
+    ///
+    /// The sequences are the shorter of setting only the attributes which differ and resetting
+    /// the style before setting `to`. Nothing is written when the attributes are equal.
+    pub fn diff(&self, to: &Self) -> AttributesDiff {
+        AttributesDiff {
+            from: Some(*self),
+            to: *to,
+        }
+    }
+
+    /// Returns the SGR sequences which reset the terminal's style and then set `self`.
+    ///
+    /// Use this when the terminal's current style is unknown, for example before the first frame
+    /// or after other output.
+    pub fn reset_to(&self) -> AttributesDiff {
+        AttributesDiff {
+            from: None,
+            to: *self,
+        }
+    }
+}
+
+/// The SGR sequences which change the terminal's style, created with [`Attributes::diff`] and
+/// [`Attributes::reset_to`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AttributesDiff {
+    from: Option<Attributes>,
+    to: Attributes,
+}
+
+impl Display for AttributesDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reset = sgr_changes(None, &self.to);
+        match self.from {
+            Some(from) if from == self.to => Ok(()),
+            Some(from) => {
+                let changes = sgr_changes(Some(&from), &self.to);
+                f.write_str(if changes.len() <= reset.len() {
+                    &changes
+                } else {
+                    &reset
+                })
+            }
+            None => f.write_str(&reset),
+        }
+    }
+}
+
+/// Returns the SGR sequences which change the style `from` to `to`, or which reset the style and
+/// then set `to` if `from` is `None`.
+fn sgr_changes(from: Option<&Attributes>, to: &Attributes) -> String {
+    let mut attributes = SgrAttributes::default();
+    let from = match from {
+        Some(from) => *from,
+        None => {
+            attributes.modifiers |= SgrModifiers::RESET;
+            Attributes::default()
+        }
+    };
+
+    if from.foreground != to.foreground {
+        attributes.foreground = Some(to.foreground);
+    }
+    if from.background != to.background {
+        attributes.background = Some(to.background);
+    }
+    if from.underline_color != to.underline_color {
+        attributes.underline_color = Some(to.underline_color);
+    }
+    if from.intensity != to.intensity {
+        // Bold and dim may be set together, so clear one before setting the other.
+        attributes.modifiers |= match (from.intensity, to.intensity) {
+            (_, Intensity::Normal) => SgrModifiers::INTENSITY_NORMAL,
+            (Intensity::Normal, Intensity::Bold) => SgrModifiers::INTENSITY_BOLD,
+            (Intensity::Normal, Intensity::Dim) => SgrModifiers::INTENSITY_DIM,
+            (_, Intensity::Bold) => SgrModifiers::INTENSITY_NORMAL | SgrModifiers::INTENSITY_BOLD,
+            (_, Intensity::Dim) => SgrModifiers::INTENSITY_NORMAL | SgrModifiers::INTENSITY_DIM,
+        };
+    }
+    if from.underline != to.underline {
+        attributes.modifiers |= match to.underline {
+            Underline::None => SgrModifiers::UNDERLINE_NONE,
+            Underline::Single => SgrModifiers::UNDERLINE_SINGLE,
+            Underline::Double => SgrModifiers::UNDERLINE_DOUBLE,
+            Underline::Curly => SgrModifiers::UNDERLINE_CURLY,
+            Underline::Dotted => SgrModifiers::UNDERLINE_DOTTED,
+            Underline::Dashed => SgrModifiers::UNDERLINE_DASHED,
+        };
+    }
+    if from.blink != to.blink {
+        attributes.modifiers |= match (from.blink, to.blink) {
+            (_, Blink::None) => SgrModifiers::BLINK_NONE,
+            (Blink::None, Blink::Slow) => SgrModifiers::BLINK_SLOW,
+            (Blink::None, Blink::Rapid) => SgrModifiers::BLINK_RAPID,
+            (_, Blink::Slow) => SgrModifiers::BLINK_NONE | SgrModifiers::BLINK_SLOW,
+            (_, Blink::Rapid) => SgrModifiers::BLINK_NONE | SgrModifiers::BLINK_RAPID,
+        };
+    }
+    let flags = [
+        (
+            from.italic,
+            to.italic,
+            SgrModifiers::ITALIC,
+            SgrModifiers::NO_ITALIC,
+        ),
+        (
+            from.reverse,
+            to.reverse,
+            SgrModifiers::REVERSE,
+            SgrModifiers::NO_REVERSE,
+        ),
+        (
+            from.invisible,
+            to.invisible,
+            SgrModifiers::INVISIBLE,
+            SgrModifiers::NO_INVISIBLE,
+        ),
+        (
+            from.strike_through,
+            to.strike_through,
+            SgrModifiers::STRIKE_THROUGH,
+            SgrModifiers::NO_STRIKE_THROUGH,
+        ),
+    ];
+    for (from, to, on, off) in flags {
+        if from != to {
+            attributes.modifiers |= if to { on } else { off };
+        }
+    }
+
+    let mut out = String::new();
+    if !attributes.is_empty() {
+        let _ = write!(out, "{}", Csi::Sgr(Sgr::Attributes(attributes)));
+    }
+    if from.overline != to.overline {
+        let _ = write!(out, "{}", Csi::Sgr(Sgr::Overline(to.overline)));
+    }
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn minimal_transitions() {
+        let plain = Attributes::default();
+        let bold = Attributes {
+            foreground: ColorSpec::PaletteIndex(208),
+            background: ColorSpec::BLUE,
+            intensity: Intensity::Bold,
+            ..plain
+        };
+        let dim = Attributes {
+            intensity: Intensity::Dim,
+            overline: true,
+            ..bold
+        };
+        assert_eq!(plain.diff(&plain).to_string(), "");
+        // Bold is cleared before dim is set.
+        assert_eq!(bold.diff(&dim).to_string(), "\x1b[22;2m\x1b[53m");
+        assert_eq!(dim.diff(&bold).to_string(), "\x1b[22;1m\x1b[55m");
+        // Resetting is shorter than turning off both attributes.
+        assert_eq!(
+            dim.diff(&Attributes {
+                foreground: ColorSpec::PaletteIndex(208),
+                ..plain
+            })
+            .to_string(),
+            "\x1b[;38;5;208m"
+        );
+        assert_eq!(plain.reset_to().to_string(), "\x1b[m");
+    }
+}
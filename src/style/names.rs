@@ -0,0 +1,596 @@
+//! X11 color names.
+//!
+//! Generated from X.Org's `rgb.txt` (xorgproto `rgb` 1.0.6, which adds the CSS names such as
+//! `rebeccapurple`). Names are lowercase without spaces, and "grey" is spelled "gray"; see
+//! [`normalize`].
+
+use super::RgbColor;
+
+/// Looks up an X11 color name. Case, spaces and the "grey" spelling are ignored.
+pub(super) fn lookup(name: &str) -> Option<RgbColor> {
+    let name = normalize(name);
+    NAMES
+        .binary_search_by(|(candidate, _)| (*candidate).cmp(name.as_str()))
+        .ok()
+        .map(|i| NAMES[i].1)
+}
+
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect::<String>()
+        .replace("grey", "gray")
+}
+
+/// Color names sorted for binary search.
+#[rustfmt::skip]
+const NAMES: &[(&str, RgbColor)] = &[
+    ("aliceblue", RgbColor::new(240, 248, 255)),
+    ("antiquewhite", RgbColor::new(250, 235, 215)),
+    ("antiquewhite1", RgbColor::new(255, 239, 219)),
+    ("antiquewhite2", RgbColor::new(238, 223, 204)),
+    ("antiquewhite3", RgbColor::new(205, 192, 176)),
+    ("antiquewhite4", RgbColor::new(139, 131, 120)),
+    ("aqua", RgbColor::new(0, 255, 255)),
+    ("aquamarine", RgbColor::new(127, 255, 212)),
+    ("aquamarine1", RgbColor::new(127, 255, 212)),
+    ("aquamarine2", RgbColor::new(118, 238, 198)),
+    ("aquamarine3", RgbColor::new(102, 205, 170)),
+    ("aquamarine4", RgbColor::new(69, 139, 116)),
+    ("azure", RgbColor::new(240, 255, 255)),
+    ("azure1", RgbColor::new(240, 255, 255)),
+    ("azure2", RgbColor::new(224, 238, 238)),
+    ("azure3", RgbColor::new(193, 205, 205)),
+    ("azure4", RgbColor::new(131, 139, 139)),
+    ("beige", RgbColor::new(245, 245, 220)),
+    ("bisque", RgbColor::new(255, 228, 196)),
+    ("bisque1", RgbColor::new(255, 228, 196)),
+    ("bisque2", RgbColor::new(238, 213, 183)),
+    ("bisque3", RgbColor::new(205, 183, 158)),
+    ("bisque4", RgbColor::new(139, 125, 107)),
+    ("black", RgbColor::new(0, 0, 0)),
+    ("blanchedalmond", RgbColor::new(255, 235, 205)),
+    ("blue", RgbColor::new(0, 0, 255)),
+    ("blue1", RgbColor::new(0, 0, 255)),
+    ("blue2", RgbColor::new(0, 0, 238)),
+    ("blue3", RgbColor::new(0, 0, 205)),
+    ("blue4", RgbColor::new(0, 0, 139)),
+    ("blueviolet", RgbColor::new(138, 43, 226)),
+    ("brown", RgbColor::new(165, 42, 42)),
+    ("brown1", RgbColor::new(255, 64, 64)),
+    ("brown2", RgbColor::new(238, 59, 59)),
+    ("brown3", RgbColor::new(205, 51, 51)),
+    ("brown4", RgbColor::new(139, 35, 35)),
+    ("burlywood", RgbColor::new(222, 184, 135)),
+    ("burlywood1", RgbColor::new(255, 211, 155)),
+    ("burlywood2", RgbColor::new(238, 197, 145)),
+    ("burlywood3", RgbColor::new(205, 170, 125)),
+    ("burlywood4", RgbColor::new(139, 115, 85)),
+    ("cadetblue", RgbColor::new(95, 158, 160)),
+    ("cadetblue1", RgbColor::new(152, 245, 255)),
+    ("cadetblue2", RgbColor::new(142, 229, 238)),
+    ("cadetblue3", RgbColor::new(122, 197, 205)),
+    ("cadetblue4", RgbColor::new(83, 134, 139)),
+    ("chartreuse", RgbColor::new(127, 255, 0)),
+    ("chartreuse1", RgbColor::new(127, 255, 0)),
+    ("chartreuse2", RgbColor::new(118, 238, 0)),
+    ("chartreuse3", RgbColor::new(102, 205, 0)),
+    ("chartreuse4", RgbColor::new(69, 139, 0)),
+    ("chocolate", RgbColor::new(210, 105, 30)),
+    ("chocolate1", RgbColor::new(255, 127, 36)),
+    ("chocolate2", RgbColor::new(238, 118, 33)),
+    ("chocolate3", RgbColor::new(205, 102, 29)),
+    ("chocolate4", RgbColor::new(139, 69, 19)),
+    ("coral", RgbColor::new(255, 127, 80)),
+    ("coral1", RgbColor::new(255, 114, 86)),
+    ("coral2", RgbColor::new(238, 106, 80)),
+    ("coral3", RgbColor::new(205, 91, 69)),
+    ("coral4", RgbColor::new(139, 62, 47)),
+    ("cornflowerblue", RgbColor::new(100, 149, 237)),
+    ("cornsilk", RgbColor::new(255, 248, 220)),
+    ("cornsilk1", RgbColor::new(255, 248, 220)),
+    ("cornsilk2", RgbColor::new(238, 232, 205)),
+    ("cornsilk3", RgbColor::new(205, 200, 177)),
+    ("cornsilk4", RgbColor::new(139, 136, 120)),
+    ("crimson", RgbColor::new(220, 20, 60)),
+    ("cyan", RgbColor::new(0, 255, 255)),
+    ("cyan1", RgbColor::new(0, 255, 255)),
+    ("cyan2", RgbColor::new(0, 238, 238)),
+    ("cyan3", RgbColor::new(0, 205, 205)),
+    ("cyan4", RgbColor::new(0, 139, 139)),
+    ("darkblue", RgbColor::new(0, 0, 139)),
+    ("darkcyan", RgbColor::new(0, 139, 139)),
+    ("darkgoldenrod", RgbColor::new(184, 134, 11)),
+    ("darkgoldenrod1", RgbColor::new(255, 185, 15)),
+    ("darkgoldenrod2", RgbColor::new(238, 173, 14)),
+    ("darkgoldenrod3", RgbColor::new(205, 149, 12)),
+    ("darkgoldenrod4", RgbColor::new(139, 101, 8)),
+    ("darkgray", RgbColor::new(169, 169, 169)),
+    ("darkgreen", RgbColor::new(0, 100, 0)),
+    ("darkkhaki", RgbColor::new(189, 183, 107)),
+    ("darkmagenta", RgbColor::new(139, 0, 139)),
+    ("darkolivegreen", RgbColor::new(85, 107, 47)),
+    ("darkolivegreen1", RgbColor::new(202, 255, 112)),
+    ("darkolivegreen2", RgbColor::new(188, 238, 104)),
+    ("darkolivegreen3", RgbColor::new(162, 205, 90)),
+    ("darkolivegreen4", RgbColor::new(110, 139, 61)),
+    ("darkorange", RgbColor::new(255, 140, 0)),
+    ("darkorange1", RgbColor::new(255, 127, 0)),
+    ("darkorange2", RgbColor::new(238, 118, 0)),
+    ("darkorange3", RgbColor::new(205, 102, 0)),
+    ("darkorange4", RgbColor::new(139, 69, 0)),
+    ("darkorchid", RgbColor::new(153, 50, 204)),
+    ("darkorchid1", RgbColor::new(191, 62, 255)),
+    ("darkorchid2", RgbColor::new(178, 58, 238)),
+    ("darkorchid3", RgbColor::new(154, 50, 205)),
+    ("darkorchid4", RgbColor::new(104, 34, 139)),
+    ("darkred", RgbColor::new(139, 0, 0)),
+    ("darksalmon", RgbColor::new(233, 150, 122)),
+    ("darkseagreen", RgbColor::new(143, 188, 143)),
+    ("darkseagreen1", RgbColor::new(193, 255, 193)),
+    ("darkseagreen2", RgbColor::new(180, 238, 180)),
+    ("darkseagreen3", RgbColor::new(155, 205, 155)),
+    ("darkseagreen4", RgbColor::new(105, 139, 105)),
+    ("darkslateblue", RgbColor::new(72, 61, 139)),
+    ("darkslategray", RgbColor::new(47, 79, 79)),
+    ("darkslategray1", RgbColor::new(151, 255, 255)),
+    ("darkslategray2", RgbColor::new(141, 238, 238)),
+    ("darkslategray3", RgbColor::new(121, 205, 205)),
+    ("darkslategray4", RgbColor::new(82, 139, 139)),
+    ("darkturquoise", RgbColor::new(0, 206, 209)),
+    ("darkviolet", RgbColor::new(148, 0, 211)),
+    ("debianred", RgbColor::new(215, 7, 81)),
+    ("deeppink", RgbColor::new(255, 20, 147)),
+    ("deeppink1", RgbColor::new(255, 20, 147)),
+    ("deeppink2", RgbColor::new(238, 18, 137)),
+    ("deeppink3", RgbColor::new(205, 16, 118)),
+    ("deeppink4", RgbColor::new(139, 10, 80)),
+    ("deepskyblue", RgbColor::new(0, 191, 255)),
+    ("deepskyblue1", RgbColor::new(0, 191, 255)),
+    ("deepskyblue2", RgbColor::new(0, 178, 238)),
+    ("deepskyblue3", RgbColor::new(0, 154, 205)),
+    ("deepskyblue4", RgbColor::new(0, 104, 139)),
+    ("dimgray", RgbColor::new(105, 105, 105)),
+    ("dodgerblue", RgbColor::new(30, 144, 255)),
+    ("dodgerblue1", RgbColor::new(30, 144, 255)),
+    ("dodgerblue2", RgbColor::new(28, 134, 238)),
+    ("dodgerblue3", RgbColor::new(24, 116, 205)),
+    ("dodgerblue4", RgbColor::new(16, 78, 139)),
+    ("firebrick", RgbColor::new(178, 34, 34)),
+    ("firebrick1", RgbColor::new(255, 48, 48)),
+    ("firebrick2", RgbColor::new(238, 44, 44)),
+    ("firebrick3", RgbColor::new(205, 38, 38)),
+    ("firebrick4", RgbColor::new(139, 26, 26)),
+    ("floralwhite", RgbColor::new(255, 250, 240)),
+    ("forestgreen", RgbColor::new(34, 139, 34)),
+    ("fuchsia", RgbColor::new(255, 0, 255)),
+    ("gainsboro", RgbColor::new(220, 220, 220)),
+    ("ghostwhite", RgbColor::new(248, 248, 255)),
+    ("gold", RgbColor::new(255, 215, 0)),
+    ("gold1", RgbColor::new(255, 215, 0)),
+    ("gold2", RgbColor::new(238, 201, 0)),
+    ("gold3", RgbColor::new(205, 173, 0)),
+    ("gold4", RgbColor::new(139, 117, 0)),
+    ("goldenrod", RgbColor::new(218, 165, 32)),
+    ("goldenrod1", RgbColor::new(255, 193, 37)),
+    ("goldenrod2", RgbColor::new(238, 180, 34)),
+    ("goldenrod3", RgbColor::new(205, 155, 29)),
+    ("goldenrod4", RgbColor::new(139, 105, 20)),
+    ("gray", RgbColor::new(190, 190, 190)),
+    ("gray0", RgbColor::new(0, 0, 0)),
+    ("gray1", RgbColor::new(3, 3, 3)),
+    ("gray10", RgbColor::new(26, 26, 26)),
+    ("gray100", RgbColor::new(255, 255, 255)),
+    ("gray11", RgbColor::new(28, 28, 28)),
+    ("gray12", RgbColor::new(31, 31, 31)),
+    ("gray13", RgbColor::new(33, 33, 33)),
+    ("gray14", RgbColor::new(36, 36, 36)),
+    ("gray15", RgbColor::new(38, 38, 38)),
+    ("gray16", RgbColor::new(41, 41, 41)),
+    ("gray17", RgbColor::new(43, 43, 43)),
+    ("gray18", RgbColor::new(46, 46, 46)),
+    ("gray19", RgbColor::new(48, 48, 48)),
+    ("gray2", RgbColor::new(5, 5, 5)),
+    ("gray20", RgbColor::new(51, 51, 51)),
+    ("gray21", RgbColor::new(54, 54, 54)),
+    ("gray22", RgbColor::new(56, 56, 56)),
+    ("gray23", RgbColor::new(59, 59, 59)),
+    ("gray24", RgbColor::new(61, 61, 61)),
+    ("gray25", RgbColor::new(64, 64, 64)),
+    ("gray26", RgbColor::new(66, 66, 66)),
+    ("gray27", RgbColor::new(69, 69, 69)),
+    ("gray28", RgbColor::new(71, 71, 71)),
+    ("gray29", RgbColor::new(74, 74, 74)),
+    ("gray3", RgbColor::new(8, 8, 8)),
+    ("gray30", RgbColor::new(77, 77, 77)),
+    ("gray31", RgbColor::new(79, 79, 79)),
+    ("gray32", RgbColor::new(82, 82, 82)),
+    ("gray33", RgbColor::new(84, 84, 84)),
+    ("gray34", RgbColor::new(87, 87, 87)),
+    ("gray35", RgbColor::new(89, 89, 89)),
+    ("gray36", RgbColor::new(92, 92, 92)),
+    ("gray37", RgbColor::new(94, 94, 94)),
+    ("gray38", RgbColor::new(97, 97, 97)),
+    ("gray39", RgbColor::new(99, 99, 99)),
+    ("gray4", RgbColor::new(10, 10, 10)),
+    ("gray40", RgbColor::new(102, 102, 102)),
+    ("gray41", RgbColor::new(105, 105, 105)),
+    ("gray42", RgbColor::new(107, 107, 107)),
+    ("gray43", RgbColor::new(110, 110, 110)),
+    ("gray44", RgbColor::new(112, 112, 112)),
+    ("gray45", RgbColor::new(115, 115, 115)),
+    ("gray46", RgbColor::new(117, 117, 117)),
+    ("gray47", RgbColor::new(120, 120, 120)),
+    ("gray48", RgbColor::new(122, 122, 122)),
+    ("gray49", RgbColor::new(125, 125, 125)),
+    ("gray5", RgbColor::new(13, 13, 13)),
+    ("gray50", RgbColor::new(127, 127, 127)),
+    ("gray51", RgbColor::new(130, 130, 130)),
+    ("gray52", RgbColor::new(133, 133, 133)),
+    ("gray53", RgbColor::new(135, 135, 135)),
+    ("gray54", RgbColor::new(138, 138, 138)),
+    ("gray55", RgbColor::new(140, 140, 140)),
+    ("gray56", RgbColor::new(143, 143, 143)),
+    ("gray57", RgbColor::new(145, 145, 145)),
+    ("gray58", RgbColor::new(148, 148, 148)),
+    ("gray59", RgbColor::new(150, 150, 150)),
+    ("gray6", RgbColor::new(15, 15, 15)),
+    ("gray60", RgbColor::new(153, 153, 153)),
+    ("gray61", RgbColor::new(156, 156, 156)),
+    ("gray62", RgbColor::new(158, 158, 158)),
+    ("gray63", RgbColor::new(161, 161, 161)),
+    ("gray64", RgbColor::new(163, 163, 163)),
+    ("gray65", RgbColor::new(166, 166, 166)),
+    ("gray66", RgbColor::new(168, 168, 168)),
+    ("gray67", RgbColor::new(171, 171, 171)),
+    ("gray68", RgbColor::new(173, 173, 173)),
+    ("gray69", RgbColor::new(176, 176, 176)),
+    ("gray7", RgbColor::new(18, 18, 18)),
+    ("gray70", RgbColor::new(179, 179, 179)),
+    ("gray71", RgbColor::new(181, 181, 181)),
+    ("gray72", RgbColor::new(184, 184, 184)),
+    ("gray73", RgbColor::new(186, 186, 186)),
+    ("gray74", RgbColor::new(189, 189, 189)),
+    ("gray75", RgbColor::new(191, 191, 191)),
+    ("gray76", RgbColor::new(194, 194, 194)),
+    ("gray77", RgbColor::new(196, 196, 196)),
+    ("gray78", RgbColor::new(199, 199, 199)),
+    ("gray79", RgbColor::new(201, 201, 201)),
+    ("gray8", RgbColor::new(20, 20, 20)),
+    ("gray80", RgbColor::new(204, 204, 204)),
+    ("gray81", RgbColor::new(207, 207, 207)),
+    ("gray82", RgbColor::new(209, 209, 209)),
+    ("gray83", RgbColor::new(212, 212, 212)),
+    ("gray84", RgbColor::new(214, 214, 214)),
+    ("gray85", RgbColor::new(217, 217, 217)),
+    ("gray86", RgbColor::new(219, 219, 219)),
+    ("gray87", RgbColor::new(222, 222, 222)),
+    ("gray88", RgbColor::new(224, 224, 224)),
+    ("gray89", RgbColor::new(227, 227, 227)),
+    ("gray9", RgbColor::new(23, 23, 23)),
+    ("gray90", RgbColor::new(229, 229, 229)),
+    ("gray91", RgbColor::new(232, 232, 232)),
+    ("gray92", RgbColor::new(235, 235, 235)),
+    ("gray93", RgbColor::new(237, 237, 237)),
+    ("gray94", RgbColor::new(240, 240, 240)),
+    ("gray95", RgbColor::new(242, 242, 242)),
+    ("gray96", RgbColor::new(245, 245, 245)),
+    ("gray97", RgbColor::new(247, 247, 247)),
+    ("gray98", RgbColor::new(250, 250, 250)),
+    ("gray99", RgbColor::new(252, 252, 252)),
+    ("green", RgbColor::new(0, 255, 0)),
+    ("green1", RgbColor::new(0, 255, 0)),
+    ("green2", RgbColor::new(0, 238, 0)),
+    ("green3", RgbColor::new(0, 205, 0)),
+    ("green4", RgbColor::new(0, 139, 0)),
+    ("greenyellow", RgbColor::new(173, 255, 47)),
+    ("honeydew", RgbColor::new(240, 255, 240)),
+    ("honeydew1", RgbColor::new(240, 255, 240)),
+    ("honeydew2", RgbColor::new(224, 238, 224)),
+    ("honeydew3", RgbColor::new(193, 205, 193)),
+    ("honeydew4", RgbColor::new(131, 139, 131)),
+    ("hotpink", RgbColor::new(255, 105, 180)),
+    ("hotpink1", RgbColor::new(255, 110, 180)),
+    ("hotpink2", RgbColor::new(238, 106, 167)),
+    ("hotpink3", RgbColor::new(205, 96, 144)),
+    ("hotpink4", RgbColor::new(139, 58, 98)),
+    ("indianred", RgbColor::new(205, 92, 92)),
+    ("indianred1", RgbColor::new(255, 106, 106)),
+    ("indianred2", RgbColor::new(238, 99, 99)),
+    ("indianred3", RgbColor::new(205, 85, 85)),
+    ("indianred4", RgbColor::new(139, 58, 58)),
+    ("indigo", RgbColor::new(75, 0, 130)),
+    ("ivory", RgbColor::new(255, 255, 240)),
+    ("ivory1", RgbColor::new(255, 255, 240)),
+    ("ivory2", RgbColor::new(238, 238, 224)),
+    ("ivory3", RgbColor::new(205, 205, 193)),
+    ("ivory4", RgbColor::new(139, 139, 131)),
+    ("khaki", RgbColor::new(240, 230, 140)),
+    ("khaki1", RgbColor::new(255, 246, 143)),
+    ("khaki2", RgbColor::new(238, 230, 133)),
+    ("khaki3", RgbColor::new(205, 198, 115)),
+    ("khaki4", RgbColor::new(139, 134, 78)),
+    ("lavender", RgbColor::new(230, 230, 250)),
+    ("lavenderblush", RgbColor::new(255, 240, 245)),
+    ("lavenderblush1", RgbColor::new(255, 240, 245)),
+    ("lavenderblush2", RgbColor::new(238, 224, 229)),
+    ("lavenderblush3", RgbColor::new(205, 193, 197)),
+    ("lavenderblush4", RgbColor::new(139, 131, 134)),
+    ("lawngreen", RgbColor::new(124, 252, 0)),
+    ("lemonchiffon", RgbColor::new(255, 250, 205)),
+    ("lemonchiffon1", RgbColor::new(255, 250, 205)),
+    ("lemonchiffon2", RgbColor::new(238, 233, 191)),
+    ("lemonchiffon3", RgbColor::new(205, 201, 165)),
+    ("lemonchiffon4", RgbColor::new(139, 137, 112)),
+    ("lightblue", RgbColor::new(173, 216, 230)),
+    ("lightblue1", RgbColor::new(191, 239, 255)),
+    ("lightblue2", RgbColor::new(178, 223, 238)),
+    ("lightblue3", RgbColor::new(154, 192, 205)),
+    ("lightblue4", RgbColor::new(104, 131, 139)),
+    ("lightcoral", RgbColor::new(240, 128, 128)),
+    ("lightcyan", RgbColor::new(224, 255, 255)),
+    ("lightcyan1", RgbColor::new(224, 255, 255)),
+    ("lightcyan2", RgbColor::new(209, 238, 238)),
+    ("lightcyan3", RgbColor::new(180, 205, 205)),
+    ("lightcyan4", RgbColor::new(122, 139, 139)),
+    ("lightgoldenrod", RgbColor::new(238, 221, 130)),
+    ("lightgoldenrod1", RgbColor::new(255, 236, 139)),
+    ("lightgoldenrod2", RgbColor::new(238, 220, 130)),
+    ("lightgoldenrod3", RgbColor::new(205, 190, 112)),
+    ("lightgoldenrod4", RgbColor::new(139, 129, 76)),
+    ("lightgoldenrodyellow", RgbColor::new(250, 250, 210)),
+    ("lightgray", RgbColor::new(211, 211, 211)),
+    ("lightgreen", RgbColor::new(144, 238, 144)),
+    ("lightpink", RgbColor::new(255, 182, 193)),
+    ("lightpink1", RgbColor::new(255, 174, 185)),
+    ("lightpink2", RgbColor::new(238, 162, 173)),
+    ("lightpink3", RgbColor::new(205, 140, 149)),
+    ("lightpink4", RgbColor::new(139, 95, 101)),
+    ("lightsalmon", RgbColor::new(255, 160, 122)),
+    ("lightsalmon1", RgbColor::new(255, 160, 122)),
+    ("lightsalmon2", RgbColor::new(238, 149, 114)),
+    ("lightsalmon3", RgbColor::new(205, 129, 98)),
+    ("lightsalmon4", RgbColor::new(139, 87, 66)),
+    ("lightseagreen", RgbColor::new(32, 178, 170)),
+    ("lightskyblue", RgbColor::new(135, 206, 250)),
+    ("lightskyblue1", RgbColor::new(176, 226, 255)),
+    ("lightskyblue2", RgbColor::new(164, 211, 238)),
+    ("lightskyblue3", RgbColor::new(141, 182, 205)),
+    ("lightskyblue4", RgbColor::new(96, 123, 139)),
+    ("lightslateblue", RgbColor::new(132, 112, 255)),
+    ("lightslategray", RgbColor::new(119, 136, 153)),
+    ("lightsteelblue", RgbColor::new(176, 196, 222)),
+    ("lightsteelblue1", RgbColor::new(202, 225, 255)),
+    ("lightsteelblue2", RgbColor::new(188, 210, 238)),
+    ("lightsteelblue3", RgbColor::new(162, 181, 205)),
+    ("lightsteelblue4", RgbColor::new(110, 123, 139)),
+    ("lightyellow", RgbColor::new(255, 255, 224)),
+    ("lightyellow1", RgbColor::new(255, 255, 224)),
+    ("lightyellow2", RgbColor::new(238, 238, 209)),
+    ("lightyellow3", RgbColor::new(205, 205, 180)),
+    ("lightyellow4", RgbColor::new(139, 139, 122)),
+    ("lime", RgbColor::new(0, 255, 0)),
+    ("limegreen", RgbColor::new(50, 205, 50)),
+    ("linen", RgbColor::new(250, 240, 230)),
+    ("magenta", RgbColor::new(255, 0, 255)),
+    ("magenta1", RgbColor::new(255, 0, 255)),
+    ("magenta2", RgbColor::new(238, 0, 238)),
+    ("magenta3", RgbColor::new(205, 0, 205)),
+    ("magenta4", RgbColor::new(139, 0, 139)),
+    ("maroon", RgbColor::new(176, 48, 96)),
+    ("maroon1", RgbColor::new(255, 52, 179)),
+    ("maroon2", RgbColor::new(238, 48, 167)),
+    ("maroon3", RgbColor::new(205, 41, 144)),
+    ("maroon4", RgbColor::new(139, 28, 98)),
+    ("mediumaquamarine", RgbColor::new(102, 205, 170)),
+    ("mediumblue", RgbColor::new(0, 0, 205)),
+    ("mediumorchid", RgbColor::new(186, 85, 211)),
+    ("mediumorchid1", RgbColor::new(224, 102, 255)),
+    ("mediumorchid2", RgbColor::new(209, 95, 238)),
+    ("mediumorchid3", RgbColor::new(180, 82, 205)),
+    ("mediumorchid4", RgbColor::new(122, 55, 139)),
+    ("mediumpurple", RgbColor::new(147, 112, 219)),
+    ("mediumpurple1", RgbColor::new(171, 130, 255)),
+    ("mediumpurple2", RgbColor::new(159, 121, 238)),
+    ("mediumpurple3", RgbColor::new(137, 104, 205)),
+    ("mediumpurple4", RgbColor::new(93, 71, 139)),
+    ("mediumseagreen", RgbColor::new(60, 179, 113)),
+    ("mediumslateblue", RgbColor::new(123, 104, 238)),
+    ("mediumspringgreen", RgbColor::new(0, 250, 154)),
+    ("mediumturquoise", RgbColor::new(72, 209, 204)),
+    ("mediumvioletred", RgbColor::new(199, 21, 133)),
+    ("midnightblue", RgbColor::new(25, 25, 112)),
+    ("mintcream", RgbColor::new(245, 255, 250)),
+    ("mistyrose", RgbColor::new(255, 228, 225)),
+    ("mistyrose1", RgbColor::new(255, 228, 225)),
+    ("mistyrose2", RgbColor::new(238, 213, 210)),
+    ("mistyrose3", RgbColor::new(205, 183, 181)),
+    ("mistyrose4", RgbColor::new(139, 125, 123)),
+    ("moccasin", RgbColor::new(255, 228, 181)),
+    ("navajowhite", RgbColor::new(255, 222, 173)),
+    ("navajowhite1", RgbColor::new(255, 222, 173)),
+    ("navajowhite2", RgbColor::new(238, 207, 161)),
+    ("navajowhite3", RgbColor::new(205, 179, 139)),
+    ("navajowhite4", RgbColor::new(139, 121, 94)),
+    ("navy", RgbColor::new(0, 0, 128)),
+    ("navyblue", RgbColor::new(0, 0, 128)),
+    ("oldlace", RgbColor::new(253, 245, 230)),
+    ("olive", RgbColor::new(128, 128, 0)),
+    ("olivedrab", RgbColor::new(107, 142, 35)),
+    ("olivedrab1", RgbColor::new(192, 255, 62)),
+    ("olivedrab2", RgbColor::new(179, 238, 58)),
+    ("olivedrab3", RgbColor::new(154, 205, 50)),
+    ("olivedrab4", RgbColor::new(105, 139, 34)),
+    ("orange", RgbColor::new(255, 165, 0)),
+    ("orange1", RgbColor::new(255, 165, 0)),
+    ("orange2", RgbColor::new(238, 154, 0)),
+    ("orange3", RgbColor::new(205, 133, 0)),
+    ("orange4", RgbColor::new(139, 90, 0)),
+    ("orangered", RgbColor::new(255, 69, 0)),
+    ("orangered1", RgbColor::new(255, 69, 0)),
+    ("orangered2", RgbColor::new(238, 64, 0)),
+    ("orangered3", RgbColor::new(205, 55, 0)),
+    ("orangered4", RgbColor::new(139, 37, 0)),
+    ("orchid", RgbColor::new(218, 112, 214)),
+    ("orchid1", RgbColor::new(255, 131, 250)),
+    ("orchid2", RgbColor::new(238, 122, 233)),
+    ("orchid3", RgbColor::new(205, 105, 201)),
+    ("orchid4", RgbColor::new(139, 71, 137)),
+    ("palegoldenrod", RgbColor::new(238, 232, 170)),
+    ("palegreen", RgbColor::new(152, 251, 152)),
+    ("palegreen1", RgbColor::new(154, 255, 154)),
+    ("palegreen2", RgbColor::new(144, 238, 144)),
+    ("palegreen3", RgbColor::new(124, 205, 124)),
+    ("palegreen4", RgbColor::new(84, 139, 84)),
+    ("paleturquoise", RgbColor::new(175, 238, 238)),
+    ("paleturquoise1", RgbColor::new(187, 255, 255)),
+    ("paleturquoise2", RgbColor::new(174, 238, 238)),
+    ("paleturquoise3", RgbColor::new(150, 205, 205)),
+    ("paleturquoise4", RgbColor::new(102, 139, 139)),
+    ("palevioletred", RgbColor::new(219, 112, 147)),
+    ("palevioletred1", RgbColor::new(255, 130, 171)),
+    ("palevioletred2", RgbColor::new(238, 121, 159)),
+    ("palevioletred3", RgbColor::new(205, 104, 137)),
+    ("palevioletred4", RgbColor::new(139, 71, 93)),
+    ("papayawhip", RgbColor::new(255, 239, 213)),
+    ("peachpuff", RgbColor::new(255, 218, 185)),
+    ("peachpuff1", RgbColor::new(255, 218, 185)),
+    ("peachpuff2", RgbColor::new(238, 203, 173)),
+    ("peachpuff3", RgbColor::new(205, 175, 149)),
+    ("peachpuff4", RgbColor::new(139, 119, 101)),
+    ("peru", RgbColor::new(205, 133, 63)),
+    ("pink", RgbColor::new(255, 192, 203)),
+    ("pink1", RgbColor::new(255, 181, 197)),
+    ("pink2", RgbColor::new(238, 169, 184)),
+    ("pink3", RgbColor::new(205, 145, 158)),
+    ("pink4", RgbColor::new(139, 99, 108)),
+    ("plum", RgbColor::new(221, 160, 221)),
+    ("plum1", RgbColor::new(255, 187, 255)),
+    ("plum2", RgbColor::new(238, 174, 238)),
+    ("plum3", RgbColor::new(205, 150, 205)),
+    ("plum4", RgbColor::new(139, 102, 139)),
+    ("powderblue", RgbColor::new(176, 224, 230)),
+    ("purple", RgbColor::new(160, 32, 240)),
+    ("purple1", RgbColor::new(155, 48, 255)),
+    ("purple2", RgbColor::new(145, 44, 238)),
+    ("purple3", RgbColor::new(125, 38, 205)),
+    ("purple4", RgbColor::new(85, 26, 139)),
+    ("rebeccapurple", RgbColor::new(102, 51, 153)),
+    ("red", RgbColor::new(255, 0, 0)),
+    ("red1", RgbColor::new(255, 0, 0)),
+    ("red2", RgbColor::new(238, 0, 0)),
+    ("red3", RgbColor::new(205, 0, 0)),
+    ("red4", RgbColor::new(139, 0, 0)),
+    ("rosybrown", RgbColor::new(188, 143, 143)),
+    ("rosybrown1", RgbColor::new(255, 193, 193)),
+    ("rosybrown2", RgbColor::new(238, 180, 180)),
+    ("rosybrown3", RgbColor::new(205, 155, 155)),
+    ("rosybrown4", RgbColor::new(139, 105, 105)),
+    ("royalblue", RgbColor::new(65, 105, 225)),
+    ("royalblue1", RgbColor::new(72, 118, 255)),
+    ("royalblue2", RgbColor::new(67, 110, 238)),
+    ("royalblue3", RgbColor::new(58, 95, 205)),
+    ("royalblue4", RgbColor::new(39, 64, 139)),
+    ("saddlebrown", RgbColor::new(139, 69, 19)),
+    ("salmon", RgbColor::new(250, 128, 114)),
+    ("salmon1", RgbColor::new(255, 140, 105)),
+    ("salmon2", RgbColor::new(238, 130, 98)),
+    ("salmon3", RgbColor::new(205, 112, 84)),
+    ("salmon4", RgbColor::new(139, 76, 57)),
+    ("sandybrown", RgbColor::new(244, 164, 96)),
+    ("seagreen", RgbColor::new(46, 139, 87)),
+    ("seagreen1", RgbColor::new(84, 255, 159)),
+    ("seagreen2", RgbColor::new(78, 238, 148)),
+    ("seagreen3", RgbColor::new(67, 205, 128)),
+    ("seagreen4", RgbColor::new(46, 139, 87)),
+    ("seashell", RgbColor::new(255, 245, 238)),
+    ("seashell1", RgbColor::new(255, 245, 238)),
+    ("seashell2", RgbColor::new(238, 229, 222)),
+    ("seashell3", RgbColor::new(205, 197, 191)),
+    ("seashell4", RgbColor::new(139, 134, 130)),
+    ("sienna", RgbColor::new(160, 82, 45)),
+    ("sienna1", RgbColor::new(255, 130, 71)),
+    ("sienna2", RgbColor::new(238, 121, 66)),
+    ("sienna3", RgbColor::new(205, 104, 57)),
+    ("sienna4", RgbColor::new(139, 71, 38)),
+    ("silver", RgbColor::new(192, 192, 192)),
+    ("skyblue", RgbColor::new(135, 206, 235)),
+    ("skyblue1", RgbColor::new(135, 206, 255)),
+    ("skyblue2", RgbColor::new(126, 192, 238)),
+    ("skyblue3", RgbColor::new(108, 166, 205)),
+    ("skyblue4", RgbColor::new(74, 112, 139)),
+    ("slateblue", RgbColor::new(106, 90, 205)),
+    ("slateblue1", RgbColor::new(131, 111, 255)),
+    ("slateblue2", RgbColor::new(122, 103, 238)),
+    ("slateblue3", RgbColor::new(105, 89, 205)),
+    ("slateblue4", RgbColor::new(71, 60, 139)),
+    ("slategray", RgbColor::new(112, 128, 144)),
+    ("slategray1", RgbColor::new(198, 226, 255)),
+    ("slategray2", RgbColor::new(185, 211, 238)),
+    ("slategray3", RgbColor::new(159, 182, 205)),
+    ("slategray4", RgbColor::new(108, 123, 139)),
+    ("snow", RgbColor::new(255, 250, 250)),
+    ("snow1", RgbColor::new(255, 250, 250)),
+    ("snow2", RgbColor::new(238, 233, 233)),
+    ("snow3", RgbColor::new(205, 201, 201)),
+    ("snow4", RgbColor::new(139, 137, 137)),
+    ("springgreen", RgbColor::new(0, 255, 127)),
+    ("springgreen1", RgbColor::new(0, 255, 127)),
+    ("springgreen2", RgbColor::new(0, 238, 118)),
+    ("springgreen3", RgbColor::new(0, 205, 102)),
+    ("springgreen4", RgbColor::new(0, 139, 69)),
+    ("steelblue", RgbColor::new(70, 130, 180)),
+    ("steelblue1", RgbColor::new(99, 184, 255)),
+    ("steelblue2", RgbColor::new(92, 172, 238)),
+    ("steelblue3", RgbColor::new(79, 148, 205)),
+    ("steelblue4", RgbColor::new(54, 100, 139)),
+    ("tan", RgbColor::new(210, 180, 140)),
+    ("tan1", RgbColor::new(255, 165, 79)),
+    ("tan2", RgbColor::new(238, 154, 73)),
+    ("tan3", RgbColor::new(205, 133, 63)),
+    ("tan4", RgbColor::new(139, 90, 43)),
+    ("teal", RgbColor::new(0, 128, 128)),
+    ("thistle", RgbColor::new(216, 191, 216)),
+    ("thistle1", RgbColor::new(255, 225, 255)),
+    ("thistle2", RgbColor::new(238, 210, 238)),
+    ("thistle3", RgbColor::new(205, 181, 205)),
+    ("thistle4", RgbColor::new(139, 123, 139)),
+    ("tomato", RgbColor::new(255, 99, 71)),
+    ("tomato1", RgbColor::new(255, 99, 71)),
+    ("tomato2", RgbColor::new(238, 92, 66)),
+    ("tomato3", RgbColor::new(205, 79, 57)),
+    ("tomato4", RgbColor::new(139, 54, 38)),
+    ("turquoise", RgbColor::new(64, 224, 208)),
+    ("turquoise1", RgbColor::new(0, 245, 255)),
+    ("turquoise2", RgbColor::new(0, 229, 238)),
+    ("turquoise3", RgbColor::new(0, 197, 205)),
+    ("turquoise4", RgbColor::new(0, 134, 139)),
+    ("violet", RgbColor::new(238, 130, 238)),
+    ("violetred", RgbColor::new(208, 32, 144)),
+    ("violetred1", RgbColor::new(255, 62, 150)),
+    ("violetred2", RgbColor::new(238, 58, 140)),
+    ("violetred3", RgbColor::new(205, 50, 120)),
+    ("violetred4", RgbColor::new(139, 34, 82)),
+    ("webgray", RgbColor::new(128, 128, 128)),
+    ("webgreen", RgbColor::new(0, 128, 0)),
+    ("webmaroon", RgbColor::new(128, 0, 0)),
+    ("webpurple", RgbColor::new(128, 0, 128)),
+    ("wheat", RgbColor::new(245, 222, 179)),
+    ("wheat1", RgbColor::new(255, 231, 186)),
+    ("wheat2", RgbColor::new(238, 216, 174)),
+    ("wheat3", RgbColor::new(205, 186, 150)),
+    ("wheat4", RgbColor::new(139, 126, 102)),
+    ("white", RgbColor::new(255, 255, 255)),
+    ("whitesmoke", RgbColor::new(245, 245, 245)),
+    ("x11gray", RgbColor::new(190, 190, 190)),
+    ("x11green", RgbColor::new(0, 255, 0)),
+    ("x11maroon", RgbColor::new(176, 48, 96)),
+    ("x11purple", RgbColor::new(160, 32, 240)),
+    ("yellow", RgbColor::new(255, 255, 0)),
+    ("yellow1", RgbColor::new(255, 255, 0)),
+    ("yellow2", RgbColor::new(238, 238, 0)),
+    ("yellow3", RgbColor::new(205, 205, 0)),
+    ("yellow4", RgbColor::new(139, 139, 0)),
+    ("yellowgreen", RgbColor::new(154, 205, 50)),
+];
@@ -0,0 +1,407 @@
+//! Semantic styles which degrade to the color support of the terminal.
+//!
+//! A [`Theme`] maps the handful of roles a small CLI tool needs (errors, warnings, hints, and
+//! selected text) to a [`Style`]. Styles are written with whatever colors the author prefers and
+//! the theme downsamples them to the terminal's [`ColorLevel`] when painting text, so true-color
+//! choices still render sensibly in a 16-color console or with colors turned off entirely.
+//!
+//! # Examples
+//!
+//! ```
+//! use termina::style::{ColorLevel, Role, Theme};
+//!
+//! # termina::style::Stylized::force_ansi_color(true);
+//! let theme = Theme::default().with_color_level(ColorLevel::Ansi16);
+//! assert_eq!(theme.paint(Role::Error, "error").to_string(), "\x1b[0;91;1merror\x1b[m");
+//!
+//! let theme = theme.with_color_level(ColorLevel::None);
+//! assert_eq!(theme.paint(Role::Error, "error").to_string(), "\x1b[0;1merror\x1b[m");
+//! ```
+//!
+//! # Implementation Notes
+//!
+//! Color level detection follows the conventions shared by most terminal color libraries (for
+//! example [supports-color] and [termstandard/colors]): `NO_COLOR` disables colors, `COLORTERM`
+//! advertises true color, and the `TERM` name hints at 256-color support. Downsampling to the
+//! 256-color palette uses the xterm 6x6x6 color cube and grayscale ramp. Colors are matched by the
+//! weighted ["redmean" distance][redmean], a cheap approximation of how different two colors look
+//! which avoids most of the hue shifts plain RGB distance causes.
+//!
+//! [supports-color]: https://docs.rs/supports-color/latest/supports_color/
+//! [termstandard/colors]: https://github.com/termstandard/colors
+//! [redmean]: https://www.compuphase.com/cmetric.htm
+
+use crate::escape::csi::Sgr;
+
+use super::{
+    color_from_env, AnsiColor, ColorSpec, Intensity, PaletteIndex, RgbColor, Stylized, Underline,
+    COLOR_ALWAYS, COLOR_NEVER,
+};
+
+/// The amount of color a terminal can display.
+///
+/// Levels are ordered so that a higher level can display everything a lower level can.
+///
+/// ```
+/// use termina::style::ColorLevel;
+///
+/// assert!(ColorLevel::TrueColor > ColorLevel::Ansi256);
+/// assert!(ColorLevel::Ansi16 > ColorLevel::None);
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ColorLevel {
+    /// Colors are disabled. Other attributes like bold and underline are still emitted.
+    None,
+    /// The 16 standard and bright ANSI colors.
+    #[default]
+    Ansi16,
+    /// The 256-color palette.
+    Ansi256,
+    /// 24-bit RGB colors.
+    TrueColor,
+}
+
+impl ColorLevel {
+    /// Guesses the color level from the process environment.
+    ///
+    /// `NO_COLOR`, `CLICOLOR`, `CLICOLOR_FORCE` and `FORCE_COLOR` are read the same way as for
+    /// [`Stylized::is_ansi_color_disabled`]: colors which are disabled there are disabled here
+    /// too. `COLORTERM=truecolor` or `COLORTERM=24bit` selects true color, a `TERM` containing
+    /// `256color` selects the 256-color palette and `TERM=dumb` disables colors unless they are
+    /// forced. Anything else is assumed to support the 16 ANSI colors.
+    ///
+    /// Windows consoles which support virtual terminal sequences also support true color, so when
+    /// `TERM` is unset on Windows this returns [`Self::TrueColor`].
+    pub fn from_env() -> Self {
+        let var = |name| std::env::var(name).ok().filter(|value| !value.is_empty());
+
+        let color = color_from_env();
+        if color == COLOR_NEVER {
+            return Self::None;
+        }
+        if var("COLORTERM").is_some_and(|value| value == "truecolor" || value == "24bit") {
+            return Self::TrueColor;
+        }
+        match var("TERM") {
+            Some(term) if term == "dumb" && color != COLOR_ALWAYS => Self::None,
+            Some(term) if term.contains("256color") => Self::Ansi256,
+            Some(_) => Self::Ansi16,
+            None if cfg!(windows) => Self::TrueColor,
+            None => Self::Ansi16,
+        }
+    }
+
+    /// Converts a color into the closest color this level can display.
+    ///
+    /// This is the same as [`ColorSpec::degrade`]. Returns `None` when colors are disabled.
+    /// [`ColorSpec::Reset`] is passed through unchanged at every other level.
+    ///
+    /// ```
+    /// use termina::style::{ColorLevel, ColorSpec, RgbColor};
+    ///
+    /// let orange = ColorSpec::from(RgbColor::new(255, 135, 0));
+    /// assert_eq!(ColorLevel::TrueColor.degrade(orange), Some(orange));
+    /// assert_eq!(ColorLevel::Ansi256.degrade(orange), Some(ColorSpec::PaletteIndex(208)));
+    /// assert_eq!(ColorLevel::Ansi16.degrade(orange), Some(ColorSpec::YELLOW));
+    /// assert_eq!(ColorLevel::None.degrade(orange), None);
+    /// ```
+    pub fn degrade(self, color: ColorSpec) -> Option<ColorSpec> {
+        let color = match (self, color) {
+            (Self::None, _) => return None,
+            (_, ColorSpec::Reset) | (Self::TrueColor, _) => color,
+            (Self::Ansi256, ColorSpec::PaletteIndex(_)) => color,
+            (Self::Ansi256, ColorSpec::TrueColor(rgba)) => {
+                ColorSpec::PaletteIndex(nearest_palette_index(rgba.into()))
+            }
+            (Self::Ansi16, ColorSpec::PaletteIndex(index)) if index < 16 => color,
+            (Self::Ansi16, ColorSpec::PaletteIndex(index)) => {
+                ColorSpec::PaletteIndex(nearest_ansi_color(palette_rgb(index)))
+            }
+            (Self::Ansi16, ColorSpec::TrueColor(rgba)) => {
+                ColorSpec::PaletteIndex(nearest_ansi_color(rgba.into()))
+            }
+        };
+        Some(color)
+    }
+}
+
+/// A set of text attributes which can be applied to a string.
+///
+/// Unset colors leave the terminal's current color in place.
+///
+/// ```
+/// use termina::style::{AnsiColor, Intensity, Style};
+///
+/// # termina::style::Stylized::force_ansi_color(true);
+/// let style = Style::new().foreground(AnsiColor::Cyan).intensity(Intensity::Dim);
+/// assert_eq!(style.paint("hint").to_string(), "\x1b[0;36;2mhint\x1b[m");
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Style {
+    /// The foreground color.
+    pub foreground: Option<ColorSpec>,
+    /// The background color.
+    pub background: Option<ColorSpec>,
+    /// Text intensity.
+    pub intensity: Intensity,
+    /// Underline style.
+    pub underline: Underline,
+    /// Whether the text is italic.
+    pub italic: bool,
+    /// Whether the foreground and background colors are swapped.
+    pub reverse: bool,
+}
+
+impl Style {
+    /// Creates a style with no attributes set.
+    pub const fn new() -> Self {
+        Self {
+            foreground: None,
+            background: None,
+            intensity: Intensity::Normal,
+            underline: Underline::None,
+            italic: false,
+            reverse: false,
+        }
+    }
+
+    /// Sets the foreground color.
+    pub fn foreground(mut self, color: impl Into<ColorSpec>) -> Self {
+        self.foreground = Some(color.into());
+        self
+    }
+
+    /// Sets the background color.
+    pub fn background(mut self, color: impl Into<ColorSpec>) -> Self {
+        self.background = Some(color.into());
+        self
+    }
+
+    /// Sets the text intensity.
+    pub fn intensity(mut self, intensity: Intensity) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    /// Sets the underline style.
+    pub fn underline(mut self, underline: Underline) -> Self {
+        self.underline = underline;
+        self
+    }
+
+    /// Sets whether the text is italic.
+    pub fn italic(mut self, italic: bool) -> Self {
+        self.italic = italic;
+        self
+    }
+
+    /// Sets whether the foreground and background colors are swapped.
+    pub fn reverse(mut self, reverse: bool) -> Self {
+        self.reverse = reverse;
+        self
+    }
+
+    /// Converts this style's colors to the closest colors the given level can display.
+    ///
+    /// Colors are dropped entirely at [`ColorLevel::None`].
+    pub fn degrade(self, level: ColorLevel) -> Self {
+        Self {
+            foreground: self.foreground.and_then(|color| level.degrade(color)),
+            background: self.background.and_then(|color| level.degrade(color)),
+            ..self
+        }
+    }
+
+    /// Returns the SGR attributes which select this style.
+    ///
+    /// Attributes left at their defaults are omitted.
+    pub fn sgr(&self) -> Vec<Sgr> {
+        let mut sgr = Vec::new();
+        if let Some(color) = self.foreground {
+            sgr.push(Sgr::Foreground(color));
+        }
+        if let Some(color) = self.background {
+            sgr.push(Sgr::Background(color));
+        }
+        if self.intensity != Intensity::Normal {
+            sgr.push(Sgr::Intensity(self.intensity));
+        }
+        if self.underline != Underline::None {
+            sgr.push(Sgr::Underline(self.underline));
+        }
+        if self.italic {
+            sgr.push(Sgr::Italic(true));
+        }
+        if self.reverse {
+            sgr.push(Sgr::Reverse(true));
+        }
+        sgr
+    }
+
+    /// Applies this style to the given text.
+    pub fn paint<'a>(&self, content: impl Into<std::borrow::Cow<'a, str>>) -> Stylized<'a> {
+        Stylized {
+            content: content.into(),
+            styles: self.sgr(),
+            stream: None,
+        }
+    }
+}
+
+/// A semantic role which a [`Theme`] assigns a [`Style`] to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Role {
+    /// Errors and failures.
+    Error,
+    /// Warnings which do not stop the program.
+    Warning,
+    /// Supplementary information like help text or suggestions.
+    Hint,
+    /// Selected or highlighted text.
+    Selection,
+}
+
+/// Styles for each [`Role`] plus the [`ColorLevel`] they are painted with.
+///
+/// [`Theme::default`] uses the 16 ANSI colors and detects the color level from the environment
+/// with [`ColorLevel::from_env`]. Fields may be replaced with any colors: they are downsampled to
+/// the theme's color level when painting.
+///
+/// ```
+/// use termina::style::{ColorLevel, RgbColor, Role, Style, Theme};
+///
+/// # termina::style::Stylized::force_ansi_color(true);
+/// let theme = Theme {
+///     warning: Style::new().foreground(RgbColor::new(255, 175, 0)),
+///     ..Theme::default()
+/// }
+/// .with_color_level(ColorLevel::Ansi256);
+///
+/// assert_eq!(theme.paint(Role::Warning, "warn").to_string(), "\x1b[0;38;5;214mwarn\x1b[m");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Theme {
+    /// The style for [`Role::Error`].
+    pub error: Style,
+    /// The style for [`Role::Warning`].
+    pub warning: Style,
+    /// The style for [`Role::Hint`].
+    pub hint: Style,
+    /// The style for [`Role::Selection`].
+    pub selection: Style,
+    /// The color level styles are degraded to when painting.
+    pub color_level: ColorLevel,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            error: Style::new()
+                .foreground(AnsiColor::BrightRed)
+                .intensity(Intensity::Bold),
+            warning: Style::new().foreground(AnsiColor::Yellow),
+            hint: Style::new()
+                .foreground(AnsiColor::Cyan)
+                .intensity(Intensity::Dim),
+            selection: Style::new().reverse(true),
+            color_level: ColorLevel::from_env(),
+        }
+    }
+}
+
+impl Theme {
+    /// Overrides the color level used when painting.
+    pub fn with_color_level(mut self, color_level: ColorLevel) -> Self {
+        self.color_level = color_level;
+        self
+    }
+
+    /// Returns the style for the role, degraded to the theme's color level.
+    pub fn style(&self, role: Role) -> Style {
+        let style = match role {
+            Role::Error => self.error,
+            Role::Warning => self.warning,
+            Role::Hint => self.hint,
+            Role::Selection => self.selection,
+        };
+        style.degrade(self.color_level)
+    }
+
+    /// Applies the style for the role to the given text.
+    pub fn paint<'a>(
+        &self,
+        role: Role,
+        content: impl Into<std::borrow::Cow<'a, str>>,
+    ) -> Stylized<'a> {
+        self.style(role).paint(content)
+    }
+}
+
+/// The xterm default values for the 16 ANSI colors.
+const ANSI_RGB: [RgbColor; 16] = [
+    RgbColor::new(0, 0, 0),
+    RgbColor::new(205, 0, 0),
+    RgbColor::new(0, 205, 0),
+    RgbColor::new(205, 205, 0),
+    RgbColor::new(0, 0, 238),
+    RgbColor::new(205, 0, 205),
+    RgbColor::new(0, 205, 205),
+    RgbColor::new(229, 229, 229),
+    RgbColor::new(127, 127, 127),
+    RgbColor::new(255, 0, 0),
+    RgbColor::new(0, 255, 0),
+    RgbColor::new(255, 255, 0),
+    RgbColor::new(92, 92, 255),
+    RgbColor::new(255, 0, 255),
+    RgbColor::new(0, 255, 255),
+    RgbColor::new(255, 255, 255),
+];
+
+/// Channel values of the 6x6x6 color cube in the 256-color palette.
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// The "redmean" distance between two colors, scaled by 256 to stay in integers.
+///
+/// Green differences count the most and red and blue are weighted by how red the colors are.
+fn distance(a: RgbColor, b: RgbColor) -> u32 {
+    let red_mean = (a.red as u32 + b.red as u32) / 2;
+    let channel = |a: u8, b: u8| (a as i32 - b as i32).unsigned_abs().pow(2);
+    (512 + red_mean) * channel(a.red, b.red)
+        + 1024 * channel(a.green, b.green)
+        + (767 - red_mean) * channel(a.blue, b.blue)
+}
+
+pub(super) fn palette_rgb(index: PaletteIndex) -> RgbColor {
+    match index {
+        0..=15 => ANSI_RGB[index as usize],
+        16..=231 => {
+            let index = index - 16;
+            RgbColor::new(
+                CUBE_LEVELS[(index / 36) as usize],
+                CUBE_LEVELS[(index / 6 % 6) as usize],
+                CUBE_LEVELS[(index % 6) as usize],
+            )
+        }
+        232..=255 => {
+            let gray = 8 + (index - 232) * 10;
+            RgbColor::new(gray, gray, gray)
+        }
+    }
+}
+
+pub(super) fn nearest_ansi_color(color: RgbColor) -> PaletteIndex {
+    (0..16)
+        .min_by_key(|&index| distance(color, ANSI_RGB[index as usize]))
+        .unwrap()
+}
+
+pub(super) fn nearest_palette_index(color: RgbColor) -> PaletteIndex {
+    // The standard 16 colors are skipped since terminals commonly remap them.
+    (16..=255)
+        .min_by_key(|&index| distance(color, palette_rgb(index)))
+        .unwrap()
+}
@@ -0,0 +1,824 @@
+//! A virtual terminal screen for asserting on rendered output.
+//!
+//! [`Surface`] consumes the bytes an application writes to the terminal (text, cursor movement,
+//! erasing and SGR styling) and materializes them as a grid of styled [`Cell`]s. Tests can then
+//! assert on the contents and colors of the screen instead of on raw escape sequences, which
+//! change whenever a renderer finds a shorter way to draw the same thing.
+//!
+//! A `Surface` can also be drawn into directly with [`Surface::put_str`] and presented with a
+//! [`Renderer`], which writes only the escape sequences needed to update the terminal from the
+//! previous frame. With the `termwiz-compat` feature, frames can also be built from a list of
+//! termwiz-style [`Change`]s.
+//!
+//! This module is available with the `surface` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::io::Write as _;
+//!
+//! use termina::{
+//!     escape::csi::{self, Csi},
+//!     style::{ColorSpec, StyleExt as _},
+//!     surface::Surface,
+//!     OneBased,
+//! };
+//!
+//! # termina::style::Stylized::force_ansi_color(true);
+//! let mut surface = Surface::new(20, 3);
+//! let position = csi::Cursor::Position {
+//!     line: OneBased::new(2).unwrap(),
+//!     col: OneBased::new(3).unwrap(),
+//! };
+//! write!(surface, "{}{}", Csi::Cursor(position), "error".red()).unwrap();
+//!
+//! assert_eq!(surface.line_text(1), "  error");
+//! assert_eq!(surface.cell(2, 1).unwrap().attributes.foreground, ColorSpec::RED);
+//! assert_eq!(surface.cursor(), (7, 1));
+//! ```
+//!
+//! # Implementation Notes
+//!
+//! The parser is a reduced form of the [DEC ANSI parser] state machine used by terminal emulators.
+//! It understands the sequences Termina's [`escape`](crate::escape) types produce for drawing:
+//! cursor movement and positioning, erasing, inserting and deleting characters and lines,
+//! scrolling, SGR, autowrap and cursor visibility. Other sequences, including OSC, DCS and APC
+//! strings, are consumed and ignored.
+//!
+//! Output is interpreted as a terminal in raw mode would: a line feed moves the cursor down
+//! without returning it to the first column. Every character occupies one cell, the whole screen
+//! is the scroll region and the alternate screen is not modeled. Erasing fills cells with the
+//! current background color like xterm's "background color erase".
+//!
+//! [DEC ANSI parser]: https://vt100.net/emu/dec_ansi_parser
+
+use std::io;
+
+use crate::style::{Blink, ColorSpec, Intensity, RgbColor, Underline};
+
+#[cfg(feature = "termwiz-compat")]
+mod change;
+mod render;
+
+#[cfg(feature = "termwiz-compat")]
+pub use change::{AttributeChange, Change, Position};
+pub use render::Renderer;
+
+/// The style of a single [`Cell`].
+///
+/// This is [`Attributes`](crate::style::Attributes), whose [`diff`](crate::style::Attributes::diff)
+/// gives the SGR sequences for switching between the styles of two cells.
+pub type CellAttributes = crate::style::Attributes;
+
+/// A character on the [`Surface`] and its style.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Cell {
+    /// The character in the cell. Blank cells hold a space.
+    pub ch: char,
+    /// The style the character was written with.
+    pub attributes: CellAttributes,
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Self {
+            ch: ' ',
+            attributes: CellAttributes::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Ground,
+    Escape,
+    EscapeIntermediate,
+    Csi,
+    String,
+    StringEscape,
+}
+
+/// The most parameter bytes kept for one CSI sequence. Longer sequences are ignored.
+const MAX_CSI_LEN: usize = 256;
+
+/// A grid of cells built by interpreting terminal output.
+///
+/// Bytes are fed with [`Self::advance`] or through the [`io::Write`] impl, so a `Surface` can
+/// stand in for the terminal in rendering code. Sequences may be split across writes.
+/// Coordinates are zero-based `(col, row)` pairs with `(0, 0)` at the top left.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Surface {
+    cols: u16,
+    rows: u16,
+    cells: Vec<Cell>,
+    cursor: (u16, u16),
+    saved_cursor: (u16, u16),
+    cursor_visible: bool,
+    /// Set after a character is written to the last column, until the next character wraps.
+    wrap_pending: bool,
+    autowrap: bool,
+    pen: CellAttributes,
+    state: State,
+    csi: Vec<u8>,
+    utf8: Vec<u8>,
+}
+
+impl Surface {
+    /// Creates a blank surface with the given dimensions and the cursor at the top left.
+    pub fn new(cols: u16, rows: u16) -> Self {
+        Self {
+            cols,
+            rows,
+            cells: vec![Cell::default(); cols as usize * rows as usize],
+            cursor: (0, 0),
+            saved_cursor: (0, 0),
+            cursor_visible: true,
+            wrap_pending: false,
+            autowrap: true,
+            pen: CellAttributes::default(),
+            state: State::Ground,
+            csi: Vec::new(),
+            utf8: Vec::new(),
+        }
+    }
+
+    /// The number of columns.
+    pub fn cols(&self) -> u16 {
+        self.cols
+    }
+
+    /// The number of rows.
+    pub fn rows(&self) -> u16 {
+        self.rows
+    }
+
+    /// The cursor position as `(col, row)`.
+    pub fn cursor(&self) -> (u16, u16) {
+        self.cursor
+    }
+
+    /// Returns `false` if the cursor was hidden with `DECTCEM`.
+    pub fn cursor_visible(&self) -> bool {
+        self.cursor_visible
+    }
+
+    /// The attributes which text written next will have.
+    pub fn attributes(&self) -> CellAttributes {
+        self.pen
+    }
+
+    /// Returns the cell at `(col, row)` or `None` if it is off the surface.
+    pub fn cell(&self, col: u16, row: u16) -> Option<&Cell> {
+        if col < self.cols && row < self.rows {
+            Some(&self.cells[self.index(col, row)])
+        } else {
+            None
+        }
+    }
+
+    /// Returns the cells of `row`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `row` is off the surface.
+    pub fn line(&self, row: u16) -> &[Cell] {
+        assert!(row < self.rows, "row {row} is out of bounds");
+        let start = self.index(0, row);
+        &self.cells[start..start + self.cols as usize]
+    }
+
+    /// Returns the text of `row` without trailing blanks.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `row` is off the surface.
+    pub fn line_text(&self, row: u16) -> String {
+        let text: String = self.line(row).iter().map(|cell| cell.ch).collect();
+        text.trim_end().to_string()
+    }
+
+    /// Returns the text of every row, without trailing blanks, joined with newlines.
+    pub fn screen_text(&self) -> String {
+        (0..self.rows)
+            .map(|row| self.line_text(row))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Replaces the cell at `(col, row)`. Cells off the surface are ignored.
+    pub fn set_cell(&mut self, col: u16, row: u16, cell: Cell) {
+        if col < self.cols && row < self.rows {
+            let index = self.index(col, row);
+            self.cells[index] = cell;
+        }
+    }
+
+    /// Writes `text` into `row` starting at `col` with the given attributes.
+    ///
+    /// Unlike writing to the surface, this doesn't interpret control characters or move the
+    /// cursor. Text which doesn't fit in the row is cut off.
+    pub fn put_str(&mut self, col: u16, row: u16, text: &str, attributes: CellAttributes) {
+        for (col, ch) in (col..self.cols).zip(text.chars()) {
+            self.set_cell(col, row, Cell { ch, attributes });
+        }
+    }
+
+    /// Moves the cursor to `(col, row)`, clamped to the surface.
+    pub fn set_cursor(&mut self, col: u16, row: u16) {
+        self.move_to(col, row);
+    }
+
+    /// Shows or hides the cursor.
+    pub fn set_cursor_visible(&mut self, visible: bool) {
+        self.cursor_visible = visible;
+    }
+
+    /// Blanks every cell.
+    pub fn clear(&mut self) {
+        self.cells.fill(Cell::default());
+    }
+
+    /// Changes the dimensions, keeping the cells which still fit.
+    pub fn resize(&mut self, cols: u16, rows: u16) {
+        let mut cells = vec![Cell::default(); cols as usize * rows as usize];
+        for row in 0..rows.min(self.rows) {
+            let keep = cols.min(self.cols) as usize;
+            let from = self.index(0, row);
+            let to = row as usize * cols as usize;
+            cells[to..to + keep].copy_from_slice(&self.cells[from..from + keep]);
+        }
+        self.cells = cells;
+        self.cols = cols;
+        self.rows = rows;
+        self.cursor = self.clamp(self.cursor.0, self.cursor.1);
+        self.saved_cursor = self.clamp(self.saved_cursor.0, self.saved_cursor.1);
+        self.wrap_pending = false;
+    }
+
+    /// Interprets terminal output, updating the cells and cursor.
+    pub fn advance(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.advance_byte(byte);
+        }
+    }
+
+    fn advance_byte(&mut self, byte: u8) {
+        match self.state {
+            State::Ground => match byte {
+                0x1b => {
+                    self.flush_utf8();
+                    self.state = State::Escape;
+                }
+                0x00..=0x1f | 0x7f => {
+                    self.flush_utf8();
+                    self.execute(byte);
+                }
+                _ => self.push_utf8(byte),
+            },
+            State::Escape => self.escape(byte),
+            State::EscapeIntermediate => match byte {
+                0x1b => self.state = State::Escape,
+                0x20..=0x2f => (),
+                0x00..=0x1f => self.execute(byte),
+                // Character set designations and the like have no effect on the cells.
+                _ => self.state = State::Ground,
+            },
+            State::Csi => match byte {
+                0x1b => self.state = State::Escape,
+                0x00..=0x1f => self.execute(byte),
+                0x20..=0x3f => {
+                    if self.csi.len() < MAX_CSI_LEN {
+                        self.csi.push(byte);
+                    }
+                }
+                0x40..=0x7e => {
+                    if self.csi.len() < MAX_CSI_LEN {
+                        self.csi_dispatch(byte);
+                    }
+                    self.state = State::Ground;
+                }
+                _ => self.state = State::Ground,
+            },
+            State::String => match byte {
+                0x07 => self.state = State::Ground,
+                0x1b => self.state = State::StringEscape,
+                _ => (),
+            },
+            State::StringEscape => {
+                if byte == b'\\' {
+                    self.state = State::Ground;
+                } else {
+                    self.escape(byte);
+                }
+            }
+        }
+    }
+
+    fn escape(&mut self, byte: u8) {
+        self.state = State::Ground;
+        match byte {
+            b'[' => {
+                self.csi.clear();
+                self.state = State::Csi;
+            }
+            b']' | b'P' | b'_' | b'^' | b'X' => self.state = State::String,
+            0x20..=0x2f => self.state = State::EscapeIntermediate,
+            0x1b => self.state = State::Escape,
+            b'7' => self.saved_cursor = self.cursor,
+            b'8' => self.move_to(self.saved_cursor.0, self.saved_cursor.1),
+            b'D' => self.line_feed(),
+            b'E' => {
+                self.cursor.0 = 0;
+                self.line_feed();
+            }
+            b'M' => self.reverse_index(),
+            b'c' => *self = Self::new(self.cols, self.rows),
+            _ => (),
+        }
+    }
+
+    fn execute(&mut self, byte: u8) {
+        match byte {
+            b'\x08' => self.move_to(self.cursor.0.saturating_sub(1), self.cursor.1),
+            b'\t' => {
+                let next = (self.cursor.0 / 8 + 1) * 8;
+                self.move_to(next.min(self.cols.saturating_sub(1)), self.cursor.1);
+            }
+            b'\n' | b'\x0b' | b'\x0c' => self.line_feed(),
+            b'\r' => self.move_to(0, self.cursor.1),
+            _ => (),
+        }
+    }
+
+    fn push_utf8(&mut self, byte: u8) {
+        self.utf8.push(byte);
+        match std::str::from_utf8(&self.utf8) {
+            Ok(s) => {
+                let ch = s.chars().next().unwrap();
+                self.utf8.clear();
+                self.print(ch);
+            }
+            Err(err) if err.error_len().is_some() => {
+                self.utf8.clear();
+                self.print(char::REPLACEMENT_CHARACTER);
+            }
+            // Wait for the rest of the character.
+            Err(_) => (),
+        }
+    }
+
+    /// Replaces a character cut short by a control or escape byte.
+    fn flush_utf8(&mut self) {
+        if !self.utf8.is_empty() {
+            self.utf8.clear();
+            self.print(char::REPLACEMENT_CHARACTER);
+        }
+    }
+
+    fn print(&mut self, ch: char) {
+        if self.cols == 0 || self.rows == 0 {
+            return;
+        }
+        if self.wrap_pending {
+            self.cursor.0 = 0;
+            self.line_feed();
+        }
+        let (col, row) = self.cursor;
+        let index = self.index(col, row);
+        self.cells[index] = Cell {
+            ch,
+            attributes: self.pen,
+        };
+        if col + 1 < self.cols {
+            self.cursor.0 += 1;
+        } else {
+            self.wrap_pending = self.autowrap;
+        }
+    }
+
+    fn csi_dispatch(&mut self, action: u8) {
+        let csi = std::mem::take(&mut self.csi);
+        let (private, rest) = match csi.first() {
+            Some(marker @ b'<'..=b'?') => (Some(*marker), &csi[1..]),
+            _ => (None, &csi[..]),
+        };
+        let params_end = rest
+            .iter()
+            .position(|byte| (0x20..=0x2f).contains(byte))
+            .unwrap_or(rest.len());
+        let has_intermediates = params_end < rest.len();
+        let params = parse_params(&rest[..params_end]);
+        self.csi = csi;
+
+        if has_intermediates {
+            return;
+        }
+        if private == Some(b'?') {
+            if matches!(action, b'h' | b'l') {
+                for param in &params {
+                    self.set_dec_mode(param[0], action == b'h');
+                }
+            }
+            return;
+        }
+        if private.is_some() {
+            return;
+        }
+
+        // The first parameter where zero or missing means one.
+        let count = params.first().map_or(1, |param| param[0].max(1));
+        let (col, row) = self.cursor;
+        match action {
+            b'A' => self.move_to(col, row.saturating_sub(count)),
+            b'B' => self.move_to(col, row.saturating_add(count)),
+            b'C' => self.move_to(col.saturating_add(count), row),
+            b'D' => self.move_to(col.saturating_sub(count), row),
+            b'E' => self.move_to(0, row.saturating_add(count)),
+            b'F' => self.move_to(0, row.saturating_sub(count)),
+            b'G' | b'`' => self.move_to(count - 1, row),
+            b'd' => self.move_to(col, count - 1),
+            b'H' | b'f' => {
+                let col = params.get(1).map_or(1, |param| param[0].max(1));
+                self.move_to(col - 1, count - 1);
+            }
+            b'J' => self.erase_in_display(params.first().map_or(0, |param| param[0])),
+            b'K' => self.erase_in_line(params.first().map_or(0, |param| param[0])),
+            b'X' => {
+                let end = col.saturating_add(count).min(self.cols);
+                self.erase(row, col, end);
+            }
+            b'@' => self.insert_chars(count),
+            b'P' => self.delete_chars(count),
+            b'L' => self.insert_lines(count),
+            b'M' => self.delete_lines(count),
+            b'S' => self.scroll_up(0, count),
+            b'T' => self.scroll_down(0, count),
+            b'm' => self.sgr(&params),
+            b's' => self.saved_cursor = self.cursor,
+            b'u' => self.move_to(self.saved_cursor.0, self.saved_cursor.1),
+            _ => (),
+        }
+    }
+
+    fn set_dec_mode(&mut self, mode: u16, enabled: bool) {
+        match mode {
+            7 => {
+                self.autowrap = enabled;
+                self.wrap_pending &= enabled;
+            }
+            25 => self.cursor_visible = enabled,
+            _ => (),
+        }
+    }
+
+    fn sgr(&mut self, params: &[Vec<u16>]) {
+        if params.is_empty() {
+            self.pen = CellAttributes::default();
+            return;
+        }
+        let mut params = params.iter();
+        while let Some(param) = params.next() {
+            match param[0] {
+                0 => self.pen = CellAttributes::default(),
+                1 => self.pen.intensity = Intensity::Bold,
+                2 => self.pen.intensity = Intensity::Dim,
+                3 => self.pen.italic = true,
+                4 => {
+                    self.pen.underline = match param.get(1) {
+                        None | Some(1) => Underline::Single,
+                        Some(2) => Underline::Double,
+                        Some(3) => Underline::Curly,
+                        Some(4) => Underline::Dotted,
+                        Some(5) => Underline::Dashed,
+                        Some(_) => Underline::None,
+                    }
+                }
+                5 => self.pen.blink = Blink::Slow,
+                6 => self.pen.blink = Blink::Rapid,
+                7 => self.pen.reverse = true,
+                8 => self.pen.invisible = true,
+                9 => self.pen.strike_through = true,
+                21 => self.pen.underline = Underline::Double,
+                22 => self.pen.intensity = Intensity::Normal,
+                23 => self.pen.italic = false,
+                24 => self.pen.underline = Underline::None,
+                25 => self.pen.blink = Blink::None,
+                27 => self.pen.reverse = false,
+                28 => self.pen.invisible = false,
+                29 => self.pen.strike_through = false,
+                n @ 30..=37 => self.pen.foreground = ColorSpec::PaletteIndex(n as u8 - 30),
+                38 => self.pen.foreground = extended_color(param, &mut params),
+                39 => self.pen.foreground = ColorSpec::Reset,
+                n @ 40..=47 => self.pen.background = ColorSpec::PaletteIndex(n as u8 - 40),
+                48 => self.pen.background = extended_color(param, &mut params),
+                49 => self.pen.background = ColorSpec::Reset,
+                53 => self.pen.overline = true,
+                55 => self.pen.overline = false,
+                58 => self.pen.underline_color = extended_color(param, &mut params),
+                59 => self.pen.underline_color = ColorSpec::Reset,
+                n @ 90..=97 => self.pen.foreground = ColorSpec::PaletteIndex(n as u8 - 90 + 8),
+                n @ 100..=107 => self.pen.background = ColorSpec::PaletteIndex(n as u8 - 100 + 8),
+                _ => (),
+            }
+        }
+    }
+
+    fn index(&self, col: u16, row: u16) -> usize {
+        row as usize * self.cols as usize + col as usize
+    }
+
+    fn clamp(&self, col: u16, row: u16) -> (u16, u16) {
+        (
+            col.min(self.cols.saturating_sub(1)),
+            row.min(self.rows.saturating_sub(1)),
+        )
+    }
+
+    fn move_to(&mut self, col: u16, row: u16) {
+        self.cursor = self.clamp(col, row);
+        self.wrap_pending = false;
+    }
+
+    fn line_feed(&mut self) {
+        self.wrap_pending = false;
+        if self.cursor.1 + 1 >= self.rows {
+            self.scroll_up(0, 1);
+        } else {
+            self.cursor.1 += 1;
+        }
+    }
+
+    fn reverse_index(&mut self) {
+        self.wrap_pending = false;
+        if self.cursor.1 == 0 {
+            self.scroll_down(0, 1);
+        } else {
+            self.cursor.1 -= 1;
+        }
+    }
+
+    /// The cell left behind by erasing, which keeps the current background color.
+    fn blank(&self) -> Cell {
+        Cell {
+            ch: ' ',
+            attributes: CellAttributes {
+                background: self.pen.background,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Erases the columns `start..end` of `row`.
+    fn erase(&mut self, row: u16, start: u16, end: u16) {
+        if self.rows == 0 {
+            return;
+        }
+        let blank = self.blank();
+        let from = self.index(start, row);
+        let to = self.index(end, row);
+        self.cells[from..to].fill(blank);
+        self.wrap_pending = false;
+    }
+
+    fn erase_in_line(&mut self, mode: u16) {
+        let (col, row) = self.cursor;
+        if self.rows == 0 {
+            return;
+        }
+        match mode {
+            0 => self.erase(row, col, self.cols),
+            1 => self.erase(row, 0, (col + 1).min(self.cols)),
+            2 => self.erase(row, 0, self.cols),
+            _ => (),
+        }
+    }
+
+    fn erase_in_display(&mut self, mode: u16) {
+        let (_, row) = self.cursor;
+        match mode {
+            0 => {
+                self.erase_in_line(0);
+                for row in row + 1..self.rows {
+                    self.erase(row, 0, self.cols);
+                }
+            }
+            1 => {
+                for row in 0..row {
+                    self.erase(row, 0, self.cols);
+                }
+                self.erase_in_line(1);
+            }
+            2 | 3 => {
+                for row in 0..self.rows {
+                    self.erase(row, 0, self.cols);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn insert_chars(&mut self, count: u16) {
+        if self.rows == 0 {
+            return;
+        }
+        let (col, row) = self.cursor;
+        let blank = self.blank();
+        let start = self.index(col, row);
+        let end = self.index(0, row) + self.cols as usize;
+        let line = &mut self.cells[start..end];
+        let count = (count as usize).min(line.len());
+        line.rotate_right(count);
+        line[..count].fill(blank);
+        self.wrap_pending = false;
+    }
+
+    fn delete_chars(&mut self, count: u16) {
+        if self.rows == 0 {
+            return;
+        }
+        let (col, row) = self.cursor;
+        let blank = self.blank();
+        let start = self.index(col, row);
+        let end = self.index(0, row) + self.cols as usize;
+        let line = &mut self.cells[start..end];
+        let count = (count as usize).min(line.len());
+        line.rotate_left(count);
+        let len = line.len();
+        line[len - count..].fill(blank);
+        self.wrap_pending = false;
+    }
+
+    fn insert_lines(&mut self, count: u16) {
+        self.scroll_down(self.cursor.1, count);
+        self.move_to(0, self.cursor.1);
+    }
+
+    fn delete_lines(&mut self, count: u16) {
+        self.scroll_up(self.cursor.1, count);
+        self.move_to(0, self.cursor.1);
+    }
+
+    /// Moves the rows from `top` down up by `count` rows, blanking the rows at the bottom.
+    fn scroll_up(&mut self, top: u16, count: u16) {
+        if top >= self.rows {
+            return;
+        }
+        let blank = self.blank();
+        let region = &mut self.cells[top as usize * self.cols as usize..];
+        let count = (count as usize * self.cols as usize).min(region.len());
+        region.rotate_left(count);
+        let len = region.len();
+        region[len - count..].fill(blank);
+    }
+
+    /// Moves the rows from `top` down down by `count` rows, blanking the rows at the top.
+    fn scroll_down(&mut self, top: u16, count: u16) {
+        if top >= self.rows {
+            return;
+        }
+        let blank = self.blank();
+        let region = &mut self.cells[top as usize * self.cols as usize..];
+        let count = (count as usize * self.cols as usize).min(region.len());
+        region.rotate_right(count);
+        region[..count].fill(blank);
+    }
+}
+
+impl io::Write for Surface {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.advance(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Splits CSI parameter bytes into parameters and their colon-separated sub-parameters.
+///
+/// Missing values are zero and every parameter has at least one value.
+fn parse_params(bytes: &[u8]) -> Vec<Vec<u16>> {
+    if bytes.is_empty() {
+        return Vec::new();
+    }
+    bytes
+        .split(|&byte| byte == b';')
+        .map(|param| {
+            param
+                .split(|&byte| byte == b':')
+                .map(|value| {
+                    value
+                        .iter()
+                        .filter(|byte| byte.is_ascii_digit())
+                        .fold(0u16, |n, digit| {
+                            n.saturating_mul(10).saturating_add((digit - b'0') as u16)
+                        })
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Reads an SGR 38, 48 or 58 color from either the colon form (`38:5:n`, `38:2::r:g:b`) or the
+/// semicolon form (`38;5;n`, `38;2;r;g;b`), consuming the parameters of the semicolon form.
+fn extended_color<'a>(param: &[u16], params: &mut impl Iterator<Item = &'a Vec<u16>>) -> ColorSpec {
+    let channel = |value: u16| value.min(u8::MAX as u16) as u8;
+    if param.len() > 1 {
+        return match param[1] {
+            5 if param.len() > 2 => ColorSpec::PaletteIndex(channel(param[2])),
+            // The color space identifier is optional.
+            2 if param.len() > 5 => {
+                let rgb = &param[param.len() - 3..];
+                RgbColor::new(channel(rgb[0]), channel(rgb[1]), channel(rgb[2])).into()
+            }
+            2 if param.len() == 5 => {
+                RgbColor::new(channel(param[2]), channel(param[3]), channel(param[4])).into()
+            }
+            _ => ColorSpec::Reset,
+        };
+    }
+    let mut next = || params.next().map_or(0, |param| param[0]);
+    match next() {
+        5 => ColorSpec::PaletteIndex(channel(next())),
+        2 => {
+            let (red, green, blue) = (next(), next(), next());
+            RgbColor::new(channel(red), channel(green), channel(blue)).into()
+        }
+        _ => ColorSpec::Reset,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Write as _;
+
+    use super::*;
+    use crate::escape::csi::{Csi, Sgr, SgrAttributes, SgrModifiers};
+
+    #[test]
+    fn text_wraps_and_scrolls() {
+        let mut surface = Surface::new(4, 2);
+        surface.advance(b"abcdef\r\nxy");
+        assert_eq!(surface.screen_text(), "ef\nxy");
+        assert_eq!(surface.cursor(), (2, 1));
+
+        // Writing the last column defers the wrap until the next character.
+        surface.advance(b"\x1b[H\x1b[2Jwxyz");
+        assert_eq!(surface.cursor(), (3, 0));
+        surface.advance(b"\x1b[1G\x1b[2@\x1b[3C\x1b[P");
+        assert_eq!(surface.line_text(0), "  w");
+
+        // Split escape sequences and characters still apply.
+        surface.advance(b"\x1b[2;");
+        surface.advance(b"2H\xe2\x82");
+        surface.advance(b"\xac\x1b[?25l");
+        assert_eq!(surface.line_text(1), " €");
+        assert!(!surface.cursor_visible());
+    }
+
+    #[test]
+    fn degenerate_size() {
+        for (cols, rows) in [(80, 0), (0, 24), (0, 0)] {
+            let mut surface = Surface::new(cols, rows);
+            surface.advance(b"ab\x1b[2@\x1b[2X\x1b[2P\x1b[L\x1b[M\x1b[S\x1b[T\x1b[J\x1b[1J");
+            surface.advance(b"\x1b[K\x1b[1K\x1b[2K\x1b[5;5H\r\n");
+        }
+    }
+
+    #[test]
+    fn sgr_styles_cells() {
+        let mut surface = Surface::new(10, 1);
+        let attributes = SgrAttributes {
+            foreground: Some(RgbColor::new(1, 2, 3).into()),
+            background: Some(ColorSpec::BRIGHT_BLUE),
+            modifiers: SgrModifiers::INTENSITY_BOLD | SgrModifiers::UNDERLINE_CURLY,
+            ..Default::default()
+        };
+        write!(
+            surface,
+            "{}a{}b{}\x1b[38:5:208mc{}\x1b[K",
+            Csi::Sgr(Sgr::Attributes(attributes)),
+            Csi::Sgr(Sgr::Reset),
+            Csi::Sgr(Sgr::Background(ColorSpec::RED)),
+            Csi::Sgr(Sgr::Foreground(ColorSpec::Reset)),
+        )
+        .unwrap();
+
+        let a = surface.cell(0, 0).unwrap().attributes;
+        assert_eq!(a.foreground, RgbColor::new(1, 2, 3).into());
+        assert_eq!(a.background, ColorSpec::BRIGHT_BLUE);
+        assert_eq!(a.intensity, Intensity::Bold);
+        assert_eq!(a.underline, Underline::Curly);
+        assert_eq!(surface.cell(1, 0).unwrap().attributes, Default::default());
+        let c = surface.cell(2, 0).unwrap().attributes;
+        assert_eq!(c.foreground, ColorSpec::PaletteIndex(208));
+        assert_eq!(c.background, ColorSpec::RED);
+        // Erasing keeps the current background.
+        let erased = surface.cell(5, 0).unwrap();
+        assert_eq!(erased.ch, ' ');
+        assert_eq!(erased.attributes.background, ColorSpec::RED);
+        assert_eq!(erased.attributes.foreground, ColorSpec::Reset);
+    }
+}
@@ -0,0 +1,263 @@
+//! termwiz-style [`Change`]s for drawing on a [`Surface`].
+//!
+//! Applications built on termwiz's `Surface` describe frames as a list of changes: text, attribute
+//! changes and cursor movement. This module accepts a simplified version of that list so such
+//! applications can move to Termina by applying their changes with [`Surface::add_changes`] and
+//! presenting the surface with a [`Renderer`](super::Renderer), which writes the minimal escape
+//! sequences for each frame.
+//!
+//! These types are available with the `termwiz-compat` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use termina::{
+//!     style::{ColorSpec, Intensity},
+//!     surface::{AttributeChange, Change, Position, Renderer, Surface},
+//! };
+//!
+//! let mut surface = Surface::new(20, 2);
+//! surface.add_changes([
+//!     Change::ClearScreen(ColorSpec::Reset),
+//!     AttributeChange::Intensity(Intensity::Bold).into(),
+//!     "Title".into(),
+//!     Change::AllAttributes(Default::default()),
+//!     Change::CursorPosition {
+//!         x: Position::Absolute(0),
+//!         y: Position::Relative(1),
+//!     },
+//!     "body".into(),
+//! ]);
+//! assert_eq!(surface.screen_text(), "Title\nbody");
+//!
+//! let mut output = Vec::new();
+//! Renderer::new().render(&surface, &mut output).unwrap();
+//! ```
+//!
+//! # Implementation Notes
+//!
+//! Variant names and semantics follow [termwiz's `Change`][termwiz change] where they overlap.
+//! Text is drawn literally apart from `\r` and `\n`, and `\n` starts a new line like `\r\n` as in
+//! termwiz. Images, hyperlinks, scroll regions and line attributes are not supported.
+//!
+//! [termwiz change]: https://docs.rs/termwiz/latest/termwiz/surface/enum.Change.html
+
+use crate::style::{Blink, ColorSpec, Intensity, Underline};
+
+use super::{CellAttributes, Surface};
+
+/// A position for [`Change::CursorPosition`], along one axis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Position {
+    /// Move relative to the current position. Negative values move left or up.
+    Relative(isize),
+    /// Move to a zero-based position.
+    Absolute(usize),
+    /// Move to a position counted back from the last column or row, so `EndRelative(0)` is the
+    /// last column or row.
+    EndRelative(usize),
+}
+
+/// A change to one attribute of the text drawn next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttributeChange {
+    /// Set the intensity.
+    Intensity(Intensity),
+    /// Set the underline style.
+    Underline(Underline),
+    /// Enable or disable italic text.
+    Italic(bool),
+    /// Set the blink mode.
+    Blink(Blink),
+    /// Enable or disable reverse video.
+    Reverse(bool),
+    /// Enable or disable strikethrough text.
+    StrikeThrough(bool),
+    /// Enable or disable invisible text.
+    Invisible(bool),
+    /// Set the foreground color.
+    Foreground(ColorSpec),
+    /// Set the background color.
+    Background(ColorSpec),
+}
+
+/// A drawing operation applied to a [`Surface`] with [`Surface::add_change`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    /// Change one attribute of the text drawn next.
+    Attribute(AttributeChange),
+    /// Replace all attributes of the text drawn next.
+    AllAttributes(CellAttributes),
+    /// Draw text at the cursor, wrapping at the end of the line.
+    Text(String),
+    /// Blank the screen with the given background and move the cursor to the top left.
+    ClearScreen(ColorSpec),
+    /// Blank the rest of the line with the given background.
+    ClearToEndOfLine(ColorSpec),
+    /// Blank the rest of the line and the lines below it with the given background.
+    ClearToEndOfScreen(ColorSpec),
+    /// Move the cursor.
+    CursorPosition {
+        /// The new column.
+        x: Position,
+        /// The new row.
+        y: Position,
+    },
+    /// Show or hide the cursor.
+    CursorVisibility(bool),
+}
+
+impl From<AttributeChange> for Change {
+    fn from(change: AttributeChange) -> Self {
+        Self::Attribute(change)
+    }
+}
+
+impl From<String> for Change {
+    fn from(text: String) -> Self {
+        Self::Text(text)
+    }
+}
+
+impl From<&str> for Change {
+    fn from(text: &str) -> Self {
+        Self::Text(text.to_string())
+    }
+}
+
+impl Surface {
+    /// Applies a termwiz-style change.
+    pub fn add_change(&mut self, change: impl Into<Change>) {
+        match change.into() {
+            Change::Attribute(change) => {
+                let pen = &mut self.pen;
+                match change {
+                    AttributeChange::Intensity(intensity) => pen.intensity = intensity,
+                    AttributeChange::Underline(underline) => pen.underline = underline,
+                    AttributeChange::Italic(italic) => pen.italic = italic,
+                    AttributeChange::Blink(blink) => pen.blink = blink,
+                    AttributeChange::Reverse(reverse) => pen.reverse = reverse,
+                    AttributeChange::StrikeThrough(strike) => pen.strike_through = strike,
+                    AttributeChange::Invisible(invisible) => pen.invisible = invisible,
+                    AttributeChange::Foreground(color) => pen.foreground = color,
+                    AttributeChange::Background(color) => pen.background = color,
+                }
+            }
+            Change::AllAttributes(attributes) => self.pen = attributes,
+            Change::Text(text) => {
+                for ch in text.chars() {
+                    match ch {
+                        '\r' => self.move_to(0, self.cursor.1),
+                        '\n' => {
+                            self.cursor.0 = 0;
+                            self.line_feed();
+                        }
+                        ch if ch.is_control() => (),
+                        ch => self.print(ch),
+                    }
+                }
+            }
+            Change::ClearScreen(background) => {
+                self.clear_pen(background);
+                self.erase_in_display(2);
+                self.move_to(0, 0);
+            }
+            Change::ClearToEndOfLine(background) => {
+                self.clear_pen(background);
+                self.erase_in_line(0);
+            }
+            Change::ClearToEndOfScreen(background) => {
+                self.clear_pen(background);
+                self.erase_in_display(0);
+            }
+            Change::CursorPosition { x, y } => {
+                let col = resolve(x, self.cursor.0, self.cols);
+                let row = resolve(y, self.cursor.1, self.rows);
+                self.move_to(col, row);
+            }
+            Change::CursorVisibility(visible) => self.cursor_visible = visible,
+        }
+    }
+
+    /// Applies termwiz-style changes in order.
+    pub fn add_changes<I>(&mut self, changes: I)
+    where
+        I: IntoIterator<Item = Change>,
+    {
+        for change in changes {
+            self.add_change(change);
+        }
+    }
+
+    /// Resets the attributes to the defaults with the given background, as termwiz does before
+    /// clearing.
+    fn clear_pen(&mut self, background: ColorSpec) {
+        self.pen = CellAttributes {
+            background,
+            ..Default::default()
+        };
+    }
+}
+
+/// Resolves `position` along an axis of length `len` where the cursor is at `current`.
+fn resolve(position: Position, current: u16, len: u16) -> u16 {
+    let last = len.saturating_sub(1) as isize;
+    let target = match position {
+        Position::Relative(delta) => current as isize + delta,
+        Position::Absolute(n) => n.min(u16::MAX as usize) as isize,
+        Position::EndRelative(n) => last - n.min(u16::MAX as usize) as isize,
+    };
+    target.clamp(0, last) as u16
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::surface::Renderer;
+
+    #[test]
+    fn apply_changes() {
+        let mut surface = Surface::new(6, 3);
+        let red = AttributeChange::Foreground(ColorSpec::RED);
+        surface.add_changes([
+            "one\ntwo".into(),
+            Change::CursorPosition {
+                x: Position::EndRelative(1),
+                y: Position::Relative(-1),
+            },
+            red.into(),
+            "xyz".into(),
+            Change::CursorPosition {
+                x: Position::Relative(-2),
+                y: Position::EndRelative(0),
+            },
+            Change::ClearToEndOfLine(ColorSpec::BLUE),
+            Change::CursorVisibility(false),
+        ]);
+        assert_eq!(surface.screen_text(), "one xy\nzwo\n");
+        assert_eq!(
+            surface.cell(4, 0).unwrap().attributes.foreground,
+            ColorSpec::RED
+        );
+        assert_eq!(
+            surface.cell(5, 2).unwrap().attributes.background,
+            ColorSpec::BLUE
+        );
+        assert_eq!(surface.cursor(), (0, 2));
+        assert_eq!(surface.attributes().foreground, ColorSpec::Reset);
+
+        // The renderer presents the same screen.
+        let mut output = Vec::new();
+        Renderer::new().render(&surface, &mut output).unwrap();
+        let mut screen = Surface::new(6, 3);
+        screen.advance(&output);
+        for row in 0..3 {
+            assert_eq!(screen.line(row), surface.line(row));
+        }
+        assert!(!screen.cursor_visible());
+
+        surface.add_change(Change::ClearScreen(ColorSpec::Reset));
+        assert_eq!(surface.screen_text(), "\n\n");
+        assert_eq!(surface.cursor(), (0, 0));
+    }
+}
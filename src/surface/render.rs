@@ -0,0 +1,341 @@
+//! Minimal escape sequence output for presenting a [`Surface`].
+//!
+//! A [`Renderer`] remembers what the terminal currently shows and, given the next frame, writes
+//! only what changed: rows which are unchanged are skipped, the cursor is moved with whichever of
+//! an absolute position, relative movement or rewriting of the cells in between is shortest, and
+//! SGR sequences are only written when the style changes between cells.
+//!
+//! # Examples
+//!
+//! ```
+//! use termina::surface::{CellAttributes, Renderer, Surface};
+//!
+//! let mut frame = Surface::new(10, 2);
+//! let mut renderer = Renderer::new();
+//! let mut output = Vec::new();
+//!
+//! frame.put_str(0, 0, "hello", CellAttributes::default());
+//! renderer.render(&frame, &mut output).unwrap();
+//!
+//! // Only the changed cell is drawn in the next frame.
+//! output.clear();
+//! frame.put_str(0, 0, "j", CellAttributes::default());
+//! frame.set_cursor(5, 0);
+//! renderer.render(&frame, &mut output).unwrap();
+//! assert_eq!(output, b"j\x1b[4C");
+//! ```
+//!
+//! # Implementation Notes
+//!
+//! The approach follows the screen update optimizations of [ncurses] and termwiz's
+//! [`Surface::diff_screens`][termwiz diff]: compare the previous and next frame row by row, emit
+//! changed runs of cells and clear blank line endings with EL instead of writing spaces. Trailing
+//! blanks are only erased when they use the default background, so terminals without
+//! "background color erase" draw the same result.
+//!
+//! [ncurses]: https://invisible-island.net/ncurses/hackguide.html#output
+//! [termwiz diff]: https://docs.rs/termwiz/latest/termwiz/surface/struct.Surface.html#method.diff_screens
+
+use std::{fmt::Write as _, io};
+
+use crate::{
+    escape::csi::{
+        Csi, Cursor, DecPrivateMode, DecPrivateModeCode, Edit, EraseInDisplay, EraseInLine, Mode,
+        Sgr,
+    },
+    style::ColorSpec,
+    OneBased,
+};
+
+use super::{Cell, CellAttributes, Surface};
+
+/// The fewest changed blank cells at the end of a row which are cleared with EL rather than
+/// written as spaces.
+const MIN_ERASE: usize = 4;
+
+/// Draws [`Surface`] frames to a terminal, writing only the differences from the last frame.
+///
+/// The renderer assumes that nothing else writes to the terminal between frames. After other
+/// output, or when the terminal contents are otherwise unknown, call [`Self::invalidate`] so the
+/// next frame is drawn in full. Changing the dimensions of the frame also redraws it in full.
+///
+/// Output is expected to be written in raw mode and is best wrapped in
+/// [`Terminal::synchronized`](crate::Terminal::synchronized) so the terminal presents each frame
+/// at once.
+#[derive(Debug, Clone)]
+pub struct Renderer {
+    /// The cells the terminal shows.
+    front: Surface,
+    invalidated: bool,
+    state: TerminalState,
+}
+
+/// What the renderer knows about the terminal. `None` means unknown.
+#[derive(Debug, Clone, Default)]
+struct TerminalState {
+    cursor: Option<(u16, u16)>,
+    pen: Option<CellAttributes>,
+    cursor_visible: Option<bool>,
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Renderer {
+    /// Creates a renderer which clears the screen and draws the first frame in full.
+    pub fn new() -> Self {
+        Self {
+            front: Surface::new(0, 0),
+            invalidated: true,
+            state: TerminalState::default(),
+        }
+    }
+
+    /// Forgets the terminal contents so the next frame is drawn in full.
+    pub fn invalidate(&mut self) {
+        self.invalidated = true;
+        self.state = TerminalState::default();
+    }
+
+    /// Writes the escape sequences which change the terminal from the last frame to `next`.
+    ///
+    /// The cells, cursor position and cursor visibility of `next` are presented. If writing fails
+    /// the renderer is invalidated, since the terminal may have received part of the frame.
+    pub fn render(&mut self, next: &Surface, writer: &mut impl io::Write) -> io::Result<()> {
+        let mut out = String::new();
+        if self.invalidated || (self.front.cols, self.front.rows) != (next.cols, next.rows) {
+            let _ = write!(
+                out,
+                "{}{}",
+                Csi::Sgr(Sgr::Reset),
+                Csi::Edit(Edit::EraseInDisplay(EraseInDisplay::EraseDisplay))
+            );
+            self.front = Surface::new(next.cols, next.rows);
+            // A resized terminal may have moved the cursor.
+            self.state.cursor = None;
+            self.state.pen = Some(CellAttributes::default());
+            self.invalidated = false;
+        }
+
+        for row in 0..next.rows {
+            let old = self.front.line(row);
+            let new = next.line(row);
+            if old != new {
+                self.state.render_line(&mut out, row, old, new);
+            }
+        }
+
+        let (col, row) = next.cursor;
+        if next.rows > 0 && next.cols > 0 {
+            self.state.move_to(&mut out, col, row, next.line(row));
+        }
+        if self.state.cursor_visible != Some(next.cursor_visible) {
+            let show_cursor = DecPrivateMode::Code(DecPrivateModeCode::ShowCursor);
+            let mode = if next.cursor_visible {
+                Mode::SetDecPrivateMode(show_cursor)
+            } else {
+                Mode::ResetDecPrivateMode(show_cursor)
+            };
+            let _ = write!(out, "{}", Csi::Mode(mode));
+            self.state.cursor_visible = Some(next.cursor_visible);
+        }
+
+        if let Err(err) = writer.write_all(out.as_bytes()) {
+            self.invalidate();
+            return Err(err);
+        }
+        self.front.cells.clone_from(&next.cells);
+        Ok(())
+    }
+}
+
+impl TerminalState {
+    fn render_line(&mut self, out: &mut String, row: u16, old: &[Cell], new: &[Cell]) {
+        let Some(start) = old.iter().zip(new).position(|(old, new)| old != new) else {
+            return;
+        };
+        let end = old
+            .iter()
+            .zip(new)
+            .rposition(|(old, new)| old != new)
+            .unwrap()
+            + 1;
+
+        // Changed cells at the end of the row which are blank can be erased in one sequence.
+        let blank = Cell::default();
+        let blank_from = new
+            .iter()
+            .rposition(|cell| *cell != blank)
+            .map_or(0, |col| col + 1);
+        let erase_from = blank_from.max(start);
+        let draw_end = if end > erase_from && end - erase_from >= MIN_ERASE {
+            erase_from
+        } else {
+            end
+        };
+
+        for col in start..draw_end {
+            if old[col] == new[col] {
+                continue;
+            }
+            self.move_to(out, col as u16, row, new);
+            self.set_pen(out, new[col].attributes);
+            out.push(new[col].ch);
+            self.cursor = if col + 1 < new.len() {
+                Some((col as u16 + 1, row))
+            } else {
+                // The cursor is in the last column with a wrap pending.
+                None
+            };
+        }
+
+        if draw_end < end {
+            self.move_to(out, draw_end as u16, row, new);
+            let pen = self.pen.unwrap_or_default();
+            self.set_pen(
+                out,
+                CellAttributes {
+                    background: ColorSpec::Reset,
+                    ..pen
+                },
+            );
+            let _ = write!(
+                out,
+                "{}",
+                Csi::Edit(Edit::EraseInLine(EraseInLine::EraseToEndOfLine))
+            );
+        }
+    }
+
+    /// Moves the cursor to `(col, row)`, where `line` holds the next contents of `row`.
+    fn move_to(&mut self, out: &mut String, col: u16, row: u16, line: &[Cell]) {
+        if self.cursor == Some((col, row)) {
+            return;
+        }
+        let mut best = Csi::Cursor(Cursor::Position {
+            line: OneBased::from_zero_based(row),
+            col: OneBased::from_zero_based(col),
+        })
+        .to_string();
+        if let Some((from_col, from_row)) = self.cursor {
+            let mut consider = |candidate: String| {
+                if candidate.len() < best.len() {
+                    best = candidate;
+                }
+            };
+            let vertical = if row < from_row {
+                Csi::Cursor(Cursor::Up((from_row - row) as u32)).to_string()
+            } else if row > from_row {
+                Csi::Cursor(Cursor::Down((row - from_row) as u32)).to_string()
+            } else {
+                String::new()
+            };
+            let horizontal = if col < from_col {
+                Csi::Cursor(Cursor::Left((from_col - col) as u32)).to_string()
+            } else if col > from_col {
+                Csi::Cursor(Cursor::Right((col - from_col) as u32)).to_string()
+            } else {
+                String::new()
+            };
+            consider(format!("{vertical}{horizontal}"));
+            if col == 0 {
+                consider(format!("\r{vertical}"));
+            }
+            // Writing the cells in between moves the cursor too if they already have the
+            // current style.
+            if row == from_row && col > from_col {
+                let skipped = &line[from_col as usize..col as usize];
+                if skipped.iter().all(|cell| Some(cell.attributes) == self.pen) {
+                    consider(skipped.iter().map(|cell| cell.ch).collect());
+                }
+            }
+        }
+        out.push_str(&best);
+        self.cursor = Some((col, row));
+    }
+
+    fn set_pen(&mut self, out: &mut String, attributes: CellAttributes) {
+        if self.pen == Some(attributes) {
+            return;
+        }
+        let _ = match self.pen {
+            Some(pen) => write!(out, "{}", pen.diff(&attributes)),
+            None => write!(out, "{}", attributes.reset_to()),
+        };
+        self.pen = Some(attributes);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::style::{Intensity, RgbColor};
+
+    /// Renders `frame` and checks that a terminal showing `screen` then shows `frame`.
+    fn present(renderer: &mut Renderer, screen: &mut Surface, frame: &Surface) -> Vec<u8> {
+        let mut output = Vec::new();
+        renderer.render(frame, &mut output).unwrap();
+        screen.advance(&output);
+        for row in 0..frame.rows() {
+            assert_eq!(screen.line(row), frame.line(row), "row {row} differs");
+        }
+        assert_eq!(screen.cursor(), frame.cursor());
+        assert_eq!(screen.cursor_visible(), frame.cursor_visible());
+        output
+    }
+
+    #[test]
+    fn renders_differences() {
+        let mut renderer = Renderer::new();
+        let mut screen = Surface::new(12, 3);
+        // Start from a screen with leftover contents.
+        screen.advance(b"\x1b[41mgarbage\x1b[2;1Hmore");
+
+        let red = CellAttributes {
+            foreground: ColorSpec::RED,
+            ..Default::default()
+        };
+        let bold = CellAttributes {
+            intensity: Intensity::Bold,
+            background: RgbColor::new(1, 2, 3).into(),
+            ..Default::default()
+        };
+        let mut frame = Surface::new(12, 3);
+        frame.put_str(0, 0, "error:", red);
+        frame.put_str(7, 0, "bad", bold);
+        frame.put_str(0, 2, "last line!!!", CellAttributes::default());
+        frame.set_cursor(3, 1);
+        present(&mut renderer, &mut screen, &frame);
+
+        // Unchanged rows and cells are skipped and the style is only set once.
+        frame.put_str(1, 2, "ost", CellAttributes::default());
+        assert_eq!(
+            present(&mut renderer, &mut screen, &frame),
+            b"\x1b[3;2Ho\x1b[2;4H"
+        );
+
+        // Blank line endings are erased.
+        frame.put_str(4, 2, "        ", CellAttributes::default());
+        frame.set_cursor_visible(false);
+        frame.set_cursor(5, 2);
+        assert_eq!(
+            present(&mut renderer, &mut screen, &frame),
+            b"\x1b[3;6H\x1b[0K\x1b[?25l"
+        );
+
+        frame.put_str(0, 1, "x", bold);
+        frame.put_str(8, 1, "y", red);
+        present(&mut renderer, &mut screen, &frame);
+        frame.put_str(0, 1, "a", red);
+        present(&mut renderer, &mut screen, &frame);
+
+        // Resizing redraws the whole frame.
+        let mut frame = Surface::new(5, 2);
+        frame.put_str(0, 1, "small", bold);
+        let mut screen = Surface::new(5, 2);
+        present(&mut renderer, &mut screen, &frame);
+    }
+}
@@ -35,24 +35,53 @@
 //! }
 //! ```
 
+#[cfg(unix)]
+mod memory;
 #[cfg(unix)]
 mod unix;
 
+mod split;
 #[cfg(windows)]
 mod windows;
+mod writer;
 
-use std::{io, time::Duration};
+use std::{
+    fmt, io,
+    ops::{Deref, DerefMut, RangeInclusive},
+    sync::{
+        atomic::{AtomicBool, AtomicU32, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 
+#[cfg(all(test, unix))]
+pub(crate) use memory::test_terminal;
+#[cfg(unix)]
+pub use memory::MemoryTerminal;
 #[cfg(unix)]
 pub use unix::*;
 
+pub use split::{TerminalInput, TerminalOutput};
 #[cfg(windows)]
 pub use windows::*;
+pub use writer::TerminalWriter;
 
-use crate::{Event, EventReader, WindowSize};
+use crate::{
+    escape::{
+        csi::{
+            Csi, Cursor, DecModeSetting, DecPrivateMode, DecPrivateModeCode, Device, Edit, Mode,
+            ThemeMode, Window,
+        },
+        esc::Esc,
+        osc::{ColorOrQuery, DynamicColorNumber, Osc},
+    },
+    style::{CursorStyle, RgbColor},
+    Event, EventReader, OneBased, WindowSize,
+};
 
 #[cfg(doc)]
-use crate::escape::csi::{DecPrivateModeCode, Keyboard};
+use crate::escape::csi::Keyboard;
 
 /// The terminal implementation for the current platform.
 ///
@@ -71,6 +100,21 @@ pub type PlatformHandle = FileDescriptor;
 #[cfg(windows)]
 pub type PlatformHandle = OutputHandle;
 
+/// The standard stream a terminal writes its output to.
+///
+/// Writing to stderr keeps stdout free for data, so a tool can draw its interface while its output
+/// is piped elsewhere. If the chosen stream is not a terminal, output goes to the controlling
+/// terminal instead (`/dev/tty` on Unix, `CONOUT$` on Windows).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum OutputTarget {
+    /// The process's standard output.
+    #[default]
+    Stdout,
+    /// The process's standard error.
+    Stderr,
+}
+
 /// Platform-agnostic terminal I/O surface.
 ///
 /// The trait is implemented by the Unix and Windows backends and also requires [`io::Write`], so a
@@ -105,13 +149,65 @@ pub trait Terminal: io::Write {
     /// Cooked mode is the normal shell-facing mode for a terminal device. The terminal driver
     /// handles echo, line editing, and Enter-delimited input before passing data to the
     /// application. On Unix, this restores the termios state captured when the terminal was opened.
-    /// On Windows, this switches the console input flags back to cooked behavior, but leaves other
-    /// captured state, such as code pages and virtual-terminal flags, for drop-time cleanup.
+    /// On Windows, this restores the console input and output modes captured by the last
+    /// [`Self::enter_raw_mode`] call, except that virtual-terminal input is returned to its setting
+    /// from when the terminal was opened. Code pages are left for drop-time cleanup. Switching
+    /// between raw and cooked mode repeatedly is lossless on both platforms.
+    ///
+    /// Modes tracked by helpers such as [`Self::enable_line_wrapping`] are returned to their
+    /// defaults while in cooked mode and set again by the next [`Self::enter_raw_mode`].
     fn enter_cooked_mode(&mut self) -> io::Result<()>;
 
+    /// Returns `true` between [`Self::enter_raw_mode`] and [`Self::enter_cooked_mode`].
+    ///
+    /// [`Self::release`] uses this to return the terminal to the same mode when it is reacquired.
+    /// The default implementation returns `true`.
+    fn is_raw_mode(&self) -> bool {
+        true
+    }
+
     /// Reads the current terminal window dimensions.
+    ///
+    /// Returns an error if the platform reports a degenerate size with zero rows or columns. On
+    /// Unix, the `LINES` and `COLUMNS` environment variables are tried before failing. Resize
+    /// events are still delivered for degenerate sizes; see [`WindowSize::is_degenerate`].
     fn get_dimensions(&self) -> io::Result<WindowSize>;
 
+    /// Returns the terminal window dimensions without asking the platform when they are known.
+    ///
+    /// The event reader remembers the size from the latest [`Event::WindowResized`] it read, as
+    /// soon as it reads it and even while the event is buffered or held back by
+    /// [`EventReader::set_resize_debounce`]. When no size is known this asks
+    /// [`Self::get_dimensions`] and remembers the answer. Render loops can call this every frame
+    /// instead of making an `ioctl` or console call each time.
+    ///
+    /// The remembered size is forgotten for a degenerate resize, after the process resumes from
+    /// being stopped (`SIGCONT`) and by [`Self::hard_reset`] and [`Self::soft_reset`]. Resizes
+    /// are only noticed once the reader reads them, so an application which doesn't read events
+    /// should call [`Self::get_dimensions`] instead.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::io;
+    ///
+    /// use termina::{Event, PlatformTerminal, Terminal};
+    ///
+    /// fn main() -> io::Result<()> {
+    ///     let terminal = PlatformTerminal::new()?;
+    ///     loop {
+    ///         let size = terminal.dimensions_cached()?;
+    ///         println!("{}x{}", size.cols, size.rows);
+    ///         if let Event::Key(_) = terminal.read(|_| true)? {
+    ///             break Ok(());
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    fn dimensions_cached(&self) -> io::Result<WindowSize> {
+        self.event_reader().cached_size_or(|| self.get_dimensions())
+    }
+
     /// Returns a cloneable event reader backed by the terminal input handle.
     fn event_reader(&self) -> EventReader;
 
@@ -134,7 +230,1446 @@ pub trait Terminal: io::Write {
     /// disabling [`DecPrivateModeCode::BracketedPaste`] or returning from
     /// [`DecPrivateModeCode::ClearAndEnableAlternateScreen`] to the main screen.
     ///
-    /// The hook receives a [`PlatformHandle`] for stdout or the platform console output. After the
+    /// The hook receives a [`PlatformHandle`] for the terminal's output. After the
     /// hook runs, Termina restores the platform mode as if [`Self::enter_cooked_mode`] had run.
     fn set_panic_hook(&mut self, f: impl Fn(&mut PlatformHandle) + Send + Sync + 'static);
+
+    /// Enables or disables automatic line wrapping ([`DecPrivateModeCode::AutoWrap`]).
+    ///
+    /// Applications which draw into the last column (status lines, full-width rows) commonly
+    /// disable wrapping so that the cursor doesn't scroll the screen. Unlike writing the mode
+    /// sequence directly, this setting is tracked: if wrapping is disabled when the terminal is
+    /// dropped, when the panic hook installed by [`Self::set_panic_hook`] runs, or while the
+    /// terminal is in cooked mode, Termina enables it so the user's shell isn't left without
+    /// wrapping.
+    ///
+    /// The sequence is buffered like other output; flush the terminal to send it. The default
+    /// implementation writes the sequence without tracking it.
+    fn enable_line_wrapping(&mut self, enabled: bool) -> io::Result<()> {
+        write_line_wrapping(self, enabled)
+    }
+
+    /// Enables or disables [grapheme clustering mode][`DecPrivateModeCode::GraphemeClustering`]
+    /// (mode 2027), in which the terminal advances the cursor once per grapheme cluster.
+    ///
+    /// Like [`Self::enable_line_wrapping`] this setting is tracked: while it is enabled, Termina
+    /// disables it when the terminal is dropped, when the panic hook runs and while the terminal
+    /// is in cooked mode, so that the shell measures text as it expects. [`crate::width::detect`]
+    /// enables it on terminals which support it.
+    ///
+    /// The sequence is buffered like other output; flush the terminal to send it. The default
+    /// implementation writes the sequence without tracking it.
+    fn enable_grapheme_clustering(&mut self, enabled: bool) -> io::Result<()> {
+        write_grapheme_clustering(self, enabled)
+    }
+
+    /// Restricts scrolling to the zero-based, inclusive range of `rows` with
+    /// [`Cursor::SetTopAndBottomMargins`] (DECSTBM), or scrolls the whole screen again when `rows`
+    /// is `None`.
+    ///
+    /// Like [`Self::enable_line_wrapping`] the region is tracked: it is removed when the terminal
+    /// is dropped, when the panic hook installed by [`Self::set_panic_hook`] runs, and while the
+    /// terminal is in cooked mode, and it is set again by the next [`Self::enter_raw_mode`].
+    /// Setting the region moves the cursor to the top left of the screen. Returns an
+    /// [`io::ErrorKind::InvalidInput`] error if the range is empty.
+    ///
+    /// See [`Self::scroll_region`] for a guard which removes the region when it is dropped. The
+    /// sequence is buffered like other output; flush the terminal to send it. The default
+    /// implementation writes the sequence without tracking it.
+    fn set_scroll_region(&mut self, rows: Option<RangeInclusive<u16>>) -> io::Result<()> {
+        write_scroll_region(self, scroll_region_rows(rows)?)
+    }
+
+    /// Saves the window and icon titles on the terminal's title stack with
+    /// [`Window::PushIconAndWindowTitle`] (XTWINOPS 22).
+    ///
+    /// Pair it with [`Self::pop_title`] to restore the user's title after changing it with
+    /// [`Self::set_title`]. This does nothing if the terminal has no title stack, see
+    /// [`Self::set_title_stack_supported`]. The sequence is buffered like other output; flush the
+    /// terminal to send it.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::io::{self, Write as _};
+    ///
+    /// use termina::{PlatformTerminal, Terminal};
+    ///
+    /// fn main() -> io::Result<()> {
+    ///     let mut terminal = PlatformTerminal::new()?;
+    ///     terminal.push_title()?;
+    ///     terminal.set_title("editing - termina")?;
+    ///     terminal.flush()?;
+    ///     // ...
+    ///     terminal.pop_title()?;
+    ///     terminal.flush()
+    /// }
+    /// ```
+    ///
+    /// The default implementation always writes the sequence.
+    fn push_title(&mut self) -> io::Result<()> {
+        write!(
+            self,
+            "{}",
+            Csi::Window(Box::new(Window::PushIconAndWindowTitle))
+        )
+    }
+
+    /// Restores the window and icon titles saved by [`Self::push_title`] with
+    /// [`Window::PopIconAndWindowTitle`] (XTWINOPS 23).
+    ///
+    /// This does nothing if the terminal has no title stack, see
+    /// [`Self::set_title_stack_supported`]. The sequence is buffered like other output; flush the
+    /// terminal to send it. The default implementation always writes the sequence.
+    fn pop_title(&mut self) -> io::Result<()> {
+        write!(
+            self,
+            "{}",
+            Csi::Window(Box::new(Window::PopIconAndWindowTitle))
+        )
+    }
+
+    /// Sets whether [`Self::push_title`] and [`Self::pop_title`] write their sequences.
+    ///
+    /// Terminals have no way to report a title stack, so the default is detected from the
+    /// environment: the Linux console and terminals with `TERM=dumb` are assumed to have none, and
+    /// all others to have one. Override the detection for terminals known to misbehave. The
+    /// default implementation does nothing.
+    fn set_title_stack_supported(&mut self, supported: bool) {
+        let _ = supported;
+    }
+
+    /// Flushes buffered output and sets the capacity of the output buffer in bytes.
+    ///
+    /// Output is only written to the terminal when the buffer fills up or is flushed, so a buffer
+    /// which holds a whole frame lets a redraw reach the terminal in one write. The default is
+    /// 4 KiB on Unix and 64 KiB on Windows, where each write is comparatively expensive. A
+    /// capacity of zero writes output straight through. The default implementation only flushes.
+    fn set_output_buffer_capacity(&mut self, capacity: usize) -> io::Result<()> {
+        let _ = capacity;
+        self.flush()
+    }
+
+    /// Brings the terminal back in line with the state Termina tracks for it.
+    ///
+    /// A child process such as a spawned shell may change the terminal mode, leave modes set and
+    /// leave unread input behind. This discards events buffered in the [`EventReader`] along with
+    /// any partially parsed sequence and input which hasn't been read yet, switches the platform
+    /// mode back to raw or cooked mode, whichever the terminal was in, and writes the modes tracked
+    /// by helpers such as [`Self::enable_line_wrapping`] again. On Windows the UTF-8 code pages and
+    /// virtual terminal output processing are enabled again as well.
+    ///
+    /// [`Self::hard_reset`] and [`Self::soft_reset`] call this after resetting the terminal. The
+    /// default implementation flushes the output and discards the reader's buffered events.
+    fn resync(&mut self) -> io::Result<()> {
+        self.flush()?;
+        self.event_reader().clear();
+        Ok(())
+    }
+
+    /// Restricts scrolling to the zero-based, inclusive range of `rows` until the returned guard
+    /// is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::io::{self, Write as _};
+    ///
+    /// use termina::{PlatformTerminal, Terminal};
+    ///
+    /// fn main() -> io::Result<()> {
+    ///     let mut terminal = PlatformTerminal::new()?;
+    ///     let rows = terminal.get_dimensions()?.rows;
+    ///     // Keep a status line at the bottom of the screen while the rows above scroll.
+    ///     let mut region = terminal.scroll_region(0..=rows - 2)?;
+    ///     region.scroll_up(1)?;
+    ///     region.move_to(rows - 2, 0)?;
+    ///     write!(region, "a new line")?;
+    ///     region.end()?;
+    ///     terminal.flush()
+    /// }
+    /// ```
+    fn scroll_region(&mut self, rows: RangeInclusive<u16>) -> io::Result<ScrollRegion<'_, Self>>
+    where
+        Self: Sized,
+    {
+        ScrollRegion::new(self, rows)
+    }
+
+    /// Moves the cursor to the zero-based `row` and `col` with [`Cursor::Position`].
+    ///
+    /// The sequence is buffered like other output; flush the terminal to send it.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::io::{self, Write as _};
+    ///
+    /// use termina::{style::CursorStyle, PlatformTerminal, Terminal};
+    ///
+    /// fn main() -> io::Result<()> {
+    ///     let mut terminal = PlatformTerminal::new()?;
+    ///     terminal.hide_cursor()?;
+    ///     terminal.move_to(4, 0)?;
+    ///     write!(terminal, "Loading...")?;
+    ///     terminal.set_cursor_style(CursorStyle::SteadyBar)?;
+    ///     terminal.show_cursor()?;
+    ///     terminal.flush()
+    /// }
+    /// ```
+    fn move_to(&mut self, row: u16, col: u16) -> io::Result<()> {
+        let position = Cursor::Position {
+            line: OneBased::from_zero_based(row.min(u16::MAX - 1)),
+            col: OneBased::from_zero_based(col.min(u16::MAX - 1)),
+        };
+        write!(self, "{}", Csi::Cursor(position))
+    }
+
+    /// Hides the cursor by resetting [`DecPrivateModeCode::ShowCursor`].
+    ///
+    /// The sequence is buffered like other output; flush the terminal to send it.
+    fn hide_cursor(&mut self) -> io::Result<()> {
+        let mode = DecPrivateMode::Code(DecPrivateModeCode::ShowCursor);
+        write!(self, "{}", Csi::Mode(Mode::ResetDecPrivateMode(mode)))
+    }
+
+    /// Shows the cursor by setting [`DecPrivateModeCode::ShowCursor`].
+    ///
+    /// The sequence is buffered like other output; flush the terminal to send it.
+    fn show_cursor(&mut self) -> io::Result<()> {
+        let mode = DecPrivateMode::Code(DecPrivateModeCode::ShowCursor);
+        write!(self, "{}", Csi::Mode(Mode::SetDecPrivateMode(mode)))
+    }
+
+    /// Sets the cursor shape and blinking with DECSCUSR, see [`Cursor::CursorStyle`].
+    ///
+    /// [`CursorStyle::Default`] returns to the shape configured in the terminal. The sequence is
+    /// buffered like other output; flush the terminal to send it.
+    fn set_cursor_style(&mut self, style: CursorStyle) -> io::Result<()> {
+        write!(self, "{}", Csi::Cursor(Cursor::CursorStyle(style)))
+    }
+
+    /// Sets the window and icon titles with [`Osc::SetIconNameAndWindowTitle`] (OSC 0).
+    ///
+    /// Setting both titles keeps the window and tab titles in agreement on terminals which
+    /// distinguish them. See [`Self::push_title`] to restore the previous title later. The
+    /// sequence is buffered like other output; flush the terminal to send it.
+    fn set_title(&mut self, title: &str) -> io::Result<()> {
+        write!(self, "{}", Osc::SetIconNameAndWindowTitle(title))
+    }
+
+    /// Resets the terminal to its initial state with [`Esc::FullReset`] (RIS) and calls
+    /// [`Self::resync`].
+    ///
+    /// Use this to recover after a child process left the terminal in an unknown state. Most
+    /// terminals clear the screen, and many also the scrollback, so redraw afterwards.
+    /// Application-level modes such as the alternate screen are reset as well and must be entered
+    /// again. The output is flushed.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::{io, process::Command};
+    ///
+    /// use termina::{PlatformTerminal, Terminal};
+    ///
+    /// fn main() -> io::Result<()> {
+    ///     let mut terminal = PlatformTerminal::new()?;
+    ///     terminal.enter_raw_mode()?;
+    ///     // ...
+    ///     terminal.enter_cooked_mode()?;
+    ///     Command::new("sh").status()?;
+    ///     terminal.enter_raw_mode()?;
+    ///     terminal.hard_reset()
+    /// }
+    /// ```
+    fn hard_reset(&mut self) -> io::Result<()> {
+        write!(self, "{}", Esc::FullReset)?;
+        self.flush()?;
+        self.resync()
+    }
+
+    /// Resets modes such as the cursor visibility, character attributes and scroll margins with
+    /// [`Device::SoftReset`] (DECSTR) and calls [`Self::resync`].
+    ///
+    /// Unlike [`Self::hard_reset`] this keeps the screen contents. The output is flushed.
+    fn soft_reset(&mut self) -> io::Result<()> {
+        write!(self, "{}", Csi::Device(Device::SoftReset))?;
+        self.flush()?;
+        self.resync()
+    }
+
+    /// Hands the terminal over to a child process, such as a shell or a commit message editor,
+    /// until [`ReleasedTerminal::reacquire`] is called.
+    ///
+    /// This flushes the output, enters cooked mode and stops the [`EventReader`] and its clones
+    /// from reading input with [`EventReader::pause`], so that a read blocked on another thread,
+    /// for example the helper thread of an `EventStream`, doesn't take the child's input. Events
+    /// which were already read stay buffered. Reacquiring the terminal enters raw mode again if
+    /// it was in raw mode when released, which discards input the child left unread on Unix, and
+    /// resumes reading input.
+    ///
+    /// Application-level modes such as the alternate screen are not tracked by the terminal. Use
+    /// [`Session::release`](crate::Session::release) to also leave and re-enter those, or write
+    /// them around this call.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::{io, process::Command};
+    ///
+    /// use termina::{PlatformTerminal, Terminal};
+    ///
+    /// fn main() -> io::Result<()> {
+    ///     let mut terminal = PlatformTerminal::new()?;
+    ///     terminal.enter_raw_mode()?;
+    ///     // ...
+    ///     let released = terminal.release()?;
+    ///     let status = Command::new("git").arg("commit").status();
+    ///     released.reacquire()?;
+    ///     println!("git exited with {:?}", status?.code());
+    ///     Ok(())
+    /// }
+    /// ```
+    fn release(&mut self) -> io::Result<ReleasedTerminal<'_, Self>>
+    where
+        Self: Sized,
+    {
+        let raw = self.is_raw_mode();
+        ReleasedTerminal::new(self, "", String::new(), raw)
+    }
+
+    /// Restores the terminal and closes it, reporting any failure.
+    ///
+    /// Dropping a terminal performs the same teardown: flushing buffered output, restoring the
+    /// modes changed through `Terminal` helpers, and returning the terminal to the state captured
+    /// when it was opened. `Drop` has to ignore errors though, for example when the terminal hung
+    /// up and writes fail with `EIO`. Call this instead to report them. Every teardown step runs
+    /// even if an earlier one fails, and the first error is returned.
+    ///
+    /// The default implementation flushes the output, reporting its error, and then drops the
+    /// terminal.
+    fn close(mut self) -> io::Result<()>
+    where
+        Self: Sized,
+    {
+        let result = self.flush();
+        drop(self);
+        result
+    }
+
+    /// Splits the terminal into a read half and a write half.
+    ///
+    /// The [`TerminalInput`] reads events and can be moved to another thread or task, while the
+    /// [`TerminalOutput`] keeps the terminal for rendering and mode changes. Neither half needs a
+    /// lock around the other: reads already go through the shared [`EventReader`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::{io::Write as _, sync::mpsc, thread};
+    ///
+    /// use termina::{Event, PlatformTerminal, Terminal as _};
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let (input, mut output) = PlatformTerminal::new()?.split();
+    /// output.enter_raw_mode()?;
+    /// let (events, received) = mpsc::channel();
+    /// thread::spawn(move || {
+    ///     while let Ok(event) = input.read(|_| true) {
+    ///         if events.send(event).is_err() {
+    ///             break;
+    ///         }
+    ///     }
+    /// });
+    /// for event in received {
+    ///     write!(output, "{event}\r\n")?;
+    ///     output.flush()?;
+    ///     if matches!(event, Event::Key(_)) {
+    ///         break;
+    ///     }
+    /// }
+    /// output.enter_cooked_mode()
+    /// # }
+    /// ```
+    fn split(self) -> (TerminalInput, TerminalOutput<Self>)
+    where
+        Self: Sized,
+    {
+        split::split(self)
+    }
+
+    /// Runs `f` as a single synchronized update.
+    ///
+    /// When `enabled` is true this writes [`DecPrivateModeCode::SynchronizedOutput`] set and reset
+    /// sequences around the output written by `f` and flushes afterwards, so that the terminal
+    /// renders the whole batch at once. Pass the result of feature detection as `enabled`: querying
+    /// the mode with [`Mode::QueryDecPrivateMode`] and checking for a [`DecModeSetting::Set`] or
+    /// [`DecModeSetting::Reset`] report. When `enabled` is false the output is only flushed.
+    ///
+    /// See [`SyncGuard`] for a guard-based version of this method.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::io::{self, Write as _};
+    ///
+    /// use termina::{PlatformTerminal, Terminal};
+    ///
+    /// fn main() -> io::Result<()> {
+    ///     let mut terminal = PlatformTerminal::new()?;
+    ///     terminal.synchronized(true, |terminal| {
+    ///         write!(terminal, "Hello, ")?;
+    ///         write!(terminal, "world!")
+    ///     })
+    /// }
+    /// ```
+    fn synchronized<R>(
+        &mut self,
+        enabled: bool,
+        f: impl FnOnce(&mut Self) -> io::Result<R>,
+    ) -> io::Result<R>
+    where
+        Self: Sized,
+    {
+        let mut guard = SyncGuard::new(self, enabled)?;
+        let result = f(&mut guard)?;
+        guard.end()?;
+        Ok(result)
+    }
+
+    /// Writes a query and waits up to `timeout` for the first reply which converts to `R`.
+    ///
+    /// This writes and flushes `query`, then reads the first event which `R::try_from` accepts.
+    /// Other events stay buffered for later reads, so input which arrives while waiting isn't
+    /// lost. Returns `None` if no matching reply arrived before the timeout, which usually means
+    /// the terminal doesn't support the query.
+    ///
+    /// Termina implements `TryFrom<Event>` for replies such as [`ThemeMode`],
+    /// [`KittyKeyboardFlags`](crate::escape::csi::KittyKeyboardFlags) and
+    /// [`CursorPosition`](crate::query::CursorPosition). Implement it for your own types to wait
+    /// for other replies. Call this in raw mode, since the reply is otherwise only delivered after
+    /// Enter is pressed. To ask several questions at once, use a
+    /// [`QueryBatch`](crate::query::QueryBatch).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::{io, time::Duration};
+    ///
+    /// use termina::{
+    ///     escape::csi::{Csi, Cursor},
+    ///     query::CursorPosition,
+    ///     PlatformTerminal, Terminal,
+    /// };
+    ///
+    /// fn main() -> io::Result<()> {
+    ///     let mut terminal = PlatformTerminal::new()?;
+    ///     terminal.enter_raw_mode()?;
+    ///     let position: Option<CursorPosition> = terminal.query(
+    ///         Csi::Cursor(Cursor::RequestActivePositionReport),
+    ///         Duration::from_millis(100),
+    ///     )?;
+    ///     terminal.enter_cooked_mode()?;
+    ///     if let Some(CursorPosition { line, col, .. }) = position {
+    ///         println!("cursor at {line},{col}");
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    fn query<R>(&mut self, query: impl fmt::Display, timeout: Duration) -> io::Result<Option<R>>
+    where
+        Self: Sized,
+        R: TryFrom<Event>,
+    {
+        write!(self, "{query}")?;
+        self.flush()?;
+        let is_reply = |event: &Event| R::try_from(event.clone()).is_ok();
+        if !self.poll(is_reply, Some(timeout))? {
+            return Ok(None);
+        }
+        Ok(R::try_from(self.read(is_reply)?).ok())
+    }
+
+    /// Asks the terminal to change its size and waits up to `timeout` for it to comply.
+    ///
+    /// This writes and flushes `request`, then returns `true` once the terminal has the requested
+    /// size, or `false` if it didn't resize to that size before the timeout. Many terminal
+    /// emulators ignore size requests, restrict them behind a setting, or can only approximate
+    /// them, so the result tells a fixed-layout application whether it must adapt to the size it
+    /// has. The [`Event::WindowResized`] for an honored request is left buffered, so the
+    /// application's usual resize handling still sees it.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::{io, time::Duration};
+    ///
+    /// use termina::{PlatformTerminal, SizeRequest, Terminal};
+    ///
+    /// fn main() -> io::Result<()> {
+    ///     let mut terminal = PlatformTerminal::new()?;
+    ///     terminal.enter_raw_mode()?;
+    ///     let honored = terminal.request_size(
+    ///         SizeRequest::Cells {
+    ///             cols: Some(80),
+    ///             rows: Some(24),
+    ///         },
+    ///         Duration::from_millis(500),
+    ///     )?;
+    ///     if !honored {
+    ///         // Lay out for `terminal.get_dimensions()` instead.
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    fn request_size(&mut self, request: SizeRequest, timeout: Duration) -> io::Result<bool>
+    where
+        Self: Sized,
+    {
+        write!(self, "{request}")?;
+        self.flush()?;
+        if request.is_satisfied_by(self.get_dimensions()?) {
+            return Ok(true);
+        }
+        let resized = |event: &Event| matches!(event, Event::WindowResized(size) if request.is_satisfied_by(*size));
+        if self.poll(resized, Some(timeout))? {
+            return Ok(true);
+        }
+        // The resize may have been delivered before the event reader noticed it.
+        Ok(request.is_satisfied_by(self.get_dimensions()?))
+    }
+
+    /// Asks the terminal whether it uses a dark or light theme, waiting up to `timeout`.
+    ///
+    /// This writes and flushes three queries: [`Mode::QueryTheme`] for terminals with the
+    /// [`DecPrivateModeCode::Theme`] (2031) extension, the background color (OSC 11), and the
+    /// primary device attributes, which every terminal answers and which marks the end of the
+    /// replies. A theme reported by the terminal is preferred. Otherwise the theme is derived from
+    /// the luminance of the background color with [`ThemeMode::from_background`]. Returns `None`
+    /// if the terminal answered neither query before the timeout.
+    ///
+    /// The replies are read from the terminal, so they aren't seen by other readers. Call this in
+    /// raw mode, before other input is expected.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::{io, time::Duration};
+    ///
+    /// use termina::{escape::csi::ThemeMode, PlatformTerminal, Terminal};
+    ///
+    /// fn main() -> io::Result<()> {
+    ///     let mut terminal = PlatformTerminal::new()?;
+    ///     terminal.enter_raw_mode()?;
+    ///     let dark = terminal
+    ///         .theme(Duration::from_millis(100))?
+    ///         .map_or(true, |report| report.mode == ThemeMode::Dark);
+    ///     terminal.enter_cooked_mode()?;
+    ///     println!("dark theme: {dark}");
+    ///     Ok(())
+    /// }
+    /// ```
+    fn theme(&mut self, timeout: Duration) -> io::Result<Option<ThemeReport>>
+    where
+        Self: Sized,
+    {
+        write!(
+            self,
+            "{}{}{}",
+            Csi::Mode(Mode::QueryTheme),
+            Osc::ChangeDynamicColors(
+                DynamicColorNumber::TextBackgroundColor,
+                vec![ColorOrQuery::Query]
+            ),
+            Csi::Device(Device::RequestPrimaryDeviceAttributes),
+        )?;
+        self.flush()?;
+
+        let is_reply = |event: &Event| match event {
+            Event::ThemeChanged(_) | Event::Csi(Csi::Device(Device::DeviceAttributes(_))) => true,
+            Event::Osc(Osc::ChangeDynamicColors(
+                DynamicColorNumber::TextBackgroundColor,
+                colors,
+            )) => {
+                matches!(colors.as_slice(), [ColorOrQuery::Color(_)])
+            }
+            _ => false,
+        };
+        let deadline = Instant::now() + timeout;
+        let mut reported = None;
+        let mut background = None;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if !self.poll(is_reply, Some(remaining))? {
+                break;
+            }
+            match self.read(is_reply)? {
+                Event::ThemeChanged(mode) => reported = Some(mode),
+                Event::Osc(Osc::ChangeDynamicColors(_, colors)) => {
+                    if let [ColorOrQuery::Color(color)] = colors.as_slice() {
+                        background = Some(*color);
+                    }
+                }
+                // The device attributes are answered last.
+                _ => break,
+            }
+        }
+
+        Ok(match (reported, background) {
+            (Some(mode), _) => Some(ThemeReport {
+                mode,
+                source: ThemeSource::Reported,
+            }),
+            (None, Some(color)) => Some(ThemeReport {
+                mode: ThemeMode::from_background(color),
+                source: ThemeSource::BackgroundColor(color),
+            }),
+            (None, None) => None,
+        })
+    }
+}
+
+/// The theme of the terminal as determined by [`Terminal::theme`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ThemeReport {
+    /// Whether the terminal uses a dark or light theme.
+    pub mode: ThemeMode,
+    /// How the theme was determined.
+    pub source: ThemeSource,
+}
+
+/// How [`Terminal::theme`] determined the theme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ThemeSource {
+    /// The terminal reported its theme through the [`DecPrivateModeCode::Theme`] extension.
+    Reported,
+    /// The theme was derived from the luminance of this background color, reported with OSC 11.
+    BackgroundColor(RgbColor),
+}
+
+/// A terminal size change for [`Terminal::request_size`].
+///
+/// Displaying a request writes its escape sequence, so it can also be sent without waiting for
+/// the result.
+///
+/// ```
+/// use termina::SizeRequest;
+///
+/// assert_eq!(SizeRequest::ColumnsPerPage(132).to_string(), "\x1b[132$|");
+/// assert_eq!(SizeRequest::Select132Columns(true).to_string(), "\x1b[?3h");
+/// assert_eq!(
+///     SizeRequest::Cells {
+///         cols: Some(80),
+///         rows: None,
+///     }
+///     .to_string(),
+///     "\x1b[8;;80t",
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SizeRequest {
+    /// Select the number of columns with DECSCPP, see [`Mode::SelectColumnsPerPage`].
+    ColumnsPerPage(u16),
+
+    /// Switch to 132 columns (`true`) or 80 columns (`false`) with DECCOLM.
+    ///
+    /// DECCOLM clears the screen and resets the scroll margins. xterm only honors it after
+    /// [`DecPrivateModeCode::Allow80To132Columns`] has been set.
+    Select132Columns(bool),
+
+    /// Resize the window to a size in cells with xterm's window manipulation sequence, see
+    /// [`Window::ResizeWindowCells`]. `None` keeps the current size in that dimension.
+    Cells {
+        /// The desired number of columns.
+        cols: Option<u16>,
+
+        /// The desired number of rows.
+        rows: Option<u16>,
+    },
+}
+
+impl SizeRequest {
+    /// Returns `true` if `size` is the size this request asks for.
+    pub fn is_satisfied_by(&self, size: WindowSize) -> bool {
+        match *self {
+            Self::ColumnsPerPage(0) => size.cols == 80,
+            Self::ColumnsPerPage(cols) => size.cols == cols,
+            Self::Select132Columns(wide) => size.cols == if wide { 132 } else { 80 },
+            Self::Cells { cols, rows } => {
+                cols.map_or(true, |cols| size.cols == cols)
+                    && rows.map_or(true, |rows| size.rows == rows)
+            }
+        }
+    }
+}
+
+impl fmt::Display for SizeRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let csi = match *self {
+            Self::ColumnsPerPage(columns) => Csi::Mode(Mode::SelectColumnsPerPage(columns)),
+            Self::Select132Columns(wide) => {
+                let mode = DecPrivateMode::Code(DecPrivateModeCode::Select132Columns);
+                if wide {
+                    Csi::Mode(Mode::SetDecPrivateMode(mode))
+                } else {
+                    Csi::Mode(Mode::ResetDecPrivateMode(mode))
+                }
+            }
+            Self::Cells { cols, rows } => Csi::Window(Box::new(Window::ResizeWindowCells {
+                width: cols.map(i64::from),
+                height: rows.map(i64::from),
+            })),
+        };
+        write!(f, "{csi}")
+    }
+}
+
+/// Terminal modes changed through [`Terminal`] helpers, which are restored on drop and in the
+/// panic hook.
+///
+/// Clones share state so the panic hook sees changes made after it was installed.
+#[derive(Debug, Clone)]
+pub(crate) struct TrackedModes {
+    line_wrapping_disabled: Arc<AtomicBool>,
+    grapheme_clustering_enabled: Arc<AtomicBool>,
+    /// Whether the terminal has a title stack. This isn't restored, but is shared like the modes.
+    title_stack_supported: Arc<AtomicBool>,
+    /// The zero-based top and bottom rows of the scroll region packed into the high and low
+    /// halves, or [`NO_SCROLL_REGION`].
+    scroll_region: Arc<AtomicU32>,
+}
+
+const NO_SCROLL_REGION: u32 = u32::MAX;
+
+impl Default for TrackedModes {
+    fn default() -> Self {
+        Self {
+            line_wrapping_disabled: Default::default(),
+            grapheme_clustering_enabled: Default::default(),
+            title_stack_supported: Arc::new(AtomicBool::new(detect_title_stack())),
+            scroll_region: Arc::new(AtomicU32::new(NO_SCROLL_REGION)),
+        }
+    }
+}
+
+impl TrackedModes {
+    pub(crate) fn enable_line_wrapping(
+        &self,
+        write: impl io::Write,
+        enabled: bool,
+    ) -> io::Result<()> {
+        write_line_wrapping(write, enabled)?;
+        self.line_wrapping_disabled
+            .store(!enabled, Ordering::Relaxed);
+        Ok(())
+    }
+
+    pub(crate) fn enable_grapheme_clustering(
+        &self,
+        write: impl io::Write,
+        enabled: bool,
+    ) -> io::Result<()> {
+        write_grapheme_clustering(write, enabled)?;
+        self.grapheme_clustering_enabled
+            .store(enabled, Ordering::Relaxed);
+        Ok(())
+    }
+
+    pub(crate) fn set_scroll_region(
+        &self,
+        write: impl io::Write,
+        rows: Option<RangeInclusive<u16>>,
+    ) -> io::Result<()> {
+        let region = scroll_region_rows(rows)?;
+        write_scroll_region(write, region)?;
+        let packed = region.map_or(NO_SCROLL_REGION, |(top, bottom)| {
+            (top as u32) << 16 | bottom as u32
+        });
+        self.scroll_region.store(packed, Ordering::Relaxed);
+        Ok(())
+    }
+
+    pub(crate) fn set_title_stack_supported(&self, supported: bool) {
+        self.title_stack_supported
+            .store(supported, Ordering::Relaxed);
+    }
+
+    pub(crate) fn push_title(&self, write: impl io::Write) -> io::Result<()> {
+        self.write_title_stack(write, Window::PushIconAndWindowTitle)
+    }
+
+    pub(crate) fn pop_title(&self, write: impl io::Write) -> io::Result<()> {
+        self.write_title_stack(write, Window::PopIconAndWindowTitle)
+    }
+
+    fn write_title_stack(&self, mut write: impl io::Write, window: Window) -> io::Result<()> {
+        if !self.title_stack_supported.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        write!(write, "{}", Csi::Window(Box::new(window)))
+    }
+
+    fn scroll_region(&self) -> Option<(u16, u16)> {
+        match self.scroll_region.load(Ordering::Relaxed) {
+            NO_SCROLL_REGION => None,
+            packed => Some(((packed >> 16) as u16, packed as u16)),
+        }
+    }
+
+    /// Writes the sequences which return tracked modes to the terminal defaults.
+    ///
+    /// The tracked state is kept so that [`Self::reapply`] can set the modes again, for example
+    /// when the terminal re-enters raw mode after a trip through cooked mode.
+    pub(crate) fn restore(&self, mut write: impl io::Write) -> io::Result<()> {
+        if self.line_wrapping_disabled.load(Ordering::Relaxed) {
+            write_line_wrapping(&mut write, true)?;
+        }
+        if self.grapheme_clustering_enabled.load(Ordering::Relaxed) {
+            write_grapheme_clustering(&mut write, false)?;
+        }
+        if self.scroll_region().is_some() {
+            write_scroll_region(&mut write, None)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the sequences which set tracked modes to their tracked state.
+    pub(crate) fn reapply(&self, mut write: impl io::Write) -> io::Result<()> {
+        if self.line_wrapping_disabled.load(Ordering::Relaxed) {
+            write_line_wrapping(&mut write, false)?;
+        }
+        if self.grapheme_clustering_enabled.load(Ordering::Relaxed) {
+            write_grapheme_clustering(&mut write, true)?;
+        }
+        if let Some(region) = self.scroll_region() {
+            write_scroll_region(&mut write, Some(region))?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns `false` for terminals known not to have a title stack.
+fn detect_title_stack() -> bool {
+    !matches!(std::env::var("TERM").as_deref(), Ok("linux" | "dumb"))
+}
+
+/// Flushes `writer` and replaces its buffer with one of `capacity` bytes, keeping the underlying
+/// writer. `placeholder` stands in for it while the buffer is swapped.
+fn set_buffer_capacity<W: io::Write>(
+    writer: &mut io::BufWriter<W>,
+    capacity: usize,
+    placeholder: W,
+) -> io::Result<()> {
+    io::Write::flush(writer)?;
+    let old = std::mem::replace(writer, io::BufWriter::with_capacity(0, placeholder));
+    let (inner, _) = old.into_parts();
+    *writer = io::BufWriter::with_capacity(capacity, inner);
+    Ok(())
+}
+
+fn write_line_wrapping(mut write: impl io::Write, enabled: bool) -> io::Result<()> {
+    let mode = DecPrivateMode::Code(DecPrivateModeCode::AutoWrap);
+    let csi = if enabled {
+        Csi::Mode(Mode::SetDecPrivateMode(mode))
+    } else {
+        Csi::Mode(Mode::ResetDecPrivateMode(mode))
+    };
+    trace!(enabled, "writing line wrapping mode");
+    write!(write, "{csi}")
+}
+
+fn write_grapheme_clustering(mut write: impl io::Write, enabled: bool) -> io::Result<()> {
+    let mode = DecPrivateMode::Code(DecPrivateModeCode::GraphemeClustering);
+    let csi = if enabled {
+        Csi::Mode(Mode::SetDecPrivateMode(mode))
+    } else {
+        Csi::Mode(Mode::ResetDecPrivateMode(mode))
+    };
+    write!(write, "{csi}")
+}
+
+/// Checks the rows of a scroll region, returning them as zero-based `(top, bottom)` rows.
+fn scroll_region_rows(rows: Option<RangeInclusive<u16>>) -> io::Result<Option<(u16, u16)>> {
+    match rows {
+        Some(rows) if rows.is_empty() => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "the scroll region must contain at least one row",
+        )),
+        // `OneBased` can't represent `u16::MAX` as a zero-based row.
+        Some(rows) => Ok(Some((
+            (*rows.start()).min(u16::MAX - 1),
+            (*rows.end()).min(u16::MAX - 1),
+        ))),
+        None => Ok(None),
+    }
+}
+
+fn write_scroll_region(mut write: impl io::Write, region: Option<(u16, u16)>) -> io::Result<()> {
+    let margins = match region {
+        Some((top, bottom)) => Cursor::SetTopAndBottomMargins {
+            top: OneBased::from_zero_based(top),
+            bottom: OneBased::from_zero_based(bottom),
+        },
+        // A bottom margin of `u16::MAX` formats as a margin reset.
+        None => Cursor::SetTopAndBottomMargins {
+            top: OneBased::from_zero_based(0),
+            bottom: OneBased::new(u16::MAX).unwrap(),
+        },
+    };
+    trace!(?region, "writing scroll region");
+    write!(write, "{}", Csi::Cursor(margins))
+}
+
+const BEGIN_SYNCHRONIZED_UPDATE: Csi = Csi::Mode(Mode::SetDecPrivateMode(DecPrivateMode::Code(
+    DecPrivateModeCode::SynchronizedOutput,
+)));
+const END_SYNCHRONIZED_UPDATE: Csi = Csi::Mode(Mode::ResetDecPrivateMode(DecPrivateMode::Code(
+    DecPrivateModeCode::SynchronizedOutput,
+)));
+
+/// A guard which wraps terminal output in a synchronized update.
+///
+/// Creating the guard writes the [`DecPrivateModeCode::SynchronizedOutput`] set sequence and
+/// ending or dropping it writes the reset sequence and flushes the terminal. The guard dereferences
+/// to the terminal so output can be written through it directly. When the guard is created with
+/// `enabled` set to false no mode sequences are written, which lets callers use the same code path
+/// for terminals that don't support synchronized output.
+///
+/// Prefer [`Self::end`] over dropping the guard when write errors should be reported. Errors from
+/// the drop-time reset are ignored.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::io::{self, Write as _};
+///
+/// use termina::{PlatformTerminal, SyncGuard};
+///
+/// fn main() -> io::Result<()> {
+///     let mut terminal = PlatformTerminal::new()?;
+///     let mut guard = SyncGuard::new(&mut terminal, true)?;
+///     write!(guard, "Hello, world!")?;
+///     guard.end()
+/// }
+/// ```
+#[derive(Debug)]
+pub struct SyncGuard<'a, T: Terminal> {
+    terminal: &'a mut T,
+    enabled: bool,
+    ended: bool,
+}
+
+impl<'a, T: Terminal> SyncGuard<'a, T> {
+    /// Begins a synchronized update if `enabled` is true.
+    pub fn new(terminal: &'a mut T, enabled: bool) -> io::Result<Self> {
+        if enabled {
+            write!(terminal, "{BEGIN_SYNCHRONIZED_UPDATE}")?;
+        }
+        Ok(Self {
+            terminal,
+            enabled,
+            ended: false,
+        })
+    }
+
+    /// Ends the synchronized update and flushes the terminal.
+    pub fn end(mut self) -> io::Result<()> {
+        self.ended = true;
+        self.finish()
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        if self.enabled {
+            write!(self.terminal, "{END_SYNCHRONIZED_UPDATE}")?;
+        }
+        self.terminal.flush()
+    }
+}
+
+impl<T: Terminal> Deref for SyncGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.terminal
+    }
+}
+
+impl<T: Terminal> DerefMut for SyncGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.terminal
+    }
+}
+
+impl<T: Terminal> Drop for SyncGuard<'_, T> {
+    fn drop(&mut self) {
+        if !self.ended {
+            let _ = self.finish();
+        }
+    }
+}
+
+/// A terminal handed over to a child process, created with [`Terminal::release`] or
+/// [`Session::release`](crate::Session::release).
+///
+/// Reacquiring or dropping the guard returns the terminal to the application. Prefer
+/// [`Self::reacquire`] over dropping the guard when errors should be reported. Errors from the
+/// drop-time reacquisition are ignored.
+#[derive(Debug)]
+pub struct ReleasedTerminal<'a, T: Terminal> {
+    terminal: &'a mut T,
+    /// Written after entering raw mode when the terminal is reacquired.
+    setup: String,
+    raw: bool,
+    reacquired: bool,
+}
+
+impl<'a, T: Terminal> ReleasedTerminal<'a, T> {
+    /// Writes `teardown`, enters cooked mode and stops reading input.
+    ///
+    /// Reacquiring enters raw mode again if `raw` is set, and then writes `setup`.
+    pub(crate) fn new(
+        terminal: &'a mut T,
+        teardown: &str,
+        setup: String,
+        raw: bool,
+    ) -> io::Result<Self> {
+        terminal.write_all(teardown.as_bytes())?;
+        terminal.flush()?;
+        terminal.event_reader().pause()?;
+        let mut released = Self {
+            terminal,
+            setup,
+            raw,
+            reacquired: false,
+        };
+        if let Err(err) = released.terminal.enter_cooked_mode() {
+            released.reacquired = true;
+            let _ = released.finish();
+            return Err(err);
+        }
+        trace!("released terminal");
+        Ok(released)
+    }
+
+    /// Returns the terminal to the application.
+    ///
+    /// Every step runs even if an earlier one fails, and the first error is returned.
+    pub fn reacquire(mut self) -> io::Result<()> {
+        self.reacquired = true;
+        self.finish()
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        let raw = if self.raw {
+            self.terminal.enter_raw_mode()
+        } else {
+            Ok(())
+        };
+        let written = self
+            .terminal
+            .write_all(self.setup.as_bytes())
+            .and_then(|()| self.terminal.flush());
+        let resumed = self.terminal.event_reader().resume();
+        trace!("reacquired terminal");
+        raw.and(written).and(resumed)
+    }
+}
+
+impl<T: Terminal> Drop for ReleasedTerminal<'_, T> {
+    fn drop(&mut self) {
+        if !self.reacquired {
+            let _ = self.finish();
+        }
+    }
+}
+
+/// A guard which restricts scrolling to a range of rows, created with [`Terminal::scroll_region`].
+///
+/// While the guard is alive, text which scrolls past the bottom row of the region, and scrolling
+/// with [`Self::scroll_up`] and [`Self::scroll_down`], only moves the rows inside the region. The
+/// region is tracked by the terminal as described in [`Terminal::set_scroll_region`]. Ending or
+/// dropping the guard scrolls the whole screen again.
+///
+/// Some terminals reset the margins when switching between the main and alternate screens. Call
+/// [`Self::reapply`] after such a switch to set the region again.
+///
+/// Prefer [`Self::end`] over dropping the guard when write errors should be reported. Errors from
+/// the drop-time reset are ignored.
+#[derive(Debug)]
+pub struct ScrollRegion<'a, T: Terminal> {
+    terminal: &'a mut T,
+    rows: RangeInclusive<u16>,
+    ended: bool,
+}
+
+impl<'a, T: Terminal> ScrollRegion<'a, T> {
+    /// Restricts scrolling to the zero-based, inclusive range of `rows`.
+    pub fn new(terminal: &'a mut T, rows: RangeInclusive<u16>) -> io::Result<Self> {
+        terminal.set_scroll_region(Some(rows.clone()))?;
+        Ok(Self {
+            terminal,
+            rows,
+            ended: false,
+        })
+    }
+
+    /// Returns the zero-based rows of the region.
+    pub fn rows(&self) -> RangeInclusive<u16> {
+        self.rows.clone()
+    }
+
+    /// Scrolls the region's content up by `n` rows, adding blank rows at the bottom.
+    pub fn scroll_up(&mut self, n: u16) -> io::Result<()> {
+        write!(self.terminal, "{}", Csi::Edit(Edit::ScrollUp(n.into())))
+    }
+
+    /// Scrolls the region's content down by `n` rows, adding blank rows at the top.
+    pub fn scroll_down(&mut self, n: u16) -> io::Result<()> {
+        write!(self.terminal, "{}", Csi::Edit(Edit::ScrollDown(n.into())))
+    }
+
+    /// Sets the region again, for example after switching to the alternate screen.
+    pub fn reapply(&mut self) -> io::Result<()> {
+        self.terminal.set_scroll_region(Some(self.rows.clone()))
+    }
+
+    /// Removes the region so that the whole screen scrolls again.
+    pub fn end(mut self) -> io::Result<()> {
+        self.ended = true;
+        self.terminal.set_scroll_region(None)
+    }
+}
+
+impl<T: Terminal> Deref for ScrollRegion<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.terminal
+    }
+}
+
+impl<T: Terminal> DerefMut for ScrollRegion<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.terminal
+    }
+}
+
+impl<T: Terminal> Drop for ScrollRegion<'_, T> {
+    fn drop(&mut self) {
+        if !self.ended {
+            let _ = self.terminal.set_scroll_region(None);
+        }
+    }
+}
+
+/// A guard which puts the terminal's sixel modes into a known state while image data is written.
+///
+/// Where a sixel image is drawn, and where the cursor ends up afterwards, depends on
+/// [`DecPrivateModeCode::SixelDisplayMode`] (DECSDM) and
+/// [`DecPrivateModeCode::SixelScrollsRight`]. The guard resets DECSDM so the image is drawn at the
+/// cursor and sets `SixelScrollsRight` so the cursor is left to the right of the image rather than
+/// below it. Ending or dropping the guard restores both modes and flushes the terminal.
+///
+/// The guard is created from the modes' current settings, as reported by the terminal in response
+/// to [`Mode::QueryDecPrivateMode`]. A mode is only changed if the terminal reported it as
+/// [`DecModeSetting::Set`] or [`DecModeSetting::Reset`] and it isn't already in the wanted state.
+/// Modes the terminal doesn't recognize or can't change are left alone, so the guard is safe to use
+/// with terminals which lack either mode. Pass [`DecModeSetting::NotRecognized`] for a mode which
+/// wasn't queried to leave it unchanged.
+///
+/// Prefer [`Self::end`] over dropping the guard when write errors should be reported. Errors from
+/// the drop-time restore are ignored.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::io::{self, Write as _};
+///
+/// use termina::{escape::csi::DecModeSetting, PlatformTerminal, SixelGuard};
+///
+/// fn main() -> io::Result<()> {
+///     let mut terminal = PlatformTerminal::new()?;
+///     // The settings reported for DECSDM and SixelScrollsRight by a `QueryDecPrivateMode`.
+///     let (display_mode, scrolls_right) = (DecModeSetting::Set, DecModeSetting::Reset);
+///     let mut guard = SixelGuard::new(&mut terminal, display_mode, scrolls_right)?;
+///     write!(guard, "\x1bPq#0;2;0;0;0#0~~@@vv@@~~$-\x1b\\")?;
+///     guard.end()
+/// }
+/// ```
+#[derive(Debug)]
+pub struct SixelGuard<'a, T: Terminal> {
+    terminal: &'a mut T,
+    modes: SixelModes,
+    ended: bool,
+}
+
+impl<'a, T: Terminal> SixelGuard<'a, T> {
+    /// Changes the sixel modes for image output, given their current settings.
+    pub fn new(
+        terminal: &'a mut T,
+        display_mode: DecModeSetting,
+        scrolls_right: DecModeSetting,
+    ) -> io::Result<Self> {
+        let modes = SixelModes {
+            reset_display_mode: display_mode == DecModeSetting::Set,
+            set_scrolls_right: scrolls_right == DecModeSetting::Reset,
+        };
+        modes.apply(&mut *terminal)?;
+        Ok(Self {
+            terminal,
+            modes,
+            ended: false,
+        })
+    }
+
+    /// Restores the sixel modes and flushes the terminal.
+    pub fn end(mut self) -> io::Result<()> {
+        self.ended = true;
+        self.finish()
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.modes.restore(&mut *self.terminal)?;
+        self.terminal.flush()
+    }
+}
+
+impl<T: Terminal> Deref for SixelGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.terminal
+    }
+}
+
+impl<T: Terminal> DerefMut for SixelGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.terminal
+    }
+}
+
+impl<T: Terminal> Drop for SixelGuard<'_, T> {
+    fn drop(&mut self) {
+        if !self.ended {
+            let _ = self.finish();
+        }
+    }
+}
+
+/// The sixel mode changes made by a [`SixelGuard`].
+#[derive(Debug, Clone, Copy)]
+struct SixelModes {
+    reset_display_mode: bool,
+    set_scrolls_right: bool,
+}
+
+impl SixelModes {
+    fn apply(&self, write: impl io::Write) -> io::Result<()> {
+        self.write(write, false)
+    }
+
+    fn restore(&self, write: impl io::Write) -> io::Result<()> {
+        self.write(write, true)
+    }
+
+    /// Writes the changed modes, either in their image-output state or their original state.
+    fn write(&self, mut write: impl io::Write, restore: bool) -> io::Result<()> {
+        let mode = |code, set| {
+            let mode = DecPrivateMode::Code(code);
+            if set {
+                Csi::Mode(Mode::SetDecPrivateMode(mode))
+            } else {
+                Csi::Mode(Mode::ResetDecPrivateMode(mode))
+            }
+        };
+        if self.reset_display_mode {
+            let csi = mode(DecPrivateModeCode::SixelDisplayMode, restore);
+            write!(write, "{csi}")?;
+        }
+        if self.set_scrolls_right {
+            let csi = mode(DecPrivateModeCode::SixelScrollsRight, !restore);
+            write!(write, "{csi}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn tracked_line_wrapping() {
+        let modes = TrackedModes::default();
+        let mut output = Vec::new();
+        modes.restore(&mut output).unwrap();
+        assert!(output.is_empty());
+
+        modes.enable_line_wrapping(&mut output, false).unwrap();
+        assert_eq!(output, b"\x1b[?7l");
+        output.clear();
+        // Clones share state, as the panic hook's copy does.
+        modes.clone().restore(&mut output).unwrap();
+        assert_eq!(output, b"\x1b[?7h");
+        output.clear();
+        // Restoring keeps the tracked state for re-entering raw mode.
+        modes.reapply(&mut output).unwrap();
+        assert_eq!(output, b"\x1b[?7l");
+        output.clear();
+        modes.enable_line_wrapping(&mut output, true).unwrap();
+        output.clear();
+        modes.restore(&mut output).unwrap();
+        modes.reapply(&mut output).unwrap();
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn title_stack() {
+        let modes = TrackedModes::default();
+        modes.set_title_stack_supported(true);
+        let mut output = Vec::new();
+        modes.push_title(&mut output).unwrap();
+        modes.pop_title(&mut output).unwrap();
+        assert_eq!(output, b"\x1b[22;0t\x1b[23;0t");
+
+        output.clear();
+        modes.set_title_stack_supported(false);
+        modes.push_title(&mut output).unwrap();
+        modes.pop_title(&mut output).unwrap();
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn size_request_satisfied() {
+        let size = crate::test_size;
+        assert!(SizeRequest::ColumnsPerPage(0).is_satisfied_by(size(80, 24)));
+        assert!(!SizeRequest::ColumnsPerPage(132).is_satisfied_by(size(80, 24)));
+        assert!(SizeRequest::Select132Columns(true).is_satisfied_by(size(132, 24)));
+        assert!(!SizeRequest::Select132Columns(false).is_satisfied_by(size(132, 24)));
+        let rows_only = SizeRequest::Cells {
+            cols: None,
+            rows: Some(40),
+        };
+        assert!(rows_only.is_satisfied_by(size(100, 40)));
+        assert!(!rows_only.is_satisfied_by(size(100, 24)));
+    }
+
+    #[test]
+    fn sixel_modes() {
+        let modes = SixelModes {
+            reset_display_mode: true,
+            set_scrolls_right: true,
+        };
+        let mut output = Vec::new();
+        modes.apply(&mut output).unwrap();
+        assert_eq!(output, b"\x1b[?80l\x1b[?8452h");
+        output.clear();
+        modes.restore(&mut output).unwrap();
+        assert_eq!(output, b"\x1b[?80h\x1b[?8452l");
+
+        // Modes which are unsupported or already in the wanted state are left alone.
+        let modes = SixelModes {
+            reset_display_mode: false,
+            set_scrolls_right: false,
+        };
+        output.clear();
+        modes.apply(&mut output).unwrap();
+        modes.restore(&mut output).unwrap();
+        assert!(output.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn split_halves() {
+        use crate::event::KeyCode;
+
+        let (input, mut output) = test_terminal().split();
+        output.push_input(b"a").unwrap();
+        let reader = std::thread::spawn(move || input.read(|_| true).unwrap());
+        output.enter_raw_mode().unwrap();
+        io::Write::write_all(&mut output, b"drawn").unwrap();
+
+        let event = reader.join().unwrap();
+        assert!(matches!(event, Event::Key(key) if key.code == KeyCode::Char('a')));
+        let terminal = output.into_inner();
+        assert!(terminal.is_raw_mode());
+        assert_eq!(terminal.output(), b"drawn");
+    }
+
+    /// A terminal implementing only the required methods, as a downstream implementation might.
+    #[cfg(unix)]
+    struct MinimalTerminal {
+        output: Vec<u8>,
+        reader: EventReader,
+    }
+
+    #[cfg(unix)]
+    impl io::Write for MinimalTerminal {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.output.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[cfg(unix)]
+    impl Terminal for MinimalTerminal {
+        fn enter_raw_mode(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn enter_cooked_mode(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn get_dimensions(&self) -> io::Result<WindowSize> {
+            Err(io::ErrorKind::Unsupported.into())
+        }
+
+        fn event_reader(&self) -> EventReader {
+            self.reader.clone()
+        }
+
+        fn poll<F: Fn(&Event) -> bool>(
+            &self,
+            filter: F,
+            timeout: Option<Duration>,
+        ) -> io::Result<bool> {
+            self.reader.poll(timeout, filter)
+        }
+
+        fn read<F: Fn(&Event) -> bool>(&self, filter: F) -> io::Result<Event> {
+            self.reader.read(filter)
+        }
+
+        fn set_panic_hook(&mut self, _: impl Fn(&mut PlatformHandle) + Send + Sync + 'static) {}
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn default_methods() {
+        let (reader, _input) = crate::event::reader::test_reader();
+        let mut terminal = MinimalTerminal {
+            output: Vec::new(),
+            reader,
+        };
+        terminal.enable_line_wrapping(false).unwrap();
+        terminal.set_scroll_region(Some(0..=9)).unwrap();
+        terminal.push_title().unwrap();
+        terminal.pop_title().unwrap();
+        terminal.set_title_stack_supported(false);
+        terminal.set_output_buffer_capacity(0).unwrap();
+        terminal.resync().unwrap();
+        assert_eq!(terminal.output, b"\x1b[?7l\x1b[1;10r\x1b[22;0t\x1b[23;0t");
+        #[allow(clippy::reversed_empty_ranges)]
+        let empty = 2..=1;
+        let err = terminal.set_scroll_region(Some(empty)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
 }
@@ -0,0 +1,446 @@
+use std::{
+    io::{self, Write as _},
+    ops::RangeInclusive,
+    os::unix::net::UnixStream,
+    time::Duration,
+};
+
+use crate::{event::source::UnixEventSource, Event, EventReader, WindowSize};
+
+use super::{FileDescriptor, Terminal, TrackedModes};
+
+/// A [`Terminal`] backed by in-memory buffers, for testing code which is generic over
+/// `Terminal` without a TTY.
+///
+/// Output written to the terminal is collected in a buffer which tests can inspect with
+/// [`Self::output`]. Input is injected either as parsed [`Event`]s with [`Self::push_events`] or
+/// as raw bytes with [`Self::push_input`], which go through the same parser as a real terminal's
+/// input. The dimensions are set with [`Self::resize`], which also delivers an
+/// [`Event::WindowResized`] like a real resize would.
+///
+/// Raw and cooked mode only toggle [`Terminal::is_raw_mode`], but modes tracked by `Terminal`
+/// helpers such as [`Terminal::enable_line_wrapping`] write the same sequences as on a real
+/// terminal.
+///
+/// The input is a socket pair, so `MemoryTerminal` is only available on Unix. Signals of the
+/// process's own terminal, such as `SIGWINCH`, are not reported by its reader.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::{self, Write};
+///
+/// use termina::{event::KeyCode, Event, MemoryTerminal, Terminal, WindowSize};
+///
+/// // Code under test, generic over the terminal.
+/// fn echo_key(terminal: &mut impl Terminal) -> io::Result<()> {
+///     if let Event::Key(key) = terminal.read(|event| matches!(event, Event::Key(_)))? {
+///         write!(terminal, "{:?}", key.code)?;
+///     }
+///     terminal.flush()
+/// }
+///
+/// # fn main() -> io::Result<()> {
+/// let size = WindowSize { cols: 80, rows: 24, pixel_width: None, pixel_height: None };
+/// let mut terminal = MemoryTerminal::new(size)?;
+/// terminal.push_input(b"\x1b[A")?;
+/// echo_key(&mut terminal)?;
+/// assert_eq!(terminal.output(), b"Up");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct MemoryTerminal {
+    output: Vec<u8>,
+    dimensions: WindowSize,
+    reader: EventReader,
+    input: UnixStream,
+    raw_mode: bool,
+    modes: TrackedModes,
+}
+
+impl MemoryTerminal {
+    /// Creates a terminal with the given dimensions, in cooked mode and with no input.
+    pub fn new(dimensions: WindowSize) -> io::Result<Self> {
+        let (read, input) = UnixStream::pair()?;
+        // The socket has no window size, which the source only reads on `SIGWINCH` anyway.
+        let size_fd = FileDescriptor::Owned(read.try_clone()?.into());
+        let source = UnixEventSource::without_signals(FileDescriptor::Owned(read.into()), size_fd)?;
+        Ok(Self {
+            output: Vec::new(),
+            dimensions,
+            reader: EventReader::new(source),
+            input,
+            raw_mode: false,
+            modes: TrackedModes::default(),
+        })
+    }
+
+    /// The bytes written to the terminal so far.
+    pub fn output(&self) -> &[u8] {
+        &self.output
+    }
+
+    /// Returns the bytes written to the terminal so far and clears the buffer.
+    pub fn take_output(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.output)
+    }
+
+    /// Queues events as if they had been read from the terminal.
+    ///
+    /// Events are delivered in order after input which has already been read. Like
+    /// [`Self::push_input`] this may be called while another thread is blocked reading.
+    pub fn push_events<I>(&self, events: I)
+    where
+        I: IntoIterator<Item = Event>,
+    {
+        self.reader.push_events(events);
+    }
+
+    /// Sends bytes of terminal input, such as typed text or escape sequences, to the parser.
+    ///
+    /// This may be called while another thread is blocked reading.
+    pub fn push_input(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.input.write_all(bytes)
+    }
+
+    /// Changes the dimensions and queues the matching [`Event::WindowResized`].
+    pub fn resize(&mut self, dimensions: WindowSize) {
+        self.dimensions = dimensions;
+        self.push_events([Event::WindowResized(dimensions)]);
+    }
+}
+
+impl io::Write for MemoryTerminal {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.output.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Terminal for MemoryTerminal {
+    fn enter_raw_mode(&mut self) -> io::Result<()> {
+        self.raw_mode = true;
+        self.modes.reapply(&mut self.output)
+    }
+
+    fn enter_cooked_mode(&mut self) -> io::Result<()> {
+        self.raw_mode = false;
+        self.modes.restore(&mut self.output)
+    }
+
+    fn is_raw_mode(&self) -> bool {
+        self.raw_mode
+    }
+
+    fn get_dimensions(&self) -> io::Result<WindowSize> {
+        if self.dimensions.is_degenerate() {
+            Err(io::Error::new(
+                io::ErrorKind::Other,
+                "memory terminal has zero cols or rows",
+            ))
+        } else {
+            Ok(self.dimensions)
+        }
+    }
+
+    fn event_reader(&self) -> EventReader {
+        self.reader.clone()
+    }
+
+    fn poll<F: Fn(&Event) -> bool>(
+        &self,
+        filter: F,
+        timeout: Option<Duration>,
+    ) -> io::Result<bool> {
+        self.reader.poll(timeout, filter)
+    }
+
+    fn read<F: Fn(&Event) -> bool>(&self, filter: F) -> io::Result<Event> {
+        self.reader.read(filter)
+    }
+
+    // NOTE: the hook is dropped since a memory terminal leaves no device state to clean up.
+    fn set_panic_hook(&mut self, _f: impl Fn(&mut FileDescriptor) + Send + Sync + 'static) {}
+
+    fn enable_line_wrapping(&mut self, enabled: bool) -> io::Result<()> {
+        self.modes.enable_line_wrapping(&mut self.output, enabled)
+    }
+
+    fn enable_grapheme_clustering(&mut self, enabled: bool) -> io::Result<()> {
+        self.modes
+            .enable_grapheme_clustering(&mut self.output, enabled)
+    }
+
+    fn push_title(&mut self) -> io::Result<()> {
+        self.modes.push_title(&mut self.output)
+    }
+
+    fn pop_title(&mut self) -> io::Result<()> {
+        self.modes.pop_title(&mut self.output)
+    }
+
+    fn set_title_stack_supported(&mut self, supported: bool) {
+        self.modes.set_title_stack_supported(supported);
+    }
+
+    fn set_output_buffer_capacity(&mut self, _capacity: usize) -> io::Result<()> {
+        // Output is written straight to memory.
+        Ok(())
+    }
+
+    fn resync(&mut self) -> io::Result<()> {
+        if self.raw_mode {
+            self.enter_raw_mode()?;
+        } else {
+            self.enter_cooked_mode()?;
+        }
+        self.reader.clear();
+        Ok(())
+    }
+
+    fn set_scroll_region(&mut self, rows: Option<RangeInclusive<u16>>) -> io::Result<()> {
+        self.modes.set_scroll_region(&mut self.output, rows)
+    }
+
+    fn close(self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Creates an 80x24 memory terminal.
+#[cfg(test)]
+pub(crate) fn test_terminal() -> MemoryTerminal {
+    MemoryTerminal::new(crate::test_size(80, 24)).unwrap()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        event::{KeyCode, KeyEvent},
+        test_size as size,
+    };
+
+    #[test]
+    fn scripted_terminal() {
+        let mut terminal = test_terminal();
+        assert_eq!(terminal.get_dimensions().unwrap(), size(80, 24));
+
+        terminal.push_input(b"a").unwrap();
+        assert!(terminal
+            .poll(|_| true, Some(Duration::from_secs(1)))
+            .unwrap());
+        terminal.push_events([Event::Key(KeyCode::Char('b').into())]);
+        terminal.resize(size(100, 30));
+        let events: Vec<_> = (0..3).map(|_| terminal.read(|_| true).unwrap()).collect();
+        assert_eq!(
+            events,
+            [
+                Event::Key(KeyEvent::from(KeyCode::Char('a'))),
+                Event::Key(KeyCode::Char('b').into()),
+                Event::WindowResized(size(100, 30)),
+            ]
+        );
+        assert!(terminal.get_dimensions().is_ok());
+        terminal.resize(size(0, 30));
+        assert!(terminal.get_dimensions().is_err());
+
+        terminal.enter_raw_mode().unwrap();
+        terminal.enable_line_wrapping(false).unwrap();
+        terminal.enter_cooked_mode().unwrap();
+        assert!(!terminal.is_raw_mode());
+        assert_eq!(terminal.take_output(), b"\x1b[?7l\x1b[?7h");
+    }
+
+    #[test]
+    fn push_events_while_reading() {
+        let mut terminal = test_terminal();
+        let reader = terminal.event_reader();
+        let read = std::thread::spawn(move || reader.read(|_| true));
+        std::thread::sleep(Duration::from_millis(50));
+        terminal.push_events([Event::Key(KeyCode::Char('b').into())]);
+        assert_eq!(
+            read.join().unwrap().unwrap(),
+            Event::Key(KeyCode::Char('b').into())
+        );
+
+        let reader = terminal.event_reader();
+        let read = std::thread::spawn(move || reader.read(|_| true));
+        std::thread::sleep(Duration::from_millis(50));
+        terminal.resize(size(80, 24));
+        assert_eq!(
+            read.join().unwrap().unwrap(),
+            Event::WindowResized(size(80, 24))
+        );
+    }
+
+    #[test]
+    fn release() {
+        let mut terminal = test_terminal();
+        terminal.release().unwrap().reacquire().unwrap();
+        assert!(!terminal.is_raw_mode());
+
+        terminal.enter_raw_mode().unwrap();
+        let released = terminal.release().unwrap();
+        released.reacquire().unwrap();
+        assert!(terminal.is_raw_mode());
+    }
+
+    #[test]
+    fn cached_dimensions() {
+        let mut terminal = test_terminal();
+        assert_eq!(terminal.dimensions_cached().unwrap(), size(80, 24));
+        // A resize is cached as soon as the reader reads it.
+        terminal.resize(size(100, 30));
+        assert_eq!(terminal.dimensions_cached().unwrap(), size(100, 30));
+        // Resets forget the cached size.
+        terminal.dimensions = size(120, 40);
+        assert_eq!(terminal.dimensions_cached().unwrap(), size(100, 30));
+        terminal.soft_reset().unwrap();
+        assert_eq!(terminal.dimensions_cached().unwrap(), size(120, 40));
+        terminal.resize(size(0, 40));
+        assert!(terminal.dimensions_cached().is_err());
+    }
+
+    #[test]
+    fn scroll_region() {
+        let mut terminal = test_terminal();
+        #[allow(clippy::reversed_empty_ranges)]
+        let empty = 5..=4;
+        assert!(terminal.set_scroll_region(Some(empty)).is_err());
+
+        let mut region = terminal.scroll_region(2..=21).unwrap();
+        region.scroll_up(1).unwrap();
+        region.scroll_down(2).unwrap();
+        region.enter_cooked_mode().unwrap();
+        region.enter_raw_mode().unwrap();
+        drop(region);
+        assert_eq!(
+            terminal.take_output(),
+            b"\x1b[3;22r\x1b[S\x1b[2T\x1b[r\x1b[3;22r\x1b[r"
+        );
+
+        // The region is no longer tracked after the guard is dropped.
+        terminal.enter_cooked_mode().unwrap();
+        assert_eq!(terminal.take_output(), b"");
+    }
+
+    #[test]
+    fn cursor_helpers() {
+        use crate::style::CursorStyle;
+
+        let mut terminal = test_terminal();
+        terminal.hide_cursor().unwrap();
+        terminal.move_to(4, 0).unwrap();
+        terminal.set_cursor_style(CursorStyle::SteadyBar).unwrap();
+        terminal.show_cursor().unwrap();
+        assert_eq!(
+            terminal.take_output(),
+            b"\x1b[?25l\x1b[5;1H\x1b[6 q\x1b[?25h"
+        );
+    }
+
+    #[test]
+    fn reset() {
+        let mut terminal = test_terminal();
+        terminal.enter_raw_mode().unwrap();
+        terminal.enable_line_wrapping(false).unwrap();
+        terminal.take_output();
+
+        // A complete key press and the start of a sequence left behind by a child process.
+        terminal.push_input(b"a\x1b[1;").unwrap();
+        assert!(terminal
+            .poll(|_| true, Some(Duration::from_secs(1)))
+            .unwrap());
+        terminal.push_events([Event::Key(KeyCode::Char('b').into())]);
+        terminal.hard_reset().unwrap();
+        assert!(terminal.is_raw_mode());
+        assert_eq!(terminal.take_output(), b"\x1bc\x1b[?7l");
+        assert!(terminal.event_reader().is_empty());
+
+        // The partial sequence was discarded, so this is not read as an arrow key.
+        terminal.push_input(b"A").unwrap();
+        assert!(matches!(
+            terminal.read(|_| true).unwrap(),
+            Event::Key(key) if key.code == KeyCode::Char('A')
+        ));
+
+        terminal.enter_cooked_mode().unwrap();
+        terminal.take_output();
+        terminal.soft_reset().unwrap();
+        assert!(!terminal.is_raw_mode());
+        assert_eq!(terminal.take_output(), b"\x1b[!p\x1b[?7h");
+    }
+
+    #[test]
+    fn theme_from_replies() {
+        use crate::{escape::csi::ThemeMode, style::RgbColor, ThemeReport, ThemeSource};
+
+        let mut terminal = test_terminal();
+        let timeout = Duration::from_secs(1);
+
+        // Without mode 2031 the theme comes from the background color.
+        terminal
+            .push_input(b"\x1b]11;rgb:fdfd/f6f6/e3e3\x1b\\\x1b[?62;22c")
+            .unwrap();
+        assert_eq!(
+            terminal.theme(timeout).unwrap(),
+            Some(ThemeReport {
+                mode: ThemeMode::Light,
+                source: ThemeSource::BackgroundColor(RgbColor::new(0xfd, 0xf6, 0xe3)),
+            })
+        );
+        assert_eq!(terminal.take_output(), b"\x1b[?996n\x1b]11;?\x1b\\\x1b[c");
+
+        // A reported theme takes precedence.
+        terminal
+            .push_input(b"\x1b[?997;1n\x1b]11;rgb:ffff/ffff/ffff\x1b\\\x1b[?62;22c")
+            .unwrap();
+        let report = terminal.theme(timeout).unwrap().unwrap();
+        assert_eq!(report.mode, ThemeMode::Dark);
+        assert_eq!(report.source, ThemeSource::Reported);
+
+        // Terminals which answer neither query only answer the device attributes.
+        terminal.push_input(b"\x1b[?1;2c").unwrap();
+        assert_eq!(terminal.theme(timeout).unwrap(), None);
+    }
+
+    #[test]
+    fn query_reply() {
+        use crate::escape::csi::{Csi, Keyboard, KittyKeyboardFlags, Mode, ThemeMode};
+
+        let mut terminal = test_terminal();
+        let timeout = Duration::from_millis(100);
+
+        // Input before the reply stays buffered.
+        terminal.push_input(b"a\x1b[?997;2n").unwrap();
+        let mode: Option<ThemeMode> = terminal
+            .query(Csi::Mode(Mode::QueryTheme), timeout)
+            .unwrap();
+        assert_eq!(mode, Some(ThemeMode::Light));
+        assert_eq!(terminal.take_output(), b"\x1b[?996n");
+        assert!(matches!(terminal.read(|_| true).unwrap(), Event::Key(_)));
+
+        terminal.push_input(b"\x1b[?5u").unwrap();
+        let flags: Option<KittyKeyboardFlags> = terminal
+            .query(Csi::Keyboard(Keyboard::QueryFlags), timeout)
+            .unwrap();
+        assert_eq!(
+            flags,
+            Some(
+                KittyKeyboardFlags::DISAMBIGUATE_ESCAPE_CODES
+                    | KittyKeyboardFlags::REPORT_ALTERNATE_KEYS
+            )
+        );
+
+        let flags: Option<KittyKeyboardFlags> = terminal
+            .query(Csi::Keyboard(Keyboard::QueryFlags), Duration::ZERO)
+            .unwrap();
+        assert_eq!(flags, None);
+    }
+}
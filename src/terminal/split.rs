@@ -0,0 +1,95 @@
+//! The read and write halves of a terminal, returned by [`Terminal::split`].
+
+use std::{
+    io,
+    ops::{Deref, DerefMut},
+    time::Duration,
+};
+
+use crate::{Event, EventReader};
+
+use super::Terminal;
+
+/// The read half of a terminal, returned by [`Terminal::split`].
+///
+/// It reads events like the terminal's own [`Terminal::poll`] and [`Terminal::read`] and can be
+/// moved to another thread or task. Clones read from the same [`EventReader`], so each event is
+/// returned to only one of them. The input keeps reading after the [`TerminalOutput`] is dropped,
+/// but the terminal is no longer in raw mode then.
+#[derive(Debug, Clone)]
+pub struct TerminalInput {
+    reader: EventReader,
+}
+
+impl TerminalInput {
+    /// Checks if an [`Event`] matching `filter` is available. See [`Terminal::poll`].
+    pub fn poll<F: Fn(&Event) -> bool>(
+        &self,
+        filter: F,
+        timeout: Option<Duration>,
+    ) -> io::Result<bool> {
+        self.reader.poll(timeout, filter)
+    }
+
+    /// Reads a single [`Event`] matching `filter`. See [`Terminal::read`].
+    pub fn read<F: Fn(&Event) -> bool>(&self, filter: F) -> io::Result<Event> {
+        self.reader.read(filter)
+    }
+
+    /// Returns the reader this input reads from, for the rest of the [`EventReader`] API such as
+    /// [`EventReader::read_timeout`] and [`EventReader::waker`].
+    pub fn event_reader(&self) -> EventReader {
+        self.reader.clone()
+    }
+}
+
+/// The write half of a terminal, returned by [`Terminal::split`].
+///
+/// It owns the terminal and dereferences to it, so output and mode changes such as
+/// [`Terminal::enter_raw_mode`] go through it as they would through the terminal. Dropping it
+/// tears the terminal down. The terminal's [`Terminal::poll`] and [`Terminal::read`] stay
+/// reachable through it and read from the same reader as the [`TerminalInput`], so an event is
+/// returned to only one of them.
+#[derive(Debug)]
+pub struct TerminalOutput<T: Terminal> {
+    terminal: T,
+}
+
+impl<T: Terminal> TerminalOutput<T> {
+    /// Returns the terminal. The [`TerminalInput`] keeps reading from the same reader.
+    pub fn into_inner(self) -> T {
+        self.terminal
+    }
+}
+
+impl<T: Terminal> Deref for TerminalOutput<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.terminal
+    }
+}
+
+impl<T: Terminal> DerefMut for TerminalOutput<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.terminal
+    }
+}
+
+impl<T: Terminal> io::Write for TerminalOutput<T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.terminal.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.terminal.flush()
+    }
+}
+
+/// Splits `terminal` for [`Terminal::split`].
+pub(super) fn split<T: Terminal>(terminal: T) -> (TerminalInput, TerminalOutput<T>) {
+    let input = TerminalInput {
+        reader: terminal.event_reader(),
+    };
+    (input, TerminalOutput { terminal })
+}
@@ -2,12 +2,13 @@ use rustix::termios::{self, Termios};
 use std::{
     fs,
     io::{self, BufWriter, IsTerminal as _, Write as _},
+    ops::RangeInclusive,
     os::unix::prelude::*,
 };
 
 use crate::{event::source::UnixEventSource, Event, EventReader, WindowSize};
 
-use super::Terminal;
+use super::{writer::SharedOutput, OutputTarget, Terminal, TerminalWriter, TrackedModes};
 
 const BUF_SIZE: usize = 4096;
 
@@ -15,6 +16,10 @@ const BUF_SIZE: usize = 4096;
 // rewritten with `rustix` instead of `libc`.
 // <https://github.com/wezterm/wezterm/blob/a87358516004a652ad840bc1661bdf65ffc89b43/filedescriptor/src/unix.rs>
 
+/// A Unix file descriptor for terminal input or output.
+///
+/// Pass file descriptors to `UnixTerminal::open_with` to drive a terminal other than the
+/// process's own. Owned file descriptors are closed when the terminal is dropped.
 #[derive(Debug)]
 pub enum FileDescriptor {
     /// A file descriptor owned by Termina.
@@ -40,6 +45,9 @@ impl FileDescriptor {
     /// The process stdout file descriptor.
     pub const STDOUT: Self = Self::Borrowed(rustix::stdio::stdout());
 
+    /// The process stderr file descriptor.
+    pub const STDERR: Self = Self::Borrowed(rustix::stdio::stderr());
+
     fn try_clone(&self) -> io::Result<Self> {
         let this = match self {
             Self::Owned(fd) => Self::Owned(fd.try_clone()?),
@@ -49,6 +57,18 @@ impl FileDescriptor {
     }
 }
 
+impl From<OwnedFd> for FileDescriptor {
+    fn from(fd: OwnedFd) -> Self {
+        Self::Owned(fd)
+    }
+}
+
+impl From<fs::File> for FileDescriptor {
+    fn from(file: fs::File) -> Self {
+        Self::Owned(file.into())
+    }
+}
+
 impl io::Read for FileDescriptor {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         let read = rustix::io::read(&self, buf)?;
@@ -67,16 +87,16 @@ impl io::Write for FileDescriptor {
     }
 }
 
-fn open_pty() -> io::Result<(FileDescriptor, FileDescriptor)> {
+fn open_pty(output: OutputTarget) -> io::Result<(FileDescriptor, FileDescriptor)> {
     let read = if io::stdin().is_terminal() {
         FileDescriptor::STDIN
     } else {
         open_dev_tty()?
     };
-    let write = if io::stdout().is_terminal() {
-        FileDescriptor::STDOUT
-    } else {
-        open_dev_tty()?
+    let write = match output {
+        OutputTarget::Stdout if io::stdout().is_terminal() => FileDescriptor::STDOUT,
+        OutputTarget::Stderr if io::stderr().is_terminal() => FileDescriptor::STDERR,
+        _ => open_dev_tty()?,
     };
 
     // Activate non-blocking mode for the reader.
@@ -91,7 +111,7 @@ fn open_dev_tty() -> io::Result<FileDescriptor> {
         .read(true)
         .write(true)
         .open("/dev/tty")?;
-    Ok(FileDescriptor::Owned(file.into()))
+    Ok(file.into())
 }
 
 impl From<termios::Winsize> for WindowSize {
@@ -107,8 +127,8 @@ impl From<termios::Winsize> for WindowSize {
 
 /// Unix terminal handle.
 ///
-/// `UnixTerminal` writes to stdout or `/dev/tty`, reads events from stdin or `/dev/tty`, and
-/// restores the captured termios state when dropped.
+/// `UnixTerminal` writes to stdout, stderr or `/dev/tty`, reads events from stdin or `/dev/tty`,
+/// and restores the captured termios state when dropped.
 ///
 /// # Implementation Notes
 ///
@@ -124,11 +144,23 @@ impl From<termios::Winsize> for WindowSize {
 pub struct UnixTerminal {
     /// Shared wrapper around the reader (stdin or `/dev/tty`)
     reader: EventReader,
-    /// Buffered handle to the writer (stdout or `/dev/tty`)
+    /// Buffered handle to the writer (stdout, stderr or `/dev/tty`)
     write: BufWriter<FileDescriptor>,
-    /// The termios of the PTY's writer detected during `Self::new`.
-    original_termios: Termios,
+    /// The output of the writers returned by `Self::writer`.
+    shared_output: SharedOutput,
+    /// The termios of the PTY's writer detected during `Self::new`, or `None` if the output is
+    /// not a terminal.
+    original_termios: Option<Termios>,
+    /// The size reported when the window size can't be read.
+    fallback_size: Option<WindowSize>,
+    /// Modes restored on drop and in the panic hook.
+    modes: TrackedModes,
+    /// Whether the terminal is in raw mode, so `Self::suspend` and `Terminal::resync` can return
+    /// to it.
+    raw_mode: bool,
     has_panic_hook: bool,
+    /// Set once the terminal has been torn down, so `Drop` doesn't repeat it.
+    closed: bool,
 }
 
 impl UnixTerminal {
@@ -137,7 +169,45 @@ impl UnixTerminal {
     /// If stdin or stdout is not a terminal, Termina opens `/dev/tty` for that side. The original
     /// termios state is captured so [`Terminal::enter_cooked_mode`] and `Drop` can restore it.
     pub fn new() -> io::Result<Self> {
-        let (read, write) = open_pty()?;
+        Self::new_with_output(OutputTarget::Stdout)
+    }
+
+    /// Opens the Unix terminal like [`Self::new`], writing to the chosen standard stream.
+    ///
+    /// Raw mode and the window size also use the chosen stream, or `/dev/tty` if it is not a
+    /// terminal.
+    pub fn new_with_output(output: OutputTarget) -> io::Result<Self> {
+        let (read, write) = open_pty(output)?;
+        Self::open_with(read, write)
+    }
+
+    /// Opens a terminal which reads events from `read` and writes to `write`.
+    ///
+    /// Use this to drive a terminal other than the process's own, such as the PTY behind a
+    /// multiplexer pane or a serial port. Both handles may refer to the same file. `write` must be
+    /// a terminal device: its termios state is changed by raw mode and restored on drop, and the
+    /// window size is read from it.
+    ///
+    /// Resize events come from `SIGWINCH`, which the kernel only sends for the process's
+    /// controlling terminal. For other terminals, poll [`Terminal::get_dimensions`] or forward the
+    /// size from wherever it is reported, such as an SSH window-change request.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::fs;
+    ///
+    /// use termina::{FileDescriptor, PlatformTerminal, Terminal as _};
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let tty = fs::OpenOptions::new().read(true).write(true).open("/dev/ttyUSB0")?;
+    /// let read = FileDescriptor::from(tty.try_clone()?);
+    /// let mut terminal = PlatformTerminal::open_with(read, tty.into())?;
+    /// terminal.enter_raw_mode()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn open_with(read: FileDescriptor, write: FileDescriptor) -> io::Result<Self> {
         let source = UnixEventSource::new(read, write.try_clone()?)?;
         let original_termios = termios::tcgetattr(&write)?;
         let reader = EventReader::new(source);
@@ -145,62 +215,167 @@ impl UnixTerminal {
         Ok(Self {
             reader,
             write: BufWriter::with_capacity(BUF_SIZE, write),
-            original_termios,
+            original_termios: Some(original_termios),
+            fallback_size: None,
+            modes: TrackedModes::default(),
+            raw_mode: false,
+            has_panic_hook: false,
+            closed: false,
+            shared_output: SharedOutput::default(),
+        })
+    }
+
+    /// Opens the Unix terminal like [`Self::new`], or falls back to a terminal which only writes
+    /// to stdout if no terminal can be opened.
+    ///
+    /// This lets programs run their non-interactive paths, such as in CI or when every stream is
+    /// redirected, through the same code. The fallback terminal never reports events, ignores raw
+    /// and cooked mode, and reports `fallback_size` as its dimensions. An interactive terminal
+    /// also reports `fallback_size` if the window size can't be read. Use
+    /// [`Self::is_interactive`] to tell the two apart.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::io::Write as _;
+    ///
+    /// use termina::{PlatformTerminal, Terminal as _, WindowSize};
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let fallback = WindowSize {
+    ///     rows: 24,
+    ///     cols: 80,
+    ///     pixel_width: None,
+    ///     pixel_height: None,
+    /// };
+    /// let mut terminal = PlatformTerminal::new_lenient(fallback)?;
+    /// let size = terminal.get_dimensions()?;
+    /// if terminal.is_interactive() {
+    ///     terminal.enter_raw_mode()?;
+    /// }
+    /// writeln!(terminal, "drawing {}x{}", size.cols, size.rows)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new_lenient(fallback_size: WindowSize) -> io::Result<Self> {
+        match Self::new() {
+            Ok(mut terminal) => {
+                terminal.fallback_size = Some(fallback_size);
+                terminal.reader.set_fallback_size(Some(fallback_size));
+                Ok(terminal)
+            }
+            Err(_) => Self::headless(FileDescriptor::STDOUT, fallback_size),
+        }
+    }
+
+    /// Creates a terminal which writes to `write` and never reads input.
+    fn headless(write: FileDescriptor, fallback_size: WindowSize) -> io::Result<Self> {
+        let mut source = UnixEventSource::idle(write.try_clone()?)?;
+        source.set_fallback_size(Some(fallback_size));
+        Ok(Self {
+            reader: EventReader::new(source),
+            write: BufWriter::with_capacity(BUF_SIZE, write),
+            original_termios: None,
+            fallback_size: Some(fallback_size),
+            modes: TrackedModes::default(),
+            raw_mode: false,
             has_panic_hook: false,
+            closed: false,
+            shared_output: SharedOutput::default(),
         })
     }
+
+    /// Checks whether this is an interactive terminal.
+    ///
+    /// This is `false` only for the fallback terminal created by [`Self::new_lenient`].
+    pub fn is_interactive(&self) -> bool {
+        self.original_termios.is_some()
+    }
+
+    /// Returns a handle which writes to this terminal's output from any thread.
+    ///
+    /// The writer has its own buffer and duplicates the output handle, so it can write while this
+    /// terminal is borrowed elsewhere, such as by a thread blocked reading events. See
+    /// [`TerminalWriter`].
+    pub fn writer(&self) -> io::Result<TerminalWriter> {
+        self.shared_output
+            .writer(|| self.write.get_ref().try_clone())
+    }
+
+    /// Stops the process like Ctrl+Z in a shell, restoring the terminal while it is stopped.
+    ///
+    /// In raw mode Ctrl+Z is read as a key press instead of stopping the process, so applications
+    /// call this when they see it. The terminal enters cooked mode, which also restores the modes
+    /// tracked by `Terminal` helpers, and the process stops itself with `SIGSTOP`. This returns
+    /// once the shell continues the process (`fg`), after returning to raw mode if the terminal
+    /// was in raw mode before.
+    ///
+    /// Application-level modes such as the alternate screen are not tracked by the terminal; leave
+    /// and re-enter them around this call, or use [`Session::suspend`](crate::Session::suspend).
+    /// The screen may have been changed and resized while the process was stopped, so redraw
+    /// afterwards. The event reader also reports the continue as [`Event::Resumed`].
+    pub fn suspend(&mut self) -> io::Result<()> {
+        let raw_mode = self.raw_mode;
+        self.enter_cooked_mode()?;
+        signal_hook::low_level::raise(signal_hook::consts::SIGSTOP)?;
+        if raw_mode {
+            self.enter_raw_mode()?;
+        }
+        Ok(())
+    }
+
+    fn teardown(&mut self) -> io::Result<()> {
+        self.closed = true;
+        let flushed = self.flush();
+        let restored = self.enter_cooked_mode();
+        flushed.and(restored)
+    }
 }
 
 impl Terminal for UnixTerminal {
     fn enter_raw_mode(&mut self) -> io::Result<()> {
-        let mut termios = termios::tcgetattr(self.write.get_ref())?;
-        termios.make_raw();
-        termios::tcsetattr(
-            self.write.get_ref(),
-            termios::OptionalActions::Flush,
-            &termios,
-        )?;
+        if self.is_interactive() {
+            let mut termios = termios::tcgetattr(self.write.get_ref())?;
+            termios.make_raw();
+            termios::tcsetattr(
+                self.write.get_ref(),
+                termios::OptionalActions::Flush,
+                &termios,
+            )?;
+        }
+        self.raw_mode = true;
+        trace!("entered raw mode");
+        self.modes.reapply(&mut self.write)?;
 
         Ok(())
     }
 
     fn enter_cooked_mode(&mut self) -> io::Result<()> {
-        termios::tcsetattr(
-            self.write.get_ref(),
-            termios::OptionalActions::Now,
-            &self.original_termios,
-        )?;
-        Ok(())
+        // Restore the termios even if the mode sequences couldn't be written.
+        let output = self.shared_output.lock();
+        let restored = self
+            .modes
+            .restore(&mut self.write)
+            .and_then(|()| self.write.flush());
+        drop(output);
+        if let Some(original_termios) = &self.original_termios {
+            termios::tcsetattr(
+                self.write.get_ref(),
+                termios::OptionalActions::Now,
+                original_termios,
+            )?;
+        }
+        self.raw_mode = false;
+        trace!("entered cooked mode");
+        restored
+    }
+
+    fn is_raw_mode(&self) -> bool {
+        self.raw_mode
     }
 
     fn get_dimensions(&self) -> io::Result<WindowSize> {
-        let winsize = termios::tcgetwinsize(self.write.get_ref())?;
-        let mut size: WindowSize = winsize.into();
-        // Over a serial connection for example, the ioctl may quietly fail by returning zeroed
-        // rows and columns. Fall back to reading LINES/COLUMNS.
-        // <https://github.com/vim/vim/blob/b88f9e4a04ce9fb70abb7cdae17688aa4f49c8c9/src/os_unix.c#L4349-L4370>
-        if size.cols == 0 || size.rows == 0 {
-            if let Some(rows) = std::env::var("LINES")
-                .ok()
-                .and_then(|l| l.parse::<u16>().ok())
-            {
-                size.rows = rows;
-            }
-            if let Some(cols) = std::env::var("COLUMNS")
-                .ok()
-                .and_then(|c| c.parse::<u16>().ok())
-            {
-                size.cols = cols;
-            }
-        }
-        if size.cols == 0 || size.rows == 0 {
-            Err(io::Error::new(
-                io::ErrorKind::Other,
-                "cannot read non-zero cols/rows from ioctl or COLUMNS/LINES environment variables",
-            ))
-        } else {
-            Ok(size)
-        }
+        window_size(self.write.get_ref().as_fd(), self.fallback_size)
     }
 
     fn event_reader(&self) -> EventReader {
@@ -221,33 +396,225 @@ impl Terminal for UnixTerminal {
 
     fn set_panic_hook(&mut self, f: impl Fn(&mut FileDescriptor) + Send + Sync + 'static) {
         let original_termios = self.original_termios.clone();
+        let modes = self.modes.clone();
+        // Write to the terminal's own output, which may not be stdout.
+        let output = self.write.get_ref().try_clone().ok();
         let hook = std::panic::take_hook();
         std::panic::set_hook(Box::new(move |info| {
-            if let Ok((_read, mut write)) = open_pty() {
+            if let Some(Ok(mut write)) = output.as_ref().map(FileDescriptor::try_clone) {
                 f(&mut write);
-                let _ = termios::tcsetattr(write, termios::OptionalActions::Now, &original_termios);
+                let _ = modes.restore(&mut write);
+                if let Some(original_termios) = &original_termios {
+                    let _ =
+                        termios::tcsetattr(write, termios::OptionalActions::Now, original_termios);
+                }
             }
             hook(info);
         }));
         self.has_panic_hook = true;
     }
+
+    fn enable_line_wrapping(&mut self, enabled: bool) -> io::Result<()> {
+        self.modes.enable_line_wrapping(&mut self.write, enabled)
+    }
+
+    fn enable_grapheme_clustering(&mut self, enabled: bool) -> io::Result<()> {
+        self.modes
+            .enable_grapheme_clustering(&mut self.write, enabled)
+    }
+
+    fn push_title(&mut self) -> io::Result<()> {
+        self.modes.push_title(&mut self.write)
+    }
+
+    fn pop_title(&mut self) -> io::Result<()> {
+        self.modes.pop_title(&mut self.write)
+    }
+
+    fn set_title_stack_supported(&mut self, supported: bool) {
+        self.modes.set_title_stack_supported(supported);
+    }
+
+    fn set_output_buffer_capacity(&mut self, capacity: usize) -> io::Result<()> {
+        let _output = self.shared_output.lock();
+        super::set_buffer_capacity(&mut self.write, capacity, FileDescriptor::STDOUT)
+    }
+
+    fn resync(&mut self) -> io::Result<()> {
+        self.flush()?;
+        if self.is_interactive() {
+            termios::tcflush(self.write.get_ref(), termios::QueueSelector::IFlush)?;
+        }
+        if self.raw_mode {
+            self.enter_raw_mode()?;
+        } else {
+            self.enter_cooked_mode()?;
+        }
+        // Input read before the flush may still be buffered or partially parsed.
+        self.reader.clear();
+        trace!(raw_mode = self.raw_mode, "resynchronized terminal");
+        Ok(())
+    }
+
+    fn set_scroll_region(&mut self, rows: Option<RangeInclusive<u16>>) -> io::Result<()> {
+        self.modes.set_scroll_region(&mut self.write, rows)
+    }
+
+    fn close(mut self) -> io::Result<()> {
+        self.teardown()
+    }
 }
 
 impl Drop for UnixTerminal {
     fn drop(&mut self) {
-        if !self.has_panic_hook || !std::thread::panicking() {
-            let _ = self.flush();
-            let _ = self.enter_cooked_mode();
+        if !self.closed && (!self.has_panic_hook || !std::thread::panicking()) {
+            let _ = self.teardown();
         }
     }
 }
 
 impl io::Write for UnixTerminal {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // A full buffer is flushed here, which must not interleave with a writer's flush.
+        let _output = self.shared_output.lock();
         self.write.write(buf)
     }
 
     fn flush(&mut self) -> io::Result<()> {
+        let _output = self.shared_output.lock();
         self.write.flush()
     }
 }
+
+/// Reads the window size of the terminal `fd` or from `LINES` and `COLUMNS`, or returns
+/// `fallback` if neither has a usable size.
+///
+/// Both [`UnixTerminal::get_dimensions`] and the sizes reported by [`Event::WindowResized`] come
+/// from here so that the two always agree.
+pub(crate) fn window_size(
+    fd: BorrowedFd<'_>,
+    fallback: Option<WindowSize>,
+) -> io::Result<WindowSize> {
+    match (terminal_dimensions(fd), fallback) {
+        (Err(_), Some(size)) => Ok(size),
+        (result, _) => result,
+    }
+}
+
+/// Reads the window size from the terminal or from `LINES` and `COLUMNS`.
+fn terminal_dimensions(fd: BorrowedFd<'_>) -> io::Result<WindowSize> {
+    let winsize = termios::tcgetwinsize(fd)?;
+    let mut size: WindowSize = winsize.into();
+    // Over a serial connection for example, the ioctl may quietly fail by returning zeroed
+    // rows and columns. Fall back to reading LINES/COLUMNS.
+    // <https://github.com/vim/vim/blob/b88f9e4a04ce9fb70abb7cdae17688aa4f49c8c9/src/os_unix.c#L4349-L4370>
+    if size.cols == 0 || size.rows == 0 {
+        if let Some(rows) = std::env::var("LINES")
+            .ok()
+            .and_then(|l| l.parse::<u16>().ok())
+        {
+            size.rows = rows;
+        }
+        if let Some(cols) = std::env::var("COLUMNS")
+            .ok()
+            .and_then(|c| c.parse::<u16>().ok())
+        {
+            size.cols = cols;
+        }
+    }
+    if size.cols == 0 || size.rows == 0 {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "cannot read non-zero cols/rows from ioctl or COLUMNS/LINES environment variables",
+        ))
+    } else {
+        Ok(size)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{io::Read as _, os::unix::net::UnixStream, time::Duration};
+
+    use super::*;
+    use crate::test_size;
+
+    /// Creates an 80x24 headless terminal, returning it and the socket its output goes to.
+    fn headless_terminal() -> (UnixTerminal, UnixStream) {
+        let (write, output) = UnixStream::pair().unwrap();
+        let terminal = UnixTerminal::headless(
+            FileDescriptor::from(OwnedFd::from(write)),
+            test_size(80, 24),
+        )
+        .unwrap();
+        (terminal, output)
+    }
+
+    #[test]
+    fn headless() {
+        let (mut terminal, mut output) = headless_terminal();
+        assert!(!terminal.is_interactive());
+        assert_eq!(terminal.get_dimensions().unwrap(), test_size(80, 24));
+
+        terminal.enter_raw_mode().unwrap();
+        assert!(!terminal.poll(|_| true, Some(Duration::ZERO)).unwrap());
+        write!(terminal, "plain").unwrap();
+        terminal.close().unwrap();
+
+        let mut written = String::new();
+        output.read_to_string(&mut written).unwrap();
+        assert_eq!(written, "plain");
+    }
+
+    #[test]
+    fn output_buffer_capacity() {
+        let (mut terminal, mut output) = headless_terminal();
+        output.set_nonblocking(true).unwrap();
+        let mut read = || {
+            let mut buf = [0; 64];
+            match output.read(&mut buf) {
+                Ok(n) => buf[..n].to_vec(),
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => Vec::new(),
+                Err(err) => panic!("{err}"),
+            }
+        };
+
+        // Buffered output is flushed when the capacity changes.
+        write!(terminal, "buffered").unwrap();
+        assert_eq!(read(), b"");
+        terminal.set_output_buffer_capacity(0).unwrap();
+        assert_eq!(read(), b"buffered");
+
+        // Without a buffer, output is written straight through.
+        write!(terminal, "direct").unwrap();
+        assert_eq!(read(), b"direct");
+
+        terminal.set_output_buffer_capacity(1024).unwrap();
+        write!(terminal, "{}", "x".repeat(1000)).unwrap();
+        assert_eq!(read(), b"");
+        terminal.flush().unwrap();
+        assert_eq!(read().len(), 64);
+    }
+
+    #[test]
+    fn shared_writer() {
+        let (mut terminal, mut output) = headless_terminal();
+        let mut writer = terminal.writer().unwrap();
+
+        write!(terminal, "main ").unwrap();
+        // The writer's output is sent whole when it flushes, from another thread.
+        let thread = std::thread::spawn(move || {
+            write!(writer, "progress").unwrap();
+            let mut clone = writer.clone();
+            writer.flush().unwrap();
+            write!(clone, " done").unwrap();
+        });
+        thread.join().unwrap();
+        terminal.flush().unwrap();
+        terminal.close().unwrap();
+
+        let mut written = String::new();
+        output.read_to_string(&mut written).unwrap();
+        assert_eq!(written, "progress donemain ");
+    }
+}
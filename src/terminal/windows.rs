@@ -1,28 +1,40 @@
+mod legacy;
+
 use std::{
     fmt,
     fs::{self, File},
     io::{self, BufWriter, IsTerminal as _, Write as _},
     mem,
+    ops::RangeInclusive,
     os::windows::prelude::*,
     ptr,
 };
 
 use windows_sys::Win32::{
-    Storage::FileSystem::WriteFile,
+    Storage::FileSystem::{
+        FileNameInfo, GetFileInformationByHandleEx, GetFileType, ReadFile, WriteFile,
+        FILE_TYPE_PIPE,
+    },
     System::Console::{
-        self, FlushConsoleInputBuffer, GetConsoleCP, GetConsoleMode, GetConsoleOutputCP,
+        self, FillConsoleOutputAttribute, FillConsoleOutputCharacterW, FlushConsoleInputBuffer,
+        GetConsoleCP, GetConsoleCursorInfo, GetConsoleMode, GetConsoleOutputCP,
         GetConsoleScreenBufferInfo, GetNumberOfConsoleInputEvents, ReadConsoleInputA,
-        ReadConsoleInputW, SetConsoleCP, SetConsoleMode, SetConsoleOutputCP, CONSOLE_MODE,
-        CONSOLE_SCREEN_BUFFER_INFO, INPUT_RECORD,
+        ReadConsoleInputW, SetConsoleCP, SetConsoleCursorInfo, SetConsoleCursorPosition,
+        SetConsoleMode, SetConsoleOutputCP, SetConsoleTextAttribute, WriteConsoleW,
+        CONSOLE_CURSOR_INFO, CONSOLE_MODE, CONSOLE_SCREEN_BUFFER_INFO, COORD, INPUT_RECORD,
+        SMALL_RECT,
     },
+    System::{Pipes::PeekNamedPipe, Threading::CreateEventW},
 };
 
 use crate::{
-    event::source::WindowsEventSource, windows::InputReaderMode, Event, EventReader, OneBased,
-    WindowSize,
+    event::source::WindowsEventSource, parse::incomplete_char_len, windows::InputReaderMode, Event,
+    EventReader, OneBased, WindowSize,
 };
 
-use super::Terminal;
+use super::{writer::SharedOutput, OutputTarget, Terminal, TerminalWriter, TrackedModes};
+
+use self::legacy::{Op, Translator};
 
 macro_rules! bail {
     ($msg:literal $(,)?) => {
@@ -33,7 +45,13 @@ macro_rules! bail {
     };
 }
 
-const BUF_SIZE: usize = 128;
+/// The number of input records read from the console at a time.
+const INPUT_BUF_SIZE: usize = 128;
+/// The number of bytes read from an MSYS pty pipe at a time.
+const PIPE_BUF_SIZE: usize = 1024;
+/// The default output buffer capacity. Each flush of the buffer is a `WriteFile` call, which is
+/// slow enough under ConPTY that small buffers make full-screen redraws flicker.
+const OUTPUT_BUF_SIZE: usize = 64 * 1024;
 
 type CodePageID = u32;
 /// The code page ID for UTF-8 encoding.
@@ -50,6 +68,10 @@ const CP_UTF8: CodePageID = 65001;
 // This crate however uses `windows-sys` instead of `winapi` and has a slightly different API for
 // the `InputHandle` and `OutputHandle`.
 
+/// A Windows handle for console input or output.
+///
+/// Pass handles to [`WindowsTerminal::open_with`] to drive a console other than the process's
+/// own. Owned handles are closed when the terminal is dropped.
 #[derive(Debug)]
 pub enum Handle {
     /// A Windows handle owned by Termina.
@@ -84,6 +106,12 @@ impl Handle {
         Self::Borrowed(unsafe { BorrowedHandle::borrow_raw(stdout) })
     }
 
+    /// Returns a borrowed handle for process stderr.
+    pub fn stderr() -> Self {
+        let stderr = io::stderr().as_raw_handle();
+        Self::Borrowed(unsafe { BorrowedHandle::borrow_raw(stderr) })
+    }
+
     pub fn try_clone(&self) -> io::Result<Self> {
         let this = match self {
             Self::Owned(handle) => Self::Owned(handle.try_clone()?),
@@ -99,10 +127,20 @@ impl From<File> for Handle {
     }
 }
 
+impl From<OwnedHandle> for Handle {
+    fn from(handle: OwnedHandle) -> Self {
+        Self::Owned(handle)
+    }
+}
+
 pub(crate) struct InputHandle {
     handle: Handle,
     input_buf: Vec<INPUT_RECORD>,
     mode: InputReaderMode,
+    /// Whether the handle is the pipe of an MSYS pty, which delivers bytes rather than input
+    /// records. See [`ConsoleKind::MsysPty`].
+    pipe: bool,
+    pipe_buf: Vec<u8>,
 }
 
 impl fmt::Debug for InputHandle {
@@ -115,14 +153,27 @@ impl fmt::Debug for InputHandle {
 
 impl InputHandle {
     fn new(handle: Handle, mode: InputReaderMode) -> Self {
-        let mut input_buf = Vec::with_capacity(BUF_SIZE);
+        let mut input_buf = Vec::with_capacity(INPUT_BUF_SIZE);
         let zeroed: INPUT_RECORD = unsafe { mem::zeroed() };
-        input_buf.resize(BUF_SIZE, zeroed);
+        input_buf.resize(INPUT_BUF_SIZE, zeroed);
 
         Self {
             handle,
             mode,
             input_buf,
+            pipe: false,
+            pipe_buf: Vec::new(),
+        }
+    }
+
+    /// Creates an input handle for the pipe of an MSYS pty.
+    fn pipe(handle: Handle) -> Self {
+        Self {
+            handle,
+            mode: InputReaderMode::Vte,
+            input_buf: Vec::new(),
+            pipe: true,
+            pipe_buf: vec![0; PIPE_BUF_SIZE],
         }
     }
 
@@ -131,9 +182,16 @@ impl InputHandle {
             handle: self.handle.try_clone()?,
             input_buf: self.input_buf.clone(),
             mode: self.mode,
+            pipe: self.pipe,
+            pipe_buf: self.pipe_buf.clone(),
         })
     }
 
+    /// Returns `true` if this is the pipe of an MSYS pty, which can't be waited on.
+    pub fn is_pipe(&self) -> bool {
+        self.pipe
+    }
+
     fn get_mode(&self) -> io::Result<CONSOLE_MODE> {
         let mut mode = 0;
         if unsafe { GetConsoleMode(self.as_raw_handle(), &mut mode) } == 0 {
@@ -146,6 +204,7 @@ impl InputHandle {
     }
 
     fn set_mode(&mut self, mode: CONSOLE_MODE) -> io::Result<()> {
+        trace!(mode, "setting input console mode");
         if unsafe { SetConsoleMode(self.as_raw_handle(), mode) } == 0 {
             bail!(
                 "failed to set input console mode: {}",
@@ -178,6 +237,26 @@ impl InputHandle {
     }
 
     pub fn has_pending_input_events(&mut self) -> io::Result<bool> {
+        if self.pipe {
+            let mut available = 0;
+            if unsafe {
+                PeekNamedPipe(
+                    self.as_raw_handle(),
+                    ptr::null_mut(),
+                    0,
+                    ptr::null_mut(),
+                    &mut available,
+                    ptr::null_mut(),
+                )
+            } == 0
+            {
+                bail!(
+                    "failed to peek at the input pipe: {}",
+                    io::Error::last_os_error()
+                );
+            }
+            return Ok(available > 0);
+        }
         let mut num = 0;
         // Since we use UTF-8 code pages and call ReadConsoleInputA to read UTF-8 data,
         // we can't rely on the result from GetNumberOfConsoleInputEvents.
@@ -208,7 +287,14 @@ impl InputHandle {
         Ok(())
     }
 
+    /// Reads the queued input records.
+    ///
+    /// This blocks while the console input buffer is empty, so check
+    /// [`Self::has_pending_input_events`] first. The result may be empty: the read can return
+    /// without records, and it is retried later if it was aborted.
     pub fn read_console_input(&mut self) -> io::Result<&[INPUT_RECORD]> {
+        use windows_sys::Win32::Foundation::ERROR_OPERATION_ABORTED;
+
         let mut num = 0;
         // NOTE: <https://learn.microsoft.com/en-us/windows/console/classic-vs-vt#unicode>
         // > UTF-8 support in the console can be utilized via the A variant of Console APIs
@@ -235,14 +321,40 @@ impl InputHandle {
                 }
             }
         } == 0
+        {
+            let err = io::Error::last_os_error();
+            // The read is aborted when the console cancels pending I/O, for example on Ctrl+C
+            // while processed input is enabled. No records were consumed, so read again later.
+            if err.raw_os_error() != Some(ERROR_OPERATION_ABORTED as i32) {
+                bail!("failed to read console input events: {}", err);
+            }
+            num = 0;
+        }
+        unsafe { self.input_buf.set_len(num as usize) };
+        Ok(&self.input_buf)
+    }
+
+    /// Reads the bytes queued in the pipe of an MSYS pty.
+    ///
+    /// This blocks while the pipe is empty, so check [`Self::has_pending_input_events`] first.
+    pub fn read_pipe(&mut self) -> io::Result<&[u8]> {
+        let mut num = 0;
+        if unsafe {
+            ReadFile(
+                self.as_raw_handle(),
+                self.pipe_buf.as_mut_ptr(),
+                self.pipe_buf.len() as u32,
+                &mut num,
+                ptr::null_mut(),
+            )
+        } == 0
         {
             bail!(
-                "failed to read console input events: {}",
+                "failed to read the input pipe: {}",
                 io::Error::last_os_error()
             );
         }
-        unsafe { self.input_buf.set_len(num as usize) };
-        Ok(&self.input_buf)
+        Ok(&self.pipe_buf[..num as usize])
     }
 }
 
@@ -254,16 +366,63 @@ impl AsRawHandle for InputHandle {
 
 /// Windows console output handle.
 ///
-/// `OutputHandle` writes bytes with `WriteFile` after `WindowsTerminal` enables virtual-terminal
-/// output processing. Panic hooks receive this type so they can write cleanup escape sequences.
+/// `OutputHandle` writes to a console with `WriteConsoleW`, converting the UTF-8 output to UTF-16,
+/// so that text is written intact whatever the console output code page. Older console hosts
+/// mangle some characters written as UTF-8 with `WriteFile`, even with the UTF-8 code page which
+/// `WindowsTerminal` sets. Other handles, such as redirected output, are written with `WriteFile`.
+/// Virtual-terminal output processing applies either way. On a
+/// [legacy console][ConsoleKind::Legacy] a core subset of escape sequences is translated to
+/// Console API calls instead. Panic hooks receive this type so they can write cleanup escape
+/// sequences.
 #[derive(Debug)]
 pub struct OutputHandle {
     handle: Handle,
+    /// Whether the handle is a console, written with `WriteConsoleW`.
+    console: bool,
+    /// The start of a UTF-8 character split across writes to a console, held until the rest of
+    /// the character is written.
+    incomplete: Vec<u8>,
+    /// Translates escape sequences on a console without virtual terminal processing.
+    legacy: Option<Translator>,
 }
 
 impl OutputHandle {
     fn new(handle: Handle) -> Self {
-        Self { handle }
+        let mut mode = 0;
+        let console = unsafe { GetConsoleMode(handle.as_raw_handle(), &mut mode) } != 0;
+        Self {
+            handle,
+            console,
+            incomplete: Vec::new(),
+            legacy: None,
+        }
+    }
+
+    fn try_clone(&self) -> io::Result<Self> {
+        let mut clone = Self::new(self.handle.try_clone()?);
+        clone.legacy = self.legacy.as_ref().map(Translator::fresh);
+        Ok(clone)
+    }
+
+    /// Translates escape sequences from now on rather than writing them to the console. The
+    /// current text attributes become the ones SGR 0 resets to.
+    fn enable_legacy(&mut self) -> io::Result<()> {
+        if !self.console {
+            bail!("legacy output needs a console handle");
+        }
+        let info = self.screen_buffer_info()?;
+        self.legacy = Some(Translator::new(info.wAttributes));
+        Ok(())
+    }
+
+    /// Sets the text attributes back to the ones captured by [`Self::enable_legacy`].
+    fn reset_legacy(&mut self) -> io::Result<()> {
+        let Some(legacy) = self.legacy.as_mut() else {
+            return Ok(());
+        };
+        *legacy = legacy.fresh();
+        let attributes = legacy.attributes();
+        check(unsafe { SetConsoleTextAttribute(self.as_raw_handle(), attributes) })
     }
 
     fn get_mode(&self) -> io::Result<CONSOLE_MODE> {
@@ -278,6 +437,7 @@ impl OutputHandle {
     }
 
     fn set_mode(&mut self, mode: CONSOLE_MODE) -> io::Result<()> {
+        trace!(mode, "setting output console mode");
         if unsafe { SetConsoleMode(self.as_raw_handle(), mode) } == 0 {
             bail!(
                 "failed to set output console mode: {}",
@@ -309,7 +469,7 @@ impl OutputHandle {
         Ok(())
     }
 
-    fn get_dimensions(&self) -> io::Result<WindowSize> {
+    fn screen_buffer_info(&self) -> io::Result<CONSOLE_SCREEN_BUFFER_INFO> {
         let mut info: CONSOLE_SCREEN_BUFFER_INFO = unsafe { mem::zeroed() };
         if unsafe { GetConsoleScreenBufferInfo(self.as_raw_handle(), &mut info) } == 0 {
             bail!(
@@ -317,6 +477,11 @@ impl OutputHandle {
                 io::Error::last_os_error()
             );
         }
+        Ok(info)
+    }
+
+    fn get_dimensions(&self) -> io::Result<WindowSize> {
+        let info = self.screen_buffer_info()?;
         let rows = OneBased::from_zero_based((info.srWindow.Bottom - info.srWindow.Top) as u16);
         let cols = OneBased::from_zero_based((info.srWindow.Right - info.srWindow.Left) as u16);
         Ok(WindowSize {
@@ -326,6 +491,174 @@ impl OutputHandle {
             pixel_height: None,
         })
     }
+
+    /// Writes the first chunk of `buf` to the console with `WriteConsoleW`.
+    ///
+    /// A character split at the end of `buf` is held back and counted as written. Invalid UTF-8
+    /// is written as U+FFFD REPLACEMENT CHARACTER. If the console fails after part of the chunk
+    /// was written, the number of bytes written is returned and the error is left to the next
+    /// write.
+    fn write_console(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // UTF-16 takes up to twice as many bytes as UTF-8, which counts towards the write limit.
+        let chunk = &buf[..chunk_len(buf, MAX_WRITE_LEN / 2)];
+        let mut text = self.incomplete.clone();
+        text.extend_from_slice(chunk);
+        let incomplete = text.split_off(text.len() - incomplete_char_len(&text));
+
+        let wide: Vec<u16> = String::from_utf8_lossy(&text).encode_utf16().collect();
+        let mut written = 0;
+        while written < wide.len() {
+            let units = &wide[written..];
+            let mut num_written = 0;
+            let result = if unsafe {
+                WriteConsoleW(
+                    self.as_raw_handle(),
+                    units.as_ptr(),
+                    units.len() as u32,
+                    &mut num_written,
+                    ptr::null(),
+                )
+            } == 0
+            {
+                Err(io::Error::last_os_error())
+            } else if num_written == 0 {
+                Err(io::ErrorKind::WriteZero.into())
+            } else {
+                Ok(())
+            };
+            if let Err(err) = result {
+                // Report the characters written so far, which always include the ones held back
+                // from the last write.
+                let len = utf8_len_of_units(&text, written);
+                if len == 0 {
+                    return Err(err);
+                }
+                let len = len.saturating_sub(self.incomplete.len());
+                self.incomplete.clear();
+                return if len == 0 { Err(err) } else { Ok(len) };
+            }
+            written += num_written as usize;
+        }
+        self.incomplete = incomplete;
+        Ok(chunk.len())
+    }
+
+    /// Writes `buf` to a legacy console, carrying out the escape sequences in it with Console API
+    /// calls.
+    ///
+    /// Returns the number of bytes handled before an operation failed, so that the rest is written
+    /// again. The error is returned if the first operation fails.
+    fn write_legacy(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut handled = 0;
+        while handled < buf.len() {
+            let Some(translator) = self.legacy.as_mut() else {
+                return self.write_console(buf);
+            };
+            let (len, op) = translator.next(&buf[handled..]);
+            let result = match op {
+                // Text may be written in part, and the rest is translated again as text.
+                Some(Op::Text(text)) => self.write_console(text),
+                Some(op) => self.apply(op).map(|()| len),
+                None => Ok(len),
+            };
+            match result {
+                Ok(len) => handled += len,
+                Err(err) if handled == 0 => return Err(err),
+                Err(_) => break,
+            }
+        }
+        Ok(handled)
+    }
+
+    fn apply(&mut self, op: Op<'_>) -> io::Result<()> {
+        let handle = self.as_raw_handle();
+        match op {
+            Op::Text(mut text) => {
+                while !text.is_empty() {
+                    let written = self.write_console(text)?;
+                    text = &text[written..];
+                }
+                Ok(())
+            }
+            Op::Attributes(attributes) => {
+                check(unsafe { SetConsoleTextAttribute(handle, attributes) })
+            }
+            Op::MoveTo { row, col } => self.move_cursor(|window, _| {
+                (
+                    i32::from(window.Top) + i32::from(row),
+                    i32::from(window.Left) + i32::from(col),
+                )
+            }),
+            Op::MoveToColumn(col) => self.move_cursor(|window, cursor| {
+                (i32::from(cursor.Y), i32::from(window.Left) + i32::from(col))
+            }),
+            Op::MoveBy { rows, cols } => self
+                .move_cursor(|_, cursor| (i32::from(cursor.Y) + rows, i32::from(cursor.X) + cols)),
+            Op::EraseDisplay(_) | Op::EraseLine(_) => {
+                let info = self.screen_buffer_info()?;
+                let cursor = info.dwCursorPosition;
+                let last_col = info.dwSize.X - 1;
+                let at = |row, col| COORD { X: col, Y: row };
+                let (start, end) = match op {
+                    Op::EraseDisplay(0) => (cursor, at(info.srWindow.Bottom, last_col)),
+                    Op::EraseDisplay(1) => (at(info.srWindow.Top, 0), cursor),
+                    Op::EraseDisplay(_) => {
+                        (at(info.srWindow.Top, 0), at(info.srWindow.Bottom, last_col))
+                    }
+                    Op::EraseLine(0) => (cursor, at(cursor.Y, last_col)),
+                    Op::EraseLine(1) => (at(cursor.Y, 0), cursor),
+                    _ => (at(cursor.Y, 0), at(cursor.Y, last_col)),
+                };
+                self.erase(&info, start, end)
+            }
+            Op::ShowCursor(visible) => {
+                let mut cursor = CONSOLE_CURSOR_INFO {
+                    dwSize: 0,
+                    bVisible: 0,
+                };
+                check(unsafe { GetConsoleCursorInfo(handle, &mut cursor) })?;
+                cursor.bVisible = visible.into();
+                check(unsafe { SetConsoleCursorInfo(handle, &cursor) })
+            }
+        }
+    }
+
+    /// Moves the cursor to the `(row, col)` buffer position returned by `to`, which is given the
+    /// window and the cursor position. The cursor stops at the edges of the window.
+    fn move_cursor(&mut self, to: impl FnOnce(SMALL_RECT, COORD) -> (i32, i32)) -> io::Result<()> {
+        let info = self.screen_buffer_info()?;
+        let window = info.srWindow;
+        let (row, col) = to(window, info.dwCursorPosition);
+        let position = COORD {
+            X: col.clamp(window.Left.into(), window.Right.into()) as i16,
+            Y: row.clamp(window.Top.into(), window.Bottom.into()) as i16,
+        };
+        check(unsafe { SetConsoleCursorPosition(self.as_raw_handle(), position) })
+    }
+
+    /// Blanks the buffer from `start` to `end` inclusive with the current attributes.
+    fn erase(
+        &mut self,
+        info: &CONSOLE_SCREEN_BUFFER_INFO,
+        start: COORD,
+        end: COORD,
+    ) -> io::Result<()> {
+        let width = i32::from(info.dwSize.X);
+        let len = (i32::from(end.Y) - i32::from(start.Y)) * width + i32::from(end.X)
+            - i32::from(start.X)
+            + 1;
+        if len <= 0 {
+            return Ok(());
+        }
+        let handle = self.as_raw_handle();
+        let mut written = 0;
+        check(unsafe {
+            FillConsoleOutputCharacterW(handle, u16::from(b' '), len as u32, start, &mut written)
+        })?;
+        check(unsafe {
+            FillConsoleOutputAttribute(handle, info.wAttributes, len as u32, start, &mut written)
+        })
+    }
 }
 
 impl AsRawHandle for OutputHandle {
@@ -335,22 +668,44 @@ impl AsRawHandle for OutputHandle {
 }
 
 impl io::Write for OutputHandle {
+    /// Writes `buf` with as many `WriteFile` calls as needed, or with `WriteConsoleW` if the
+    /// handle is a console.
+    ///
+    /// Large payloads such as sixel images are split into chunks of at most [`MAX_WRITE_LEN`]
+    /// bytes, since console writes of very large buffers can fail or be cut short. Returns the
+    /// number of bytes written before a call fell short, or an error if nothing was written.
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        let mut num_written = 0;
-        if unsafe {
-            WriteFile(
-                self.as_raw_handle(),
-                buf.as_ptr(),
-                buf.len() as u32,
-                &mut num_written,
-                ptr::null_mut(),
-            )
-        } == 0
-        {
-            Err(io::Error::last_os_error())
-        } else {
-            Ok(num_written as usize)
+        if self.legacy.is_some() {
+            return self.write_legacy(buf);
+        }
+        if self.console {
+            return self.write_console(buf);
         }
+        let mut written = 0;
+        while written < buf.len() {
+            let chunk = &buf[written..];
+            let chunk = &chunk[..chunk_len(chunk, MAX_WRITE_LEN)];
+            let mut num_written = 0;
+            if unsafe {
+                WriteFile(
+                    self.as_raw_handle(),
+                    chunk.as_ptr(),
+                    chunk.len() as u32,
+                    &mut num_written,
+                    ptr::null_mut(),
+                )
+            } == 0
+            {
+                let err = io::Error::last_os_error();
+                // Report the bytes already written. The error recurs on the next call.
+                return if written == 0 { Err(err) } else { Ok(written) };
+            }
+            written += num_written as usize;
+            if (num_written as usize) < chunk.len() {
+                break;
+            }
+        }
+        Ok(written)
     }
 
     fn flush(&mut self) -> io::Result<()> {
@@ -358,16 +713,284 @@ impl io::Write for OutputHandle {
     }
 }
 
-fn open_pty(mode: InputReaderMode) -> io::Result<(InputHandle, OutputHandle)> {
+/// Converts the result of a Console API call into an `io::Result`.
+fn check(result: i32) -> io::Result<()> {
+    if result == 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+/// The most bytes passed to a single `WriteFile` call by [`OutputHandle`].
+///
+/// Older console hosts fail writes of more than about 64 KiB with `ERROR_NOT_ENOUGH_MEMORY`.
+const MAX_WRITE_LEN: usize = 32 * 1024;
+
+/// Returns the length of the first chunk of `buf` to write, at most `max` bytes.
+///
+/// The chunk ends on a UTF-8 character boundary where possible so that the console never sees a
+/// character split across two writes.
+fn chunk_len(buf: &[u8], max: usize) -> usize {
+    if buf.len() <= max {
+        return buf.len();
+    }
+    // Back up over at most three continuation bytes to the start of the split character.
+    let is_continuation = |byte: u8| byte & 0b1100_0000 == 0b1000_0000;
+    (max.saturating_sub(3)..=max)
+        .rev()
+        .find(|&len| len > 0 && !is_continuation(buf[len]))
+        .unwrap_or(max)
+}
+
+/// Returns the number of bytes of `text` whose characters take up the first `units` UTF-16 code
+/// units of its lossy conversion, in which invalid UTF-8 is one U+FFFD REPLACEMENT CHARACTER.
+fn utf8_len_of_units(text: &[u8], mut units: usize) -> usize {
+    let mut len = 0;
+    let mut rest = text;
+    while !rest.is_empty() {
+        let (valid, invalid_len) = match std::str::from_utf8(rest) {
+            Ok(valid) => (valid, 0),
+            Err(err) => (
+                std::str::from_utf8(&rest[..err.valid_up_to()]).unwrap(),
+                err.error_len().unwrap_or(rest.len() - err.valid_up_to()),
+            ),
+        };
+        for c in valid.chars() {
+            if c.len_utf16() > units {
+                return len;
+            }
+            units -= c.len_utf16();
+            len += c.len_utf8();
+        }
+        if invalid_len == 0 || units == 0 {
+            return len;
+        }
+        units -= 1;
+        len += invalid_len;
+        rest = &rest[valid.len() + invalid_len..];
+    }
+    len
+}
+
+/// The kind of terminal behind a `WindowsTerminal`, as reported by
+/// `WindowsTerminal::console_kind`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ConsoleKind {
+    /// A console with virtual terminal support: a ConPTY pseudoconsole, as hosted by Windows
+    /// Terminal and other modern terminals, or conhost since Windows 10.
+    ConPty,
+    /// A console which doesn't support `ENABLE_VIRTUAL_TERMINAL_PROCESSING`, such as conhost
+    /// before Windows 10 or with the legacy console option enabled.
+    ///
+    /// Output escape sequences are translated to Console API calls for a core subset: SGR colors
+    /// and bold, underline and reverse video, cursor movement, erasing the display or line, and
+    /// showing or hiding the cursor. Other sequences, such as the alternate screen, mouse modes
+    /// and OSC strings, are dropped. These consoles don't support virtual terminal input either,
+    /// so input is read in [legacy mode][InputReaderMode::Legacy], which needs the
+    /// `windows-legacy` feature. Without it `WindowsTerminal::new` fails on these consoles.
+    Legacy,
+    /// A Cygwin or MSYS2 pty, as used by mintty and Git Bash. These are named pipes rather than
+    /// consoles.
+    ///
+    /// Output is written as is, and input is read from the pipe and parsed like VT input. A
+    /// native program can't change the pty's line discipline, so [`Terminal::enter_raw_mode`]
+    /// fails with [`io::ErrorKind::Unsupported`] and cooked mode only writes the tracked modes:
+    /// input arrives however the pty delivers it. The window size can't be read either, so
+    /// [`Terminal::get_dimensions`] fails unless the terminal was opened with
+    /// `WindowsTerminal::new_lenient`.
+    MsysPty,
+    /// Not a terminal: output is redirected to a file or pipe, or there is no console.
+    Redirected,
+}
+
+impl ConsoleKind {
+    /// Detects the kind of terminal behind an output handle.
+    fn detect(handle: &Handle) -> Self {
+        let handle = handle.as_raw_handle();
+        let mut mode = 0;
+        if unsafe { GetConsoleMode(handle, &mut mode) } != 0 {
+            // Probe for virtual terminal support, then put the original mode back.
+            let vt = mode | Console::ENABLE_VIRTUAL_TERMINAL_PROCESSING;
+            if unsafe { SetConsoleMode(handle, vt) } == 0 {
+                return Self::Legacy;
+            }
+            unsafe { SetConsoleMode(handle, mode) };
+            return Self::ConPty;
+        }
+        if is_msys_pty(handle) {
+            Self::MsysPty
+        } else {
+            Self::Redirected
+        }
+    }
+}
+
+/// Checks whether `handle` is the pipe of a Cygwin or MSYS2 pty.
+///
+/// These ptys are named pipes, so the only way to tell them apart from other pipes is the pipe's
+/// name. The standard library's [`IsTerminal`](std::io::IsTerminal) makes a similar check.
+fn is_msys_pty(handle: RawHandle) -> bool {
+    if unsafe { GetFileType(handle) } != FILE_TYPE_PIPE {
+        return false;
+    }
+    // `FILE_NAME_INFO`: the name's length in bytes followed by the UTF-16 name.
+    #[repr(C)]
+    struct NameInfo {
+        len: u32,
+        name: [u16; 260],
+    }
+    let mut info = NameInfo {
+        len: 0,
+        name: [0; 260],
+    };
+    if unsafe {
+        GetFileInformationByHandleEx(
+            handle,
+            FileNameInfo,
+            &mut info as *mut NameInfo as *mut _,
+            mem::size_of::<NameInfo>() as u32,
+        )
+    } == 0
+    {
+        return false;
+    }
+    let len = (info.len as usize / 2).min(info.name.len());
+    is_msys_pty_name(&String::from_utf16_lossy(&info.name[..len]))
+}
+
+/// Checks whether `name` is the name of a Cygwin or MSYS2 pty pipe, such as
+/// `\msys-dd50a72ab4668b33-pty0-to-master`.
+fn is_msys_pty_name(name: &str) -> bool {
+    let name = name.rsplit('\\').next().unwrap_or(name);
+    (name.starts_with("msys-") || name.starts_with("cygwin-"))
+        && name.contains("-pty")
+        && (name.ends_with("-from-master") || name.ends_with("-to-master"))
+}
+
+/// Watches output for the DEC private modes which turn mouse tracking on and off.
+///
+/// `WindowsTerminal` uses this to turn off quick edit mode while the application tracks the
+/// mouse, see [`WindowsTerminal::set_quick_edit_auto_disable`]. Sequences may be split across
+/// writes.
+#[derive(Debug, Default)]
+struct MouseTrackingScanner {
+    state: ScanState,
+    /// The tracking modes turned on so far, as bits from [`mouse_tracking_bit`].
+    modes: u8,
+    /// The modes named by the parameters of the current sequence.
+    sequence_modes: u8,
+    /// The value of the current parameter.
+    param: u16,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum ScanState {
+    #[default]
+    Ground,
+    Escape,
+    /// After `CSI`, before its first parameter byte.
+    Csi,
+    /// Within a `CSI ?` sequence.
+    PrivateCsi,
+    /// Within any other CSI sequence, which is skipped up to its final byte.
+    OtherCsi,
+}
+
+impl MouseTrackingScanner {
+    /// Checks whether any mouse tracking mode is on.
+    fn is_tracking(&self) -> bool {
+        self.modes != 0
+    }
+
+    /// Scans `buf`, returning whether it turned mouse tracking as a whole on or off.
+    fn scan(&mut self, buf: &[u8]) -> bool {
+        let was_tracking = self.is_tracking();
+        let mut rest = buf;
+        while !rest.is_empty() {
+            if self.state == ScanState::Ground {
+                match memchr::memchr(0x1B, rest) {
+                    Some(index) => rest = &rest[index..],
+                    None => break,
+                }
+            }
+            self.advance(rest[0]);
+            rest = &rest[1..];
+        }
+        was_tracking != self.is_tracking()
+    }
+
+    fn advance(&mut self, byte: u8) {
+        self.state = match (self.state, byte) {
+            (_, 0x1B) => ScanState::Escape,
+            (ScanState::Ground, _) => ScanState::Ground,
+            (ScanState::Escape, b'[') => ScanState::Csi,
+            (ScanState::Escape, _) => ScanState::Ground,
+            (ScanState::Csi, b'?') => {
+                self.sequence_modes = 0;
+                self.param = 0;
+                ScanState::PrivateCsi
+            }
+            (ScanState::PrivateCsi, b'0'..=b'9') => {
+                self.param = self
+                    .param
+                    .saturating_mul(10)
+                    .saturating_add(u16::from(byte - b'0'));
+                ScanState::PrivateCsi
+            }
+            (ScanState::PrivateCsi, b';') => {
+                self.finish_param();
+                ScanState::PrivateCsi
+            }
+            (ScanState::PrivateCsi, b'h' | b'l') => {
+                self.finish_param();
+                if byte == b'h' {
+                    self.modes |= self.sequence_modes;
+                } else {
+                    self.modes &= !self.sequence_modes;
+                }
+                ScanState::Ground
+            }
+            (ScanState::Csi | ScanState::PrivateCsi | ScanState::OtherCsi, 0x40..=0x7E) => {
+                ScanState::Ground
+            }
+            (ScanState::Csi | ScanState::PrivateCsi | ScanState::OtherCsi, _) => {
+                ScanState::OtherCsi
+            }
+        };
+    }
+
+    fn finish_param(&mut self) {
+        self.sequence_modes |= mouse_tracking_bit(self.param);
+        self.param = 0;
+    }
+}
+
+/// Returns a bit standing for `mode` if it is a mouse tracking mode, or `0` otherwise.
+fn mouse_tracking_bit(mode: u16) -> u8 {
+    // `DecPrivateModeCode::{MouseTracking, ButtonEventMouse, AnyEventMouse}`.
+    match mode {
+        1000 => 1,
+        1002 => 2,
+        1003 => 4,
+        _ => 0,
+    }
+}
+
+fn open_pty(
+    mode: InputReaderMode,
+    target: OutputTarget,
+) -> io::Result<(InputHandle, OutputHandle)> {
     let input = if io::stdin().is_terminal() {
         Handle::stdin()
     } else {
         open_file("CONIN$")?.into()
     };
-    let output = if io::stdout().is_terminal() {
-        Handle::stdout()
-    } else {
-        open_file("CONOUT$")?.into()
+    let output = match target {
+        OutputTarget::Stdout if io::stdout().is_terminal() => Handle::stdout(),
+        OutputTarget::Stderr if io::stderr().is_terminal() => Handle::stderr(),
+        _ => open_file("CONOUT$")?.into(),
     };
     Ok((InputHandle::new(input, mode), OutputHandle::new(output)))
 }
@@ -379,23 +1002,57 @@ fn open_file(path: &str) -> io::Result<File> {
 // CREDIT: Again, like the UnixTerminal in the unix module this is mostly based on WezTerm but
 // only covers the parts not related to the event source.
 // <https://github.com/wezterm/wezterm/blob/a87358516004a652ad840bc1661bdf65ffc89b43/termwiz/src/terminal/windows.rs#L482-L860>
-// Also, the legacy Console API is not implemented.
+// Also, the legacy Console API is only used to emulate a core subset of escape sequences, see the
+// `legacy` module.
 
 /// Windows terminal handle.
 ///
-/// `WindowsTerminal` opens `CONIN$` or stdin for input and `CONOUT$` or stdout for output, enables
-/// virtual-terminal output processing, and captures console modes/code pages so they can be
-/// restored on drop.
+/// `WindowsTerminal` opens `CONIN$` or stdin for input and `CONOUT$`, stdout or stderr for
+/// output, enables virtual-terminal output processing, and captures console modes/code pages so
+/// they can be restored on drop. Under mintty and other Cygwin or MSYS2 terminals it uses the pty
+/// pipes behind stdin and stdout instead, see [`ConsoleKind::MsysPty`]. Consoles without virtual
+/// terminal processing get a core subset of escape sequences translated to Console API calls, see
+/// [`ConsoleKind::Legacy`].
+///
+/// # Sharing console input
+///
+/// The console has a single input buffer per process and each input record goes to whichever
+/// reader takes it first, whether that is Termina or another library reading console input in the
+/// same process. Termina only reads when records are queued, and if another reader drains the
+/// buffer in between Termina goes back to waiting rather than blocking in the read. Events are
+/// still split between the readers, so only one of them should read input at a time.
 #[derive(Debug)]
 pub struct WindowsTerminal {
     input: InputHandle,
     output: BufWriter<OutputHandle>,
+    /// The output of the writers returned by `Self::writer`.
+    shared_output: SharedOutput,
     reader: EventReader,
     original_input_mode: CONSOLE_MODE,
     original_output_mode: CONSOLE_MODE,
     original_input_cp: CodePageID,
     original_output_cp: CodePageID,
+    /// Input and output console modes captured when entering raw mode, restored when entering
+    /// cooked mode.
+    cooked_modes: Option<(CONSOLE_MODE, CONSOLE_MODE)>,
+    /// Modes restored on drop and in the panic hook.
+    modes: TrackedModes,
+    /// The size reported when the window size can't be read.
+    fallback_size: Option<WindowSize>,
+    /// The console modes and code pages are only used for [`ConsoleKind::ConPty`] and
+    /// [`ConsoleKind::Legacy`].
+    kind: ConsoleKind,
+    /// Set for the fallback terminal of [`Self::new_lenient`], which reports the kind of its
+    /// stdout but doesn't manage it.
+    headless: bool,
+    /// Watches the output for mouse tracking, see [`Self::set_quick_edit_auto_disable`].
+    mouse_tracking: MouseTrackingScanner,
+    quick_edit_auto_disable: bool,
+    /// Set while quick edit mode is turned off because the application tracks the mouse.
+    quick_edit_disabled: bool,
     has_panic_hook: bool,
+    /// Set once the terminal has been torn down, so `Drop` doesn't repeat it.
+    closed: bool,
     mode: InputReaderMode,
 }
 
@@ -405,7 +1062,15 @@ impl WindowsTerminal {
     /// This mode enables virtual-terminal input and sets the input/output code pages to UTF-8
     /// while the terminal is active.
     pub fn new() -> io::Result<Self> {
-        Self::with_mode_internal(InputReaderMode::Vte)
+        Self::with_mode_internal(InputReaderMode::Vte, OutputTarget::Stdout)
+    }
+
+    /// Opens the Windows terminal like [`Self::new`], writing to the chosen standard stream.
+    ///
+    /// The output console mode, code page and window size also use the chosen stream, or
+    /// `CONOUT$` if it is not a console.
+    pub fn new_with_output(output: OutputTarget) -> io::Result<Self> {
+        Self::with_mode_internal(InputReaderMode::Vte, output)
     }
 
     /// Opens the Windows terminal using the specified [`InputReaderMode`].
@@ -418,16 +1083,76 @@ impl WindowsTerminal {
         // because creating an instance with `InputReaderMode::Legacy`
         // without the appropriate parsing mechanisms enabled will
         // result in no events being parsed.
-        Self::with_mode_internal(mode)
+        Self::with_mode_internal(mode, OutputTarget::Stdout)
     }
 
-    fn with_mode_internal(mode: InputReaderMode) -> io::Result<Self> {
-        let (mut input, mut output) = open_pty(mode)?;
+    /// Opens a terminal which reads input from the console handle `input` and writes to the
+    /// console handle `output`, in [VTE input mode][InputReaderMode::Vte].
+    ///
+    /// Use this to drive a console other than the process's own, such as a pseudoconsole created
+    /// for a multiplexer pane. Both handles must be console handles: their modes and code pages
+    /// are changed like in [`Self::new`] and restored on drop.
+    pub fn open_with(input: Handle, output: Handle) -> io::Result<Self> {
+        let mode = InputReaderMode::Vte;
+        Self::open_internal(
+            InputHandle::new(input, mode),
+            OutputHandle::new(output),
+            mode,
+        )
+    }
+
+    fn with_mode_internal(mode: InputReaderMode, target: OutputTarget) -> io::Result<Self> {
+        let output = match target {
+            OutputTarget::Stdout => Handle::stdout(),
+            OutputTarget::Stderr => Handle::stderr(),
+        };
+        let input = Handle::stdin();
+        if is_msys_pty(input.as_raw_handle()) && is_msys_pty(output.as_raw_handle()) {
+            return Self::open_msys(input, output);
+        }
+        let (input, output) = open_pty(mode, target)?;
+        Self::open_internal(input, output, mode)
+    }
+
+    /// Opens the pty of mintty or another Cygwin or MSYS2 terminal. See [`ConsoleKind::MsysPty`].
+    fn open_msys(input: Handle, output: Handle) -> io::Result<Self> {
+        let mode = InputReaderMode::Vte;
+        let input = InputHandle::pipe(input);
+        let reader = EventReader::new(WindowsEventSource::new(input.try_clone()?, mode)?);
+
+        Ok(Self {
+            input,
+            output: BufWriter::with_capacity(OUTPUT_BUF_SIZE, OutputHandle::new(output)),
+            reader,
+            original_input_mode: 0,
+            original_output_mode: 0,
+            original_input_cp: 0,
+            original_output_cp: 0,
+            mode,
+            cooked_modes: None,
+            modes: TrackedModes::default(),
+            fallback_size: None,
+            kind: ConsoleKind::MsysPty,
+            headless: false,
+            mouse_tracking: MouseTrackingScanner::default(),
+            quick_edit_auto_disable: true,
+            quick_edit_disabled: false,
+            has_panic_hook: false,
+            closed: false,
+            shared_output: SharedOutput::default(),
+        })
+    }
 
+    fn open_internal(
+        mut input: InputHandle,
+        mut output: OutputHandle,
+        mut mode: InputReaderMode,
+    ) -> io::Result<Self> {
         let original_input_mode = input.get_mode()?;
         let original_output_mode = output.get_mode()?;
         let original_input_cp = input.get_code_page()?;
         let original_output_cp = output.get_code_page()?;
+        let mut kind = ConsoleKind::ConPty;
 
         // Switch the console to UTF-8 + VT modes. Each step mutates global console state, and a
         // later step can fail. Because there is no `WindowsTerminal` yet, `Drop` won't run, so on
@@ -438,27 +1163,40 @@ impl WindowsTerminal {
                 output.set_code_page(CP_UTF8)?;
             }
 
-            // Enable VT processing for the output handle.
+            // Enable VT processing for the output handle. Consoles without it get the escape
+            // sequences translated instead, see `ConsoleKind::Legacy`.
             let desired_output_mode = original_output_mode
                 | Console::ENABLE_VIRTUAL_TERMINAL_PROCESSING
                 | Console::DISABLE_NEWLINE_AUTO_RETURN;
-            output.set_mode(desired_output_mode).map_err(|_| {
-                io::Error::new(
-                    io::ErrorKind::Other,
-                    "virtual terminal processing could not be enabled for the output handle",
-                )
-            })?;
+            if output.set_mode(desired_output_mode).is_err() {
+                output.enable_legacy().map_err(|err| {
+                    io::Error::new(
+                        io::ErrorKind::Other,
+                        format!(
+                            "virtual terminal processing could not be enabled for the output \
+                             handle and legacy output failed: {err}"
+                        ),
+                    )
+                })?;
+                kind = ConsoleKind::Legacy;
+            }
 
             if mode == InputReaderMode::Vte {
                 // And now the input handle too.
                 let desired_input_mode =
                     original_input_mode | Console::ENABLE_VIRTUAL_TERMINAL_INPUT;
-                input.set_mode(desired_input_mode).map_err(|_| {
-                    io::Error::new(
-                        io::ErrorKind::Other,
-                        "virtual terminal processing could not be enabled for the input handle",
-                    )
-                })?;
+                if input.set_mode(desired_input_mode).is_err() {
+                    // Legacy consoles only deliver input records, which need the classic parser.
+                    if cfg!(feature = "windows-legacy") {
+                        mode = InputReaderMode::Legacy;
+                        input.mode = mode;
+                    } else {
+                        bail!(
+                            "virtual terminal processing could not be enabled for the input \
+                             handle; enable the `windows-legacy` feature to read input records"
+                        );
+                    }
+                }
             }
 
             Ok(EventReader::new(WindowsEventSource::new(
@@ -478,59 +1216,348 @@ impl WindowsTerminal {
 
         Ok(Self {
             input,
-            output: BufWriter::with_capacity(BUF_SIZE, output),
+            output: BufWriter::with_capacity(OUTPUT_BUF_SIZE, output),
             reader,
             original_input_mode,
             original_output_mode,
             original_input_cp,
             original_output_cp,
             mode,
+            cooked_modes: None,
+            modes: TrackedModes::default(),
+            fallback_size: None,
+            kind,
+            headless: false,
+            mouse_tracking: MouseTrackingScanner::default(),
+            quick_edit_auto_disable: true,
+            quick_edit_disabled: false,
             has_panic_hook: false,
+            closed: false,
+            shared_output: SharedOutput::default(),
         })
     }
+
+    /// Opens the Windows terminal like [`Self::new`], or falls back to a terminal which only
+    /// writes to stdout if no console can be opened.
+    ///
+    /// This lets programs run their non-interactive paths, such as in CI or when every stream is
+    /// redirected, through the same code. The fallback terminal never reports events, ignores raw
+    /// and cooked mode, and reports `fallback_size` as its dimensions. An interactive terminal
+    /// also reports `fallback_size` if the window size can't be read. Use
+    /// [`Self::is_interactive`] to tell the two apart.
+    pub fn new_lenient(fallback_size: WindowSize) -> io::Result<Self> {
+        match Self::new() {
+            Ok(mut terminal) => {
+                terminal.fallback_size = Some(fallback_size);
+                Ok(terminal)
+            }
+            Err(_) => Self::headless(fallback_size),
+        }
+    }
+
+    /// Creates a terminal which writes to stdout and never reads input.
+    fn headless(fallback_size: WindowSize) -> io::Result<Self> {
+        let mode = InputReaderMode::Vte;
+        // An event which is never set, so the event source waits without reading input.
+        let event = unsafe { CreateEventW(ptr::null(), 0, 0, ptr::null()) };
+        if event.is_null() {
+            return Err(io::Error::last_os_error());
+        }
+        let input = InputHandle::new(
+            Handle::Owned(unsafe { OwnedHandle::from_raw_handle(event) }),
+            mode,
+        );
+        let reader = EventReader::new(WindowsEventSource::new(input.try_clone()?, mode)?);
+
+        Ok(Self {
+            input,
+            output: BufWriter::with_capacity(OUTPUT_BUF_SIZE, OutputHandle::new(Handle::stdout())),
+            reader,
+            original_input_mode: 0,
+            original_output_mode: 0,
+            original_input_cp: 0,
+            original_output_cp: 0,
+            mode,
+            cooked_modes: None,
+            modes: TrackedModes::default(),
+            fallback_size: Some(fallback_size),
+            kind: ConsoleKind::detect(&Handle::stdout()),
+            headless: true,
+            mouse_tracking: MouseTrackingScanner::default(),
+            quick_edit_auto_disable: true,
+            quick_edit_disabled: false,
+            has_panic_hook: false,
+            closed: false,
+            shared_output: SharedOutput::default(),
+        })
+    }
+
+    /// Checks whether this is an interactive console.
+    ///
+    /// This is `false` only for the fallback terminal created by [`Self::new_lenient`].
+    pub fn is_interactive(&self) -> bool {
+        !self.headless
+    }
+
+    /// Returns a handle which writes to this terminal's output from any thread.
+    ///
+    /// The writer has its own buffer and duplicates the output handle, so it can write while this
+    /// terminal is borrowed elsewhere, such as by a thread blocked reading events. See
+    /// [`TerminalWriter`].
+    pub fn writer(&self) -> io::Result<TerminalWriter> {
+        self.shared_output
+            .writer(|| self.output.get_ref().try_clone())
+    }
+
+    /// Returns the kind of terminal this is.
+    ///
+    /// The fallback terminal of [`Self::new_lenient`] reports the kind of its stdout, which tells
+    /// a legacy console apart from redirected output.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use termina::{ConsoleKind, PlatformTerminal, WindowSize};
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let fallback = WindowSize {
+    ///     rows: 24,
+    ///     cols: 80,
+    ///     pixel_width: None,
+    ///     pixel_height: None,
+    /// };
+    /// let terminal = PlatformTerminal::new_lenient(fallback)?;
+    /// if terminal.console_kind() == ConsoleKind::Legacy {
+    ///     eprintln!("this console doesn't support escape sequences");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn console_kind(&self) -> ConsoleKind {
+        self.kind
+    }
+
+    /// Returns the input console mode captured when the terminal was opened, which is restored
+    /// when it is closed or dropped.
+    ///
+    /// The mode is a set of `ENABLE_*` flags from `windows_sys::Win32::System::Console`. It is `0`
+    /// if the terminal doesn't manage a console, see [`Self::console_kind`].
+    pub fn original_input_mode(&self) -> u32 {
+        self.original_input_mode
+    }
+
+    /// Returns the output console mode captured when the terminal was opened, which is restored
+    /// when it is closed or dropped.
+    ///
+    /// Like [`Self::original_input_mode`] this is `0` if the terminal doesn't manage a console.
+    pub fn original_output_mode(&self) -> u32 {
+        self.original_output_mode
+    }
+
+    /// Changes the input console mode to the one `f` returns given the current mode.
+    ///
+    /// Use this for flags which [`Terminal::enter_raw_mode`] leaves alone, such as clearing
+    /// `ENABLE_QUICK_EDIT_MODE` so that selecting text with the mouse doesn't hold up mouse
+    /// reporting. The original mode is restored when the terminal is closed or dropped, and in
+    /// the panic hook. Raw mode keeps the flags it doesn't set, while
+    /// [`Terminal::enter_cooked_mode`] returns to the modes from before
+    /// [`Terminal::enter_raw_mode`], so make changes meant for raw mode after entering it.
+    ///
+    /// Fails if the terminal doesn't manage a console, such as under mintty.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use termina::{PlatformTerminal, Terminal};
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// // `windows_sys::Win32::System::Console::ENABLE_QUICK_EDIT_MODE`
+    /// const ENABLE_QUICK_EDIT_MODE: u32 = 0x0040;
+    ///
+    /// let mut terminal = PlatformTerminal::new()?;
+    /// terminal.enter_raw_mode()?;
+    /// terminal.modify_input_mode(|mode| mode & !ENABLE_QUICK_EDIT_MODE)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn modify_input_mode(&mut self, f: impl FnOnce(u32) -> u32) -> io::Result<()> {
+        if !self.is_console() {
+            bail!("the input console mode can't be changed: the terminal is not a console");
+        }
+        let mode = self.input.get_mode()?;
+        self.input.set_mode(f(mode))
+    }
+
+    /// Changes the output console mode to the one `f` returns given the current mode.
+    ///
+    /// This works like [`Self::modify_input_mode`], flushing buffered output first. Clearing
+    /// `ENABLE_VIRTUAL_TERMINAL_PROCESSING` makes the console print escape sequences literally.
+    pub fn modify_output_mode(&mut self, f: impl FnOnce(u32) -> u32) -> io::Result<()> {
+        if !self.is_console() {
+            bail!("the output console mode can't be changed: the terminal is not a console");
+        }
+        // Buffered output is written under the mode it was written for.
+        io::Write::flush(self)?;
+        let output = self.output.get_mut();
+        let mode = output.get_mode()?;
+        output.set_mode(f(mode))
+    }
+
+    /// Sets whether quick edit mode is turned off while the application tracks the mouse. This is
+    /// on by default.
+    ///
+    /// With quick edit mode on, the console selects text when the user drags the mouse rather than
+    /// reporting mouse events, and output stops until the selection is finished. So while the
+    /// application has mouse tracking on, having written the `MouseTracking`, `ButtonEventMouse`
+    /// or `AnyEventMouse` [`DecPrivateModeCode`] to the terminal, `WindowsTerminal` turns quick
+    /// edit mode off. It is turned back on when the application turns those modes off, and the
+    /// original input mode is restored when the terminal is closed or dropped. Turning this off
+    /// while quick edit mode is off for mouse tracking turns quick edit mode back on.
+    ///
+    /// [`DecPrivateModeCode`]: crate::escape::csi::DecPrivateModeCode
+    pub fn set_quick_edit_auto_disable(&mut self, enabled: bool) -> io::Result<()> {
+        self.quick_edit_auto_disable = enabled;
+        self.update_quick_edit()
+    }
+
+    /// Turns quick edit mode off or back on to match the application's mouse tracking.
+    fn update_quick_edit(&mut self) -> io::Result<()> {
+        let disable =
+            self.quick_edit_auto_disable && self.mouse_tracking.is_tracking() && self.is_console();
+        if disable == self.quick_edit_disabled {
+            return Ok(());
+        }
+        let mode = self.input.get_mode()?;
+        if disable && mode & Console::ENABLE_QUICK_EDIT_MODE == 0 {
+            // Quick edit mode is already off, and stays that way when tracking ends.
+            return Ok(());
+        }
+        let mode = if disable {
+            mode & !Console::ENABLE_QUICK_EDIT_MODE
+        } else {
+            mode | Console::ENABLE_QUICK_EDIT_MODE
+        };
+        // Quick edit mode is an extended flag, which is only changed along with this flag.
+        self.input.set_mode(mode | Console::ENABLE_EXTENDED_FLAGS)?;
+        self.quick_edit_disabled = disable;
+        Ok(())
+    }
+
+    /// Checks whether the handles are a console, whose modes and code pages are managed.
+    fn is_console(&self) -> bool {
+        !self.headless && matches!(self.kind, ConsoleKind::ConPty | ConsoleKind::Legacy)
+    }
+
+    fn teardown(&mut self) -> io::Result<()> {
+        self.closed = true;
+        if !self.is_console() {
+            return self.modes.restore(&mut self.output).and(self.flush());
+        }
+        let results = [
+            self.modes.restore(&mut self.output),
+            self.flush(),
+            self.output.get_mut().reset_legacy(),
+            // Drain unread input before handing the console back in cooked mode
+            self.input.flush(),
+            self.input.set_code_page(self.original_input_cp),
+            self.output.get_mut().set_code_page(self.original_output_cp),
+            self.input.set_mode(self.original_input_mode),
+            self.output.get_mut().set_mode(self.original_output_mode),
+        ];
+        results.into_iter().collect()
+    }
 }
 
 impl Terminal for WindowsTerminal {
     fn enter_raw_mode(&mut self) -> io::Result<()> {
-        let mode = self.output.get_mut().get_mode()?;
+        if self.kind == ConsoleKind::MsysPty {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "raw mode is not supported on a Cygwin or MSYS2 pty",
+            ));
+        }
+        if !self.is_console() {
+            return self.modes.reapply(&mut self.output);
+        }
+        let output_mode = self.output.get_mut().get_mode()?;
+        let input_mode = self.input.get_mode()?;
+        // Only capture the modes on the way in from cooked mode so that repeated calls don't
+        // record raw mode as the state to return to.
+        if self.cooked_modes.is_none() {
+            self.cooked_modes = Some((input_mode, output_mode));
+        }
+
         self.output
             .get_mut()
-            .set_mode(mode | Console::DISABLE_NEWLINE_AUTO_RETURN)
+            .set_mode(output_mode | Console::DISABLE_NEWLINE_AUTO_RETURN)
             .ok();
-        let mode = self.input.get_mode()?;
-        self.input.set_mode(
-            (mode
-                & !(Console::ENABLE_ECHO_INPUT
-                    | Console::ENABLE_LINE_INPUT
-                    | Console::ENABLE_PROCESSED_INPUT))
-                | Console::ENABLE_MOUSE_INPUT
-                | Console::ENABLE_WINDOW_INPUT,
-        )?;
+        let mut raw_input_mode = (input_mode
+            & !(Console::ENABLE_ECHO_INPUT
+                | Console::ENABLE_LINE_INPUT
+                | Console::ENABLE_PROCESSED_INPUT))
+            | Console::ENABLE_MOUSE_INPUT
+            | Console::ENABLE_WINDOW_INPUT;
+        if self.mode == InputReaderMode::Vte {
+            // Cooked mode may have handed virtual-terminal input back to the user's setting.
+            raw_input_mode |= Console::ENABLE_VIRTUAL_TERMINAL_INPUT;
+        }
+        if self.quick_edit_disabled {
+            // Cooked mode may have brought quick edit mode back while mouse tracking is on.
+            raw_input_mode = (raw_input_mode & !Console::ENABLE_QUICK_EDIT_MODE)
+                | Console::ENABLE_EXTENDED_FLAGS;
+        }
+        self.input.set_mode(raw_input_mode)?;
 
-        Ok(())
+        self.modes.reapply(&mut self.output)
     }
 
     fn enter_cooked_mode(&mut self) -> io::Result<()> {
-        let mode = self.output.get_mut().get_mode()?;
-        self.output
-            .get_mut()
-            .set_mode(mode & !Console::DISABLE_NEWLINE_AUTO_RETURN)
-            .ok();
+        // Switch the console modes even if the mode sequences couldn't be written.
+        let output = self.shared_output.lock();
+        let restored = self
+            .modes
+            .restore(&mut self.output)
+            .and_then(|()| self.output.flush());
+        drop(output);
+        if !self.is_console() {
+            return restored;
+        }
 
-        let mode = self.input.get_mode()?;
-        self.input.set_mode(
-            (mode & !(Console::ENABLE_MOUSE_INPUT | Console::ENABLE_WINDOW_INPUT))
-                | Console::ENABLE_ECHO_INPUT
-                | Console::ENABLE_LINE_INPUT
-                | Console::ENABLE_PROCESSED_INPUT,
-        )?;
-        Ok(())
+        let (input_mode, output_mode) = match self.cooked_modes.take() {
+            Some(modes) => modes,
+            // Raw mode was never entered: derive cooked modes from the current ones.
+            None => {
+                let output_mode =
+                    self.output.get_mut().get_mode()? & !Console::DISABLE_NEWLINE_AUTO_RETURN;
+                let input_mode = (self.input.get_mode()?
+                    & !(Console::ENABLE_MOUSE_INPUT | Console::ENABLE_WINDOW_INPUT))
+                    | Console::ENABLE_ECHO_INPUT
+                    | Console::ENABLE_LINE_INPUT
+                    | Console::ENABLE_PROCESSED_INPUT;
+                (input_mode, output_mode)
+            }
+        };
+        // Hand virtual-terminal input back in the state the console was in when it was opened.
+        let input_mode = (input_mode & !Console::ENABLE_VIRTUAL_TERMINAL_INPUT)
+            | (self.original_input_mode & Console::ENABLE_VIRTUAL_TERMINAL_INPUT);
+
+        self.output.get_mut().set_mode(output_mode).ok();
+        self.input.set_mode(input_mode)?;
+        restored
+    }
+
+    fn is_raw_mode(&self) -> bool {
+        // The console modes are only captured while the terminal is in raw mode.
+        self.cooked_modes.is_some()
     }
 
     fn get_dimensions(&self) -> io::Result<WindowSize> {
         // NOTE: setting dimensions should be done by VT instead of `SetConsoleScreenBufferInfo`.
         // <https://learn.microsoft.com/en-us/windows/console/console-virtual-terminal-sequences#window-width>
-        self.output.get_ref().get_dimensions()
+        match (self.output.get_ref().get_dimensions(), self.fallback_size) {
+            (Err(_), Some(size)) => Ok(size),
+            (result, _) => result,
+        }
     }
 
     fn event_reader(&self) -> EventReader {
@@ -554,42 +1581,211 @@ impl Terminal for WindowsTerminal {
         let original_input_mode = self.original_input_mode;
         let original_output_cp = self.original_output_cp;
         let original_output_mode = self.original_output_mode;
+        let console = self.is_console();
+        let modes = self.modes.clone();
+        // Write to the terminal's own handles, which may not be stdin and stdout.
+        let handles = self
+            .input
+            .try_clone()
+            .and_then(|input| Ok((input, self.output.get_ref().try_clone()?)))
+            .ok();
         let hook = std::panic::take_hook();
-        let mode = self.mode;
         std::panic::set_hook(Box::new(move |info| {
-            if let Ok((mut input, mut output)) = open_pty(mode) {
+            let cloned = handles.as_ref().map(|(input, output)| {
+                Ok::<_, io::Error>((input.try_clone()?, output.try_clone()?))
+            });
+            if let Some(Ok((mut input, mut output))) = cloned {
                 f(&mut output);
-                let _ = input.flush();
-                let _ = input.set_code_page(original_input_cp);
-                let _ = input.set_mode(original_input_mode);
-                let _ = output.set_code_page(original_output_cp);
-                let _ = output.set_mode(original_output_mode);
+                let _ = modes.restore(&mut output);
+                if console {
+                    let _ = input.flush();
+                    let _ = input.set_code_page(original_input_cp);
+                    let _ = input.set_mode(original_input_mode);
+                    let _ = output.set_code_page(original_output_cp);
+                    let _ = output.set_mode(original_output_mode);
+                    let _ = output.reset_legacy();
+                }
             }
             hook(info);
         }));
         self.has_panic_hook = true;
     }
+
+    fn enable_line_wrapping(&mut self, enabled: bool) -> io::Result<()> {
+        self.modes.enable_line_wrapping(&mut self.output, enabled)
+    }
+
+    fn enable_grapheme_clustering(&mut self, enabled: bool) -> io::Result<()> {
+        self.modes
+            .enable_grapheme_clustering(&mut self.output, enabled)
+    }
+
+    fn push_title(&mut self) -> io::Result<()> {
+        self.modes.push_title(&mut self.output)
+    }
+
+    fn pop_title(&mut self) -> io::Result<()> {
+        self.modes.pop_title(&mut self.output)
+    }
+
+    fn set_title_stack_supported(&mut self, supported: bool) {
+        self.modes.set_title_stack_supported(supported);
+    }
+
+    fn set_output_buffer_capacity(&mut self, capacity: usize) -> io::Result<()> {
+        let placeholder = OutputHandle::new(Handle::stdout());
+        let _output = self.shared_output.lock();
+        super::set_buffer_capacity(&mut self.output, capacity, placeholder)
+    }
+
+    fn resync(&mut self) -> io::Result<()> {
+        io::Write::flush(self)?;
+        if !self.is_console() {
+            self.reader.clear();
+            return self.modes.reapply(&mut self.output);
+        }
+        self.input.flush()?;
+        if self.mode == InputReaderMode::Vte {
+            self.input.set_code_page(CP_UTF8)?;
+            self.output.get_mut().set_code_page(CP_UTF8)?;
+        }
+        if self.kind == ConsoleKind::ConPty {
+            let output_mode = self.output.get_mut().get_mode()?;
+            self.output
+                .get_mut()
+                .set_mode(output_mode | Console::ENABLE_VIRTUAL_TERMINAL_PROCESSING)?;
+        }
+        // The console modes are only captured while the terminal is in raw mode.
+        if self.cooked_modes.is_some() {
+            self.enter_raw_mode()?;
+        } else {
+            self.enter_cooked_mode()?;
+        }
+        // Input read before the flush may still be buffered or partially parsed.
+        self.reader.clear();
+        trace!(
+            raw_mode = self.cooked_modes.is_some(),
+            "resynchronized console"
+        );
+        Ok(())
+    }
+
+    fn set_scroll_region(&mut self, rows: Option<RangeInclusive<u16>>) -> io::Result<()> {
+        self.modes.set_scroll_region(&mut self.output, rows)
+    }
+
+    fn close(mut self) -> io::Result<()> {
+        self.teardown()
+    }
 }
 
 impl Drop for WindowsTerminal {
     fn drop(&mut self) {
-        if !self.has_panic_hook || !std::thread::panicking() {
-            let _ = self.flush();
-            let _ = self.input.flush(); // Drain unread input before handing the console back in cooked mode
-            let _ = self.input.set_code_page(self.original_input_cp);
-            let _ = self.output.get_mut().set_code_page(self.original_output_cp);
-            let _ = self.input.set_mode(self.original_input_mode);
-            let _ = self.output.get_mut().set_mode(self.original_output_mode);
+        if !self.closed && (!self.has_panic_hook || !std::thread::panicking()) {
+            let _ = self.teardown();
         }
     }
 }
 
 impl io::Write for WindowsTerminal {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        self.output.write(buf)
+        // A full buffer is flushed here, which must not interleave with a writer's flush.
+        let output = self.shared_output.lock();
+        let written = self.output.write(buf)?;
+        drop(output);
+        if self.is_console() && self.mouse_tracking.scan(&buf[..written]) {
+            // The output was written either way, so failing to change the mode doesn't fail the
+            // write.
+            let _ = self.update_quick_edit();
+        }
+        Ok(written)
     }
 
     fn flush(&mut self) -> io::Result<()> {
+        let _output = self.shared_output.lock();
         self.output.flush()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::io::Write as _;
+
+    use super::*;
+
+    #[test]
+    fn chunks_end_on_char_boundaries() {
+        assert_eq!(chunk_len(b"abc", 8), 3);
+        assert_eq!(chunk_len(b"abcdef", 4), 4);
+        // "€" is three bytes: never split it.
+        let text = "ab€cd".as_bytes();
+        assert_eq!(chunk_len(text, 3), 2);
+        assert_eq!(chunk_len(text, 4), 2);
+        assert_eq!(chunk_len(text, 5), 5);
+    }
+
+    #[test]
+    fn msys_pty_names() {
+        assert!(is_msys_pty_name(r"\msys-dd50a72ab4668b33-pty0-to-master"));
+        assert!(is_msys_pty_name(
+            r"\cygwin-e022582115c10879-pty4-from-master"
+        ));
+        assert!(!is_msys_pty_name(r"\msys-dd50a72ab4668b33-pipe-0x1"));
+        assert!(!is_msys_pty_name(r"\Device\NamedPipe\other-pty0-to-master"));
+        assert!(!is_msys_pty_name(""));
+    }
+
+    #[test]
+    fn mouse_tracking_scanner() {
+        let mut scanner = MouseTrackingScanner::default();
+        assert!(!scanner.scan(b"text\x1b[?1049h\x1b[1000h"));
+        assert!(scanner.scan(b"\x1b[?1000;1006h"));
+        assert!(scanner.is_tracking());
+        // Turning on another tracking mode doesn't change tracking as a whole.
+        assert!(!scanner.scan(b"\x1b[?1003h\x1b[?1000l"));
+        // Sequences split across writes.
+        assert!(!scanner.scan(b"\x1b[?10"));
+        assert!(scanner.scan(b"03l"));
+        assert!(!scanner.is_tracking());
+    }
+
+    #[test]
+    fn partial_utf16_writes() {
+        let text = "a€😀".as_bytes();
+        assert_eq!(utf8_len_of_units(text, 0), 0);
+        assert_eq!(utf8_len_of_units(text, 2), 4);
+        // Half of a surrogate pair doesn't count.
+        assert_eq!(utf8_len_of_units(text, 3), 4);
+        assert_eq!(utf8_len_of_units(text, 4), 8);
+        // Each run of invalid UTF-8 is one replacement character.
+        assert_eq!(utf8_len_of_units(b"a\xff\xffb", 2), 2);
+        assert_eq!(utf8_len_of_units(b"a\xe2\x80b", 2), 3);
+        assert_eq!(utf8_len_of_units(b"a\xe2\x80b", 3), 4);
+    }
+
+    #[test]
+    fn write_multi_megabyte_payload() {
+        // A sixel-like payload several times larger than a single `WriteFile` call.
+        let mut payload = b"\x1bPq".to_vec();
+        payload.extend((0..4 * 1024 * 1024).map(|i| b'?' + (i % 63) as u8));
+        payload.extend("€".repeat(1024).bytes());
+        payload.extend(b"\x1b\\");
+
+        let path = std::env::temp_dir().join(format!("termina-write-{}", std::process::id()));
+        let file = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&path)
+            .unwrap();
+        let mut output = BufWriter::with_capacity(OUTPUT_BUF_SIZE, OutputHandle::new(file.into()));
+        output.write_all(&payload).unwrap();
+        output.flush().unwrap();
+        drop(output);
+
+        let written = fs::read(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        assert!(written == payload, "the payload was not written intact");
+    }
+}
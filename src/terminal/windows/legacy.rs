@@ -0,0 +1,407 @@
+//! Output for consoles without virtual terminal processing.
+//!
+//! Consoles before Windows 10, or with the legacy console option enabled, print escape sequences
+//! literally. [`Translator`] picks the sequences out of the output and translates a core subset
+//! into console operations which `OutputHandle` carries out with the Console API:
+//!
+//! * SGR (`CSI … m`): the 16 ANSI colors, approximations of 256-color and RGB colors, bold (as
+//!   the intensity bit), underline and reverse video.
+//! * Cursor movement: CUP (`CSI row ; col H`), CUU, CUD, CUF, CUB and CHA.
+//! * Erasing: ED (`CSI n J`) and EL (`CSI n K`).
+//! * Cursor visibility: DECTCEM (`CSI ? 25 h` and `CSI ? 25 l`).
+//!
+//! Every other sequence, such as OSC strings, mode changes and the alternate screen, is dropped.
+
+use windows_sys::Win32::System::Console::{
+    BACKGROUND_BLUE, BACKGROUND_GREEN, BACKGROUND_INTENSITY, BACKGROUND_RED,
+    COMMON_LVB_REVERSE_VIDEO, COMMON_LVB_UNDERSCORE, FOREGROUND_BLUE, FOREGROUND_GREEN,
+    FOREGROUND_INTENSITY, FOREGROUND_RED,
+};
+
+/// The most parameter and intermediate bytes kept for a CSI sequence. Longer sequences are
+/// dropped.
+const MAX_PARAMS_LEN: usize = 64;
+
+const FOREGROUND: u16 = FOREGROUND_RED | FOREGROUND_GREEN | FOREGROUND_BLUE | FOREGROUND_INTENSITY;
+const BACKGROUND: u16 = BACKGROUND_RED | BACKGROUND_GREEN | BACKGROUND_BLUE | BACKGROUND_INTENSITY;
+
+/// A console operation standing in for an escape sequence.
+#[derive(Debug, PartialEq, Eq)]
+pub(super) enum Op<'a> {
+    /// Text, including control characters which the console processes itself such as CR and LF.
+    Text(&'a [u8]),
+    /// Sets the attributes of text written from now on.
+    Attributes(u16),
+    /// Moves the cursor to a zero-based position within the window.
+    MoveTo {
+        row: u16,
+        col: u16,
+    },
+    /// Moves the cursor to a zero-based column on its row.
+    MoveToColumn(u16),
+    /// Moves the cursor relative to its position, stopping at the edges of the window.
+    MoveBy {
+        rows: i32,
+        cols: i32,
+    },
+    /// Erases the window after the cursor (0), before it (1) or all of it (2).
+    EraseDisplay(u16),
+    /// Erases the cursor's line after the cursor (0), before it (1) or all of it (2).
+    EraseLine(u16),
+    ShowCursor(bool),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Ground,
+    /// After ESC, and after any intermediate bytes of an escape sequence.
+    Escape,
+    /// Within a CSI sequence.
+    Csi,
+    /// Within an OSC, DCS, APC, PM or SOS string, which is dropped up to its terminator.
+    String,
+    /// After ESC within a string.
+    StringEscape,
+}
+
+/// Splits output into text and [`Op`]s, keeping track of the text attributes.
+#[derive(Debug, Clone)]
+pub(super) struct Translator {
+    state: State,
+    /// Parameter and intermediate bytes of the current CSI sequence.
+    params: Vec<u8>,
+    /// The attributes when the console was opened, which SGR 0 returns to.
+    default_attributes: u16,
+    attributes: u16,
+}
+
+impl Translator {
+    pub(super) fn new(default_attributes: u16) -> Self {
+        Self {
+            state: State::Ground,
+            params: Vec::new(),
+            default_attributes,
+            attributes: default_attributes,
+        }
+    }
+
+    /// Returns a translator in the initial state with the same default attributes.
+    pub(super) fn fresh(&self) -> Self {
+        Self::new(self.default_attributes)
+    }
+
+    /// Returns the attributes for text written now.
+    pub(super) fn attributes(&self) -> u16 {
+        self.attributes
+    }
+
+    /// Translates the start of `buf`, returning the number of bytes consumed and the [`Op`] they
+    /// make up, if any.
+    ///
+    /// Text is returned up to the next escape sequence, and a sequence up to its end or the end of
+    /// `buf`. Sequences may be split across calls: the rest of a sequence is picked up by the next
+    /// call.
+    pub(super) fn next<'a>(&mut self, buf: &'a [u8]) -> (usize, Option<Op<'a>>) {
+        if self.state == State::Ground {
+            let len = buf.iter().position(|&b| b == 0x1B).unwrap_or(buf.len());
+            if len > 0 {
+                return (len, Some(Op::Text(&buf[..len])));
+            }
+        }
+        let mut consumed = 0;
+        for &byte in buf {
+            consumed += 1;
+            if let Some(op) = self.advance(byte) {
+                return (consumed, Some(op));
+            }
+            if self.state == State::Ground {
+                break;
+            }
+        }
+        (consumed, None)
+    }
+
+    fn advance(&mut self, byte: u8) -> Option<Op<'static>> {
+        match (self.state, byte) {
+            (_, 0x1B) if self.state != State::String => self.state = State::Escape,
+            (State::Ground, _) => unreachable!("text is split off in `Translator::next`"),
+            (State::Escape, b'[') => {
+                self.params.clear();
+                self.state = State::Csi;
+            }
+            (State::Escape, b']' | b'P' | b'_' | b'^' | b'X') => self.state = State::String,
+            // Intermediate bytes, as in `ESC ( B`.
+            (State::Escape, 0x20..=0x2F) => (),
+            (State::Escape, _) => self.state = State::Ground,
+            (State::Csi, 0x20..=0x3F) => {
+                if self.params.len() < MAX_PARAMS_LEN {
+                    self.params.push(byte);
+                } else {
+                    self.params.clear();
+                    self.state = State::String;
+                }
+            }
+            (State::Csi, 0x40..=0x7E) => {
+                self.state = State::Ground;
+                return self.dispatch_csi(byte);
+            }
+            // Other C0 controls within a sequence are dropped.
+            (State::Csi, _) => (),
+            (State::String, 0x07) => self.state = State::Ground,
+            (State::String, 0x1B) => self.state = State::StringEscape,
+            (State::String, _) => (),
+            (State::StringEscape, b'\\') => self.state = State::Ground,
+            (State::StringEscape, _) => {
+                // An ESC which doesn't form ST ends the string and starts a new sequence.
+                self.state = State::Escape;
+                return self.advance(byte);
+            }
+        }
+        None
+    }
+
+    fn dispatch_csi(&mut self, final_byte: u8) -> Option<Op<'static>> {
+        let (private, params) = match self.params.split_first() {
+            Some((b'?', params)) => (true, params),
+            _ => (false, &self.params[..]),
+        };
+        let params: Vec<u16> = params
+            .split(|&b| b == b';' || b == b':')
+            .map(|param| {
+                std::str::from_utf8(param)
+                    .ok()
+                    .and_then(|param| param.parse().ok())
+                    .unwrap_or(0)
+            })
+            .collect();
+        let param = |index: usize| params.get(index).copied().unwrap_or(0);
+        // Movements count at least one cell.
+        let count = |index: usize| i32::from(param(index).max(1));
+        let op = match (private, final_byte) {
+            (false, b'm') => {
+                self.attributes = apply_sgr(self.attributes, self.default_attributes, &params);
+                Op::Attributes(self.attributes)
+            }
+            (false, b'H' | b'f') => Op::MoveTo {
+                row: param(0).saturating_sub(1),
+                col: param(1).saturating_sub(1),
+            },
+            (false, b'A') => Op::MoveBy {
+                rows: -count(0),
+                cols: 0,
+            },
+            (false, b'B') => Op::MoveBy {
+                rows: count(0),
+                cols: 0,
+            },
+            (false, b'C') => Op::MoveBy {
+                rows: 0,
+                cols: count(0),
+            },
+            (false, b'D') => Op::MoveBy {
+                rows: 0,
+                cols: -count(0),
+            },
+            (false, b'G') => Op::MoveToColumn(param(0).saturating_sub(1)),
+            (false, b'J') => Op::EraseDisplay(param(0).min(2)),
+            (false, b'K') => Op::EraseLine(param(0).min(2)),
+            (true, b'h') if params.contains(&25) => Op::ShowCursor(true),
+            (true, b'l') if params.contains(&25) => Op::ShowCursor(false),
+            _ => return None,
+        };
+        Some(op)
+    }
+}
+
+/// Applies the SGR parameters `params` to `attributes`.
+fn apply_sgr(mut attributes: u16, default: u16, params: &[u16]) -> u16 {
+    let mut params = params.iter().copied();
+    while let Some(param) = params.next() {
+        match param {
+            0 => attributes = default,
+            1 => attributes |= FOREGROUND_INTENSITY,
+            22 => {
+                attributes = (attributes & !FOREGROUND_INTENSITY) | (default & FOREGROUND_INTENSITY)
+            }
+            4 => attributes |= COMMON_LVB_UNDERSCORE,
+            24 => attributes &= !COMMON_LVB_UNDERSCORE,
+            7 => attributes |= COMMON_LVB_REVERSE_VIDEO,
+            27 => attributes &= !COMMON_LVB_REVERSE_VIDEO,
+            // The intensity bit stands for both bold and bright colors, so normal colors keep it.
+            30..=37 => {
+                attributes =
+                    foreground(attributes, param - 30) | (attributes & FOREGROUND_INTENSITY)
+            }
+            90..=97 => attributes = foreground(attributes, param - 90 + 8),
+            39 => attributes = (attributes & !FOREGROUND) | (default & FOREGROUND),
+            40..=47 => attributes = background(attributes, param - 40),
+            100..=107 => attributes = background(attributes, param - 100 + 8),
+            49 => attributes = (attributes & !BACKGROUND) | (default & BACKGROUND),
+            38 | 48 => {
+                let color = match params.next() {
+                    Some(5) => params.next().map(indexed_color),
+                    Some(2) => match (params.next(), params.next(), params.next()) {
+                        (Some(r), Some(g), Some(b)) => Some(nearest_ansi(r, g, b)),
+                        _ => None,
+                    },
+                    _ => None,
+                };
+                match color {
+                    Some(color) if param == 38 => attributes = foreground(attributes, color),
+                    Some(color) => attributes = background(attributes, color),
+                    None => (),
+                }
+            }
+            _ => (),
+        }
+    }
+    attributes
+}
+
+fn foreground(attributes: u16, ansi: u16) -> u16 {
+    (attributes & !FOREGROUND) | console_color(ansi)
+}
+
+fn background(attributes: u16, ansi: u16) -> u16 {
+    (attributes & !BACKGROUND) | console_color(ansi) << 4
+}
+
+/// Converts one of the 16 ANSI colors to the console's foreground attributes. ANSI colors list
+/// red in the lowest bit and blue in the highest, the console the other way around.
+fn console_color(ansi: u16) -> u16 {
+    let mut color = 0;
+    if ansi & 1 != 0 {
+        color |= FOREGROUND_RED;
+    }
+    if ansi & 2 != 0 {
+        color |= FOREGROUND_GREEN;
+    }
+    if ansi & 4 != 0 {
+        color |= FOREGROUND_BLUE;
+    }
+    if ansi & 8 != 0 {
+        color |= FOREGROUND_INTENSITY;
+    }
+    color
+}
+
+/// Approximates a color of the 256-color palette with one of the 16 ANSI colors.
+fn indexed_color(index: u16) -> u16 {
+    match index {
+        0..=15 => index,
+        16..=231 => {
+            const LEVELS: [u16; 6] = [0, 95, 135, 175, 215, 255];
+            let index = index - 16;
+            let level = |i: u16| LEVELS[usize::from(i % 6)];
+            nearest_ansi(level(index / 36), level(index / 6), level(index))
+        }
+        _ => {
+            let gray = 8 + 10 * (index.min(255) - 232);
+            nearest_ansi(gray, gray, gray)
+        }
+    }
+}
+
+/// Approximates an RGB color with one of the 16 ANSI colors: each channel which is at least half
+/// on sets its bit, and bright colors set the intensity bit.
+fn nearest_ansi(r: u16, g: u16, b: u16) -> u16 {
+    let max = r.max(g).max(b);
+    // Parameters go up to `u16::MAX`, so the comparison is done in `u32` to not overflow.
+    let on = |channel: u16| {
+        channel >= 0x80 || (max >= 0x40 && u32::from(channel) * 2 >= u32::from(max) + 0x20)
+    };
+    let mut ansi = u16::from(on(r)) | u16::from(on(g)) << 1 | u16::from(on(b)) << 2;
+    if max > 0xC0 || (ansi == 0 && max >= 0x60) {
+        ansi |= 8;
+    }
+    ansi
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const DEFAULT: u16 = FOREGROUND_RED | FOREGROUND_GREEN | FOREGROUND_BLUE;
+
+    fn translate(input: &[&[u8]]) -> Vec<String> {
+        let mut translator = Translator::new(DEFAULT);
+        let mut ops = Vec::new();
+        for chunk in input {
+            let mut rest = *chunk;
+            while !rest.is_empty() {
+                let (len, op) = translator.next(rest);
+                ops.extend(op);
+                rest = &rest[len..];
+            }
+        }
+        ops.iter()
+            .map(|op| match op {
+                Op::Text(text) => format!("{:?}", String::from_utf8_lossy(text)),
+                op => format!("{op:?}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn translate_sequences() {
+        assert_eq!(
+            translate(&[b"a\x1b[2;3Hb\r\n\x1b[K\x1b[?25l"]),
+            [
+                r#""a""#,
+                "MoveTo { row: 1, col: 2 }",
+                r#""b\r\n""#,
+                "EraseLine(0)",
+                "ShowCursor(false)",
+            ]
+        );
+        assert_eq!(
+            translate(&[b"\x1b[A\x1b[3D\x1b[G\x1b[2J"]),
+            [
+                "MoveBy { rows: -1, cols: 0 }",
+                "MoveBy { rows: 0, cols: -3 }",
+                "MoveToColumn(0)",
+                "EraseDisplay(2)",
+            ]
+        );
+        // Sequences split across writes.
+        assert_eq!(
+            translate(&[b"a\x1b[", b"5", b"Cb"]),
+            [r#""a""#, "MoveBy { rows: 0, cols: 5 }", r#""b""#]
+        );
+        // Unsupported sequences and strings are dropped.
+        assert_eq!(
+            translate(&[b"\x1b[?1049h\x1b]0;title\x07\x1b]8;;x\x1b\\a\x1b(Bb"]),
+            [r#""a""#, r#""b""#]
+        );
+    }
+
+    #[test]
+    fn sgr_attributes() {
+        let sgr = |params: &[u16]| apply_sgr(DEFAULT, DEFAULT, params);
+        assert_eq!(sgr(&[31]), FOREGROUND_RED);
+        assert_eq!(sgr(&[1, 34]), FOREGROUND_BLUE | FOREGROUND_INTENSITY);
+        assert_eq!(
+            sgr(&[92, 44]),
+            FOREGROUND_GREEN | FOREGROUND_INTENSITY | BACKGROUND_BLUE
+        );
+        assert_eq!(sgr(&[31, 0]), DEFAULT);
+        assert_eq!(sgr(&[31, 39]), DEFAULT);
+        assert_eq!(
+            sgr(&[7, 4]),
+            DEFAULT | COMMON_LVB_REVERSE_VIDEO | COMMON_LVB_UNDERSCORE
+        );
+        assert_eq!(sgr(&[38, 5, 9]), FOREGROUND_RED | FOREGROUND_INTENSITY);
+        assert_eq!(sgr(&[38, 2, 0, 0, 0x80]), FOREGROUND_BLUE);
+        assert_eq!(
+            sgr(&[48, 5, 196]) & BACKGROUND,
+            BACKGROUND_RED | BACKGROUND_INTENSITY
+        );
+        // Out of range channels don't overflow.
+        assert_eq!(
+            sgr(&[38, 2, u16::MAX, u16::MAX, u16::MAX]),
+            DEFAULT | FOREGROUND_INTENSITY
+        );
+        // Incomplete colors are ignored.
+        assert_eq!(sgr(&[38, 2, 1]), DEFAULT);
+    }
+}
@@ -0,0 +1,124 @@
+//! A handle for writing to a terminal's output from other threads.
+//!
+//! Writes through `&mut Terminal` need the terminal itself, which a thread blocked in
+//! [`Terminal::read`](super::Terminal::read) holds on to. A [`TerminalWriter`] instead owns a
+//! duplicate of the terminal's output handle behind a lock which the terminal also takes while it
+//! writes, so the bytes of one flush are never interleaved with the other's.
+
+use std::{
+    io,
+    sync::{Arc, OnceLock},
+};
+
+use parking_lot::{Mutex, MutexGuard};
+
+use super::PlatformHandle;
+
+/// A cloneable handle which writes to a terminal's output.
+///
+/// Each clone has its own buffer. Writes are collected in the buffer and sent to the terminal
+/// together by [`flush`](io::Write::flush), or when the writer is dropped, so that a line written
+/// by a background thread, such as a progress ticker, isn't split by the terminal's own output.
+/// The terminal waits for a writer's flush to finish before writing its own buffered output and
+/// the other way around.
+///
+/// Create writers with `UnixTerminal::writer` or `WindowsTerminal::writer`. Modes which the
+/// terminal tracks, such as line wrapping set with
+/// [`Terminal::enable_line_wrapping`](super::Terminal::enable_line_wrapping), are not tracked for
+/// sequences written through a writer.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::{io::Write as _, thread, time::Duration};
+///
+/// use termina::{Event, PlatformTerminal, Terminal as _};
+///
+/// # fn main() -> std::io::Result<()> {
+/// let terminal = PlatformTerminal::new()?;
+/// let mut writer = terminal.writer()?;
+/// thread::spawn(move || {
+///     for percent in (0..=100).step_by(10) {
+///         write!(writer, "\r{percent}%")?;
+///         writer.flush()?;
+///         thread::sleep(Duration::from_millis(100));
+///     }
+///     std::io::Result::Ok(())
+/// });
+/// // The main thread blocks reading input meanwhile.
+/// let _ = terminal.read(|event| matches!(event, Event::Key(_)))?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct TerminalWriter {
+    output: Arc<Mutex<PlatformHandle>>,
+    buf: Vec<u8>,
+}
+
+impl Clone for TerminalWriter {
+    /// Returns a writer to the same terminal with an empty buffer.
+    fn clone(&self) -> Self {
+        Self {
+            output: self.output.clone(),
+            buf: Vec::new(),
+        }
+    }
+}
+
+impl io::Write for TerminalWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let mut output = self.output.lock();
+        if !self.buf.is_empty() {
+            // Bytes which weren't written are dropped rather than sent with the next flush, which
+            // could split an escape sequence.
+            let result = output.write_all(&self.buf);
+            self.buf.clear();
+            result?;
+        }
+        output.flush()
+    }
+}
+
+impl Drop for TerminalWriter {
+    fn drop(&mut self) {
+        let _ = io::Write::flush(self);
+    }
+}
+
+/// The output shared between a terminal and its [`TerminalWriter`]s.
+///
+/// It is created by the first writer, so a terminal without writers never takes the lock.
+#[derive(Debug, Default)]
+pub(crate) struct SharedOutput(OnceLock<Arc<Mutex<PlatformHandle>>>);
+
+impl SharedOutput {
+    /// Returns a new writer, duplicating the terminal's output with `try_clone` for the first.
+    pub(crate) fn writer<F>(&self, try_clone: F) -> io::Result<TerminalWriter>
+    where
+        F: FnOnce() -> io::Result<PlatformHandle>,
+    {
+        let output = match self.0.get() {
+            Some(output) => output.clone(),
+            None => {
+                let handle = try_clone()?;
+                self.0.get_or_init(|| Arc::new(Mutex::new(handle))).clone()
+            }
+        };
+        Ok(TerminalWriter {
+            output,
+            buf: Vec::new(),
+        })
+    }
+
+    /// Waits for writers to finish flushing and keeps them from flushing until the guard is
+    /// dropped. Returns `None` if no writer was created.
+    pub(crate) fn lock(&self) -> Option<MutexGuard<'_, PlatformHandle>> {
+        self.0.get().map(|output| output.lock())
+    }
+}
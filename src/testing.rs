@@ -0,0 +1,289 @@
+//! Helpers for end-to-end tests of terminal applications.
+//!
+//! [`RecordingTerminal`] wraps a [`Terminal`] and records the bytes the application writes and
+//! the events it reads, so a test can assert on both. On Unix, [`ReplayEventSource`] feeds a
+//! [`Script`] of input bytes into an ordinary [`EventReader`], with delays between steps, so the
+//! application's real parser and reader handle the input without a PTY.
+//!
+//! This module is available with the `testing` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(unix)]
+//! # fn main() -> std::io::Result<()> {
+//! use std::time::Duration;
+//!
+//! use termina::{
+//!     event::KeyCode,
+//!     testing::{ReplayEventSource, Script},
+//!     Event,
+//! };
+//!
+//! let script = Script::new()
+//!     .input("hi")
+//!     .wait(Duration::from_millis(10))
+//!     .input("\x1b[A");
+//! let replay = ReplayEventSource::spawn(script)?;
+//! let reader = replay.event_reader();
+//!
+//! let mut codes = Vec::new();
+//! while let Ok(Event::Key(key)) = reader.read(|_| true) {
+//!     codes.push(key.code);
+//! }
+//! assert_eq!(codes, [KeyCode::Char('h'), KeyCode::Char('i'), KeyCode::Up]);
+//! replay.join()
+//! # }
+//! # #[cfg(windows)]
+//! # fn main() {}
+//! ```
+
+use std::{
+    io,
+    ops::RangeInclusive,
+    time::{Duration, Instant},
+};
+
+use parking_lot::Mutex;
+
+use crate::{Event, EventReader, PlatformHandle, Terminal, WindowSize};
+
+/// A [`Terminal`] wrapper which records output and the events read through it.
+///
+/// Bytes written to the wrapper are recorded before they are passed on to the wrapped terminal.
+/// Events are recorded when they are returned by [`Terminal::read`] on the wrapper, along with the
+/// time since the wrapper was created. Events read from a reader returned by
+/// [`Terminal::event_reader`] bypass the wrapper and aren't recorded.
+#[derive(Debug)]
+pub struct RecordingTerminal<T> {
+    inner: T,
+    start: Instant,
+    output: Vec<u8>,
+    events: Mutex<Vec<(Duration, Event)>>,
+}
+
+impl<T: Terminal> RecordingTerminal<T> {
+    /// Wraps `terminal`, starting with an empty recording.
+    pub fn new(terminal: T) -> Self {
+        Self {
+            inner: terminal,
+            start: Instant::now(),
+            output: Vec::new(),
+            events: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// The bytes written so far.
+    pub fn output(&self) -> &[u8] {
+        &self.output
+    }
+
+    /// Returns the bytes written so far and clears the recorded output.
+    pub fn take_output(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.output)
+    }
+
+    /// The events read so far, with the time each was returned relative to [`Self::new`].
+    pub fn events(&self) -> Vec<(Duration, Event)> {
+        self.events.lock().clone()
+    }
+
+    /// Returns the wrapped terminal.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Returns the wrapped terminal mutably. Writes made through it aren't recorded.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Unwraps the terminal, discarding the recording.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: Terminal> io::Write for RecordingTerminal<T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.output.extend_from_slice(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<T: Terminal> Terminal for RecordingTerminal<T> {
+    fn enter_raw_mode(&mut self) -> io::Result<()> {
+        self.inner.enter_raw_mode()
+    }
+
+    fn enter_cooked_mode(&mut self) -> io::Result<()> {
+        self.inner.enter_cooked_mode()
+    }
+
+    fn is_raw_mode(&self) -> bool {
+        self.inner.is_raw_mode()
+    }
+
+    fn get_dimensions(&self) -> io::Result<WindowSize> {
+        self.inner.get_dimensions()
+    }
+
+    fn event_reader(&self) -> EventReader {
+        self.inner.event_reader()
+    }
+
+    fn poll<F: Fn(&Event) -> bool>(
+        &self,
+        filter: F,
+        timeout: Option<Duration>,
+    ) -> io::Result<bool> {
+        self.inner.poll(filter, timeout)
+    }
+
+    fn read<F: Fn(&Event) -> bool>(&self, filter: F) -> io::Result<Event> {
+        let event = self.inner.read(filter)?;
+        self.events
+            .lock()
+            .push((self.start.elapsed(), event.clone()));
+        Ok(event)
+    }
+
+    fn set_panic_hook(&mut self, f: impl Fn(&mut PlatformHandle) + Send + Sync + 'static) {
+        self.inner.set_panic_hook(f)
+    }
+
+    fn enable_line_wrapping(&mut self, enabled: bool) -> io::Result<()> {
+        self.inner.enable_line_wrapping(enabled)
+    }
+
+    fn enable_grapheme_clustering(&mut self, enabled: bool) -> io::Result<()> {
+        self.inner.enable_grapheme_clustering(enabled)
+    }
+
+    fn set_scroll_region(&mut self, rows: Option<RangeInclusive<u16>>) -> io::Result<()> {
+        self.inner.set_scroll_region(rows)
+    }
+
+    fn push_title(&mut self) -> io::Result<()> {
+        self.inner.push_title()
+    }
+
+    fn pop_title(&mut self) -> io::Result<()> {
+        self.inner.pop_title()
+    }
+
+    fn set_title_stack_supported(&mut self, supported: bool) {
+        self.inner.set_title_stack_supported(supported);
+    }
+
+    fn set_output_buffer_capacity(&mut self, capacity: usize) -> io::Result<()> {
+        self.inner.set_output_buffer_capacity(capacity)
+    }
+
+    fn resync(&mut self) -> io::Result<()> {
+        self.inner.resync()
+    }
+
+    fn close(self) -> io::Result<()> {
+        self.inner.close()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Step {
+    Input(Vec<u8>),
+    Wait(Duration),
+}
+
+/// A sequence of terminal input with delays, replayed by [`ReplayEventSource`].
+///
+/// Each [`Self::input`] step is written in one piece, so an escape sequence split across two
+/// steps arrives in two reads, as it might from a slow connection.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Script {
+    steps: Vec<Step>,
+}
+
+impl Script {
+    /// Creates an empty script.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends bytes of terminal input, such as typed text or escape sequences.
+    pub fn input(mut self, bytes: impl AsRef<[u8]>) -> Self {
+        self.steps.push(Step::Input(bytes.as_ref().to_vec()));
+        self
+    }
+
+    /// Appends a pause before the next input.
+    pub fn wait(mut self, duration: Duration) -> Self {
+        self.steps.push(Step::Wait(duration));
+        self
+    }
+}
+
+/// Replays a [`Script`] into an [`EventReader`].
+///
+/// A background thread writes the script's input to one end of a socket pair and the reader
+/// reads from the other end, through the same parser as a real terminal. Once the whole script
+/// has been written the socket is closed: the reader then reports [`Event::Disconnected`] after
+/// the remaining events, which marks the end of the replay.
+///
+/// Resize events still come from the process's own terminal, since they are delivered by
+/// `SIGWINCH` rather than input bytes. This type is only available on Unix.
+#[cfg(unix)]
+#[derive(Debug)]
+pub struct ReplayEventSource {
+    reader: EventReader,
+    feeder: std::thread::JoinHandle<io::Result<()>>,
+}
+
+#[cfg(unix)]
+impl ReplayEventSource {
+    /// Starts replaying `script`.
+    pub fn spawn(script: Script) -> io::Result<Self> {
+        use std::{io::Write as _, os::unix::net::UnixStream};
+
+        use crate::{event::source::UnixEventSource, terminal::FileDescriptor};
+
+        let (read, mut write) = UnixStream::pair()?;
+        let source =
+            UnixEventSource::new(FileDescriptor::Owned(read.into()), FileDescriptor::STDOUT)?;
+        let feeder = std::thread::Builder::new()
+            .name("termina-replay".into())
+            .spawn(move || {
+                for step in script.steps {
+                    match step {
+                        Step::Input(bytes) => write.write_all(&bytes)?,
+                        Step::Wait(duration) => std::thread::sleep(duration),
+                    }
+                }
+                Ok(())
+            })?;
+        Ok(Self {
+            reader: EventReader::new(source),
+            feeder,
+        })
+    }
+
+    /// Returns a reader of the replayed events.
+    pub fn event_reader(&self) -> EventReader {
+        self.reader.clone()
+    }
+
+    /// Waits for the whole script to be written, returning any error from writing it.
+    pub fn join(self) -> io::Result<()> {
+        self.feeder.join().unwrap_or_else(|_| {
+            Err(io::Error::new(
+                io::ErrorKind::Other,
+                "replay thread panicked",
+            ))
+        })
+    }
+}
@@ -0,0 +1,302 @@
+//! Measuring text the way the terminal advances the cursor.
+//!
+//! Layout code has to agree with the terminal about how many columns text occupies, otherwise
+//! every line after an emoji or a combining sequence is drawn out of place. Terminals measure text
+//! in one of two ways:
+//!
+//! * Traditionally each code point advances the cursor by its own `wcwidth`, so a sequence such
+//!   as a family emoji joined by zero width joiners occupies the width of all of its pieces.
+//! * Terminals with [grapheme clustering mode][spec] (mode 2027) enabled, and some terminals which
+//!   always cluster, advance the cursor once per grapheme cluster, which keeps such sequences in a
+//!   single cell.
+//!
+//! [`WidthMode`] selects between the two, and [`str_width`] and [`grapheme_widths`] measure text
+//! with it. [`detect`] asks the terminal which one it uses.
+//!
+//! This module is available with the `width` feature.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::{io, time::Duration};
+//!
+//! use termina::{
+//!     width::{self, str_width},
+//!     PlatformTerminal, Terminal,
+//! };
+//!
+//! fn main() -> io::Result<()> {
+//!     let mut terminal = PlatformTerminal::new()?;
+//!     terminal.enter_raw_mode()?;
+//!     let mode = width::detect(&mut terminal, Duration::from_millis(200))?;
+//!     terminal.enter_cooked_mode()?;
+//!
+//!     println!("{}", str_width("👨‍👩‍👧", mode));
+//!     Ok(())
+//! }
+//! ```
+//!
+//! # Implementation Notes
+//!
+//! Code point widths come from the `unicode-width` crate and grapheme clusters from the
+//! `unicode-segmentation` crate. In grapheme clustering mode a cluster is as wide as its first
+//! code point, except that a variation selector 16 (emoji presentation) widens it to two columns
+//! and a variation selector 15 (text presentation) narrows it to one, following the [Contour
+//! Unicode core specification][spec]. Flags are two columns wide. Control characters have no width
+//! in either mode.
+//!
+//! [spec]: https://github.com/contour-terminal/terminal-unicode-core/
+
+use std::{io, time::Duration};
+
+use unicode_segmentation::UnicodeSegmentation as _;
+use unicode_width::UnicodeWidthChar as _;
+
+use crate::{
+    escape::csi::{
+        Csi, Cursor, DecModeSetting, DecPrivateMode, DecPrivateModeCode, Edit, EraseInLine,
+    },
+    query::{CursorPosition, QueryBatch},
+    Terminal,
+};
+
+/// Selects the emoji presentation of the preceding character.
+const VARIATION_SELECTOR_16: char = '\u{FE0F}';
+/// Selects the text presentation of the preceding character.
+const VARIATION_SELECTOR_15: char = '\u{FE0E}';
+
+/// A family emoji: two columns wide when clustered and six columns wide otherwise.
+const PROBE: &str = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+
+/// How the terminal advances the cursor over text.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WidthMode {
+    /// Every code point advances the cursor by its own width, like `wcwidth`.
+    #[default]
+    Legacy,
+    /// Every grapheme cluster advances the cursor once, as with mode 2027 enabled.
+    GraphemeClustering,
+}
+
+impl WidthMode {
+    /// Selects the mode from the reported setting of
+    /// [`DecPrivateModeCode::GraphemeClustering`].
+    ///
+    /// Terminals which support the mode but report it as reset measure with
+    /// [`Self::GraphemeClustering`] once it is set, so the caller should set the mode with
+    /// [`Terminal::enable_grapheme_clustering`] when this returns that width mode. Terminals
+    /// which didn't answer or don't recognize the mode measure with [`Self::Legacy`].
+    pub fn from_mode_setting(setting: Option<DecModeSetting>) -> Self {
+        match setting {
+            Some(DecModeSetting::Set | DecModeSetting::PermanentlySet | DecModeSetting::Reset) => {
+                Self::GraphemeClustering
+            }
+            _ => Self::Legacy,
+        }
+    }
+}
+
+/// Asks the terminal how it advances the cursor, waiting up to `timeout` for each reply.
+///
+/// The terminal is first asked for the setting of
+/// [`DecPrivateModeCode::GraphemeClustering`], which is set if it is supported but reset.
+/// Terminals which don't support the mode may still cluster, so a family emoji is then printed at
+/// the start of the cursor's line and the cursor position is requested to see how far the cursor
+/// moved. This erases the cursor's line: it is cleared afterwards and the cursor is left at its
+/// start, so probe before drawing. Terminals which answer neither query are assumed to use
+/// [`WidthMode::Legacy`].
+///
+/// A mode which is supported but reset is enabled with [`Terminal::enable_grapheme_clustering`],
+/// so it is disabled again in cooked mode and when the terminal is dropped.
+///
+/// Call this in raw mode, since replies are otherwise only delivered after Enter is pressed.
+pub fn detect<T: Terminal>(terminal: &mut T, timeout: Duration) -> io::Result<WidthMode> {
+    let mode = DecPrivateMode::Code(DecPrivateModeCode::GraphemeClustering);
+    let results = QueryBatch::new().dec_mode(mode).run(terminal, timeout)?;
+    match results.dec_mode(mode) {
+        Some(DecModeSetting::Set | DecModeSetting::PermanentlySet) => {
+            return Ok(WidthMode::GraphemeClustering)
+        }
+        Some(DecModeSetting::Reset) => {
+            terminal.enable_grapheme_clustering(true)?;
+            terminal.flush()?;
+            return Ok(WidthMode::GraphemeClustering);
+        }
+        _ => (),
+    }
+
+    let probe = format!(
+        "\r{PROBE}{}",
+        Csi::Cursor(Cursor::RequestActivePositionReport)
+    );
+    let position: Option<CursorPosition> = terminal.query(probe, timeout)?;
+    write!(
+        terminal,
+        "\r{}",
+        Csi::Edit(Edit::EraseInLine(EraseInLine::EraseToEndOfLine))
+    )?;
+    terminal.flush()?;
+    Ok(match position {
+        Some(position) if position.col.get_zero_based() == 2 => WidthMode::GraphemeClustering,
+        _ => WidthMode::Legacy,
+    })
+}
+
+/// Returns the number of columns `text` occupies.
+///
+/// # Examples
+///
+/// ```
+/// use termina::width::{str_width, WidthMode};
+///
+/// // A woman, a zero width joiner and a laptop.
+/// let technologist = "\u{1F469}\u{200D}\u{1F4BB}";
+/// assert_eq!(str_width(technologist, WidthMode::Legacy), 4);
+/// assert_eq!(str_width(technologist, WidthMode::GraphemeClustering), 2);
+/// assert_eq!(str_width("e\u{301}", WidthMode::Legacy), 1);
+/// ```
+pub fn str_width(text: &str, mode: WidthMode) -> usize {
+    match mode {
+        WidthMode::Legacy => text.chars().map(char_width).sum(),
+        WidthMode::GraphemeClustering => grapheme_widths(text, mode).map(|(_, w)| w).sum(),
+    }
+}
+
+/// Returns each grapheme cluster of `text` together with the number of columns it occupies.
+///
+/// With [`WidthMode::Legacy`] a cluster is as wide as all of its code points together.
+///
+/// # Examples
+///
+/// ```
+/// use termina::width::{grapheme_widths, WidthMode};
+///
+/// let widths: Vec<_> = grapheme_widths("a\u{2764}\u{FE0F}", WidthMode::GraphemeClustering)
+///     .collect();
+/// assert_eq!(widths, [("a", 1), ("\u{2764}\u{FE0F}", 2)]);
+/// ```
+pub fn grapheme_widths(text: &str, mode: WidthMode) -> impl Iterator<Item = (&str, usize)> + '_ {
+    text.graphemes(true).map(move |grapheme| {
+        let width = match mode {
+            WidthMode::Legacy => grapheme.chars().map(char_width).sum(),
+            WidthMode::GraphemeClustering => cluster_width(grapheme),
+        };
+        (grapheme, width)
+    })
+}
+
+/// Returns the width of a code point, treating control characters as zero width.
+fn char_width(ch: char) -> usize {
+    ch.width().unwrap_or(0)
+}
+
+/// Returns the width of a grapheme cluster in grapheme clustering mode.
+fn cluster_width(grapheme: &str) -> usize {
+    let mut chars = grapheme.chars();
+    let first = chars.next();
+    // A pair of regional indicators forms a flag, which is displayed as an emoji.
+    if first.is_some_and(is_regional_indicator) && chars.clone().any(is_regional_indicator) {
+        return 2;
+    }
+    let width = first.map_or(0, char_width);
+    if width == 0 {
+        return 0;
+    }
+    match chars.find(|&ch| ch == VARIATION_SELECTOR_16 || ch == VARIATION_SELECTOR_15) {
+        Some(VARIATION_SELECTOR_16) => 2,
+        Some(_) => 1,
+        None => width,
+    }
+}
+
+/// Returns `true` for the letters used in pairs to encode flags.
+fn is_regional_indicator(ch: char) -> bool {
+    ('\u{1F1E6}'..='\u{1F1FF}').contains(&ch)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn modes_disagree_on_clusters() {
+        let cases = [
+            ("hello", 5, 5),
+            // Wide CJK characters.
+            ("\u{4F60}\u{597D}", 4, 4),
+            // A combining acute accent.
+            ("e\u{301}", 1, 1),
+            // A flag is a pair of regional indicators.
+            ("\u{1F1E9}\u{1F1EA}", 2, 2),
+            // A heart with emoji presentation.
+            ("\u{2764}\u{FE0F}", 1, 2),
+            // A watch with text presentation.
+            ("\u{231A}\u{FE0E}", 2, 1),
+            // A family joined by zero width joiners.
+            ("\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}", 6, 2),
+            // Control characters don't advance the cursor.
+            ("a\tb\x1b", 2, 2),
+        ];
+        for (text, legacy, clustered) in cases {
+            assert_eq!(str_width(text, WidthMode::Legacy), legacy, "{text:?}");
+            assert_eq!(
+                str_width(text, WidthMode::GraphemeClustering),
+                clustered,
+                "{text:?}"
+            );
+            let sum: usize = grapheme_widths(text, WidthMode::Legacy)
+                .map(|(_, w)| w)
+                .sum();
+            assert_eq!(sum, legacy, "{text:?}");
+        }
+    }
+
+    #[test]
+    fn width_mode_from_mode_setting() {
+        use DecModeSetting::*;
+
+        assert_eq!(WidthMode::from_mode_setting(None), WidthMode::Legacy);
+        for setting in [NotRecognized, PermanentlyReset] {
+            assert_eq!(
+                WidthMode::from_mode_setting(Some(setting)),
+                WidthMode::Legacy
+            );
+        }
+        for setting in [Set, Reset, PermanentlySet] {
+            assert_eq!(
+                WidthMode::from_mode_setting(Some(setting)),
+                WidthMode::GraphemeClustering
+            );
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn detect_width_mode() {
+        use crate::terminal::test_terminal;
+
+        fn detect_with(input: &[u8]) -> (WidthMode, Vec<u8>, Vec<u8>) {
+            let mut terminal = test_terminal();
+            terminal.push_input(input).unwrap();
+            let mode = detect(&mut terminal, Duration::from_secs(1)).unwrap();
+            let output = terminal.take_output();
+            terminal.enter_cooked_mode().unwrap();
+            (mode, output, terminal.take_output())
+        }
+
+        // A supported but reset mode is set.
+        let (mode, output, restored) = detect_with(b"\x1b[?2027;2$y\x1b[?62c");
+        assert_eq!(mode, WidthMode::GraphemeClustering);
+        assert_eq!(output, b"\x1b[?2027$p\x1b[c\x1b[?2027h");
+        // The mode is tracked, so cooked mode resets it.
+        assert_eq!(restored, b"\x1b[?2027l");
+
+        // Without the mode the cursor movement over the probe decides.
+        let (mode, output, restored) = detect_with(b"\x1b[?2027;0$y\x1b[?62c\x1b[5;3R");
+        assert_eq!(mode, WidthMode::GraphemeClustering);
+        assert!(output.ends_with(b"\x1b[6n\r\x1b[0K"));
+        assert_eq!(restored, b"");
+        let (mode, ..) = detect_with(b"\x1b[?62c\x1b[5;7R");
+        assert_eq!(mode, WidthMode::Legacy);
+    }
+}